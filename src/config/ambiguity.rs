@@ -0,0 +1,329 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Ambiguity detection for mappings
+use crate::config::{Header, Mapping, Request};
+use getset::Getters;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Two mappings with the same priority that could match the same request.
+///
+/// Which of the two is used for a request they both match is not defined.
+#[derive(Clone, Debug, Eq, Getters, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Ambiguity {
+    /// The priority shared by both mappings.
+    #[get = "pub"]
+    priority: u8,
+    /// The name of the first mapping.
+    #[get = "pub"]
+    first: String,
+    /// The name of the second mapping.
+    #[get = "pub"]
+    second: String,
+}
+
+impl Ambiguity {
+    crate fn new(priority: u8, first: &str, second: &str) -> Self {
+        let (first, second) = if first <= second {
+            (first, second)
+        } else {
+            (second, first)
+        };
+
+        Self {
+            priority,
+            first: first.to_string(),
+            second: second.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Ambiguity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' and '{}' (priority {}) may match the same request",
+            self.first, self.second, self.priority
+        )
+    }
+}
+
+/// Could a single request match both of the given request configurations?
+///
+/// This is conservative: two configurations overlap unless some exact criterion, or an exact
+/// criterion checked against a pattern, rules it out.
+crate fn overlaps(left: &Request, right: &Request) -> bool {
+    compatible_methods(left, right)
+        && compatible_url(left, right)
+        && compatible(left.body(), right.body())
+        && compatible(left.host(), right.host())
+        && compatible(left.scheme(), right.scheme())
+        && exact_matches_pattern(left.url(), right.url_pattern())
+        && exact_matches_pattern(right.url(), left.url_pattern())
+        && methods_match_pattern(left, right.method_pattern())
+        && methods_match_pattern(right, left.method_pattern())
+        && exact_matches_pattern(left.host(), right.host_pattern())
+        && exact_matches_pattern(right.host(), left.host_pattern())
+        && exact_matches_pattern(left.body(), right.body_pattern())
+        && exact_matches_pattern(right.body(), left.body_pattern())
+        && contains_all(left.body(), right.body_contains())
+        && contains_all(right.body(), left.body_contains())
+        && !methods_excluded(left, right.method_not())
+        && !methods_excluded(right, left.method_not())
+        && compatible_headers(&headers(left), &headers(right))
+        && compatible_params(left.query_params(), right.query_params())
+        && params_match_patterns(left.query_params(), right.query_params_pattern())
+        && params_match_patterns(right.query_params(), left.query_params_pattern())
+}
+
+/// Can the given mappings never match at the same time, because they require different states
+/// of the same scenario?
+crate fn exclusive_states(left: &Mapping, right: &Mapping) -> bool {
+    match (
+        (left.scenario(), left.required_state()),
+        (right.scenario(), right.required_state()),
+    ) {
+        ((Some(left_scenario), Some(left_state)), (Some(right_scenario), Some(right_state))) => {
+            left_scenario == right_scenario && left_state != right_state
+        }
+        _ => false,
+    }
+}
+
+fn compatible(left: &Option<String>, right: &Option<String>) -> bool {
+    match (left, right) {
+        (Some(left), Some(right)) => left == right,
+        _ => true,
+    }
+}
+
+fn compatible_methods(left: &Request, right: &Request) -> bool {
+    match (left.allowed_methods(), right.allowed_methods()) {
+        (Some(left), Some(right)) => left.iter().any(|method| right.contains(method)),
+        _ => true,
+    }
+}
+
+fn methods_match_pattern(request: &Request, pattern: &Option<String>) -> bool {
+    request.allowed_methods().map_or(true, |methods| {
+        methods
+            .iter()
+            .any(|method| exact_matches_pattern(&Some(method.to_string()), pattern))
+    })
+}
+
+fn methods_excluded(request: &Request, not: &Option<String>) -> bool {
+    request.allowed_methods().map_or(false, |methods| {
+        methods
+            .iter()
+            .all(|method| excluded(&Some(method.to_string()), not))
+    })
+}
+
+fn compatible_url(left: &Request, right: &Request) -> bool {
+    match (left.url(), right.url()) {
+        (Some(left_url), Some(right_url))
+            if *left.url_case_insensitive() || *right.url_case_insensitive() =>
+        {
+            left_url.eq_ignore_ascii_case(right_url)
+        }
+        (left_url, right_url) => compatible(left_url, right_url),
+    }
+}
+
+fn excluded(exact: &Option<String>, not: &Option<String>) -> bool {
+    match (exact, not) {
+        (Some(exact), Some(not)) => exact == not,
+        _ => false,
+    }
+}
+
+fn exact_matches_pattern(exact: &Option<String>, pattern: &Option<String>) -> bool {
+    match (exact, pattern) {
+        (Some(exact), Some(pattern)) => Regex::new(pattern)
+            .map(|regex| regex.is_match(exact))
+            .unwrap_or(true),
+        _ => true,
+    }
+}
+
+fn contains_all(exact: &Option<String>, substrings: &[String]) -> bool {
+    exact.as_ref().map_or(true, |exact| {
+        substrings
+            .iter()
+            .all(|substring| exact.contains(&substring[..]))
+    })
+}
+
+fn headers(request: &Request) -> Vec<&Header> {
+    request.headers().iter().chain(request.header()).collect()
+}
+
+fn compatible_headers(left: &[&Header], right: &[&Header]) -> bool {
+    left.iter().all(|left| {
+        right.iter().all(|right| {
+            !left.key().eq_ignore_ascii_case(right.key()) || left.value() == right.value()
+        })
+    })
+}
+
+fn compatible_params(left: &BTreeMap<String, String>, right: &BTreeMap<String, String>) -> bool {
+    left.iter()
+        .all(|(name, value)| right.get(name).map_or(true, |other| other == value))
+}
+
+fn params_match_patterns(
+    params: &BTreeMap<String, String>,
+    patterns: &BTreeMap<String, String>,
+) -> bool {
+    params.iter().all(|(name, value)| {
+        exact_matches_pattern(&Some(value.clone()), &patterns.get(name).cloned())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{overlaps, Ambiguity};
+    use crate::config::mappings::test::test_mappings;
+    use crate::config::{Header, Request};
+
+    #[test]
+    fn different_urls() {
+        let left = Request::builder().url("/a").build();
+        let right = Request::builder().url("/b").build();
+        assert!(!overlaps(&left, &right));
+    }
+
+    #[test]
+    fn case_insensitive_urls() {
+        let left = Request::builder().url("/Orders").build();
+        let right = Request::builder()
+            .url("/orders")
+            .url_case_insensitive(true)
+            .build();
+        assert!(overlaps(&left, &right));
+        assert!(!overlaps(&left, &Request::builder().url("/orders").build()));
+    }
+
+    #[test]
+    fn url_against_pattern() {
+        let left = Request::builder().url("/orders/1").build();
+        assert!(overlaps(
+            &left,
+            &Request::builder().url_pattern("^/orders/.*").build()
+        ));
+        assert!(!overlaps(
+            &left,
+            &Request::builder().url_pattern("^/users/.*").build()
+        ));
+    }
+
+    #[test]
+    fn query_param_against_pattern() {
+        let left = Request::builder().query_param("id", "12345").build();
+        assert!(overlaps(
+            &left,
+            &Request::builder()
+                .query_param_pattern("id", "^[0-9]{5}$")
+                .build()
+        ));
+        assert!(!overlaps(
+            &left,
+            &Request::builder()
+                .query_param_pattern("id", "^[a-z]+$")
+                .build()
+        ));
+        assert!(overlaps(
+            &left,
+            &Request::builder()
+                .query_param_pattern("page", "^[a-z]+$")
+                .build()
+        ));
+    }
+
+    #[test]
+    fn body_against_substrings() {
+        let left = Request::builder().body(r#"{"order_id": "A-1"}"#).build();
+        assert!(overlaps(
+            &left,
+            &Request::builder().body_contains("A-1").build()
+        ));
+        assert!(!overlaps(
+            &left,
+            &Request::builder()
+                .body_contains("A-1")
+                .body_contains("B-2")
+                .build()
+        ));
+    }
+
+    #[test]
+    fn method_lists() {
+        let left = Request::builder().methods("GET").methods("HEAD").build();
+        assert!(overlaps(&left, &Request::builder().method("HEAD").build()));
+        assert!(overlaps(&left, &Request::builder().method("ANY").build()));
+        assert!(!overlaps(&left, &Request::builder().method("POST").build()));
+        assert!(!overlaps(
+            &left,
+            &Request::builder().method_pattern("^P").build()
+        ));
+        assert!(overlaps(
+            &left,
+            &Request::builder().method_not("GET").build()
+        ));
+    }
+
+    #[test]
+    fn different_criteria() {
+        let left = Request::builder().url("/a").build();
+        let right = Request::builder().method("GET").build();
+        assert!(overlaps(&left, &right));
+    }
+
+    #[test]
+    fn conflicting_headers() {
+        let left = Request::builder()
+            .header(Header::new("X-Version", "1"))
+            .build();
+        let right = Request::builder()
+            .headers(Header::new("x-version", "2"))
+            .build();
+        assert!(!overlaps(&left, &right));
+    }
+
+    #[test]
+    fn conflicting_query_params() {
+        let left = Request::builder().query_param("page", "1").build();
+        let right = Request::builder().query_param("page", "2").build();
+        assert!(!overlaps(&left, &right));
+    }
+
+    #[test]
+    fn ambiguities() {
+        match test_mappings() {
+            Ok(mappings) => {
+                let ambiguities = mappings.ambiguities();
+                assert!(ambiguities.contains(&Ambiguity::new(
+                    1,
+                    "Exact Match - URL",
+                    "Exact Match - Header"
+                )));
+                assert!(!ambiguities.contains(&Ambiguity::new(
+                    1,
+                    "Exact Match - URL",
+                    "Exact Match - URL (JSON)"
+                )));
+                assert!(mappings.check_ambiguities(false).is_ok());
+                assert!(mappings.check_ambiguities(true).is_err());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
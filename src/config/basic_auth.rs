@@ -0,0 +1,38 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP basic authentication matching configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// The credentials a request must carry in an `Authorization: Basic` header.
+///
+/// ```toml
+/// [request.basic_auth]
+/// username = "admin"
+/// password = "s3cr3t"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct BasicAuth {
+    /// The expected username.
+    #[get = "pub"]
+    username: String,
+    /// The expected password.
+    #[get = "pub"]
+    password: String,
+}
+
+impl BasicAuth {
+    /// Create new basic authentication credentials.
+    pub fn new<U: Into<String>, P: Into<String>>(username: U, password: P) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Binary data embedded in configuration as base64
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+
+/// Binary data that is written in configuration as a base64 string.
+///
+/// The data is decoded once, when the configuration is deserialized.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Binary(Vec<u8>);
+
+impl Binary {
+    /// Create binary data from the given bytes.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Deref for Binary {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for Binary {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&base64::encode(&self.0))
+    }
+}
+
+struct BinaryVisitor;
+
+impl<'de> Visitor<'de> for BinaryVisitor {
+    type Value = Binary;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a base64 encoded string")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Binary, E>
+    where
+        E: de::Error,
+    {
+        base64::decode(value)
+            .map(Binary)
+            .map_err(|e| E::custom(format!("invalid base64: {}", e)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Binary {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(BinaryVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Binary;
+
+    #[test]
+    fn round_trip() {
+        let binary = Binary::new(vec![0x89, 0x50, 0x4e, 0x47, 0x00, 0xff]);
+        match serde_json::to_string(&binary) {
+            Ok(json) => {
+                assert_eq!(json, r#""iVBORwD/""#);
+                match serde_json::from_str::<Binary>(&json) {
+                    Ok(decoded) => assert_eq!(decoded, binary),
+                    Err(e) => assert!(false, e.to_string()),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn invalid_base64() {
+        assert!(serde_json::from_str::<Binary>(r#""not base64!""#).is_err());
+    }
+}
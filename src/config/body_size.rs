@@ -0,0 +1,44 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Request body size matching configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// Bounds, in bytes, on the size of a request body.  Both bounds are inclusive.
+///
+/// The size is taken from the `Content-Length` header, or the body itself if there is none.
+///
+/// ```toml
+/// # Payloads over 1MiB
+/// [request.body_size]
+/// min = 1048577
+/// ```
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct BodySize {
+    /// The minimum body size.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<u64>,
+    /// The maximum body size.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<u64>,
+}
+
+impl BodySize {
+    /// Create new body size bounds.
+    pub fn new(min: Option<u64>, max: Option<u64>) -> Self {
+        Self { min, max }
+    }
+
+    /// Is the given size within the bounds?
+    pub fn contains(&self, size: u64) -> bool {
+        self.min.map_or(true, |min| size >= min) && self.max.map_or(true, |max| size <= max)
+    }
+}
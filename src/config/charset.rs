@@ -0,0 +1,74 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response body character set configuration
+use serde_derive::{Deserialize, Serialize};
+
+/// The character set a response body is sent in.  Bodies are configured as UTF-8, and are
+/// transcoded to the character set before they are sent.
+///
+/// ```toml
+/// [response]
+/// body_file_name = "orders.txt"
+/// charset = "iso-8859-1"
+/// ```
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Charset {
+    /// UTF-8, sent as is.
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// ISO-8859-1, i.e. Latin-1.  Characters outside of it are sent as `?`.
+    #[serde(rename = "iso-8859-1")]
+    Iso88591,
+    /// UTF-16, big endian with a byte order mark.
+    #[serde(rename = "utf-16")]
+    Utf16,
+    /// UTF-16, little endian without a byte order mark.
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    /// UTF-16, big endian without a byte order mark.
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+}
+
+impl Charset {
+    /// The name of the character set, as used in the `charset` parameter of a `Content-Type`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "utf-8",
+            Charset::Iso88591 => "iso-8859-1",
+            Charset::Utf16 => "utf-16",
+            Charset::Utf16Le => "utf-16le",
+            Charset::Utf16Be => "utf-16be",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Charset;
+
+    #[test]
+    fn deserialize() {
+        match serde_json::from_str::<Vec<Charset>>(
+            r#"["utf-8","iso-8859-1","utf-16","utf-16le","utf-16be"]"#,
+        ) {
+            Ok(charsets) => assert_eq!(
+                charsets,
+                vec![
+                    Charset::Utf8,
+                    Charset::Iso88591,
+                    Charset::Utf16,
+                    Charset::Utf16Le,
+                    Charset::Utf16Be
+                ]
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
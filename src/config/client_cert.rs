@@ -0,0 +1,41 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! TLS client certificate matching configuration
+use getset::{Getters, Setters};
+use serde_derive::{Deserialize, Serialize};
+
+/// Match the certificate a client presented during the TLS handshake.
+///
+/// Client certificates are only requested when the runtime has a `tls_client_ca`.  Every field
+/// that is set must match, and a request without a client certificate never matches.
+///
+/// ```toml
+/// [request.client_cert]
+/// subject = "O=Acme, CN=billing"
+/// san = "billing.acme.internal"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize, Setters)]
+pub struct ClientCert {
+    /// The subject distinguished name, with the attributes in certificate order, i.e.
+    /// `C=GB, O=Acme, CN=client`.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subject: Option<String>,
+    /// A DNS name, email address, URI or IP address in the subject alternative names.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    san: Option<String>,
+    /// The SHA-256 fingerprint of the certificate, in hex.  Case and `:` separators are ignored.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+}
@@ -0,0 +1,155 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response compression configuration
+use serde_derive::{Deserialize, Serialize};
+
+/// How a response body is compressed.
+///
+/// ```toml
+/// [response]
+/// body_file_name = "orders.json"
+/// compression = "auto"
+/// ```
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Use the best encoding the request's `Accept-Encoding` header allows, preferring brotli,
+    /// then gzip, then deflate.  The body isn't compressed if the request doesn't accept any of
+    /// them.
+    Auto,
+    /// Always compress with gzip.
+    Gzip,
+    /// Always compress with deflate, i.e. zlib.
+    Deflate,
+    /// Always compress with brotli.
+    Brotli,
+}
+
+impl Compression {
+    /// The `Content-Encoding` of a body compressed this way, or `None` for `Auto`.
+    pub fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Compression::Auto => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Deflate => Some("deflate"),
+            Compression::Brotli => Some("br"),
+        }
+    }
+
+    /// The compression to use for a request with the given `Accept-Encoding` header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::Compression;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(
+    ///     Compression::Auto.negotiate(Some("gzip, deflate;q=0.5")),
+    ///     Some(Compression::Gzip)
+    /// );
+    /// assert_eq!(Compression::Auto.negotiate(None), None);
+    /// assert_eq!(Compression::Brotli.negotiate(None), Some(Compression::Brotli));
+    /// # }
+    /// ```
+    pub fn negotiate(self, accept_encoding: Option<&str>) -> Option<Self> {
+        if self != Compression::Auto {
+            return Some(self);
+        }
+
+        let accepted: Vec<(String, f32)> = accept_encoding
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|coding| {
+                let mut params = coding.split(';');
+                let name = params.next()?.trim().to_ascii_lowercase();
+                let quality = params
+                    .filter_map(|param| {
+                        let mut pair = param.splitn(2, '=');
+                        match (pair.next(), pair.next()) {
+                            (Some(key), Some(value)) if key.trim() == "q" => {
+                                value.trim().parse::<f32>().ok()
+                            }
+                            _ => None,
+                        }
+                    })
+                    .next()
+                    .unwrap_or(1.0);
+
+                if name.is_empty() {
+                    None
+                } else {
+                    Some((name, quality))
+                }
+            })
+            .collect();
+
+        let quality = |encoding: &str| {
+            accepted
+                .iter()
+                .find(|(name, _)| name == encoding)
+                .or_else(|| accepted.iter().find(|(name, _)| name == "*"))
+                .map_or(0.0, |(_, quality)| *quality)
+        };
+
+        let mut best: Option<(Self, f32)> = None;
+        for compression in &[Compression::Brotli, Compression::Gzip, Compression::Deflate] {
+            let q = compression.content_encoding().map_or(0.0, quality);
+            if q > 0.0 && best.map_or(true, |(_, best_q)| q > best_q) {
+                best = Some((*compression, q));
+            }
+        }
+        best.map(|(compression, _)| compression)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Compression;
+
+    #[test]
+    fn deserialize() {
+        match serde_json::from_str::<Vec<Compression>>(r#"["auto","gzip","deflate","brotli"]"#) {
+            Ok(compressions) => assert_eq!(
+                compressions,
+                vec![
+                    Compression::Auto,
+                    Compression::Gzip,
+                    Compression::Deflate,
+                    Compression::Brotli
+                ]
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn negotiate() {
+        let auto = Compression::Auto;
+        assert_eq!(
+            auto.negotiate(Some("gzip, deflate, br")),
+            Some(Compression::Brotli)
+        );
+        assert_eq!(
+            auto.negotiate(Some("br;q=0.5, gzip")),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(auto.negotiate(Some("*")), Some(Compression::Brotli));
+        assert_eq!(
+            auto.negotiate(Some("*, br;q=0, gzip;q=0")),
+            Some(Compression::Deflate)
+        );
+        assert_eq!(auto.negotiate(Some("identity")), None);
+        assert_eq!(auto.negotiate(Some("")), None);
+        assert_eq!(
+            Compression::Deflate.negotiate(Some("gzip")),
+            Some(Compression::Deflate)
+        );
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Cross-origin resource sharing configuration
+use crate::util;
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// Answer CORS preflight requests, and add the CORS headers to every response, so browser based
+/// apps can call the mock without `OPTIONS` mappings.
+///
+/// Requests from an origin that isn't allowed are handled as if CORS wasn't configured.
+///
+/// ```toml
+/// [cors]
+/// allowed_origins = ["http://localhost:3000"]
+/// allowed_methods = ["GET", "POST"]
+/// allowed_headers = ["Content-Type", "Authorization"]
+/// allow_credentials = true
+/// max_age_secs = 600
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct Cors {
+    /// The origins allowed to call the mock, i.e. `http://localhost:3000`, or `*` for any.
+    /// Defaults to any.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allowed_origins: Vec<String>,
+    /// The methods a preflight request allows.  Defaults to the common methods.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allowed_methods: Vec<String>,
+    /// The request headers a preflight request allows.  Defaults to those the preflight request
+    /// asks for.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allowed_headers: Vec<String>,
+    /// The response headers the browser exposes to the app.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exposed_headers: Vec<String>,
+    /// Allow requests with credentials, i.e. cookies.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    allow_credentials: bool,
+    /// How long, in seconds, the browser may cache a preflight response.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_age_secs: Option<u64>,
+}
+
+impl Cors {
+    /// Create a new CORS configuration builder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::Cors;
+    /// #
+    /// # fn main() {
+    /// let cors = Cors::builder()
+    ///     .allowed_origin("http://localhost:3000")
+    ///     .allowed_method("GET")
+    ///     .allow_credentials(true)
+    ///     .build();
+    /// assert!(cors.allows_origin("http://localhost:3000"));
+    /// assert!(!cors.allows_origin("http://evil.example.com"));
+    /// # }
+    /// ```
+    pub fn builder() -> CorsBuilder {
+        CorsBuilder::default()
+    }
+
+    /// Is the given origin allowed to call the mock?
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.is_empty()
+            || self
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+}
+
+/// A builder for a [`Cors`](crate::config::Cors) configuration.
+#[derive(Clone, Debug, Default)]
+pub struct CorsBuilder {
+    inner: Cors,
+}
+
+impl CorsBuilder {
+    /// Allow an origin to call the mock.
+    pub fn allowed_origin<S: Into<String>>(mut self, origin: S) -> Self {
+        self.inner.allowed_origins.push(origin.into());
+        self
+    }
+
+    /// Allow a method in preflight requests.
+    pub fn allowed_method<S: Into<String>>(mut self, method: S) -> Self {
+        self.inner.allowed_methods.push(method.into());
+        self
+    }
+
+    /// Allow a request header in preflight requests.
+    pub fn allowed_header<S: Into<String>>(mut self, header: S) -> Self {
+        self.inner.allowed_headers.push(header.into());
+        self
+    }
+
+    /// Expose a response header to the app.
+    pub fn exposed_header<S: Into<String>>(mut self, header: S) -> Self {
+        self.inner.exposed_headers.push(header.into());
+        self
+    }
+
+    /// Allow requests with credentials.
+    pub fn allow_credentials(mut self, allow_credentials: bool) -> Self {
+        self.inner.allow_credentials = allow_credentials;
+        self
+    }
+
+    /// How long, in seconds, the browser may cache a preflight response.
+    pub fn max_age_secs(mut self, max_age_secs: u64) -> Self {
+        self.inner.max_age_secs = Some(max_age_secs);
+        self
+    }
+
+    /// Build the CORS configuration.
+    pub fn build(self) -> Cors {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cors;
+
+    #[test]
+    fn deserialize() {
+        let toml = "allowed_origins = [\"http://localhost:3000\"]\nallowed_methods = [\"GET\"]\nmax_age_secs = 600\n";
+        match toml::from_str::<Cors>(toml) {
+            Ok(cors) => assert_eq!(
+                cors,
+                Cors::builder()
+                    .allowed_origin("http://localhost:3000")
+                    .allowed_method("GET")
+                    .max_age_secs(600)
+                    .build()
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn origins() {
+        assert!(Cors::default().allows_origin("http://localhost:3000"));
+        assert!(Cors::builder()
+            .allowed_origin("*")
+            .build()
+            .allows_origin("http://localhost:3000"));
+        assert!(!Cors::builder()
+            .allowed_origin("http://localhost:8080")
+            .build()
+            .allows_origin("http://localhost:3000"));
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response configuration for unmatched requests
+use crate::config::Header;
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// The response sent when no mapping matches a request.
+///
+/// # Example
+///
+/// ```toml
+/// [default_response]
+/// status = 418
+/// body = '{"message":"nothing here"}'
+/// near_misses = true
+///
+/// [[default_response.headers]]
+/// key = "Content-Type"
+/// value = "application/json"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct DefaultResponse {
+    /// The http status code to send.  Defaults to 404.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    status: Option<u16>,
+    /// The http headers to send.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    headers: Vec<Header>,
+    /// The http response body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body: Option<String>,
+    /// Send a JSON body describing the mappings that nearly matched the request instead of `body`.
+    #[serde(default)]
+    #[get = "pub"]
+    near_misses: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::DefaultResponse;
+    use crate::config::Header;
+
+    const DEFAULT_RESPONSE_TOML: &str = r#"status = 418
+body = "nothing here"
+near_misses = true
+
+[[headers]]
+key = "Content-Type"
+value = "text/plain"
+"#;
+
+    #[test]
+    fn deserialize_default_response() {
+        match toml::from_str::<DefaultResponse>(DEFAULT_RESPONSE_TOML) {
+            Ok(default_response) => {
+                assert_eq!(default_response.status(), &Some(418));
+                assert_eq!(
+                    default_response.headers(),
+                    &vec![Header::new("Content-Type", "text/plain")]
+                );
+                assert_eq!(default_response.body(), &Some("nothing here".to_string()));
+                assert!(*default_response.near_misses());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn deserialize_empty_default_response() {
+        match toml::from_str::<DefaultResponse>("") {
+            Ok(default_response) => assert_eq!(default_response, DefaultResponse::default()),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
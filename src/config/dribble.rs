@@ -0,0 +1,79 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Slow response body delivery configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Send the response body slowly, split into `chunks` pieces spread evenly over
+/// `total_duration_ms` milliseconds.  The status line and headers are sent straight away.
+///
+/// ```toml
+/// [response.dribble]
+/// chunks = 10
+/// total_duration_ms = 5000
+/// ```
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct Dribble {
+    /// The number of pieces to split the body into.
+    #[get = "pub"]
+    chunks: u32,
+    /// How long sending the whole body takes, in milliseconds.
+    #[get = "pub"]
+    total_duration_ms: u64,
+}
+
+impl Dribble {
+    /// Send the body in `chunks` pieces over `total_duration_ms` milliseconds.
+    pub fn new(chunks: u32, total_duration_ms: u64) -> Self {
+        Self {
+            chunks,
+            total_duration_ms,
+        }
+    }
+
+    /// Split a body into pieces, each with the delay, from when the response is ready, before
+    /// it is sent.
+    crate fn split<'a>(&self, body: &'a [u8]) -> Vec<(Duration, &'a [u8])> {
+        let chunks = self.chunks.max(1) as usize;
+        let boundary = |index: usize| body.len() * index / chunks;
+
+        (1..=chunks)
+            .map(|index| {
+                let delay = self.total_duration_ms * index as u64 / chunks as u64;
+                (
+                    Duration::from_millis(delay),
+                    &body[boundary(index - 1)..boundary(index)],
+                )
+            })
+            .filter(|(_, piece)| !piece.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Dribble;
+    use std::time::Duration;
+
+    #[test]
+    fn split() {
+        let pieces = Dribble::new(4, 1000).split(b"0123456789");
+        assert_eq!(
+            pieces,
+            vec![
+                (Duration::from_millis(250), &b"01"[..]),
+                (Duration::from_millis(500), &b"234"[..]),
+                (Duration::from_millis(750), &b"56"[..]),
+                (Duration::from_millis(1000), &b"789"[..]),
+            ]
+        );
+        assert!(Dribble::new(4, 1000).split(b"").is_empty());
+    }
+}
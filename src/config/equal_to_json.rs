@@ -0,0 +1,53 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Structural JSON request body matching configuration
+use crate::util;
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// A JSON document the request body must be structurally equal to.  Key order and whitespace
+/// are ignored.
+///
+/// ```toml
+/// [request.equal_to_json]
+/// json = '{"id": 12345, "tags": ["a", "b"]}'
+/// ignore_extra_fields = true
+/// ignore_array_order = true
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct EqualToJson {
+    /// The expected JSON document.
+    #[get = "pub"]
+    json: String,
+    /// Allow object fields in the body that aren't in the expected document.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    ignore_extra_fields: bool,
+    /// Allow array elements in any order.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    ignore_array_order: bool,
+}
+
+impl EqualToJson {
+    /// Create a new structural JSON match.
+    pub fn new<S: Into<String>>(
+        json: S,
+        ignore_extra_fields: bool,
+        ignore_array_order: bool,
+    ) -> Self {
+        Self {
+            json: json.into(),
+            ignore_extra_fields,
+            ignore_array_order,
+        }
+    }
+}
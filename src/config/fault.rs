@@ -0,0 +1,87 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Connection fault configuration
+use serde_derive::{Deserialize, Serialize};
+
+/// Close the connection part way through sending a response, to reproduce truncated responses
+/// and dropped connections.
+///
+/// ```toml
+/// [response]
+/// status = 200
+/// body_file_name = "orders.json"
+/// fault = "close_mid_body"
+/// ```
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// Close the connection without sending anything.
+    CloseBeforeResponse,
+    /// Close the connection after sending the status line.
+    CloseAfterStatusLine,
+    /// Close the connection after sending the status line and headers.
+    CloseAfterHeaders,
+    /// Close the connection after sending half of the body.
+    CloseMidBody,
+}
+
+impl Fault {
+    /// How much of an encoded response is sent before the connection is closed.
+    crate fn truncate(self, encoded: &[u8]) -> &[u8] {
+        let find = |needle: &[u8]| {
+            encoded
+                .windows(needle.len())
+                .position(|window| window == needle)
+                .map_or(encoded.len(), |position| position + needle.len())
+        };
+
+        let len = match self {
+            Fault::CloseBeforeResponse => 0,
+            Fault::CloseAfterStatusLine => find(b"\r\n"),
+            Fault::CloseAfterHeaders => find(b"\r\n\r\n"),
+            Fault::CloseMidBody => {
+                let head = find(b"\r\n\r\n");
+                head + (encoded.len() - head) / 2
+            }
+        };
+        &encoded[..len]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fault;
+
+    const ENCODED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nbody";
+
+    #[test]
+    fn truncate() {
+        assert_eq!(Fault::CloseBeforeResponse.truncate(ENCODED), b"");
+        assert_eq!(
+            Fault::CloseAfterStatusLine.truncate(ENCODED),
+            &b"HTTP/1.1 200 OK\r\n"[..]
+        );
+        assert_eq!(
+            Fault::CloseAfterHeaders.truncate(ENCODED),
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\n"[..]
+        );
+        assert_eq!(
+            Fault::CloseMidBody.truncate(ENCODED),
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nbo"[..]
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        match serde_json::from_str::<Fault>(r#""close_after_status_line""#) {
+            Ok(fault) => assert_eq!(fault, Fault::CloseAfterStatusLine),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Mapping file formats
+use crate::error::Error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::Path;
+
+/// The serialization formats supported for mapping files.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Format {
+    /// JSON, read from `.json` files.
+    Json,
+    /// TOML, read from `.toml` files.
+    Toml,
+    /// YAML, read from `.yml` or `.yaml` files.
+    Yaml,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Toml
+    }
+}
+
+impl Format {
+    /// Choose a format by file extension, falling back to TOML for unknown extensions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::Format;
+    /// # use std::path::Path;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(Format::from_path(Path::new("a.json")), Format::Json);
+    /// assert_eq!(Format::from_path(Path::new("a.yaml")), Format::Yaml);
+    /// assert_eq!(Format::from_path(Path::new("a.toml")), Format::Toml);
+    /// # }
+    /// ```
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("json") => Format::Json,
+            Some("yml") | Some("yaml") => Format::Yaml,
+            _ => Format::Toml,
+        }
+    }
+
+    /// The file extension used when writing this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+            Format::Yaml => "yml",
+        }
+    }
+
+    /// Deserialize a value from the given bytes.
+    pub fn from_slice<T: DeserializeOwned>(self, buffer: &[u8]) -> Result<T, Error> {
+        match self {
+            Format::Json => Ok(serde_json::from_slice(buffer)?),
+            Format::Toml => Ok(toml::from_slice(buffer)?),
+            Format::Yaml => Ok(serde_yaml::from_slice(buffer)?),
+        }
+    }
+
+    /// Serialize a value into a pretty printed string.
+    pub fn to_string_pretty<T: Serialize>(self, value: &T) -> Result<String, Error> {
+        match self {
+            Format::Json => Ok(serde_json::to_string_pretty(value)?),
+            // Go through `toml::Value` so tables are emitted after plain values.
+            Format::Toml => Ok(toml::to_string_pretty(&toml::Value::try_from(value)?)?),
+            Format::Yaml => Ok(serde_yaml::to_string(value)?),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Format;
+    use crate::config::mapping::test::partial_mapping;
+    use crate::config::Mapping;
+    use std::path::Path;
+
+    fn round_trip(format: Format) {
+        match format.to_string_pretty(&partial_mapping()) {
+            Ok(serialized) => match format.from_slice::<Mapping>(serialized.as_bytes()) {
+                Ok(deserialized) => assert_eq!(deserialized, partial_mapping()),
+                Err(e) => assert!(false, e.to_string()),
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn from_path() {
+        assert_eq!(Format::from_path(Path::new("a.json")), Format::Json);
+        assert_eq!(Format::from_path(Path::new("a.yml")), Format::Yaml);
+        assert_eq!(Format::from_path(Path::new("a.yaml")), Format::Yaml);
+        assert_eq!(Format::from_path(Path::new("a.toml")), Format::Toml);
+        assert_eq!(Format::from_path(Path::new("a")), Format::Toml);
+    }
+
+    #[test]
+    fn round_trip_json() {
+        round_trip(Format::Json);
+    }
+
+    #[test]
+    fn round_trip_toml() {
+        round_trip(Format::Toml);
+    }
+
+    #[test]
+    fn round_trip_yaml() {
+        round_trip(Format::Yaml);
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Shared mapping fragments
+//!
+//! Any table in a mapping file may contain an `$include` key naming another file, relative to the
+//! including file.  The contents of that file are merged into the table, with the keys already in
+//! the table taking precedence, i.e.
+//!
+//! ```toml
+//! [response]
+//! "$include" = "../fragments/json-response.toml"
+//! status = 201
+//! ```
+use crate::config::Format;
+use crate::error::Error::{self, FragmentCycle};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The key used to reference a fragment.
+crate const INCLUDE: &str = "$include";
+
+/// Resolve every `$include` in `value`, which was read from the file at `path`.
+crate fn resolve(value: Value, path: &Path) -> Result<Value, Error> {
+    resolve_value(value, path, &mut vec![path.to_path_buf()])
+}
+
+fn resolve_value(value: Value, path: &Path, stack: &mut Vec<PathBuf>) -> Result<Value, Error> {
+    match value {
+        Value::Object(map) => resolve_object(map, path, stack),
+        Value::Array(values) => Ok(Value::Array(
+            values
+                .into_iter()
+                .map(|value| resolve_value(value, path, stack))
+                .collect::<Result<Vec<Value>, Error>>()?,
+        )),
+        _ => Ok(value),
+    }
+}
+
+fn resolve_object(
+    mut map: Map<String, Value>,
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Value, Error> {
+    let mut resolved = if let Some(Value::String(include)) = map.remove(INCLUDE) {
+        let include_path = path
+            .parent()
+            .map_or_else(|| PathBuf::from(&include), |dir| dir.join(&include));
+
+        if stack.contains(&include_path) {
+            return Err(FragmentCycle(include_path));
+        }
+
+        let contents = fs::read_to_string(&include_path)?;
+        let fragment: Value = Format::from_path(&include_path).from_slice(contents.as_bytes())?;
+        stack.push(include_path.clone());
+        let fragment = resolve_value(fragment, &include_path, stack)?;
+        let _ = stack.pop();
+
+        match fragment {
+            Value::Object(fragment) => fragment,
+            _ => Map::new(),
+        }
+    } else {
+        Map::new()
+    };
+
+    for (key, value) in map {
+        let _ = resolved.insert(key, resolve_value(value, path, stack)?);
+    }
+
+    Ok(Value::Object(resolved))
+}
+
+#[cfg(test)]
+mod test {
+    use super::resolve;
+    use serde_json::json;
+    use std::path::Path;
+
+    #[test]
+    fn no_includes() {
+        let value = json!({"name": "Test", "request": {"url": "/a"}});
+        match resolve(value.clone(), Path::new("tests/mappings/test.json")) {
+            Ok(resolved) => assert_eq!(resolved, value),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn include_and_override() {
+        let value =
+            json!({"response": {"$include": "../fragments/json-response.toml", "status": 201}});
+        match resolve(value, Path::new("tests/mappings/test.json")) {
+            Ok(resolved) => {
+                assert_eq!(resolved["response"]["status"], json!(201));
+                assert_eq!(
+                    resolved["response"]["body_file_name"],
+                    json!("fragment-response.json")
+                );
+                assert!(resolved["response"].get("$include").is_none());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn missing_include() {
+        let value = json!({"response": {"$include": "../fragments/missing.toml"}});
+        assert!(resolve(value, Path::new("tests/mappings/test.json")).is_err());
+    }
+}
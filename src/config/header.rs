@@ -7,6 +7,8 @@
 // modified, or distributed except according to those terms.
 
 //! HTTP header configuration
+use crate::error::Error;
+use crate::util;
 use getset::{Getters, MutGetters, Setters};
 use libeither::Either;
 use serde_derive::{Deserialize, Serialize};
@@ -25,6 +27,29 @@ pub struct Header {
     #[get = "pub"]
     #[get_mut]
     value: String,
+    /// Match the value regardless of ASCII case.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    #[set = "pub"]
+    value_case_insensitive: bool,
+}
+
+impl Header {
+    /// Create a new header, i.e. `Header::new("Content-Type", "application/json")`.
+    pub fn new<K: Into<String>, V: Into<String>>(key: K, value: V) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            value_case_insensitive: false,
+        }
+    }
+
+    /// Resolve the `${ENV_VAR}` placeholders in the value.
+    crate fn interpolate_env(&mut self) -> Result<(), Error> {
+        self.value = util::interpolate_env(&self.value)?;
+        Ok(())
+    }
 }
 
 impl fmt::Display for Header {
@@ -46,6 +71,25 @@ pub struct HeaderPattern {
     #[get = "pub"]
     #[get_mut]
     value: Either<String, String>,
+    /// Match the value, or value pattern, regardless of case.  Keys are always matched
+    /// regardless of case.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    #[set = "pub"]
+    value_case_insensitive: bool,
+}
+
+impl HeaderPattern {
+    /// Create a new header pattern.  A `left` value is matched exactly, a `right` value is
+    /// matched as a regex.
+    pub fn new(key: Either<String, String>, value: Either<String, String>) -> Self {
+        Self {
+            key,
+            value,
+            value_case_insensitive: false,
+        }
+    }
 }
 
 impl fmt::Display for HeaderPattern {
@@ -77,6 +121,7 @@ right = "^application/.*"
         HeaderPattern {
             key: Either::new_left("Content-Type".to_string()),
             value: Either::new_right("^application/.*".to_string()),
+            value_case_insensitive: false,
         }
     }
 
@@ -84,6 +129,7 @@ right = "^application/.*"
         Header {
             key: "Content-Type".to_string(),
             value: "application/json".to_string(),
+            value_case_insensitive: false,
         }
     }
 
@@ -91,6 +137,7 @@ right = "^application/.*"
         HeaderPattern {
             key: Either::new_left("Content-Type".to_string()),
             value: Either::new_right("*".to_string()),
+            value_case_insensitive: false,
         }
     }
 
@@ -98,6 +145,7 @@ right = "^application/.*"
         HeaderPattern {
             key: Either::new_left("Accept".to_string()),
             value: Either::new_right("*".to_string()),
+            value_case_insensitive: false,
         }
     }
 
@@ -108,6 +156,21 @@ right = "^application/.*"
         header
     }
 
+    #[test]
+    fn new_header() {
+        assert_eq!(
+            Header::new("Content-Type", "application/json"),
+            content_type_header()
+        );
+        assert_eq!(
+            HeaderPattern::new(
+                Either::new_left("Content-Type".to_string()),
+                Either::new_right("^application/.*".to_string())
+            ),
+            content_type_header_pattern()
+        );
+    }
+
     #[test]
     fn serialize_empty_header() {
         if let Ok(serialized) = serde_json::to_string(&Header::default()) {
@@ -0,0 +1,214 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Proxy header rewrite configuration
+use crate::error::Error::{self, InvalidResponseConfig};
+use crate::util;
+use getset::Getters;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_derive::{Deserialize, Serialize};
+
+/// A rule that removes or rewrites a header on proxied traffic, either on the request sent to
+/// `proxy_base_url` or on the response sent back to the client.
+///
+/// ```toml
+/// [[response.proxy_request_rewrites]]
+/// key = "Authorization"
+/// remove = true
+///
+/// [[response.proxy_response_rewrites]]
+/// key = "Location"
+/// pattern = "^https://api\\.example\\.com"
+/// replacement = "http://localhost:8080"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct HeaderRewrite {
+    /// The header key, i.e. 'Set-Cookie'.  Keys are matched regardless of case.
+    #[get = "pub"]
+    key: String,
+    /// Remove the header.  Mutually exclusive with `pattern` and `replacement`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    remove: bool,
+    /// A regex matched against each value of the header.  Every match is replaced with
+    /// `replacement`.  Without a pattern, the header is set to `replacement`, whether or not it
+    /// was present.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    pattern: Option<String>,
+    /// The replacement value, which can refer to capture groups of `pattern`, i.e. `$1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    replacement: Option<String>,
+}
+
+impl HeaderRewrite {
+    /// Remove the given header.
+    pub fn remove<K: Into<String>>(key: K) -> Self {
+        Self {
+            key: key.into(),
+            remove: true,
+            pattern: None,
+            replacement: None,
+        }
+    }
+
+    /// Replace every match of `pattern` in the values of the given header, or set the header if
+    /// there is no pattern.
+    pub fn replace<K: Into<String>, R: Into<String>>(
+        key: K,
+        pattern: Option<String>,
+        replacement: R,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            remove: false,
+            pattern,
+            replacement: Some(replacement.into()),
+        }
+    }
+
+    /// Validate the parts of the rule that can't be checked by deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        if let Err(e) = HeaderName::from_bytes(self.key.as_bytes()) {
+            return Err(InvalidResponseConfig(format!(
+                "invalid rewrite header '{}': {}",
+                self.key, e
+            )));
+        }
+
+        if self.remove && (self.pattern.is_some() || self.replacement.is_some()) {
+            Err(InvalidResponseConfig(
+                "field `remove` cannot be used with field `pattern` or `replacement`".to_string(),
+            ))
+        } else if !self.remove && self.replacement.is_none() {
+            Err(InvalidResponseConfig(
+                "a header rewrite requires field `remove` or `replacement`".to_string(),
+            ))
+        } else if let Some(Err(e)) = self.pattern.as_ref().map(|pattern| util::regex(pattern)) {
+            Err(InvalidResponseConfig(format!(
+                "invalid regex in field `pattern`: {}",
+                e
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Apply this rule to the given headers.
+    crate fn apply(&self, headers: &mut HeaderMap) -> Result<(), Error> {
+        let name = HeaderName::from_bytes(self.key.as_bytes())?;
+        let replacement = self.replacement.as_ref().map_or("", String::as_str);
+
+        if self.remove {
+            let _ = headers.remove(&name);
+        } else if let Some(pattern) = &self.pattern {
+            let regex = util::regex(pattern).map_err(InvalidResponseConfig)?;
+            let values = headers
+                .get_all(&name)
+                .iter()
+                .map(|value| {
+                    let value = String::from_utf8_lossy(value.as_bytes());
+                    HeaderValue::from_str(&regex.replace_all(&value, replacement))
+                })
+                .collect::<Result<Vec<HeaderValue>, _>>()?;
+
+            let _ = headers.remove(&name);
+            for value in values {
+                let _ = headers.append(name.clone(), value);
+            }
+        } else {
+            let _ = headers.insert(name, HeaderValue::from_str(replacement)?);
+        }
+        Ok(())
+    }
+}
+
+/// Apply each of the given rules, in order, to the given headers.
+crate fn rewrite(rules: &[HeaderRewrite], headers: &mut HeaderMap) -> Result<(), Error> {
+    for rule in rules {
+        rule.apply(headers)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{rewrite, HeaderRewrite};
+    use http::header::{HeaderMap, HeaderValue, AUTHORIZATION, LOCATION, SET_COOKIE};
+
+    #[test]
+    fn apply() {
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc"));
+        let _ = headers.insert(
+            LOCATION,
+            HeaderValue::from_static("https://api.example.com/orders/1"),
+        );
+        let _ = headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("a=1; Domain=api.example.com"),
+        );
+        let _ = headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("b=2; Domain=api.example.com"),
+        );
+
+        let rules = vec![
+            HeaderRewrite::remove("authorization"),
+            HeaderRewrite::replace(
+                "Location",
+                Some("^https://api\\.example\\.com".to_string()),
+                "http://localhost:8080",
+            ),
+            HeaderRewrite::replace(
+                "Set-Cookie",
+                Some("Domain=[^;]+".to_string()),
+                "Domain=localhost",
+            ),
+            HeaderRewrite::replace("X-Proxied", None, "true"),
+        ];
+
+        if let Err(e) = rewrite(&rules, &mut headers) {
+            assert!(false, e.to_string());
+        }
+        assert!(headers.get(AUTHORIZATION).is_none());
+        assert_eq!(
+            headers.get(LOCATION),
+            Some(&HeaderValue::from_static("http://localhost:8080/orders/1"))
+        );
+        let cookies: Vec<&HeaderValue> = headers.get_all(SET_COOKIE).iter().collect();
+        assert_eq!(
+            cookies,
+            vec![
+                &HeaderValue::from_static("a=1; Domain=localhost"),
+                &HeaderValue::from_static("b=2; Domain=localhost")
+            ]
+        );
+        assert_eq!(
+            headers.get("x-proxied"),
+            Some(&HeaderValue::from_static("true"))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert!(HeaderRewrite::remove("Authorization").validate().is_ok());
+        assert!(HeaderRewrite::replace("Location", None, "/")
+            .validate()
+            .is_ok());
+        assert!(
+            HeaderRewrite::replace("Location", Some("(".to_string()), "/")
+                .validate()
+                .is_err()
+        );
+        assert!(HeaderRewrite::remove("Bad Header").validate().is_err());
+        assert!(HeaderRewrite::default().validate().is_err());
+    }
+}
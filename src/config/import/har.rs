@@ -0,0 +1,219 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Convert the entries of a [HAR](https://w3c.github.io/web-performance/specs/HAR/Overview.html)
+//! file into mappings.
+//!
+//! Requests are matched on method, path, query string and body only, as captured headers
+//! (cookies, user agents) would rarely be sent again verbatim.  When the same request was
+//! captured more than once, the first response is kept.
+use crate::config::{Header, Mapping, Request, Response};
+use crate::error::Error::{self, InvalidImport};
+use serde_derive::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Response headers that describe the captured transfer rather than the content.
+const SKIPPED_HEADERS: [&str; 4] = [
+    "content-encoding",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+];
+
+#[derive(Deserialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Deserialize)]
+struct Log {
+    #[serde(default)]
+    entries: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    request: HarRequest,
+    response: HarResponse,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    query_string: Vec<NameValue>,
+    post_data: Option<PostData>,
+}
+
+#[derive(Deserialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HarResponse {
+    status: u16,
+    #[serde(default)]
+    headers: Vec<NameValue>,
+    content: Option<Content>,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    text: Option<String>,
+    encoding: Option<String>,
+}
+
+/// Convert the entries of HAR JSON into mappings.
+pub fn from_slice(bytes: &[u8]) -> Result<Vec<Mapping>, Error> {
+    let har: Har = serde_json::from_slice(bytes)?;
+    let mut mappings: Vec<Mapping> = vec![];
+
+    for (idx, entry) in har.log.entries.into_iter().enumerate() {
+        let name = format!(
+            "HAR {} - {} {}",
+            idx + 1,
+            entry.request.method,
+            entry.request.url
+        );
+        let request = to_request(entry.request);
+
+        if mappings.iter().any(|mapping| mapping.request() == &request) {
+            continue;
+        }
+
+        let response = to_response(&name, entry.response)?;
+        mappings.push(
+            Mapping::builder()
+                .name(name)
+                .request(request)
+                .response(response)
+                .build(),
+        );
+    }
+
+    Ok(mappings)
+}
+
+/// Convert the entries of the HAR file at the given path into mappings.
+pub fn from_path(path: &Path) -> Result<Vec<Mapping>, Error> {
+    from_slice(&fs::read(path)?)
+}
+
+/// The path of a captured url, without scheme, authority, query or fragment.
+fn url_path(url: &str) -> &str {
+    let url = match url.find("://") {
+        Some(idx) => {
+            let rest = &url[idx + 3..];
+            rest.find('/').map_or("/", |idx| &rest[idx..])
+        }
+        None => url,
+    };
+    url.split(|c| c == '?' || c == '#').next().unwrap_or("/")
+}
+
+fn to_request(har: HarRequest) -> Request {
+    let mut request = Request::builder()
+        .method(har.method)
+        .url(url_path(&har.url));
+
+    for param in har.query_string {
+        request = request.query_param(param.name, param.value);
+    }
+
+    if let Some(body) = har.post_data.and_then(|post_data| post_data.text) {
+        if !body.is_empty() {
+            request = request.body(body);
+        }
+    }
+
+    request.build()
+}
+
+fn to_response(name: &str, har: HarResponse) -> Result<Response, Error> {
+    let mut response = Response::builder().status(har.status);
+
+    for header in har.headers {
+        let lower = header.name.to_lowercase();
+        if !header.name.starts_with(':') && !SKIPPED_HEADERS.contains(&lower.as_str()) {
+            response = response.headers(Header::new(header.name, header.value));
+        }
+    }
+
+    if let Some(content) = har.content {
+        match (content.text, content.encoding.as_ref().map(String::as_str)) {
+            (Some(text), Some("base64")) => {
+                let body = base64::decode(&text).map_err(|e| {
+                    InvalidImport(format!("'{}': invalid base64 content: {}", name, e))
+                })?;
+                response = response.body_base64(body);
+            }
+            (Some(text), _) => {
+                if !text.is_empty() {
+                    response = response.body(text);
+                }
+            }
+            (None, _) => {}
+        }
+    }
+
+    Ok(response.build())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_path, url_path};
+    use std::path::Path;
+
+    #[test]
+    fn path_of_url() {
+        assert_eq!(
+            url_path("https://example.com/api/orders?page=2"),
+            "/api/orders"
+        );
+        assert_eq!(url_path("https://example.com"), "/");
+        assert_eq!(url_path("/a#top"), "/a");
+    }
+
+    #[test]
+    fn import_har() {
+        match from_path(Path::new("tests/har/session.har")) {
+            Ok(mappings) => {
+                assert_eq!(mappings.len(), 2);
+
+                let orders = &mappings[0];
+                assert_eq!(orders.request().method(), &Some("GET".to_string()));
+                assert_eq!(orders.request().url(), &Some("/api/orders".to_string()));
+                assert_eq!(orders.request().query_params().len(), 1);
+                assert_eq!(orders.response().status(), &Some(200));
+                assert_eq!(orders.response().headers().len(), 1);
+                assert_eq!(orders.response().body(), &Some("[]".to_string()));
+
+                let logo = &mappings[1];
+                assert_eq!(
+                    logo.request().body(),
+                    &Some("{\"size\":\"small\"}".to_string())
+                );
+                match logo.response().body_base64() {
+                    Some(body) => assert_eq!(&body[..], b"PNG"),
+                    None => assert!(false, "expected a binary body"),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
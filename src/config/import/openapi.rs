@@ -0,0 +1,272 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generate mappings from an [OpenAPI 3](https://swagger.io/specification/) document.
+//!
+//! One mapping is generated for every operation.  The response is the lowest documented `2xx`
+//! response (or `default`), with a body taken from the first example found on the media type,
+//! its `examples`, or its schema.  Paths with templated segments (`/pets/{id}`) are matched by
+//! pattern and given a lower priority than literal paths.
+use crate::config::{Format, Header, Mapping, Request, Response};
+use crate::error::Error::{self, InvalidImport};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// The operations of a path item, in the order mappings are generated.
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// The media type preferred when an operation documents several.
+const JSON: &str = "application/json";
+/// The priority given to mappings on literal paths.
+const LITERAL_PRIORITY: u8 = 1;
+/// The priority given to mappings on templated paths.
+const TEMPLATED_PRIORITY: u8 = 2;
+
+/// Generate mappings from an OpenAPI document in the given format.
+pub fn from_slice(bytes: &[u8], format: Format) -> Result<Vec<Mapping>, Error> {
+    let document: Value = format.from_slice(bytes)?;
+
+    if !document
+        .get("openapi")
+        .and_then(Value::as_str)
+        .map_or(false, |version| version.starts_with("3."))
+    {
+        return Err(InvalidImport(
+            "only OpenAPI 3 documents are supported".to_string(),
+        ));
+    }
+
+    let base_path = base_path(&document);
+    let mut mappings = vec![];
+
+    if let Some(paths) = document.get("paths").and_then(Value::as_object) {
+        for (path, path_item) in paths {
+            let path_item = resolve(&document, path_item);
+
+            for method in &METHODS {
+                if let Some(operation) = path_item.get(*method) {
+                    mappings.push(to_mapping(
+                        &document,
+                        &format!("{}{}", base_path, path),
+                        method,
+                        resolve(&document, operation),
+                    )?);
+                }
+            }
+        }
+    }
+
+    Ok(mappings)
+}
+
+/// Generate mappings from the OpenAPI document at the given path.
+///
+/// The format is chosen by file extension.
+pub fn from_path(path: &Path) -> Result<Vec<Mapping>, Error> {
+    from_slice(&fs::read(path)?, Format::from_path(path))
+}
+
+/// The path component of the first server url, without a trailing slash.
+fn base_path(document: &Value) -> String {
+    let url = document
+        .pointer("/servers/0/url")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let path = match url.find("://") {
+        Some(idx) => {
+            let rest = &url[idx + 3..];
+            rest.find('/').map_or("", |idx| &rest[idx..])
+        }
+        None => url,
+    };
+    path.trim_end_matches('/').to_string()
+}
+
+/// Follow a local `$ref` (`#/components/...`), if there is one.
+fn resolve<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+    match value.get("$ref").and_then(Value::as_str) {
+        Some(reference) if reference.starts_with('#') => document
+            .pointer(&reference[1..])
+            .map_or(value, |target| resolve(document, target)),
+        _ => value,
+    }
+}
+
+fn to_mapping(
+    document: &Value,
+    path: &str,
+    method: &str,
+    operation: &Value,
+) -> Result<Mapping, Error> {
+    let method = method.to_uppercase();
+    let name = operation
+        .get("operationId")
+        .and_then(Value::as_str)
+        .map_or_else(|| format!("{} {}", method, path), str::to_string);
+    let request = Request::builder().method(method);
+    let (request, priority) = if path.contains('{') {
+        (request.url_pattern(path_pattern(path)), TEMPLATED_PRIORITY)
+    } else {
+        (request.url(path), LITERAL_PRIORITY)
+    };
+
+    Ok(Mapping::builder()
+        .name(name.clone())
+        .priority(priority)
+        .request(request.build())
+        .response(to_response(document, &name, operation)?)
+        .build())
+}
+
+/// Convert a templated path into an anchored regex, i.e. `/pets/{id}` into `^/pets/[^/]+$`.
+fn path_pattern(path: &str) -> String {
+    let mut pattern = String::from("^");
+    let mut rest = path;
+
+    while let Some(start) = rest.find('{') {
+        pattern.push_str(&regex::escape(&rest[..start]));
+        match rest[start..].find('}') {
+            Some(end) => {
+                pattern.push_str("[^/]+");
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        }
+    }
+
+    pattern.push_str(&regex::escape(rest));
+    pattern.push('$');
+    pattern
+}
+
+fn to_response(document: &Value, name: &str, operation: &Value) -> Result<Response, Error> {
+    let responses = operation
+        .get("responses")
+        .and_then(Value::as_object)
+        .ok_or_else(|| InvalidImport(format!("'{}': operation has no responses", name)))?;
+    let (status, response) = responses
+        .iter()
+        .filter(|(code, _)| code.starts_with('2'))
+        .filter_map(|(code, response)| code.parse::<u16>().ok().map(|code| (code, response)))
+        .min_by_key(|(code, _)| *code)
+        .or_else(|| responses.get("default").map(|response| (200, response)))
+        .ok_or_else(|| {
+            InvalidImport(format!(
+                "'{}': operation has no success or default response",
+                name
+            ))
+        })?;
+    let response = resolve(document, response);
+    let mut builder = Response::builder().status(status);
+
+    if let Some(content) = response.get("content").and_then(Value::as_object) {
+        let media = content.get(JSON).map(|media| (JSON, media)).or_else(|| {
+            content
+                .iter()
+                .next()
+                .map(|(media_type, media)| (media_type.as_str(), media))
+        });
+
+        if let Some((media_type, media)) = media {
+            builder = builder.headers(Header::new("Content-Type", media_type));
+
+            if let Some(example) = example(document, resolve(document, media)) {
+                builder = builder.body(match example {
+                    Value::String(body) => body.clone(),
+                    other => other.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(builder.build())
+}
+
+/// Find an example for a media type, checking `example`, then `examples`, then the schema.
+fn example<'a>(document: &'a Value, media: &'a Value) -> Option<&'a Value> {
+    media
+        .get("example")
+        .or_else(|| {
+            media
+                .get("examples")
+                .and_then(Value::as_object)
+                .and_then(|examples| examples.values().next())
+                .and_then(|example| resolve(document, example).get("value"))
+        })
+        .or_else(|| {
+            media
+                .get("schema")
+                .and_then(|schema| resolve(document, schema).get("example"))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_path, from_slice, path_pattern};
+    use crate::config::Format;
+    use std::path::Path;
+
+    #[test]
+    fn templated_path() {
+        assert_eq!(path_pattern("/pets"), "^/pets$");
+        assert_eq!(path_pattern("/pets/{id}"), "^/pets/[^/]+$");
+        assert_eq!(
+            path_pattern("/pets/{id}/toys/{toy}.json"),
+            r"^/pets/[^/]+/toys/[^/]+\.json$"
+        );
+    }
+
+    #[test]
+    fn import_petstore() {
+        match from_path(Path::new("tests/openapi/petstore.yml")) {
+            Ok(mappings) => {
+                assert_eq!(mappings.len(), 3);
+
+                let list = &mappings[0];
+                assert_eq!(list.name(), "listPets");
+                assert_eq!(*list.priority(), 1);
+                assert_eq!(list.request().method(), &Some("GET".to_string()));
+                assert_eq!(list.request().url(), &Some("/v1/pets".to_string()));
+                assert_eq!(list.response().status(), &Some(200));
+                assert_eq!(
+                    list.response().body(),
+                    &Some(r#"[{"id":1,"name":"Rex"}]"#.to_string())
+                );
+
+                let create = &mappings[1];
+                assert_eq!(create.name(), "POST /v1/pets");
+                assert_eq!(create.response().status(), &Some(201));
+                assert_eq!(create.response().body(), &None);
+
+                let show = &mappings[2];
+                assert_eq!(show.name(), "showPetById");
+                assert_eq!(*show.priority(), 2);
+                assert_eq!(
+                    show.request().url_pattern(),
+                    &Some("^/v1/pets/[^/]+$".to_string())
+                );
+                assert_eq!(
+                    show.response().body(),
+                    &Some(r#"{"id":1,"name":"Rex"}"#.to_string())
+                );
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn swagger_2_unsupported() {
+        assert!(from_slice(br#"{"swagger": "2.0", "paths": {}}"#, Format::Json).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Import [WireMock](http://wiremock.org/docs/stubbing/) stub mappings.
+//!
+//! Both a single stub and a `{"mappings": [...]}` file are supported.  Only the `equalTo` and
+//! `matches` value matchers have a `libdeadmock` equivalent, so a stub using any other matcher
+//! fails to import rather than silently matching more than it should.
+use crate::config::{Header, HeaderPattern, Mapping, Request, Response};
+use crate::error::Error::{self, InvalidImport};
+use libeither::Either;
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// The priority WireMock gives a stub without one.
+const DEFAULT_PRIORITY: u64 = 5;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stubs {
+    Many { mappings: Vec<Stub> },
+    One(Stub),
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Stub {
+    id: Option<String>,
+    uuid: Option<String>,
+    name: Option<String>,
+    priority: Option<u64>,
+    #[serde(default)]
+    request: StubRequest,
+    #[serde(default)]
+    response: StubResponse,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StubRequest {
+    method: Option<String>,
+    url: Option<String>,
+    url_path: Option<String>,
+    url_pattern: Option<String>,
+    url_path_pattern: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, ValuePattern>,
+    #[serde(default)]
+    query_parameters: BTreeMap<String, ValuePattern>,
+    #[serde(default)]
+    body_patterns: Vec<ValuePattern>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ValuePattern {
+    equal_to: Option<String>,
+    matches: Option<String>,
+    #[serde(flatten)]
+    other: BTreeMap<String, Value>,
+}
+
+enum ValueMatch {
+    Exact(String),
+    Pattern(String),
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StubResponse {
+    status: Option<u16>,
+    #[serde(default)]
+    headers: BTreeMap<String, Value>,
+    body: Option<String>,
+    json_body: Option<Value>,
+    base64_body: Option<String>,
+    body_file_name: Option<String>,
+    fixed_delay_milliseconds: Option<u64>,
+    proxy_base_url: Option<String>,
+}
+
+/// Convert WireMock stub mapping JSON into mappings.
+pub fn from_slice(bytes: &[u8]) -> Result<Vec<Mapping>, Error> {
+    let stubs = match serde_json::from_slice(bytes)? {
+        Stubs::Many { mappings } => mappings,
+        Stubs::One(stub) => vec![stub],
+    };
+
+    stubs.into_iter().map(to_mapping).collect()
+}
+
+/// Convert the WireMock stub mapping file at the given path into mappings.
+pub fn from_path(path: &Path) -> Result<Vec<Mapping>, Error> {
+    from_slice(&fs::read(path)?)
+}
+
+fn to_mapping(stub: Stub) -> Result<Mapping, Error> {
+    let name = stub
+        .name
+        .clone()
+        .or_else(|| stub.id.clone())
+        .or_else(|| stub.uuid.clone())
+        .unwrap_or_else(|| {
+            format!(
+                "WireMock - {} {}",
+                stub.request.method.as_ref().map_or("ANY", String::as_str),
+                stub.request
+                    .url
+                    .as_ref()
+                    .or_else(|| stub.request.url_path.as_ref())
+                    .or_else(|| stub.request.url_pattern.as_ref())
+                    .or_else(|| stub.request.url_path_pattern.as_ref())
+                    .map_or("", String::as_str)
+            )
+        });
+    let priority = stub.priority.unwrap_or(DEFAULT_PRIORITY).min(255) as u8;
+
+    Ok(Mapping::builder()
+        .name(name.clone())
+        .priority(priority)
+        .request(to_request(&name, stub.request)?)
+        .response(to_response(&name, stub.response)?)
+        .build())
+}
+
+fn to_request(name: &str, stub: StubRequest) -> Result<Request, Error> {
+    let mut request = Request::builder();
+
+    if let Some(method) = stub.method.filter(|method| method != "ANY") {
+        request = request.method(method);
+    }
+
+    if let Some(url) = stub.url {
+        let mut parts = url.splitn(2, '?');
+        request = request.url(parts.next().unwrap_or(""));
+        for pair in parts.next().unwrap_or("").split('&') {
+            if !pair.is_empty() {
+                let mut pair = pair.splitn(2, '=');
+                request = request.query_param(pair.next().unwrap_or(""), pair.next().unwrap_or(""));
+            }
+        }
+    } else if let Some(url_path) = stub.url_path {
+        request = request.url(url_path);
+    } else if let Some(url_pattern) = stub.url_pattern.or(stub.url_path_pattern) {
+        request = request.url_pattern(url_pattern);
+    }
+
+    for (header, pattern) in stub.headers {
+        request = match value_match(name, &header, pattern)? {
+            ValueMatch::Exact(value) => request.headers(Header::new(header, value)),
+            ValueMatch::Pattern(value_pattern) => request.headers_pattern(HeaderPattern::new(
+                Either::new_left(header),
+                Either::new_right(value_pattern),
+            )),
+        };
+    }
+
+    for (param, pattern) in stub.query_parameters {
+        request = match value_match(name, &param, pattern)? {
+            ValueMatch::Exact(value) => request.query_param(param, value),
+            ValueMatch::Pattern(value_pattern) => request.query_param_pattern(param, value_pattern),
+        };
+    }
+
+    match stub.body_patterns.len() {
+        0 => {}
+        1 => match stub.body_patterns.into_iter().next() {
+            Some(ValuePattern {
+                equal_to: Some(body),
+                matches: None,
+                ref other,
+            }) if other.is_empty() => request = request.body(body),
+            _ => {
+                return Err(import_error(
+                    name,
+                    "only `equalTo` body patterns are supported",
+                ))
+            }
+        },
+        _ => {
+            return Err(import_error(
+                name,
+                "only a single body pattern is supported",
+            ))
+        }
+    }
+
+    Ok(request.build())
+}
+
+/// Convert a WireMock value matcher.
+fn value_match(name: &str, key: &str, pattern: ValuePattern) -> Result<ValueMatch, Error> {
+    match pattern {
+        ValuePattern {
+            equal_to: Some(value),
+            matches: None,
+            ref other,
+        } if other.is_empty() => Ok(ValueMatch::Exact(value)),
+        ValuePattern {
+            equal_to: None,
+            matches: Some(value_pattern),
+            ref other,
+        } if other.is_empty() => Ok(ValueMatch::Pattern(value_pattern)),
+        _ => Err(import_error(
+            name,
+            &format!(
+                "unsupported matcher for '{}', only `equalTo` and `matches` are supported",
+                key
+            ),
+        )),
+    }
+}
+
+fn to_response(name: &str, stub: StubResponse) -> Result<Response, Error> {
+    let mut response = Response::builder().status(stub.status.unwrap_or(200));
+
+    for (header, value) in stub.headers {
+        match value {
+            Value::String(value) => response = response.headers(Header::new(header, value)),
+            Value::Array(values) => {
+                for value in values {
+                    if let Value::String(value) = value {
+                        response = response.headers(Header::new(header.clone(), value));
+                    }
+                }
+            }
+            other => response = response.headers(Header::new(header, other.to_string())),
+        }
+    }
+
+    if let Some(body) = stub.body {
+        response = response.body(body);
+    } else if let Some(json_body) = stub.json_body {
+        response = response.body(json_body.to_string());
+    } else if let Some(base64_body) = stub.base64_body {
+        let body = base64::decode(&base64_body)
+            .map_err(|e| import_error(name, &format!("invalid base64Body: {}", e)))?;
+        response = response.body_base64(body);
+    } else if let Some(body_file_name) = stub.body_file_name {
+        response = response.body_file_name(body_file_name);
+    }
+
+    if let Some(delay_ms) = stub.fixed_delay_milliseconds {
+        response = response.delay_ms(delay_ms);
+    }
+
+    if let Some(proxy_base_url) = stub.proxy_base_url {
+        response = response.proxy_base_url(proxy_base_url);
+    }
+
+    Ok(response.build())
+}
+
+fn import_error(name: &str, message: &str) -> Error {
+    InvalidImport(format!("'{}': {}", name, message))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_path, from_slice};
+    use crate::config::{Header, Mapping, Request, Response};
+    use std::path::Path;
+
+    #[test]
+    fn import_stub() {
+        let stub = br#"{
+            "request": {
+                "method": "GET",
+                "url": "/orders?page=2"
+            },
+            "response": {
+                "status": 200,
+                "jsonBody": {"id": 12345},
+                "headers": {"Content-Type": "application/json"}
+            }
+        }"#;
+        match from_slice(stub) {
+            Ok(mappings) => assert_eq!(
+                mappings,
+                vec![Mapping::builder()
+                    .name("WireMock - GET /orders?page=2")
+                    .priority(5)
+                    .request(
+                        Request::builder()
+                            .method("GET")
+                            .url("/orders")
+                            .query_param("page", "2")
+                            .build()
+                    )
+                    .response(
+                        Response::builder()
+                            .status(200)
+                            .headers(Header::new("Content-Type", "application/json"))
+                            .body(r#"{"id":12345}"#)
+                            .build()
+                    )
+                    .build()]
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn import_mappings_file() {
+        match from_path(Path::new("tests/wiremock/stubs.json")) {
+            Ok(mappings) => {
+                assert_eq!(mappings.len(), 2);
+                let orders = &mappings[0];
+                assert_eq!(orders.name(), "Get Order");
+                assert_eq!(*orders.priority(), 1);
+                assert_eq!(
+                    orders.request().url_pattern(),
+                    &Some("/orders/[0-9]+".to_string())
+                );
+                assert_eq!(orders.request().headers().len(), 1);
+                assert_eq!(orders.request().headers_pattern().len(), 1);
+                assert_eq!(
+                    orders.response().body_file_name(),
+                    &Some("order.json".to_string())
+                );
+                assert_eq!(orders.response().delay_ms(), &Some(250));
+
+                let create = &mappings[1];
+                assert_eq!(create.request().body(), &Some(r#"{"id":1}"#.to_string()));
+                assert_eq!(create.response().status(), &Some(201));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn unsupported_matcher() {
+        let stub = br#"{"request": {"urlPath": "/a", "headers": {"X-Id": {"contains": "abc"}}}}"#;
+        assert!(from_slice(stub).is_err());
+    }
+}
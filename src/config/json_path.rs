@@ -0,0 +1,52 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! JSONPath request body matching configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// A JSONPath expression evaluated against a JSON request body.
+///
+/// The body matches if the expression selects at least one value.  With `equal_to`, one of the
+/// selected values must equal it, and with `matches`, one must match the regex.  Strings are
+/// compared without quotes, any other value is compared as JSON, i.e. `12345` or `true`.
+///
+/// ```toml
+/// [[request.body_json_path]]
+/// expression = "$.customer.tier"
+/// equal_to = "gold"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct JsonPath {
+    /// The JSONPath expression, i.e. `$.items[0].sku`.
+    #[get = "pub"]
+    expression: String,
+    /// The value a selected value must equal.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    equal_to: Option<String>,
+    /// The regex a selected value must match.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<String>,
+}
+
+impl JsonPath {
+    /// Create a new JSONPath expression, optionally with an expected value and/or regex.
+    pub fn new<S: Into<String>>(
+        expression: S,
+        equal_to: Option<String>,
+        matches: Option<String>,
+    ) -> Self {
+        Self {
+            expression: expression.into(),
+            equal_to,
+            matches,
+        }
+    }
+}
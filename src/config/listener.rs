@@ -0,0 +1,101 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Listener configuration
+use crate::error::Error;
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::net::SocketAddr;
+
+const DEFAULT_IP: &str = "0.0.0.0";
+
+/// A socket the server listens on.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct Listener {
+    /// The IP address to listen on.  Defaults to `0.0.0.0`.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ip: Option<String>,
+    /// The port to listen on.
+    #[get = "pub"]
+    port: u16,
+    /// Terminate TLS on this listener, using the certificate and key configured on the `Runtime`.
+    #[get = "pub"]
+    #[serde(default)]
+    tls: bool,
+}
+
+impl Listener {
+    /// Create a new listener.
+    pub fn new(ip: Option<String>, port: u16, tls: bool) -> Self {
+        Self { ip, port, tls }
+    }
+
+    /// The socket address for this listener.
+    pub fn socket_addr(&self) -> Result<SocketAddr, Error> {
+        let ip = self.ip.as_ref().map_or(DEFAULT_IP, String::as_str);
+        Ok(format!("{}:{}", ip, self.port).parse()?)
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ip = self.ip.as_ref().map_or(DEFAULT_IP, String::as_str);
+        let scheme = if self.tls { "https" } else { "http" };
+        write!(f, "{}://{}:{}", scheme, ip, self.port)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Listener;
+
+    const LISTENER_TOML: &str = r#"ip = "127.0.0.1"
+port = 8443
+tls = true
+"#;
+
+    #[test]
+    fn socket_addr() {
+        let listener = Listener::new(Some("127.0.0.1".to_string()), 8080, false);
+        match listener.socket_addr() {
+            Ok(addr) => assert_eq!(addr.to_string(), "127.0.0.1:8080"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn default_ip() {
+        let listener = Listener::new(None, 8080, false);
+        match listener.socket_addr() {
+            Ok(addr) => assert_eq!(addr.to_string(), "0.0.0.0:8080"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn bad_ip() {
+        let listener = Listener::new(Some("not an ip".to_string()), 8080, false);
+        assert!(listener.socket_addr().is_err());
+    }
+
+    #[test]
+    fn deserialize_listener_toml() {
+        match toml::from_str::<Listener>(LISTENER_TOML) {
+            Ok(listener) => {
+                assert_eq!(
+                    listener,
+                    Listener::new(Some("127.0.0.1".to_string()), 8443, true)
+                );
+                assert_eq!(listener.to_string(), "https://127.0.0.1:8443");
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
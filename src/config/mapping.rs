@@ -7,16 +7,18 @@
 // modified, or distributed except according to those terms.
 
 //! `libdeadmock` request/response mapping
-use crate::config::{Request, Response};
+use crate::config::{RateLimit, Request, Response, Rotation, WebSocket, Webhook, WeightedResponse};
+use crate::error::Error::{self, InvalidResponseConfig};
 use getset::{Getters, MutGetters};
+use rand::Rng;
 use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
 use std::cmp::{Ord, Ordering};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// `libdeadmock` mapping configuration
-#[derive(
-    Clone, Debug, Default, Deserialize, Eq, Getters, Hash, MutGetters, PartialEq, Serialize,
-)]
+#[derive(Clone, Debug, Deserialize, Eq, Getters, MutGetters, PartialEq, Serialize)]
 pub struct Mapping {
     /// The name of this mapping.
     #[get = "pub"]
@@ -29,7 +31,374 @@ pub struct Mapping {
     request: Request,
     /// The response configuration.
     #[get = "pub"]
+    #[serde(default)]
     response: Response,
+    /// Candidate responses, one of which is chosen at random (by weight) for each request.
+    /// When any are configured, `response` is not used.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    responses: Vec<WeightedResponse>,
+    /// Responses sent in order on successive matches, i.e. `201` for the first call, then `409`.
+    /// Once every response has been sent, the last one is repeated, unless a `rotation` is
+    /// configured.  When any are configured, `response` and `responses` are not used.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sequence: Vec<Response>,
+    /// What the `sequence` does once every response has been sent.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rotation: Option<Rotation>,
+    /// Is this mapping enabled?  Disabled mappings are never matched.  Defaults to `true`.
+    #[get = "pub"]
+    #[serde(default = "enabled")]
+    #[serde(skip_serializing_if = "is_enabled")]
+    enabled: bool,
+    /// The tags used to select this mapping at startup.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// The scenario this mapping belongs to.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scenario: Option<String>,
+    /// The state the scenario must be in for this mapping to match.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    required_state: Option<String>,
+    /// The state the scenario moves to when this mapping is matched.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_state: Option<String>,
+    /// Limit the number of requests this mapping responds to per window.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_limit: Option<RateLimit>,
+    /// Requests sent after a response from this mapping is served.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    webhooks: Vec<Webhook>,
+    /// Upgrade matching requests to a WebSocket and run a scripted exchange, instead of sending
+    /// a response.  Requests that aren't WebSocket upgrades get a `426 Upgrade Required`.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    websocket: Option<WebSocket>,
+    /// Free-form metadata, i.e. ticket ids, owners, or descriptions.  Never used for matching.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<Value>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            priority: 0,
+            request: Request::default(),
+            response: Response::default(),
+            responses: Vec::new(),
+            sequence: Vec::new(),
+            rotation: None,
+            enabled: true,
+            tags: Vec::new(),
+            scenario: None,
+            required_state: None,
+            new_state: None,
+            rate_limit: None,
+            webhooks: Vec::new(),
+            websocket: None,
+            metadata: None,
+        }
+    }
+}
+
+impl Mapping {
+    /// Create a new mapping builder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::{Mapping, Request, Response};
+    /// #
+    /// # fn main() {
+    /// let mapping = Mapping::builder()
+    ///     .name("Plaintext")
+    ///     .priority(5)
+    ///     .request(Request::builder().url("/plaintext").build())
+    ///     .response(Response::builder().body_file_name("plaintext.txt").build())
+    ///     .build();
+    /// assert_eq!(*mapping.priority(), 5);
+    /// # }
+    /// ```
+    pub fn builder() -> MappingBuilder {
+        MappingBuilder::default()
+    }
+
+    /// Choose the response to send, picking one of the weighted `responses` at random if any are
+    /// configured.
+    pub fn pick_response(&self) -> &Response {
+        let total = self.total_weight();
+
+        if total == 0 {
+            &self.response
+        } else {
+            self.pick(rand::thread_rng().gen_range(0, total))
+        }
+    }
+
+    /// Choose the response to send for the given match of this mapping, counting from 0.  This is
+    /// the matching response from the `sequence`, following its `rotation`, if one is
+    /// configured, and otherwise the same as
+    /// [`pick_response`](Mapping::pick_response).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::{Mapping, Response};
+    /// #
+    /// # fn main() {
+    /// let mapping = Mapping::builder()
+    ///     .sequence_response(Response::builder().status(201).build())
+    ///     .sequence_response(Response::builder().status(409).build())
+    ///     .build();
+    /// assert_eq!(mapping.nth_response(0).status(), &Some(201));
+    /// assert_eq!(mapping.nth_response(1).status(), &Some(409));
+    /// assert_eq!(mapping.nth_response(2).status(), &Some(409));
+    /// # }
+    /// ```
+    pub fn nth_response(&self, call: usize) -> &Response {
+        let response = match self.rotation {
+            Some(rotation) => self.sequence.get(rotation.index(call, self.sequence.len())),
+            None => self.sequence.get(call).or_else(|| self.sequence.last()),
+        };
+
+        match response {
+            Some(response) => response,
+            None => self.pick_response(),
+        }
+    }
+
+    /// Resolve the `${ENV_VAR}` placeholders in the urls, headers, `proxy_base_url` and
+    /// `body_file_name` of every request and response.  This runs on the parsed mapping, so a
+    /// value can't break the file format, and a placeholder anywhere else is left alone.
+    crate fn interpolate_env(&mut self) -> Result<(), Error> {
+        self.request.interpolate_env()?;
+        self.response.interpolate_env()?;
+        for response in &mut self.sequence {
+            response.interpolate_env()?;
+        }
+        for weighted in &mut self.responses {
+            weighted.interpolate_env()?;
+        }
+        if let Some(rate_limit) = &mut self.rate_limit {
+            rate_limit.interpolate_env()?;
+        }
+        Ok(())
+    }
+
+    /// Validate the parts of the mapping configuration that can't be checked by deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        if !self.responses.is_empty() && self.total_weight() == 0 {
+            return Err(InvalidResponseConfig(
+                "field `responses` must have a total weight greater than 0".to_string(),
+            ));
+        }
+
+        if !self.responses.is_empty() && !self.sequence.is_empty() {
+            return Err(InvalidResponseConfig(
+                "fields `responses` and `sequence` are mutually exclusive".to_string(),
+            ));
+        }
+
+        if self.rotation.is_some() && self.sequence.is_empty() {
+            return Err(InvalidResponseConfig(
+                "field `rotation` requires field `sequence`".to_string(),
+            ));
+        }
+
+        self.response.validate()?;
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.validate()?;
+        }
+        for response in &self.sequence {
+            response.validate()?;
+        }
+        for webhook in &self.webhooks {
+            webhook.validate()?;
+        }
+        if let Some(websocket) = &self.websocket {
+            websocket.validate()?;
+        }
+        self.responses
+            .iter()
+            .map(|weighted| weighted.response().validate())
+            .collect()
+    }
+
+    /// Every response that could be sent.
+    crate fn all_responses(&self) -> Vec<&Response> {
+        if !self.sequence.is_empty() {
+            self.sequence.iter().collect()
+        } else if self.responses.is_empty() {
+            vec![&self.response]
+        } else {
+            self.responses
+                .iter()
+                .map(WeightedResponse::response)
+                .collect()
+        }
+    }
+
+    fn total_weight(&self) -> u64 {
+        self.responses
+            .iter()
+            .map(|weighted| u64::from(*weighted.weight()))
+            .sum()
+    }
+
+    fn pick(&self, mut roll: u64) -> &Response {
+        for weighted in &self.responses {
+            let weight = u64::from(*weighted.weight());
+            if roll < weight {
+                return weighted.response();
+            }
+            roll -= weight;
+        }
+        &self.response
+    }
+}
+
+/// A builder for a [`Mapping`](crate::config::Mapping).
+#[derive(Clone, Debug, Default)]
+pub struct MappingBuilder {
+    inner: Mapping,
+}
+
+impl MappingBuilder {
+    /// The name of this mapping.
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.inner.name = name.into();
+        self
+    }
+
+    /// The priority of this mapping.
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.inner.priority = priority;
+        self
+    }
+
+    /// The request matching configuration.
+    pub fn request(mut self, request: Request) -> Self {
+        self.inner.request = request;
+        self
+    }
+
+    /// The response configuration.
+    pub fn response(mut self, response: Response) -> Self {
+        self.inner.response = response;
+        self
+    }
+
+    /// Add a candidate response with the given weight.
+    pub fn weighted_response(mut self, weight: u32, response: Response) -> Self {
+        self.inner
+            .responses
+            .push(WeightedResponse::new(weight, response));
+        self
+    }
+
+    /// Add a response to the end of the sequence sent on successive matches.
+    pub fn sequence_response(mut self, response: Response) -> Self {
+        self.inner.sequence.push(response);
+        self
+    }
+
+    /// What the response sequence does once every response has been sent.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.inner.rotation = Some(rotation);
+        self
+    }
+
+    /// Enable or disable this mapping.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.inner.enabled = enabled;
+        self
+    }
+
+    /// Add a tag to this mapping.
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.inner.tags.push(tag.into());
+        self
+    }
+
+    /// The scenario this mapping belongs to.
+    pub fn scenario<S: Into<String>>(mut self, scenario: S) -> Self {
+        self.inner.scenario = Some(scenario.into());
+        self
+    }
+
+    /// The state the scenario must be in for this mapping to match.
+    pub fn required_state<S: Into<String>>(mut self, required_state: S) -> Self {
+        self.inner.required_state = Some(required_state.into());
+        self
+    }
+
+    /// The state the scenario moves to when this mapping is matched.
+    pub fn new_state<S: Into<String>>(mut self, new_state: S) -> Self {
+        self.inner.new_state = Some(new_state.into());
+        self
+    }
+
+    /// Limit the number of requests this mapping responds to per window.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.inner.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Add a request sent after a response from this mapping is served.
+    pub fn webhook(mut self, webhook: Webhook) -> Self {
+        self.inner.webhooks.push(webhook);
+        self
+    }
+
+    /// Upgrade matching requests to a WebSocket and run a scripted exchange.
+    pub fn websocket(mut self, websocket: WebSocket) -> Self {
+        self.inner.websocket = Some(websocket);
+        self
+    }
+
+    /// Free-form metadata for this mapping.
+    pub fn metadata(mut self, metadata: Value) -> Self {
+        self.inner.metadata = Some(metadata);
+        self
+    }
+
+    /// Build the mapping.
+    pub fn build(self) -> Mapping {
+        self.inner
+    }
+}
+
+// `serde_json::Value` isn't `Hash`.  Equal mappings always have equal names, priorities and
+// requests, so hashing those is enough.
+impl Hash for Mapping {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.priority.hash(state);
+        self.request.hash(state);
+    }
 }
 
 impl Ord for Mapping {
@@ -57,6 +426,8 @@ crate mod test {
     use super::Mapping;
     use crate::config::request::test::{full_request, partial_request};
     use crate::config::response::test::{full_response, partial_response};
+    use crate::config::{Format, Response, Rotation};
+    use serde_json::json;
     use std::collections::BTreeMap;
 
     const EMPTY_MAPPING: &str = r#"{"name":"","priority":0,"request":{},"response":{}}"#;
@@ -111,6 +482,8 @@ value = "Basic abcdef123"
 key = "Content-Type"
 value = "application/json"
 "#;
+    const DISABLED_MAPPING: &str =
+        r#"{"name":"","priority":0,"request":{},"response":{},"enabled":false}"#;
     const BAD_MAPPING_JSON: &str = r#"{"priority":"abc"}"#;
 
     crate fn partial_mapping() -> Mapping {
@@ -129,6 +502,117 @@ value = "application/json"
         mapping
     }
 
+    #[test]
+    fn builder() {
+        let mapping = Mapping::builder()
+            .name("Test")
+            .priority(10)
+            .request(full_request())
+            .response(full_response())
+            .build();
+        assert_eq!(mapping, full_mapping());
+        assert!(*mapping.enabled());
+
+        let disabled = Mapping::builder().enabled(false).tag("slow").build();
+        assert!(!*disabled.enabled());
+        assert_eq!(disabled.tags(), &vec!["slow".to_string()]);
+    }
+
+    fn weighted_mapping() -> Mapping {
+        Mapping::builder()
+            .weighted_response(90, Response::builder().status(200).build())
+            .weighted_response(0, Response::builder().status(500).build())
+            .weighted_response(10, Response::builder().status(503).build())
+            .build()
+    }
+
+    #[test]
+    fn pick_weighted_response() {
+        let mapping = weighted_mapping();
+        assert_eq!(mapping.pick(0).status(), &Some(200));
+        assert_eq!(mapping.pick(89).status(), &Some(200));
+        assert_eq!(mapping.pick(90).status(), &Some(503));
+        assert_eq!(mapping.pick(99).status(), &Some(503));
+
+        for _ in 0..100 {
+            assert_ne!(mapping.pick_response().status(), &Some(500));
+        }
+    }
+
+    #[test]
+    fn pick_single_response() {
+        let mapping = full_mapping();
+        assert_eq!(mapping.pick_response(), &full_response());
+    }
+
+    #[test]
+    fn zero_total_weight() {
+        let mapping = Mapping::builder()
+            .weighted_response(0, Response::builder().status(200).build())
+            .build();
+        assert!(mapping.validate().is_err());
+        assert!(weighted_mapping().validate().is_ok());
+    }
+
+    #[test]
+    fn sequence() {
+        let toml = "name = \"Create\"\npriority = 1\n\n[request]\n\n[[sequence]]\nstatus = 201\n\n[[sequence]]\nstatus = 409\n";
+        match Format::Toml.from_slice::<Mapping>(toml.as_bytes()) {
+            Ok(mapping) => {
+                assert!(mapping.validate().is_ok());
+                assert_eq!(mapping.nth_response(0).status(), &Some(201));
+                assert_eq!(mapping.nth_response(1).status(), &Some(409));
+                assert_eq!(mapping.nth_response(5).status(), &Some(409));
+                assert_eq!(mapping.all_responses().len(), 2);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        let both = Mapping::builder()
+            .weighted_response(1, Response::builder().status(200).build())
+            .sequence_response(Response::builder().status(201).build())
+            .build();
+        assert!(both.validate().is_err());
+
+        let polling = Mapping::builder()
+            .sequence_response(Response::builder().status(202).build())
+            .sequence_response(Response::builder().status(200).build())
+            .rotation(Rotation::RoundRobin)
+            .build();
+        let statuses: Vec<Option<u16>> = (0..4)
+            .map(|call| *polling.nth_response(call).status())
+            .collect();
+        assert_eq!(statuses, vec![Some(202), Some(200), Some(202), Some(200)]);
+        assert!(Mapping::builder()
+            .rotation(Rotation::RoundRobin)
+            .build()
+            .validate()
+            .is_err());
+        assert_eq!(
+            full_mapping().nth_response(3),
+            full_mapping().pick_response()
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let toml = "name = \"Orders\"\npriority = 1\n\n[request]\n\n[response]\n\n[metadata]\nowner = \"orders-team\"\nticket = \"ORD-123\"\n";
+        match Format::Toml.from_slice::<Mapping>(toml.as_bytes()) {
+            Ok(mapping) => {
+                assert_eq!(
+                    mapping.metadata(),
+                    &Some(json!({ "owner": "orders-team", "ticket": "ORD-123" }))
+                );
+                match serde_json::to_string(&mapping) {
+                    Ok(serialized) => assert!(serialized
+                        .ends_with(r#""metadata":{"owner":"orders-team","ticket":"ORD-123"}}"#)),
+                    Err(e) => assert!(false, e.to_string()),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
     #[test]
     fn order() {
         let mut first = Mapping::default();
@@ -238,6 +722,41 @@ value = "application/json"
         }
     }
 
+    #[test]
+    fn serialize_disabled_mapping() {
+        let mut mapping = Mapping::default();
+        mapping.enabled = false;
+        if let Ok(serialized) = serde_json::to_string(&mapping) {
+            assert_eq!(serialized, DISABLED_MAPPING);
+        } else {
+            assert!(false, "Serialization not expected to fail!");
+        }
+    }
+
+    #[test]
+    fn deserialize_enabled_by_default() {
+        if let Ok(deserialized) = serde_json::from_str::<Mapping>(EMPTY_MAPPING) {
+            assert!(deserialized.enabled());
+        } else {
+            assert!(
+                false,
+                "Expected deserialization of string into Mapping to succeed!"
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_disabled_mapping() {
+        if let Ok(deserialized) = serde_json::from_str::<Mapping>(DISABLED_MAPPING) {
+            assert!(!deserialized.enabled());
+        } else {
+            assert!(
+                false,
+                "Expected deserialization of string into Mapping to succeed!"
+            );
+        }
+    }
+
     #[test]
     fn deserialize_bad_mapping() {
         assert!(
@@ -7,21 +7,39 @@
 // modified, or distributed except according to those terms.
 
 //! `libdeadmock` request/response mappings
-use crate::config::Mapping;
-use crate::error::Error::{self, MappingKeyCollision};
+use crate::config::ambiguity;
+use crate::config::fragment;
+use crate::config::import::har;
+use crate::config::validation;
+use crate::config::{Ambiguity, Format, Mapping, Runtime, TagFilter, ValidationReport};
+use crate::error::Error::{self, AmbiguousMappings, InvalidMapping, MappingKeyCollision};
 use crate::util;
 use clap::ArgMatches;
 use getset::Getters;
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{BufReader, Read};
-use std::path::PathBuf;
-use toml;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+lazy_static! {
+    static ref FIELD_RE: Regex = Regex::new(r"field `([^`]+)`").expect("invalid field regex");
+}
+
 /// A map of `Mappings`.   Each is stored by `Uuid`.
+///
+/// Mapping files may be written in any supported [`Format`](crate::config::Format), chosen by
+/// file extension.  Once a mapping is deserialized, any `${ENV_VAR}` placeholders in its urls,
+/// header values, `proxy_base_url` and `body_file_name` are replaced with the value of that
+/// environment variable; write `$${ENV_VAR}` to keep a literal `${ENV_VAR}`.  Placeholders in any
+/// other field, e.g. a response body, are left as is.  Tables may pull in shared
+/// fragments from other files with an `$include` key.  Every regex pattern is compiled as its
+/// mapping is loaded, so an invalid pattern fails the load with an error naming the mapping and
+/// field.
 #[derive(Clone, Debug, Default, Deserialize, Eq, Getters, PartialEq, Serialize)]
 pub struct Mappings {
     /// The private inner hashmap.
@@ -29,40 +47,278 @@ pub struct Mappings {
     inner: HashMap<Uuid, Mapping>,
 }
 
-impl<'a> TryFrom<&'a ArgMatches<'a>> for Mappings {
-    type Error = Error;
+impl Mappings {
+    /// Load all of the mappings found under the given directory.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let mut mappings = Self::default();
+        util::visit_dirs(path, &mut |path| mappings.add_file(path))?;
+        Ok(mappings)
+    }
 
-    fn try_from(matches: &'a ArgMatches<'_>) -> Result<Self, Error> {
+    /// Load the mappings from every file matching the given glob pattern, i.e.
+    /// `mappings/**/orders-*.json`.
+    pub fn from_glob(pattern: &str) -> Result<Self, Error> {
         let mut mappings = Self::default();
+        util::visit_glob(pattern, &mut |path| mappings.add_file(path))?;
+        Ok(mappings)
+    }
 
-        let mappings_path = if let Some(mappings_path) = matches.value_of("mappings_path") {
-            PathBuf::from(mappings_path).join("mappings")
+    /// Convert the request/response pairs captured in a browser-exported HAR file into mappings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::Mappings;
+    /// # use std::path::Path;
+    /// #
+    /// # fn main() {
+    /// match Mappings::from_har(Path::new("tests/har/session.har")) {
+    ///     Ok(mappings) => assert_eq!(mappings.inner().len(), 2),
+    ///     Err(e) => assert!(false, e.to_string()),
+    /// }
+    /// # }
+    /// ```
+    pub fn from_har(path: &Path) -> Result<Self, Error> {
+        let mut mappings = Self::default();
+        for mapping in har::from_path(path)? {
+            mappings.add(mapping)?;
+        }
+        Ok(mappings)
+    }
+
+    /// Write each mapping to its own file in the given directory, in the given format.
+    ///
+    /// Files are prefixed with their position in priority order, i.e. `000-get-orders.json`, so
+    /// a directory listing reads in the order mappings are tried.  The directory can be loaded
+    /// again with [`from_path`](Mappings::from_path).
+    pub fn export(&self, dir: &Path, format: Format) -> Result<Vec<PathBuf>, Error> {
+        let mut mappings: Vec<&Mapping> = self.inner.values().collect();
+        mappings.sort_by(|a, b| a.cmp(b).then_with(|| a.name().cmp(b.name())));
+        fs::create_dir_all(dir)?;
+
+        mappings
+            .iter()
+            .enumerate()
+            .map(|(idx, mapping)| {
+                let path = dir.join(format!(
+                    "{:03}-{}.{}",
+                    idx,
+                    file_stem(mapping.name()),
+                    format.extension()
+                ));
+                fs::write(&path, format.to_string_pretty(mapping)?)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Keep only the mappings whose tags satisfy the given filter.
+    pub fn select(mut self, filter: &TagFilter) -> Self {
+        self.inner
+            .retain(|_uuid, mapping| filter.is_match(mapping.tags()));
+        self
+    }
+
+    /// Find the enabled mappings with equal priority that could match the same request.
+    pub fn ambiguities(&self) -> Vec<Ambiguity> {
+        let mappings: Vec<&Mapping> = self
+            .inner
+            .values()
+            .filter(|mapping| *mapping.enabled())
+            .collect();
+        let mut ambiguities = Vec::new();
+
+        for (i, first) in mappings.iter().enumerate() {
+            for second in &mappings[i + 1..] {
+                if first.priority() == second.priority()
+                    && !ambiguity::exclusive_states(first, second)
+                    && ambiguity::overlaps(first.request(), second.request())
+                {
+                    ambiguities.push(Ambiguity::new(
+                        *first.priority(),
+                        first.name(),
+                        second.name(),
+                    ));
+                }
+            }
+        }
+
+        ambiguities.sort();
+        ambiguities
+    }
+
+    /// Find the ambiguous mappings.  In strict mode, any ambiguity is an error.
+    pub fn check_ambiguities(&self, strict: bool) -> Result<Vec<Ambiguity>, Error> {
+        let ambiguities = self.ambiguities();
+
+        if strict && !ambiguities.is_empty() {
+            Err(AmbiguousMappings(ambiguities))
         } else {
-            PathBuf::from("mappings")
-        };
+            Ok(ambiguities)
+        }
+    }
+
+    /// Check every mapping found under `path/mappings` without starting a server.
+    ///
+    /// Every mapping is loaded, every regex pattern is compiled, and every body file is looked
+    /// for under `path/files`.  All of the problems found are collected into the report, so
+    /// this only fails if the mappings can't be walked at all.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::Mappings;
+    /// # use std::path::Path;
+    /// #
+    /// # fn main() {
+    /// match Mappings::validate(Path::new("tests/validation")) {
+    ///     Ok(report) => assert!(!report.is_valid()),
+    ///     Err(e) => assert!(false, e.to_string()),
+    /// }
+    /// # }
+    /// ```
+    pub fn validate(path: &Path) -> Result<ValidationReport, Error> {
+        let mut report = ValidationReport::default();
+        let mut body_files = HashSet::new();
+        let files_path = path.join("files");
 
-        util::visit_dirs(&mappings_path, &mut |entry| -> Result<(), Error> {
-            let f = File::open(entry.path())?;
-            let mut reader = BufReader::new(f);
-            let mut buffer = Vec::new();
-            let _bytes_read = reader.read_to_end(&mut buffer)?;
-            let mapping: Mapping = toml::from_slice(&buffer)?;
-            if let Some(_v) = mappings.inner.insert(Uuid::new_v4(), mapping) {
-                Err(MappingKeyCollision)
-            } else {
+        if files_path.is_dir() {
+            util::visit_dirs(&files_path, &mut |path| {
+                if let Some(file_name) = path.file_name() {
+                    let _ = body_files.insert(file_name.to_string_lossy().into_owned());
+                }
                 Ok(())
+            })?;
+        }
+
+        util::visit_dirs(&path.join("mappings"), &mut |path| {
+            match load_file(path) {
+                Ok(mapping) => report.check(path, &mapping, &body_files),
+                Err(e) => report.problem(path, e.to_string()),
             }
+            Ok(())
         })?;
-        Ok(mappings)
+
+        Ok(report)
+    }
+
+    fn add_file(&mut self, path: &Path) -> Result<(), Error> {
+        let mapping = load_file(path)?;
+        validation::compile_patterns(&mapping).map_err(|e| invalid_mapping(path, &e))?;
+        self.add(mapping)
+    }
+
+    fn add(&mut self, mapping: Mapping) -> Result<(), Error> {
+        if let Some(_v) = self.inner.insert(Uuid::new_v4(), mapping) {
+            Err(MappingKeyCollision)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A file name safe version of a mapping name, i.e. `Get Orders (v2)` becomes `get-orders-v2`.
+crate fn file_stem(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+/// Load a single mapping file.
+fn load_file(path: &Path) -> Result<Mapping, Error> {
+    let f = File::open(path)?;
+    let mut reader = BufReader::new(f);
+    let mut buffer = Vec::new();
+    let _bytes_read = reader.read_to_end(&mut buffer)?;
+    let contents = String::from_utf8_lossy(&buffer);
+    let format = Format::from_path(path);
+    let mut mapping: Mapping = if contents.contains(fragment::INCLUDE) {
+        let value = format
+            .from_slice(contents.as_bytes())
+            .map_err(|e| invalid_mapping(path, &e))?;
+        serde_json::from_value(fragment::resolve(value, path)?)
+            .map_err(|e| invalid_mapping(path, &e.into()))?
+    } else {
+        format
+            .from_slice(contents.as_bytes())
+            .map_err(|e| invalid_mapping(path, &e))?
+    };
+    mapping.interpolate_env()?;
+    mapping.validate().map_err(|e| invalid_mapping(path, &e))?;
+    Ok(mapping)
+}
+
+impl<'a> TryFrom<&'a ArgMatches<'a>> for Mappings {
+    type Error = Error;
+
+    fn try_from(matches: &'a ArgMatches<'_>) -> Result<Self, Error> {
+        let mappings = if let Some(mappings_glob) = matches.value_of("mappings_glob") {
+            Self::from_glob(mappings_glob)?
+        } else if let Some(mappings_path) = matches.value_of("mappings_path") {
+            Self::from_path(&PathBuf::from(mappings_path).join("mappings"))?
+        } else {
+            Self::from_path(&PathBuf::from("mappings"))?
+        };
+
+        if let Some(tags) = matches.value_of("tags") {
+            Ok(mappings.select(&TagFilter::new(tags)))
+        } else {
+            Ok(mappings)
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Runtime> for Mappings {
+    type Error = Error;
+
+    fn try_from(runtime: &'a Runtime) -> Result<Self, Error> {
+        let mappings = if let Some(mappings_glob) = runtime.mappings_glob() {
+            Self::from_glob(mappings_glob)?
+        } else if let Some(path) = runtime.path() {
+            Self::from_path(&PathBuf::from(path).join("mappings"))?
+        } else {
+            Self::from_path(&PathBuf::from("mappings"))?
+        };
+
+        if let Some(tags) = runtime.tags() {
+            Ok(mappings.select(&TagFilter::new(tags)))
+        } else {
+            Ok(mappings)
+        }
+    }
+}
+
+/// Convert a deserialization error into an `InvalidMapping` error describing where the mapping
+/// file is broken.
+fn invalid_mapping(path: &Path, e: &Error) -> Error {
+    let message = e.to_string();
+    let (line, column) = e
+        .location()
+        .map_or((None, None), |(line, column)| (Some(line), Some(column)));
+    let field = FIELD_RE.captures(&message).map(|caps| caps[1].to_string());
+
+    InvalidMapping {
+        path: path.to_path_buf(),
+        line,
+        column,
+        field,
+        message,
     }
 }
 
 #[cfg(test)]
 crate mod test {
-    use super::Mappings;
+    use super::{file_stem, invalid_mapping, Mappings};
+    use crate::config::{Format, Mapping, TagFilter};
     use crate::error::Error;
     use clap::{App, Arg};
     use std::convert::TryFrom;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use uuid::Uuid;
 
     crate fn test_mappings() -> Result<Mappings, Error> {
         let args = vec!["test", "-m", "tests"];
@@ -82,4 +338,158 @@ crate mod test {
 
         Ok(Mappings::try_from(&matches)?)
     }
+
+    #[test]
+    fn from_glob() {
+        match Mappings::from_glob("tests/mappings/**/pattern-match-header*.toml") {
+            Ok(mappings) => assert_eq!(mappings.inner().len(), 2),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn select_tags() {
+        match test_mappings() {
+            Ok(mappings) => {
+                let total = mappings.inner().len();
+                let tagged = mappings.clone().select(&TagFilter::new("tagged"));
+                assert_eq!(tagged.inner().len(), 1);
+                let untagged = mappings.select(&TagFilter::new("!tagged"));
+                assert_eq!(untagged.inner().len(), total - 1);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn stem() {
+        assert_eq!(file_stem("Get Orders (v2)"), "get-orders-v2");
+        assert_eq!(file_stem("GET /api/orders"), "get-api-orders");
+    }
+
+    fn export_round_trip(format: Format) {
+        let dir = env::temp_dir().join(format!("deadmock-export-{}", Uuid::new_v4()));
+        match test_mappings() {
+            Ok(mappings) => {
+                match mappings.export(&dir, format) {
+                    Ok(paths) => {
+                        assert_eq!(paths.len(), mappings.inner().len());
+                        assert!(paths[0]
+                            .to_string_lossy()
+                            .ends_with(&format!(".{}", format.extension())));
+                    }
+                    Err(e) => assert!(false, e.to_string()),
+                }
+                match Mappings::from_path(&dir) {
+                    Ok(exported) => {
+                        let mut expected: Vec<&Mapping> = mappings.inner().values().collect();
+                        let mut actual: Vec<&Mapping> = exported.inner().values().collect();
+                        expected.sort_by_key(|mapping| mapping.name());
+                        actual.sort_by_key(|mapping| mapping.name());
+                        assert_eq!(actual, expected);
+                    }
+                    Err(e) => assert!(false, e.to_string()),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn interpolate_fields() {
+        let dir = env::temp_dir().join(format!("deadmock-interpolate-{}", Uuid::new_v4()));
+        let mapping = "name = \"Env\"\npriority = 1\n\n[request]\nurl = \"${DEADMOCK_TEST_URL}\"\n\n[response]\nbody = \"${DEADMOCK_TEST_BODY} \\\"quoted\\\"\"\nheaders = [{ key = \"X-Env\", value = \"$${DEADMOCK_TEST_URL}\" }]\n";
+        env::set_var("DEADMOCK_TEST_URL", "/env\"");
+        env::remove_var("DEADMOCK_TEST_BODY");
+        let _ = fs::create_dir_all(&dir);
+        let _ = fs::write(dir.join("env.toml"), mapping);
+        match Mappings::from_path(&dir) {
+            Ok(mappings) => match mappings.inner().values().next() {
+                Some(mapping) => {
+                    assert_eq!(mapping.request().url(), &Some("/env\"".to_string()));
+                    assert_eq!(
+                        mapping.response().body(),
+                        &Some("${DEADMOCK_TEST_BODY} \"quoted\"".to_string())
+                    );
+                    assert_eq!(
+                        mapping.response().headers()[0].value(),
+                        "${DEADMOCK_TEST_URL}"
+                    );
+                }
+                None => assert!(false, "Expected a mapping!"),
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn export_json() {
+        export_round_trip(Format::Json);
+    }
+
+    #[test]
+    fn export_yaml() {
+        export_round_trip(Format::Yaml);
+    }
+
+    #[test]
+    fn invalid_mapping_location() {
+        let bad = "{\n  \"name\": \"Bad\",\n  \"priority\": \"abc\"\n}";
+        match Format::Json.from_slice::<Mapping>(bad.as_bytes()) {
+            Ok(_) => assert!(false, "Not expected to succeed!"),
+            Err(e) => match invalid_mapping(&PathBuf::from("bad.json"), &e) {
+                Error::InvalidMapping {
+                    path, line, column, ..
+                } => {
+                    assert_eq!(path, PathBuf::from("bad.json"));
+                    assert_eq!(line, Some(3));
+                    assert!(column.is_some());
+                }
+                _ => assert!(false, "Expected an invalid mapping error!"),
+            },
+        }
+    }
+
+    #[test]
+    fn invalid_response_body() {
+        let bad = "name = \"Bad\"\npriority = 1\n\n[request]\nurl = \"/bad\"\n\n[response]\nbody = \"{}\"\nbody_file_name = \"bad.json\"\n";
+        match Format::Toml.from_slice::<Mapping>(bad.as_bytes()) {
+            Ok(mapping) => match mapping.response().validate() {
+                Ok(_) => assert!(false, "Not expected to validate!"),
+                Err(e) => match invalid_mapping(&PathBuf::from("bad.toml"), &e) {
+                    Error::InvalidMapping { field, .. } => {
+                        assert_eq!(field, Some("body".to_string()))
+                    }
+                    _ => assert!(false, "Expected an invalid mapping error!"),
+                },
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        match Mappings::from_glob("tests/validation/mappings/bad-regex.toml") {
+            Ok(_) => assert!(false, "Not expected to load!"),
+            Err(Error::InvalidMapping { field, message, .. }) => {
+                assert_eq!(field, Some("url_pattern".to_string()));
+                assert!(message.contains("mapping 'Bad Regex'"));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn invalid_mapping_field() {
+        let bad = "priority = 1\n";
+        match Format::Toml.from_slice::<Mapping>(bad.as_bytes()) {
+            Ok(_) => assert!(false, "Not expected to succeed!"),
+            Err(e) => match invalid_mapping(&PathBuf::from("bad.toml"), &e) {
+                Error::InvalidMapping { field, .. } => assert_eq!(field, Some("name".to_string())),
+                _ => assert!(false, "Expected an invalid mapping error!"),
+            },
+        }
+    }
 }
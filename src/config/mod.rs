@@ -7,20 +7,94 @@
 // modified, or distributed except according to those terms.
 
 //! Configuration for the server.
+crate mod ambiguity;
+crate mod basic_auth;
+crate mod binary;
+crate mod body_size;
+crate mod charset;
+crate mod client_cert;
+crate mod compression;
+crate mod cors;
+crate mod default_response;
+crate mod dribble;
+crate mod equal_to_json;
+crate mod fault;
 crate mod files;
+crate mod format;
+crate mod fragment;
 crate mod header;
+crate mod header_rewrite;
+pub mod import;
+crate mod json_path;
+crate mod listener;
 crate mod mapping;
 crate mod mappings;
+crate mod multipart_part;
+crate mod multipart_response;
+crate mod protobuf_body;
 crate mod proxy;
+crate mod proxy_cache;
+crate mod rate_limit;
+crate mod redirect_chain;
 crate mod request;
 crate mod response;
+crate mod rotation;
 crate mod runtime;
+crate mod server_cert;
+crate mod server_sent_event;
+crate mod soap;
+crate mod strict;
+crate mod tags;
+crate mod url_glob;
+crate mod validation;
+crate mod variant;
+crate mod virtual_host;
+crate mod webhook;
+crate mod websocket;
+crate mod weighted_response;
+crate mod xpath;
 
+pub use self::ambiguity::Ambiguity;
+pub use self::basic_auth::BasicAuth;
+pub use self::binary::Binary;
+pub use self::body_size::BodySize;
+pub use self::charset::Charset;
+pub use self::client_cert::ClientCert;
+pub use self::compression::Compression;
+pub use self::cors::{Cors, CorsBuilder};
+pub use self::default_response::DefaultResponse;
+pub use self::dribble::Dribble;
+pub use self::equal_to_json::EqualToJson;
+pub use self::fault::Fault;
 pub use self::files::Files;
+pub use self::format::Format;
 pub use self::header::{Header, HeaderPattern};
-pub use self::mapping::Mapping;
+pub use self::header_rewrite::HeaderRewrite;
+pub use self::json_path::JsonPath;
+pub use self::listener::Listener;
+pub use self::mapping::{Mapping, MappingBuilder};
 pub use self::mappings::Mappings;
-pub use self::proxy::Proxy;
-pub use self::request::Request;
-pub use self::response::Response;
-pub use self::runtime::Runtime;
+pub use self::multipart_part::MultipartPart;
+pub use self::multipart_response::{MultipartKind, MultipartResponse, ResponsePart};
+pub use self::protobuf_body::ProtobufBody;
+pub use self::proxy::{Proxy, ProxyBuilder};
+pub use self::proxy_cache::ProxyCache;
+pub use self::rate_limit::RateLimit;
+pub use self::redirect_chain::RedirectChain;
+pub use self::request::{Request, RequestBuilder};
+pub use self::response::{Response, ResponseBuilder};
+pub use self::rotation::Rotation;
+pub use self::runtime::{Runtime, RuntimeBuilder};
+pub use self::server_cert::ServerCert;
+pub use self::server_sent_event::ServerSentEvent;
+pub use self::soap::Soap;
+pub use self::strict::Strict;
+pub use self::tags::TagFilter;
+pub use self::url_glob::UrlGlob;
+pub use self::validation::{Problem, ValidationReport};
+pub use self::variant::Variant;
+pub use self::virtual_host::VirtualHost;
+pub use self::webhook::{Webhook, WebhookBuilder};
+pub use self::websocket::{WebSocket, WebSocketStep};
+pub use self::weighted_response::WeightedResponse;
+pub use self::xpath::XPath;
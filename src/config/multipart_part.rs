@@ -0,0 +1,60 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `multipart/form-data` request body matching configuration
+use getset::{Getters, Setters};
+use serde_derive::{Deserialize, Serialize};
+
+/// A part a `multipart/form-data` request body must contain.
+///
+/// A part matches on its name and any of the other criteria that are given.  The content type is
+/// compared without parameters, i.e. `text/plain; charset=utf-8` matches `text/plain`.
+///
+/// ```toml
+/// [[request.multipart]]
+/// name = "upload"
+/// filename = "report.csv"
+/// content_type = "text/csv"
+/// body_pattern = "^id,name"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize, Setters)]
+pub struct MultipartPart {
+    /// The part name, from the `Content-Disposition` header.
+    #[get = "pub"]
+    name: String,
+    /// The file name, from the `Content-Disposition` header.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    /// The part content type.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    /// The part body to match (exact).
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    /// The part body to match (regex).
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_pattern: Option<String>,
+}
+
+impl MultipartPart {
+    /// Create a new part matched on name only.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+}
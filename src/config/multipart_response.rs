@@ -0,0 +1,205 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Multipart response body configuration
+use crate::config::Header;
+use crate::error::Error::{self, InvalidResponseConfig};
+use getset::{Getters, Setters};
+use serde_derive::{Deserialize, Serialize};
+
+/// The kind of multipart body to send.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MultipartKind {
+    /// `multipart/mixed`
+    Mixed,
+    /// `multipart/form-data`.  Every part needs a name.
+    FormData,
+}
+
+impl Default for MultipartKind {
+    fn default() -> Self {
+        MultipartKind::Mixed
+    }
+}
+
+impl MultipartKind {
+    /// The media type of the body, without the boundary.
+    pub fn media_type(self) -> &'static str {
+        match self {
+            MultipartKind::Mixed => "multipart/mixed",
+            MultipartKind::FormData => "multipart/form-data",
+        }
+    }
+}
+
+/// A response body assembled from several parts.  The boundary is generated for each response,
+/// and the `Content-Type` header is set to match.
+///
+/// ```toml
+/// [response.multipart]
+/// kind = "form-data"
+///
+/// [[response.multipart.parts]]
+/// name = "metadata"
+/// content_type = "application/json"
+/// body = "{\"id\": 1}"
+///
+/// [[response.multipart.parts]]
+/// name = "report"
+/// filename = "report.csv"
+/// body_file_name = "report.csv"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct MultipartResponse {
+    /// The kind of multipart body, `mixed` by default.
+    #[serde(default)]
+    #[get = "pub"]
+    kind: MultipartKind,
+    /// The parts, in the order they are sent.
+    #[get = "pub"]
+    parts: Vec<ResponsePart>,
+}
+
+impl MultipartResponse {
+    /// A multipart body of the given kind, with the given parts.
+    pub fn new(kind: MultipartKind, parts: Vec<ResponsePart>) -> Self {
+        Self { kind, parts }
+    }
+
+    /// Validate the parts of the multipart configuration that can't be checked by
+    /// deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        if self.parts.is_empty() {
+            return Err(InvalidResponseConfig(
+                "field `multipart.parts` cannot be empty".to_string(),
+            ));
+        }
+
+        for part in &self.parts {
+            if part.body.is_some() == part.body_file_name.is_some() {
+                return Err(InvalidResponseConfig(
+                    "each multipart part needs exactly one of `body` or `body_file_name`"
+                        .to_string(),
+                ));
+            } else if self.kind == MultipartKind::FormData && part.name.is_none() {
+                return Err(InvalidResponseConfig(
+                    "each `form-data` multipart part needs a `name`".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One part of a multipart response body.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize, Setters)]
+pub struct ResponsePart {
+    /// The part name, sent in the `Content-Disposition` header.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// The file name, sent in the `Content-Disposition` header.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    /// The part content type.  For a file part without one, it is inferred from the file
+    /// extension.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    /// Additional headers to send on the part.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    headers: Vec<Header>,
+    /// The part body, inline.  Mutually exclusive with `body_file_name`.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    /// The file to use as the part body.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_file_name: Option<String>,
+}
+
+impl ResponsePart {
+    /// A part with an inline body.
+    pub fn body<S: Into<String>>(body: S) -> Self {
+        Self {
+            body: Some(body.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A part with a file as the body.
+    pub fn file<S: Into<String>>(body_file_name: S) -> Self {
+        Self {
+            body_file_name: Some(body_file_name.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Add a header to send on the part.
+    pub fn header(mut self, header: Header) -> Self {
+        self.headers.push(header);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MultipartKind, MultipartResponse, ResponsePart};
+
+    #[test]
+    fn deserialize() {
+        let toml = r#"kind = "form-data"
+
+[[parts]]
+name = "report"
+body_file_name = "report.csv"
+"#;
+        match toml::from_str::<MultipartResponse>(toml) {
+            Ok(multipart) => {
+                let mut part = ResponsePart::file("report.csv");
+                let _ = part.set_name(Some("report".to_string()));
+                assert_eq!(
+                    multipart,
+                    MultipartResponse::new(MultipartKind::FormData, vec![part])
+                );
+                assert!(multipart.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert!(MultipartResponse::default().validate().is_err());
+        assert!(
+            MultipartResponse::new(MultipartKind::Mixed, vec![ResponsePart::body("a")])
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            MultipartResponse::new(MultipartKind::FormData, vec![ResponsePart::body("a")])
+                .validate()
+                .is_err()
+        );
+        assert!(
+            MultipartResponse::new(MultipartKind::Mixed, vec![ResponsePart::default()])
+                .validate()
+                .is_err()
+        );
+    }
+}
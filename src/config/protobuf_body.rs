@@ -0,0 +1,71 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Protobuf request body matching configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Field values to match in a protobuf encoded request body.
+///
+/// The body is decoded as `message`, using the types in `descriptor_set`, a
+/// `FileDescriptorSet` as written by `protoc --descriptor_set_out`.  The path is relative to the
+/// working directory of the server.  Each entry in `fields` is a dotted path to a field, through
+/// nested messages, and the value one of the values of that field must equal.  Enum values are
+/// given by name, and `bytes` values as base64.
+///
+/// Fields holding their default value are not encoded by proto3, so they can't be matched.
+///
+/// ```toml
+/// [request.body_protobuf]
+/// descriptor_set = "protos/orders.pb"
+/// message = "orders.CreateOrder"
+///
+/// [request.body_protobuf.fields]
+/// "customer.id" = "42"
+/// status = "PAID"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct ProtobufBody {
+    /// The path to the descriptor set file.
+    #[get = "pub"]
+    descriptor_set: String,
+    /// The fully qualified name of the message type, i.e. `orders.CreateOrder`.
+    #[get = "pub"]
+    message: String,
+    /// The field values to match, by field path.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[get = "pub"]
+    fields: BTreeMap<String, String>,
+}
+
+impl ProtobufBody {
+    /// Create a new protobuf body configuration for the given descriptor set and message type.
+    pub fn new<S, T>(descriptor_set: S, message: T) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        Self {
+            descriptor_set: descriptor_set.into(),
+            message: message.into(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Add a field value to match.
+    pub fn field<S, T>(mut self, path: S, value: T) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let _ = self.fields.insert(path.into(), value.into());
+        self
+    }
+}
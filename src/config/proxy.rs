@@ -7,10 +7,13 @@
 // modified, or distributed except according to those terms.
 
 //! `libdeadmock` proxy configuration
+use crate::config::Runtime;
 use crate::error::Error::{self, InvalidProxyConfig};
 use clap::ArgMatches;
 use getset::{Getters, Setters};
+use serde_derive::{Deserialize, Serialize};
 use std::convert::TryFrom;
+use std::env;
 
 /// The proxy configuration for deadmock
 ///
@@ -80,24 +83,40 @@ use std::convert::TryFrom;
 ///     let proxy_config = config::Proxy::new(true, Some("http://a.proxyurl.com".to_string()));
 /// # }
 /// ```
-#[derive(Clone, Debug, Default, Getters, Hash, Eq, PartialEq, Setters)]
+#[derive(Clone, Debug, Default, Deserialize, Getters, Hash, Eq, PartialEq, Serialize, Setters)]
 pub struct Proxy {
     /// Turn the proxy on.  If this is true, `proxy_url` is required.
     #[get = "pub"]
     #[set = "pub"]
+    #[serde(default)]
     use_proxy: bool,
     /// The proxy url.
     #[get = "pub"]
     #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
     proxy_url: Option<String>,
+    /// The proxy url used for `https` requests.  Defaults to `proxy_url`.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    https_proxy_url: Option<String>,
     /// Username for proxy authentication.
     #[get = "pub"]
     #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
     proxy_username: Option<String>,
     /// Password for proxy authentication.
     #[get = "pub"]
     #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
     proxy_password: Option<String>,
+    /// Hosts that bypass the proxy.  An entry matches the host itself and any of its subdomains,
+    /// and `*` matches every host.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    no_proxy: Vec<String>,
 }
 
 impl Proxy {
@@ -119,10 +138,147 @@ impl Proxy {
         Self {
             use_proxy,
             proxy_url,
+            https_proxy_url: None,
             proxy_username: None,
             proxy_password: None,
+            no_proxy: vec![],
         }
     }
+
+    /// Create a proxy configuration from the standard `HTTP_PROXY`, `HTTPS_PROXY`, and
+    /// `NO_PROXY` environment variables (or their lowercase forms).
+    ///
+    /// The proxy is enabled if either `HTTP_PROXY` or `HTTPS_PROXY` is set.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let lookup = |name: &str| {
+            var(name)
+                .or_else(|| var(&name.to_lowercase()))
+                .filter(|value| !value.trim().is_empty())
+        };
+        let proxy_url = lookup("HTTP_PROXY");
+        let https_proxy_url = lookup("HTTPS_PROXY");
+        let no_proxy = lookup("NO_PROXY")
+            .map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|host| !host.is_empty())
+                    .map(|host| host.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            use_proxy: proxy_url.is_some() || https_proxy_url.is_some(),
+            proxy_url,
+            https_proxy_url,
+            proxy_username: None,
+            proxy_password: None,
+            no_proxy,
+        }
+    }
+
+    /// The proxy url to use for a request to the given url.
+    pub fn proxy_url_for(&self, url: &str) -> Option<&String> {
+        if url.starts_with("https") {
+            self.https_proxy_url
+                .as_ref()
+                .or_else(|| self.proxy_url.as_ref())
+        } else {
+            self.proxy_url
+                .as_ref()
+                .or_else(|| self.https_proxy_url.as_ref())
+        }
+    }
+
+    /// Should requests to the given host bypass the proxy?
+    ///
+    /// # Example
+    /// ```
+    /// # use libdeadmock::config;
+    /// #
+    /// # fn main() {
+    /// let proxy_config = config::Proxy::builder()
+    ///     .proxy_url("http://a.proxyurl.com")
+    ///     .no_proxy("internal.com")
+    ///     .build();
+    /// assert!(proxy_config.bypass("api.internal.com"));
+    /// assert!(!proxy_config.bypass("external.com"));
+    /// # }
+    /// ```
+    pub fn bypass(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim().trim_start_matches('.').to_lowercase();
+            entry == "*" || host == entry || host.ends_with(&format!(".{}", entry))
+        })
+    }
+}
+
+impl Proxy {
+    /// Create a new proxy configuration builder.
+    ///
+    /// # Example
+    /// ```
+    /// # use libdeadmock::config;
+    /// #
+    /// # fn main() {
+    /// let proxy_config = config::Proxy::builder()
+    ///     .proxy_url("http://a.proxyurl.com")
+    ///     .proxy_username("user")
+    ///     .proxy_password("pass")
+    ///     .build();
+    /// assert!(proxy_config.use_proxy());
+    /// # }
+    /// ```
+    pub fn builder() -> ProxyBuilder {
+        ProxyBuilder::default()
+    }
+}
+
+/// A builder for [`Proxy`](crate::config::Proxy) configuration.
+#[derive(Clone, Debug, Default)]
+pub struct ProxyBuilder {
+    inner: Proxy,
+}
+
+impl ProxyBuilder {
+    /// The proxy url.  Setting this turns the proxy on.
+    pub fn proxy_url<S: Into<String>>(mut self, proxy_url: S) -> Self {
+        self.inner.use_proxy = true;
+        self.inner.proxy_url = Some(proxy_url.into());
+        self
+    }
+
+    /// Username for proxy authentication.
+    pub fn proxy_username<S: Into<String>>(mut self, proxy_username: S) -> Self {
+        self.inner.proxy_username = Some(proxy_username.into());
+        self
+    }
+
+    /// Password for proxy authentication.
+    pub fn proxy_password<S: Into<String>>(mut self, proxy_password: S) -> Self {
+        self.inner.proxy_password = Some(proxy_password.into());
+        self
+    }
+
+    /// Add a host that bypasses the proxy.
+    pub fn no_proxy<S: Into<String>>(mut self, host: S) -> Self {
+        self.inner.no_proxy.push(host.into());
+        self
+    }
+
+    /// Build the proxy configuration.
+    pub fn build(self) -> Proxy {
+        self.inner
+    }
 }
 
 impl<'a> TryFrom<&'a ArgMatches<'a>> for Proxy {
@@ -131,29 +287,54 @@ impl<'a> TryFrom<&'a ArgMatches<'a>> for Proxy {
     fn try_from(matches: &'a ArgMatches<'a>) -> Result<Self, Error> {
         let use_proxy = matches.is_present("proxy");
         let proxy_url = matches.value_of("proxy-url").map(|s| s.to_string());
+
+        if !use_proxy && proxy_url.is_none() && matches.is_present("proxy-from-env") {
+            return Ok(Self::from_env());
+        }
+
         let proxy_username = matches.value_of("proxy-username").map(|s| s.to_string());
         let proxy_password = matches.value_of("proxy-password").map(|s| s.to_string());
+        let no_proxy = matches
+            .values_of("no-proxy")
+            .map(|values| values.map(|s| s.to_string()).collect())
+            .unwrap_or_default();
 
         if use_proxy && proxy_url.is_some() {
             Ok(Self {
                 proxy_url,
+                https_proxy_url: None,
                 use_proxy,
                 proxy_username,
                 proxy_password,
+                no_proxy,
             })
         } else if use_proxy && proxy_url.is_none() {
             Err(InvalidProxyConfig)
         } else {
             Ok(Self {
                 proxy_url,
+                https_proxy_url: None,
                 use_proxy,
                 proxy_username,
                 proxy_password,
+                no_proxy,
             })
         }
     }
 }
 
+impl<'a> TryFrom<&'a Runtime> for Proxy {
+    type Error = Error;
+
+    fn try_from(runtime: &'a Runtime) -> Result<Self, Error> {
+        if runtime.proxy_from_env().unwrap_or(false) {
+            Ok(Self::from_env())
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Proxy;
@@ -193,6 +374,15 @@ mod test {
                     .value_name("PROXY_PASS")
                     .help("Your proxy password, if applicable"),
             )
+            .arg(
+                Arg::with_name("no-proxy")
+                    .long("no-proxy")
+                    .takes_value(true)
+                    .multiple(true)
+                    .use_delimiter(true)
+                    .value_name("HOSTS")
+                    .help("Hosts that bypass the proxy"),
+            )
     }
 
     fn test_cli_no_requires() -> App<'static, 'static> {
@@ -221,6 +411,22 @@ mod test {
         assert!(!proxy_config.use_proxy());
     }
 
+    #[test]
+    fn builder() {
+        let proxy_config = Proxy::builder()
+            .proxy_url("http://a.proxy.com")
+            .proxy_username("test")
+            .proxy_password("test")
+            .build();
+        assert!(proxy_config.use_proxy());
+        assert_eq!(
+            proxy_config.proxy_url(),
+            &Some("http://a.proxy.com".to_string())
+        );
+        assert_eq!(proxy_config.proxy_username(), &Some("test".to_string()));
+        assert_eq!(proxy_config.proxy_password(), &Some("test".to_string()));
+    }
+
     #[test]
     fn from_all_args() {
         let arg_vec = vec![
@@ -266,6 +472,70 @@ mod test {
         }
     }
 
+    #[test]
+    fn no_proxy_args() {
+        let arg_vec = vec![
+            "test-cli",
+            "-p",
+            "--proxy-url",
+            "http://a.proxy.com",
+            "--no-proxy",
+            "localhost,.internal.com",
+        ];
+        let matches = test_cli().get_matches_from(arg_vec);
+        match Proxy::try_from(&matches) {
+            Ok(proxy_config) => {
+                assert_eq!(
+                    proxy_config.no_proxy(),
+                    &vec!["localhost".to_string(), ".internal.com".to_string()]
+                );
+                assert!(proxy_config.bypass("localhost"));
+                assert!(proxy_config.bypass("api.internal.com"));
+                assert!(proxy_config.bypass("INTERNAL.com"));
+                assert!(!proxy_config.bypass("notinternal.com"));
+                assert!(!proxy_config.bypass("a.proxy.com"));
+            }
+            Err(_) => assert!(false, "Not expected to error!"),
+        }
+    }
+
+    #[test]
+    fn no_proxy_wildcard() {
+        let proxy_config = Proxy::builder()
+            .proxy_url("http://a.proxy.com")
+            .no_proxy("*")
+            .build();
+        assert!(proxy_config.bypass("anything.com"));
+        assert!(!Proxy::default().bypass("anything.com"));
+    }
+
+    #[test]
+    fn from_vars() {
+        let proxy_config = Proxy::from_vars(|name| match name {
+            "HTTP_PROXY" => Some("http://a.proxy.com".to_string()),
+            "https_proxy" => Some("http://secure.proxy.com".to_string()),
+            "NO_PROXY" => Some("localhost, .internal.com".to_string()),
+            _ => None,
+        });
+        assert!(proxy_config.use_proxy());
+        assert_eq!(
+            proxy_config.proxy_url_for("http://upstream.com"),
+            Some(&"http://a.proxy.com".to_string())
+        );
+        assert_eq!(
+            proxy_config.proxy_url_for("https://upstream.com"),
+            Some(&"http://secure.proxy.com".to_string())
+        );
+        assert!(proxy_config.bypass("api.internal.com"));
+    }
+
+    #[test]
+    fn from_empty_vars() {
+        let proxy_config = Proxy::from_vars(|_| None);
+        assert!(!proxy_config.use_proxy());
+        assert_eq!(proxy_config, Proxy::default());
+    }
+
     #[test]
     fn proxy_requires_proxy_url() {
         let arg_vec = vec!["test-cli", "-p", "--proxy-username", "password"];
@@ -0,0 +1,48 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Proxied response caching configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// Cache the responses from `proxy_base_url`, so repeated identical requests are answered without
+/// calling the upstream again.
+///
+/// Responses are cached by request method and URL, along with the values of any `key_headers`.
+/// Requests with an `Authorization`, `Cookie`, `If-None-Match` or `If-Modified-Since` header
+/// always go to the upstream, and only cacheable statuses, i.e. 200, 301 or 404, are cached.
+///
+/// ```toml
+/// [response]
+/// proxy_base_url = "https://api.example.com"
+///
+/// [response.proxy_cache]
+/// ttl_ms = 60000
+/// key_headers = ["Accept"]
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct ProxyCache {
+    /// How long, in milliseconds, a cached response is reused.
+    #[get = "pub"]
+    ttl_ms: u64,
+    /// The request headers that are part of the cache key, i.e. `Accept`.
+    #[get = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    key_headers: Vec<String>,
+}
+
+impl ProxyCache {
+    /// Cache responses for `ttl_ms` milliseconds, keyed by method, URL and the given headers.
+    pub fn new(ttl_ms: u64, key_headers: Vec<String>) -> Self {
+        Self {
+            ttl_ms,
+            key_headers,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Mapping rate limit configuration
+use crate::config::Response;
+use crate::error::Error::{self, InvalidResponseConfig};
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// Limit a mapping to a number of requests per window.
+///
+/// Once the limit is reached, `response` is sent until the window ends.  Without a `response`, a
+/// `429 Too Many Requests` with a `Retry-After` header is sent.
+///
+/// ```toml
+/// [rate_limit]
+/// requests = 10
+/// window_ms = 1000
+///
+/// [rate_limit.response]
+/// status = 429
+/// body = "{\"message\": \"slow down\"}"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct RateLimit {
+    /// The number of requests allowed per window.
+    #[get = "pub"]
+    requests: u32,
+    /// The length of the window, in milliseconds.
+    #[get = "pub"]
+    window_ms: u64,
+    /// The response sent once the limit is reached.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<Response>,
+}
+
+impl RateLimit {
+    /// Allow `requests` requests every `window_ms` milliseconds, sending the given response (or
+    /// a `429`) once the limit is reached.
+    pub fn new(requests: u32, window_ms: u64, response: Option<Response>) -> Self {
+        Self {
+            requests,
+            window_ms,
+            response,
+        }
+    }
+
+    /// Resolve the `${ENV_VAR}` placeholders in the response sent once the limit is reached.
+    crate fn interpolate_env(&mut self) -> Result<(), Error> {
+        match &mut self.response {
+            Some(response) => response.interpolate_env(),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate the parts of the rate limit configuration that can't be checked by
+    /// deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        if self.window_ms == 0 {
+            Err(InvalidResponseConfig(
+                "field `rate_limit.window_ms` must be greater than 0".to_string(),
+            ))
+        } else if let Some(response) = &self.response {
+            response.validate()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RateLimit;
+    use crate::config::Response;
+
+    #[test]
+    fn validate() {
+        assert!(RateLimit::new(10, 1000, None).validate().is_ok());
+        assert!(RateLimit::new(10, 0, None).validate().is_err());
+        assert!(
+            RateLimit::new(10, 1000, Some(Response::builder().status(429).build()))
+                .validate()
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Redirect chain configuration
+use crate::error::Error::{self, InvalidResponseConfig};
+use crate::util;
+use getset::Getters;
+use http::Request;
+use serde_derive::{Deserialize, Serialize};
+
+/// The query parameter that carries the hop, unless the chain configures one.
+const DEFAULT_PARAM: &str = "redirect_hop";
+
+/// Answer `hops` requests with redirects before sending the response, i.e. `302 → 302 → 200`,
+/// all from one mapping.
+///
+/// The hop a request is at is carried in a query parameter, `redirect_hop` by default, so the
+/// mapping's request matching must also match the redirected urls.  Each redirect points at the
+/// request url with the parameter set to the next hop, unless a `location` is configured.  The
+/// `location` is rendered as a [template](crate::template), with `{{redirect.hop}}`,
+/// `{{redirect.next}}` and `{{redirect.param}}` available alongside the request data.
+///
+/// With `endless = true` the chain never ends, the last hop redirects back to the first, to
+/// exercise a client's loop detection.
+///
+/// ```toml
+/// [response]
+/// status = 200
+/// body = "Signed in"
+///
+/// [response.redirect_chain]
+/// hops = 2
+/// status = 307
+/// location = "/sso/step{{redirect.next}}?redirect_hop={{redirect.next}}"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct RedirectChain {
+    /// The number of redirects before the response is sent.
+    #[get = "pub"]
+    hops: u32,
+    /// The status of each redirect.  Defaults to `302`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    status: Option<u16>,
+    /// The `Location` of each redirect, as a template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    location: Option<String>,
+    /// The query parameter that carries the hop.  Defaults to `redirect_hop`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    param: Option<String>,
+    /// Redirect forever, rather than sending the response after the last hop.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    endless: bool,
+}
+
+impl RedirectChain {
+    /// Redirect `hops` times, with `302 Found`, before sending the response.
+    pub fn new(hops: u32) -> Self {
+        Self {
+            hops,
+            ..Self::default()
+        }
+    }
+
+    /// The status of each redirect.
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// The `Location` of each redirect, as a template.
+    pub fn with_location<S: Into<String>>(mut self, location: S) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// The query parameter that carries the hop.
+    pub fn with_param<S: Into<String>>(mut self, param: S) -> Self {
+        self.param = Some(param.into());
+        self
+    }
+
+    /// Redirect forever, rather than sending the response after the last hop.
+    pub fn with_endless(mut self, endless: bool) -> Self {
+        self.endless = endless;
+        self
+    }
+
+    /// The query parameter that carries the hop.
+    crate fn param_name(&self) -> &str {
+        self.param.as_ref().map_or(DEFAULT_PARAM, String::as_str)
+    }
+
+    /// The hop the request is at, starting from 0 for a request without the query parameter.
+    crate fn hop(&self, request: &Request<Vec<u8>>) -> u32 {
+        request
+            .uri()
+            .query()
+            .map(util::parse_urlencoded)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|(name, _)| name == self.param_name())
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// The hop to redirect a request at `hop` to, or `None` if the response should be sent.
+    crate fn next(&self, hop: u32) -> Option<u32> {
+        if self.endless {
+            Some((hop + 1) % self.hops.max(1))
+        } else if hop < self.hops {
+            Some(hop + 1)
+        } else {
+            None
+        }
+    }
+
+    /// The request url with the query parameter set to `next`.
+    crate fn default_location(&self, request: &Request<Vec<u8>>, next: u32) -> String {
+        let param = self.param_name();
+        let mut query: Vec<String> = request.uri().query().map_or_else(Vec::new, |query| {
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some(param))
+                .map(str::to_string)
+                .collect()
+        });
+        query.push(format!("{}={}", param, next));
+        format!("{}?{}", request.uri().path(), query.join("&"))
+    }
+
+    /// Validate the parts of the redirect chain that can't be checked by deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        match self.status {
+            Some(status) if status < 300 || status > 399 => Err(InvalidResponseConfig(format!(
+                "field `redirect_chain.status` must be a redirect, not {}",
+                status
+            ))),
+            _ if self.hops == 0 => Err(InvalidResponseConfig(
+                "field `redirect_chain.hops` must be greater than 0".to_string(),
+            )),
+            _ if self.param.as_ref().map_or(false, String::is_empty) => Err(InvalidResponseConfig(
+                "field `redirect_chain.param` cannot be empty".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RedirectChain;
+    use http::Request;
+
+    fn request(uri: &str) -> Request<Vec<u8>> {
+        Request::builder()
+            .uri(uri)
+            .body(vec![])
+            .expect("Unable to build request!")
+    }
+
+    #[test]
+    fn hops() {
+        let chain = RedirectChain::new(2);
+        assert_eq!(chain.hop(&request("/login")), 0);
+        assert_eq!(chain.hop(&request("/login?redirect_hop=2")), 2);
+        assert_eq!(chain.next(0), Some(1));
+        assert_eq!(chain.next(1), Some(2));
+        assert_eq!(chain.next(2), None);
+
+        let chain = RedirectChain::new(2).with_endless(true);
+        assert_eq!(chain.next(1), Some(0));
+    }
+
+    #[test]
+    fn default_location() {
+        let chain = RedirectChain::new(2).with_param("hop");
+        assert_eq!(
+            chain.default_location(&request("/login?user=a&hop=1"), 2),
+            "/login?user=a&hop=2"
+        );
+        assert_eq!(
+            chain.default_location(&request("/login"), 1),
+            "/login?hop=1"
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert!(RedirectChain::new(1).with_status(308).validate().is_ok());
+        assert!(RedirectChain::new(0).validate().is_err());
+        assert!(RedirectChain::new(1).with_status(200).validate().is_err());
+        assert!(RedirectChain::new(1).with_param("").validate().is_err());
+    }
+}
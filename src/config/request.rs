@@ -7,29 +7,92 @@
 // modified, or distributed except according to those terms.
 
 //! HTTP request matching configuration
-use crate::config::{Header, HeaderPattern};
+use crate::config::{
+    BasicAuth, BodySize, ClientCert, EqualToJson, Header, HeaderPattern, JsonPath, MultipartPart,
+    ProtobufBody, Soap, Strict, UrlGlob, XPath,
+};
+use crate::error::Error;
+use crate::util;
 use getset::Getters;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The method that matches every HTTP request method.
+const ANY_METHOD: &str = "ANY";
 
 /// HTTP request matching configuration.
 #[derive(Clone, Debug, Default, Deserialize, Getters, Hash, Eq, PartialEq, Serialize)]
 pub struct Request {
-    /// The HTTP request method to match.
+    /// The HTTP request method to match.  `ANY` matches every method.
     #[get = "pub"]
     #[serde(skip_serializing_if = "Option::is_none")]
     method: Option<String>,
+    /// The HTTP request methods to match.  The request method must be one of these, or `method`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    methods: Vec<String>,
     /// The HTTP request method pattern to match
     #[get = "pub"]
     #[serde(skip_serializing_if = "Option::is_none")]
     method_pattern: Option<String>,
+    /// The HTTP request method the request must not have.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method_not: Option<String>,
     /// The url to exact match.
     #[get = "pub"]
     #[serde(skip_serializing_if = "Option::is_none")]
     url: Option<String>,
+    /// Ignore ASCII case when matching `url` exactly.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    url_case_insensitive: bool,
+    /// Normalize the request path before the url is matched: percent-decode it, collapse
+    /// repeated slashes and resolve `.` and `..` segments.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    normalize_url: bool,
     /// The url to pattern match (regex).
     #[get = "pub"]
     #[serde(skip_serializing_if = "Option::is_none")]
     url_pattern: Option<String>,
+    /// The url to glob match, i.e. `/api/*/items/**`.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url_glob: Option<UrlGlob>,
+    /// The url pattern (regex) the request must not match.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url_not_pattern: Option<String>,
+    /// The host to exact match, from the `Host` header or an absolute-form request uri.
+    ///
+    /// The port is ignored unless one is given here.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+    /// The host to pattern match (regex), without the port.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_pattern: Option<String>,
+    /// The scheme, `http` or `https`, the request must be received over.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheme: Option<String>,
+    /// The address, or CIDR block, i.e. `10.1.0.0/16`, of the client that sent the request.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote_addr: Option<String>,
+    /// The certificate the client presented during the TLS handshake.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_cert: Option<ClientCert>,
+    /// The SNI server name the client asked for during the TLS handshake (case-insensitive).
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sni: Option<String>,
     /// The HTTP headers to match (exact).
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -48,6 +111,484 @@ pub struct Request {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[get = "pub"]
     header_pattern: Option<HeaderPattern>,
+    /// The names of HTTP headers the request must not carry.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    absent_headers: Vec<String>,
+    /// The credentials the request must carry in an `Authorization: Basic` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    basic_auth: Option<BasicAuth>,
+    /// The query parameters to match (exact).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[get = "pub"]
+    query_params: BTreeMap<String, String>,
+    /// The query parameters to match, where each value is a regex.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[get = "pub"]
+    query_params_pattern: BTreeMap<String, String>,
+    /// The `application/x-www-form-urlencoded` body fields to match (exact).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[get = "pub"]
+    form_params: BTreeMap<String, String>,
+    /// The `application/x-www-form-urlencoded` body fields to match, where each value is a regex.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[get = "pub"]
+    form_params_pattern: BTreeMap<String, String>,
+    /// The HTTP request body to match (exact).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body: Option<String>,
+    /// The HTTP request body to match, as a regex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_pattern: Option<String>,
+    /// Substrings that must all appear in the HTTP request body.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    body_contains: Vec<String>,
+    /// The hex encoded SHA-256 hash of the HTTP request body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_sha256: Option<String>,
+    /// The hex encoded MD5 hash of the HTTP request body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_md5: Option<String>,
+    /// The bounds on the size of the request body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_size: Option<BodySize>,
+    /// JSONPath expressions to evaluate against a JSON request body.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    body_json_path: Vec<JsonPath>,
+    /// The JSON document the request body must be structurally equal to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    equal_to_json: Option<EqualToJson>,
+    /// XPath expressions to evaluate against an XML request body.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    xpath: Vec<XPath>,
+    /// The namespaces used by the XPath expressions, by prefix.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[get = "pub"]
+    xml_namespaces: BTreeMap<String, String>,
+    /// The SOAP action, operation and body XPath expressions to match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    soap: Option<Soap>,
+    /// The parts a `multipart/form-data` request body must contain.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    multipart: Vec<MultipartPart>,
+    /// The field values to match in a protobuf request body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_protobuf: Option<ProtobufBody>,
+    /// Nested request configurations that must all match.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    all_of: Vec<Request>,
+    /// Nested request configurations, at least one of which must match.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    any_of: Vec<Request>,
+    /// A nested request configuration that must not match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    not: Option<Box<Request>>,
+    /// The name of a matcher registered with
+    /// [`register_matcher`](crate::matcher::register_matcher) that must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    custom_matcher: Option<String>,
+    /// A script that must return `true`.  See [`ScriptMatch`](crate::matcher::ScriptMatch).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    script: Option<String>,
+    /// Fail the match if the request has something this configuration doesn't match on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    strict: Option<Strict>,
+}
+
+impl Request {
+    /// Create a new request configuration builder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::{Header, Request};
+    /// #
+    /// # fn main() {
+    /// let request = Request::builder()
+    ///     .method("GET")
+    ///     .url("/plaintext")
+    ///     .header(Header::new("X-Exact-Match", "header"))
+    ///     .build();
+    /// assert_eq!(request.url(), &Some("/plaintext".to_string()));
+    /// # }
+    /// ```
+    pub fn builder() -> RequestBuilder {
+        RequestBuilder::default()
+    }
+
+    /// Resolve the `${ENV_VAR}` placeholders in the url, url pattern and header values.
+    crate fn interpolate_env(&mut self) -> Result<(), Error> {
+        for url in self.url.iter_mut().chain(self.url_pattern.iter_mut()) {
+            *url = util::interpolate_env(url)?;
+        }
+        for header in self.headers.iter_mut().chain(self.header.iter_mut()) {
+            header.interpolate_env()?;
+        }
+        Ok(())
+    }
+
+    /// The methods a request may have, from `method` and `methods`, or `None` if every method is
+    /// allowed, i.e. neither is set or one of them is `ANY`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::Request;
+    /// #
+    /// # fn main() {
+    /// let request = Request::builder().methods("GET").methods("HEAD").build();
+    /// assert_eq!(request.allowed_methods(), Some(vec!["GET", "HEAD"]));
+    ///
+    /// let request = Request::builder().method("ANY").build();
+    /// assert_eq!(request.allowed_methods(), None);
+    /// # }
+    /// ```
+    pub fn allowed_methods(&self) -> Option<Vec<&str>> {
+        let methods: Vec<&str> = self
+            .method
+            .iter()
+            .chain(&self.methods)
+            .map(String::as_str)
+            .collect();
+
+        if methods.is_empty()
+            || methods
+                .iter()
+                .any(|method| method.eq_ignore_ascii_case(ANY_METHOD))
+        {
+            None
+        } else {
+            Some(methods)
+        }
+    }
+}
+
+/// A builder for [`Request`](crate::config::Request) configuration.
+#[derive(Clone, Debug, Default)]
+pub struct RequestBuilder {
+    inner: Request,
+}
+
+impl RequestBuilder {
+    /// The HTTP request method to match.
+    pub fn method<S: Into<String>>(mut self, method: S) -> Self {
+        self.inner.method = Some(method.into());
+        self
+    }
+
+    /// Add an HTTP request method to match.
+    pub fn methods<S: Into<String>>(mut self, method: S) -> Self {
+        self.inner.methods.push(method.into());
+        self
+    }
+
+    /// The HTTP request method pattern to match.
+    pub fn method_pattern<S: Into<String>>(mut self, method_pattern: S) -> Self {
+        self.inner.method_pattern = Some(method_pattern.into());
+        self
+    }
+
+    /// The HTTP request method the request must not have.
+    pub fn method_not<S: Into<String>>(mut self, method_not: S) -> Self {
+        self.inner.method_not = Some(method_not.into());
+        self
+    }
+
+    /// The url to exact match.
+    pub fn url<S: Into<String>>(mut self, url: S) -> Self {
+        self.inner.url = Some(url.into());
+        self
+    }
+
+    /// Ignore ASCII case when matching the url exactly.
+    pub fn url_case_insensitive(mut self, url_case_insensitive: bool) -> Self {
+        self.inner.url_case_insensitive = url_case_insensitive;
+        self
+    }
+
+    /// Normalize the request path before the url is matched.
+    pub fn normalize_url(mut self, normalize_url: bool) -> Self {
+        self.inner.normalize_url = normalize_url;
+        self
+    }
+
+    /// The url to pattern match (regex).
+    pub fn url_pattern<S: Into<String>>(mut self, url_pattern: S) -> Self {
+        self.inner.url_pattern = Some(url_pattern.into());
+        self
+    }
+
+    /// The url to glob match.
+    pub fn url_glob(mut self, url_glob: UrlGlob) -> Self {
+        self.inner.url_glob = Some(url_glob);
+        self
+    }
+
+    /// The url pattern (regex) the request must not match.
+    pub fn url_not_pattern<S: Into<String>>(mut self, url_not_pattern: S) -> Self {
+        self.inner.url_not_pattern = Some(url_not_pattern.into());
+        self
+    }
+
+    /// The host to exact match.
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.inner.host = Some(host.into());
+        self
+    }
+
+    /// The host to pattern match (regex).
+    pub fn host_pattern<S: Into<String>>(mut self, host_pattern: S) -> Self {
+        self.inner.host_pattern = Some(host_pattern.into());
+        self
+    }
+
+    /// The scheme, `http` or `https`, the request must be received over.
+    pub fn scheme<S: Into<String>>(mut self, scheme: S) -> Self {
+        self.inner.scheme = Some(scheme.into());
+        self
+    }
+
+    /// The address, or CIDR block, of the client that sent the request.
+    pub fn remote_addr<S: Into<String>>(mut self, remote_addr: S) -> Self {
+        self.inner.remote_addr = Some(remote_addr.into());
+        self
+    }
+
+    /// The certificate the client presented during the TLS handshake.
+    pub fn client_cert(mut self, client_cert: ClientCert) -> Self {
+        self.inner.client_cert = Some(client_cert);
+        self
+    }
+
+    /// The SNI server name the client asked for during the TLS handshake.
+    pub fn sni<S: Into<String>>(mut self, sni: S) -> Self {
+        self.inner.sni = Some(sni.into());
+        self
+    }
+
+    /// Add an HTTP header to match (exact).
+    pub fn headers(mut self, header: Header) -> Self {
+        self.inner.headers.push(header);
+        self
+    }
+
+    /// Add an HTTP header to match (pattern).
+    pub fn headers_pattern(mut self, header_pattern: HeaderPattern) -> Self {
+        self.inner.headers_pattern.push(header_pattern);
+        self
+    }
+
+    /// The HTTP header to match (exact).
+    pub fn header(mut self, header: Header) -> Self {
+        self.inner.header = Some(header);
+        self
+    }
+
+    /// The HTTP header to match (regex).
+    pub fn header_pattern(mut self, header_pattern: HeaderPattern) -> Self {
+        self.inner.header_pattern = Some(header_pattern);
+        self
+    }
+
+    /// Add the name of an HTTP header the request must not carry.
+    pub fn absent_header<S: Into<String>>(mut self, name: S) -> Self {
+        self.inner.absent_headers.push(name.into());
+        self
+    }
+
+    /// The credentials the request must carry in an `Authorization: Basic` header.
+    pub fn basic_auth(mut self, basic_auth: BasicAuth) -> Self {
+        self.inner.basic_auth = Some(basic_auth);
+        self
+    }
+
+    /// Add a query parameter to match (exact).
+    pub fn query_param<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        let _ = self.inner.query_params.insert(name.into(), value.into());
+        self
+    }
+
+    /// Add a query parameter to match, where the value is a regex.
+    pub fn query_param_pattern<K: Into<String>, V: Into<String>>(
+        mut self,
+        name: K,
+        value_pattern: V,
+    ) -> Self {
+        let _ = self
+            .inner
+            .query_params_pattern
+            .insert(name.into(), value_pattern.into());
+        self
+    }
+
+    /// Add a form body field to match (exact).
+    pub fn form_param<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        let _ = self.inner.form_params.insert(name.into(), value.into());
+        self
+    }
+
+    /// Add a form body field to match, where the value is a regex.
+    pub fn form_param_pattern<K: Into<String>, V: Into<String>>(
+        mut self,
+        name: K,
+        value_pattern: V,
+    ) -> Self {
+        let _ = self
+            .inner
+            .form_params_pattern
+            .insert(name.into(), value_pattern.into());
+        self
+    }
+
+    /// The HTTP request body to match (exact).
+    pub fn body<S: Into<String>>(mut self, body: S) -> Self {
+        self.inner.body = Some(body.into());
+        self
+    }
+
+    /// The HTTP request body to match, as a regex.
+    pub fn body_pattern<S: Into<String>>(mut self, body_pattern: S) -> Self {
+        self.inner.body_pattern = Some(body_pattern.into());
+        self
+    }
+
+    /// Add a substring that must appear in the HTTP request body.
+    pub fn body_contains<S: Into<String>>(mut self, substring: S) -> Self {
+        self.inner.body_contains.push(substring.into());
+        self
+    }
+
+    /// The hex encoded SHA-256 hash of the HTTP request body.
+    pub fn body_sha256<S: Into<String>>(mut self, body_sha256: S) -> Self {
+        self.inner.body_sha256 = Some(body_sha256.into());
+        self
+    }
+
+    /// The hex encoded MD5 hash of the HTTP request body.
+    pub fn body_md5<S: Into<String>>(mut self, body_md5: S) -> Self {
+        self.inner.body_md5 = Some(body_md5.into());
+        self
+    }
+
+    /// The bounds on the size of the request body.
+    pub fn body_size(mut self, body_size: BodySize) -> Self {
+        self.inner.body_size = Some(body_size);
+        self
+    }
+
+    /// Add a JSONPath expression to evaluate against a JSON request body.
+    pub fn body_json_path(mut self, json_path: JsonPath) -> Self {
+        self.inner.body_json_path.push(json_path);
+        self
+    }
+
+    /// The JSON document the request body must be structurally equal to.
+    pub fn equal_to_json(mut self, equal_to_json: EqualToJson) -> Self {
+        self.inner.equal_to_json = Some(equal_to_json);
+        self
+    }
+
+    /// Add an XPath expression to evaluate against an XML request body.
+    pub fn xpath(mut self, xpath: XPath) -> Self {
+        self.inner.xpath.push(xpath);
+        self
+    }
+
+    /// Declare a namespace prefix used by the XPath expressions.
+    pub fn xml_namespace<K: Into<String>, V: Into<String>>(mut self, prefix: K, uri: V) -> Self {
+        let _ = self.inner.xml_namespaces.insert(prefix.into(), uri.into());
+        self
+    }
+
+    /// The SOAP action, operation and body XPath expressions to match.
+    pub fn soap(mut self, soap: Soap) -> Self {
+        self.inner.soap = Some(soap);
+        self
+    }
+
+    /// Add a part a `multipart/form-data` request body must contain.
+    pub fn multipart(mut self, part: MultipartPart) -> Self {
+        self.inner.multipart.push(part);
+        self
+    }
+
+    /// The field values to match in a protobuf request body.
+    pub fn body_protobuf(mut self, body_protobuf: ProtobufBody) -> Self {
+        self.inner.body_protobuf = Some(body_protobuf);
+        self
+    }
+
+    /// Add a nested request configuration that must match.
+    pub fn all_of(mut self, request: Request) -> Self {
+        self.inner.all_of.push(request);
+        self
+    }
+
+    /// Add a nested request configuration, at least one of which must match.
+    pub fn any_of(mut self, request: Request) -> Self {
+        self.inner.any_of.push(request);
+        self
+    }
+
+    /// A nested request configuration that must not match.
+    pub fn not(mut self, request: Request) -> Self {
+        self.inner.not = Some(Box::new(request));
+        self
+    }
+
+    /// The name of a registered matcher that must match.
+    pub fn custom_matcher<S: Into<String>>(mut self, name: S) -> Self {
+        self.inner.custom_matcher = Some(name.into());
+        self
+    }
+
+    /// A script that must return `true`.
+    pub fn script<S: Into<String>>(mut self, script: S) -> Self {
+        self.inner.script = Some(script.into());
+        self
+    }
+
+    /// Fail the match if the request has something this configuration doesn't match on.
+    pub fn strict(mut self, strict: Strict) -> Self {
+        self.inner.strict = Some(strict);
+        self
+    }
+
+    /// Build the request configuration.
+    pub fn build(self) -> Request {
+        self.inner
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +654,48 @@ right = "^application/.*"
         request
     }
 
+    #[test]
+    fn builder() {
+        let request = Request::builder()
+            .method("GET")
+            .method_pattern("P.*")
+            .url("http://a.url.com")
+            .url_pattern(".*jasonozias.*")
+            .headers(content_type_header())
+            .headers_pattern(accept_star_pattern())
+            .headers_pattern(content_type_star_pattern())
+            .header(content_type_header())
+            .header_pattern(content_type_header_pattern())
+            .build();
+        assert_eq!(request, full_request());
+    }
+
+    #[test]
+    fn deserialize_body() {
+        match toml::from_str::<Request>(r#"body = '{"id": 12345}'"#) {
+            Ok(request) => assert_eq!(request, Request::builder().body(r#"{"id": 12345}"#).build()),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn deserialize_query_params() {
+        let query_toml = r#"[query_params]
+page = "2"
+
+[query_params_pattern]
+q = "^rust.*"
+"#;
+        let expected = Request::builder()
+            .query_param("page", "2")
+            .query_param_pattern("q", "^rust.*")
+            .build();
+        match toml::from_str::<Request>(query_toml) {
+            Ok(request) => assert_eq!(request, expected),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
     #[test]
     fn serialize_empty_reqeust() {
         if let Ok(req_str) = serde_json::to_string(&Request::default()) {
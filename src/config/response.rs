@@ -7,8 +7,14 @@
 // modified, or distributed except according to those terms.
 
 //! `libdeadmock` response templating configuration
-use crate::config::Header;
+use crate::config::{
+    Binary, Charset, Compression, Dribble, Fault, Header, HeaderRewrite, MultipartResponse, Proxy,
+    ProxyCache, RedirectChain, ServerSentEvent, Variant,
+};
+use crate::error::Error::{self, InvalidResponseConfig};
+use crate::util;
 use getset::Getters;
+use http::Uri;
 use serde_derive::{Deserialize, Serialize};
 
 /// `libdeadmock` response configuration
@@ -23,10 +29,29 @@ pub struct Response {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[get = "pub"]
     headers: Vec<Header>,
+    /// The http response body, inline.  Mutually exclusive with `body_file_name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body: Option<String>,
+    /// The http response body, as base64 encoded binary data.  Mutually exclusive with `body` and
+    /// `body_file_name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_base64: Option<Binary>,
     /// The file to use as the http response body.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[get = "pub"]
     body_file_name: Option<String>,
+    /// A url to download the http response body from.  The body is downloaded once, when the
+    /// server starts, and cached on disk under the files path, so later starts don't download it
+    /// again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_url: Option<String>,
+    /// A multipart body assembled from several parts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    multipart: Option<MultipartResponse>,
     /// The base url of the proxy you wish to generate the response from.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[get = "pub"]
@@ -36,12 +61,510 @@ pub struct Response {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[get = "pub"]
     additional_proxy_request_headers: Vec<Header>,
+    /// Forward the headers of the incoming request to `proxy_base_url`, less the hop-by-hop
+    /// headers, `Host` and `Content-Length`.  Off by default, in which case only the
+    /// `additional_proxy_request_headers` are sent.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    forward_request_headers: bool,
+    /// Proxy settings for requests to `proxy_base_url` from this mapping.  These override the
+    /// global proxy configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    proxy: Option<Proxy>,
+    /// Rules that remove or rewrite the headers sent to `proxy_base_url`, i.e. to strip
+    /// `Authorization`.  The rules are applied after the `additional_proxy_request_headers`,
+    /// and the headers of the incoming request if `forward_request_headers` is set, are added.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    proxy_request_rewrites: Vec<HeaderRewrite>,
+    /// Rules that remove or rewrite the headers of the upstream response before it is sent back,
+    /// i.e. to point `Location` or `Set-Cookie` at the mock's own host.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    proxy_response_rewrites: Vec<HeaderRewrite>,
+    /// Cache the responses from `proxy_base_url` for a while, rather than calling the upstream
+    /// for every request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    proxy_cache: Option<ProxyCache>,
+    /// A fixed delay, in milliseconds, to wait before sending the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    delay_ms: Option<u64>,
+    /// Render the response body and headers as [templates](crate::template) for each request.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    templated: bool,
+    /// Send the incoming request back, as JSON, instead of a body.  Mutually exclusive with
+    /// `body`, `body_base64` and `body_file_name`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    echo: bool,
+    /// Send the response body with `Transfer-Encoding: chunked`, rather than a `Content-Length`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    chunked: bool,
+    /// The size, in bytes, of each chunk of a chunked response body.  Defaults to 8192.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    chunk_size: Option<usize>,
+    /// Headers to send after the last chunk of a chunked response body, i.e. `grpc-status`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    trailers: Vec<Header>,
+    /// Stream the `body_file_name` file from disk as it is sent, rather than reading it into
+    /// memory.  Streamed files can't be templated.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    stream: bool,
+    /// Compress the response body, and set the `Content-Encoding` header to match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    compression: Option<Compression>,
+    /// Transcode the response body to this character set, and set the `charset` parameter of the
+    /// `Content-Type` header to match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    charset: Option<Charset>,
+    /// Close the connection part way through sending the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    fault: Option<Fault>,
+    /// Send the body slowly, a piece at a time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    dribble: Option<Dribble>,
+    /// Send these events as a `text/event-stream`, instead of a body.  Mutually exclusive with
+    /// `body`, `body_base64`, `body_file_name` and `echo`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    events: Vec<ServerSentEvent>,
+    /// Redirect a number of times before sending this response, i.e. `302 → 302 → 200`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    redirect_chain: Option<RedirectChain>,
+    /// The names of transformers registered with
+    /// [`register_transformer`](crate::server::register_transformer) to apply to the response,
+    /// in order.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    transformers: Vec<String>,
+    /// Send an `ETag` computed from the body, and answer requests whose `If-None-Match` matches
+    /// it with `304 Not Modified`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    #[get = "pub"]
+    etag: bool,
+    /// Bodies in different media types, one of which is chosen by the request's `Accept` header.
+    /// The body configured on the response itself is sent if none of them are acceptable.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    variants: Vec<Variant>,
+}
+
+impl Response {
+    /// Create a new response configuration builder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::{Header, Response};
+    /// #
+    /// # fn main() {
+    /// let response = Response::builder()
+    ///     .status(200)
+    ///     .headers(Header::new("Content-Type", "application/json"))
+    ///     .body_file_name("test.json")
+    ///     .build();
+    /// assert_eq!(response.status(), &Some(200));
+    /// # }
+    /// ```
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::default()
+    }
+
+    /// Resolve the `${ENV_VAR}` placeholders in the header values, `body_file_name`,
+    /// `proxy_base_url` and `additional_proxy_request_headers`.
+    crate fn interpolate_env(&mut self) -> Result<(), Error> {
+        for value in self
+            .body_file_name
+            .iter_mut()
+            .chain(self.proxy_base_url.iter_mut())
+        {
+            *value = util::interpolate_env(value)?;
+        }
+        for header in self
+            .headers
+            .iter_mut()
+            .chain(self.additional_proxy_request_headers.iter_mut())
+        {
+            header.interpolate_env()?;
+        }
+        Ok(())
+    }
+
+    /// Validate the parts of the response configuration that can't be checked by deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        let bodies = [
+            ("body", self.body.is_some()),
+            ("body_base64", self.body_base64.is_some()),
+            ("body_file_name", self.body_file_name.is_some()),
+            ("body_url", self.body_url.is_some()),
+            ("multipart", self.multipart.is_some()),
+            ("echo", self.echo),
+            ("events", !self.events.is_empty()),
+        ];
+        let configured: Vec<&str> = bodies
+            .iter()
+            .filter(|(_, is_some)| *is_some)
+            .map(|(field, _)| *field)
+            .collect();
+
+        if configured.len() > 1 {
+            Err(InvalidResponseConfig(format!(
+                "field `{}` cannot be used with field `{}`",
+                configured[0], configured[1]
+            )))
+        } else if self.body_url.as_ref().map_or(false, |url| {
+            url.parse::<Uri>()
+                .ok()
+                .and_then(|uri| uri.scheme_part().map(|scheme| scheme.as_str().to_string()))
+                .map_or(true, |scheme| scheme != "http" && scheme != "https")
+        }) {
+            Err(InvalidResponseConfig(
+                "field `body_url` must be an http or https url".to_string(),
+            ))
+        } else if self.chunk_size == Some(0) {
+            Err(InvalidResponseConfig(
+                "field `chunk_size` must be greater than 0".to_string(),
+            ))
+        } else if !self.trailers.is_empty() && !self.chunked {
+            Err(InvalidResponseConfig(
+                "field `trailers` requires field `chunked`".to_string(),
+            ))
+        } else if self.stream && self.body_file_name.is_none() {
+            Err(InvalidResponseConfig(
+                "field `stream` requires field `body_file_name`".to_string(),
+            ))
+        } else if self.stream && self.templated {
+            Err(InvalidResponseConfig(
+                "field `stream` cannot be used with field `templated`".to_string(),
+            ))
+        } else if self.stream && self.compression.is_some() {
+            Err(InvalidResponseConfig(
+                "field `stream` cannot be used with field `compression`".to_string(),
+            ))
+        } else if self.stream && self.fault.is_some() {
+            Err(InvalidResponseConfig(
+                "field `stream` cannot be used with field `fault`".to_string(),
+            ))
+        } else if self.charset.is_some()
+            && (self.stream || self.body_base64.is_some() || self.proxy_base_url.is_some())
+        {
+            Err(InvalidResponseConfig(
+                "field `charset` cannot be used with field `stream`, `body_base64` or `proxy_base_url`"
+                    .to_string(),
+            ))
+        } else if self.dribble.is_some() && (self.stream || self.fault.is_some()) {
+            Err(InvalidResponseConfig(
+                "field `dribble` cannot be used with field `stream` or `fault`".to_string(),
+            ))
+        } else if self.dribble.map_or(false, |dribble| *dribble.chunks() == 0) {
+            Err(InvalidResponseConfig(
+                "field `dribble.chunks` must be greater than 0".to_string(),
+            ))
+        } else if !self.events.is_empty()
+            && (self.stream
+                || self.compression.is_some()
+                || self.fault.is_some()
+                || self.dribble.is_some())
+        {
+            Err(InvalidResponseConfig(
+                "field `events` cannot be used with field `stream`, `compression`, `fault` or `dribble`"
+                    .to_string(),
+            ))
+        } else if self.etag
+            && (self.stream || !self.events.is_empty() || self.proxy_base_url.is_some())
+        {
+            Err(InvalidResponseConfig(
+                "field `etag` cannot be used with field `stream`, `events` or `proxy_base_url`"
+                    .to_string(),
+            ))
+        } else if !self.variants.is_empty()
+            && (self.stream
+                || self.echo
+                || !self.events.is_empty()
+                || self.proxy_base_url.is_some())
+        {
+            Err(InvalidResponseConfig(
+                "field `variants` cannot be used with field `stream`, `echo`, `events` or `proxy_base_url`"
+                    .to_string(),
+            ))
+        } else if !self.variants.is_empty()
+            && self
+                .headers
+                .iter()
+                .any(|header| header.key().eq_ignore_ascii_case("Content-Type"))
+        {
+            Err(InvalidResponseConfig(
+                "field `variants` sets the `Content-Type` header itself".to_string(),
+            ))
+        } else if self.multipart.is_some()
+            && self
+                .headers
+                .iter()
+                .any(|header| header.key().eq_ignore_ascii_case("Content-Type"))
+        {
+            Err(InvalidResponseConfig(
+                "field `multipart` sets the `Content-Type` header itself".to_string(),
+            ))
+        } else if let Some(Err(e)) = self.multipart.as_ref().map(MultipartResponse::validate) {
+            Err(e)
+        } else if let Some(Err(e)) = self
+            .variants
+            .iter()
+            .map(Variant::validate)
+            .find(Result::is_err)
+        {
+            Err(e)
+        } else if self.proxy_cache.is_some() && self.proxy_base_url.is_none() {
+            Err(InvalidResponseConfig(
+                "field `proxy_cache` requires field `proxy_base_url`".to_string(),
+            ))
+        } else if self
+            .proxy_cache
+            .as_ref()
+            .map_or(false, |proxy_cache| *proxy_cache.ttl_ms() == 0)
+        {
+            Err(InvalidResponseConfig(
+                "field `proxy_cache.ttl_ms` must be greater than 0".to_string(),
+            ))
+        } else if let Some(Err(e)) = self.redirect_chain.as_ref().map(RedirectChain::validate) {
+            Err(e)
+        } else {
+            self.proxy_request_rewrites
+                .iter()
+                .chain(&self.proxy_response_rewrites)
+                .map(HeaderRewrite::validate)
+                .collect()
+        }
+    }
+}
+
+/// A builder for [`Response`](crate::config::Response) configuration.
+#[derive(Clone, Debug, Default)]
+pub struct ResponseBuilder {
+    inner: Response,
+}
+
+impl ResponseBuilder {
+    /// The http status code to send on the response.
+    pub fn status(mut self, status: u16) -> Self {
+        self.inner.status = Some(status);
+        self
+    }
+
+    /// Add an http header to send on the response.
+    pub fn headers(mut self, header: Header) -> Self {
+        self.inner.headers.push(header);
+        self
+    }
+
+    /// The http response body, inline.
+    pub fn body<S: Into<String>>(mut self, body: S) -> Self {
+        self.inner.body = Some(body.into());
+        self
+    }
+
+    /// The http response body, as binary data.
+    pub fn body_base64(mut self, body: Vec<u8>) -> Self {
+        self.inner.body_base64 = Some(Binary::new(body));
+        self
+    }
+
+    /// The file to use as the http response body.
+    pub fn body_file_name<S: Into<String>>(mut self, body_file_name: S) -> Self {
+        self.inner.body_file_name = Some(body_file_name.into());
+        self
+    }
+
+    /// A url to download the http response body from when the server starts.
+    pub fn body_url<S: Into<String>>(mut self, body_url: S) -> Self {
+        self.inner.body_url = Some(body_url.into());
+        self
+    }
+
+    /// Send a multipart body assembled from several parts.
+    pub fn multipart(mut self, multipart: MultipartResponse) -> Self {
+        self.inner.multipart = Some(multipart);
+        self
+    }
+
+    /// The base url of the proxy you wish to generate the response from.
+    pub fn proxy_base_url<S: Into<String>>(mut self, proxy_base_url: S) -> Self {
+        self.inner.proxy_base_url = Some(proxy_base_url.into());
+        self
+    }
+
+    /// Add an additional header to send along with the request to the proxy.
+    pub fn additional_proxy_request_headers(mut self, header: Header) -> Self {
+        self.inner.additional_proxy_request_headers.push(header);
+        self
+    }
+
+    /// Forward the headers of the incoming request to the proxy.
+    pub fn forward_request_headers(mut self, forward_request_headers: bool) -> Self {
+        self.inner.forward_request_headers = forward_request_headers;
+        self
+    }
+
+    /// Proxy settings that override the global proxy configuration for this mapping.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.inner.proxy = Some(proxy);
+        self
+    }
+
+    /// Add a rule that rewrites the headers sent to the proxy.
+    pub fn proxy_request_rewrite(mut self, rewrite: HeaderRewrite) -> Self {
+        self.inner.proxy_request_rewrites.push(rewrite);
+        self
+    }
+
+    /// Add a rule that rewrites the headers of the upstream response.
+    pub fn proxy_response_rewrite(mut self, rewrite: HeaderRewrite) -> Self {
+        self.inner.proxy_response_rewrites.push(rewrite);
+        self
+    }
+
+    /// Cache the responses from the proxy for a while.
+    pub fn proxy_cache(mut self, proxy_cache: ProxyCache) -> Self {
+        self.inner.proxy_cache = Some(proxy_cache);
+        self
+    }
+
+    /// A fixed delay, in milliseconds, to wait before sending the response.
+    pub fn delay_ms(mut self, delay_ms: u64) -> Self {
+        self.inner.delay_ms = Some(delay_ms);
+        self
+    }
+
+    /// Render the response body and headers as templates for each request.
+    pub fn templated(mut self, templated: bool) -> Self {
+        self.inner.templated = templated;
+        self
+    }
+
+    /// Send the incoming request back, as JSON, instead of a body.
+    pub fn echo(mut self, echo: bool) -> Self {
+        self.inner.echo = echo;
+        self
+    }
+
+    /// Send the response body with `Transfer-Encoding: chunked`.
+    pub fn chunked(mut self, chunked: bool) -> Self {
+        self.inner.chunked = chunked;
+        self
+    }
+
+    /// The size, in bytes, of each chunk of a chunked response body.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.inner.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Add a trailer to send after the last chunk of a chunked response body.
+    pub fn trailers(mut self, trailer: Header) -> Self {
+        self.inner.trailers.push(trailer);
+        self
+    }
+
+    /// Stream the body file from disk as it is sent.
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.inner.stream = stream;
+        self
+    }
+
+    /// Compress the response body.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.inner.compression = Some(compression);
+        self
+    }
+
+    /// Transcode the response body to a character set.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.inner.charset = Some(charset);
+        self
+    }
+
+    /// Close the connection part way through sending the response.
+    pub fn fault(mut self, fault: Fault) -> Self {
+        self.inner.fault = Some(fault);
+        self
+    }
+
+    /// Send the body slowly, a piece at a time.
+    pub fn dribble(mut self, dribble: Dribble) -> Self {
+        self.inner.dribble = Some(dribble);
+        self
+    }
+
+    /// Add an event to send as a `text/event-stream`.
+    pub fn event(mut self, event: ServerSentEvent) -> Self {
+        self.inner.events.push(event);
+        self
+    }
+
+    /// Redirect a number of times before sending this response.
+    pub fn redirect_chain(mut self, redirect_chain: RedirectChain) -> Self {
+        self.inner.redirect_chain = Some(redirect_chain);
+        self
+    }
+
+    /// Add the name of a registered transformer to apply to the response.
+    pub fn transformer<S: Into<String>>(mut self, name: S) -> Self {
+        self.inner.transformers.push(name.into());
+        self
+    }
+
+    /// Send an `ETag` computed from the body, and answer matching conditional requests with
+    /// `304 Not Modified`.
+    pub fn etag(mut self, etag: bool) -> Self {
+        self.inner.etag = etag;
+        self
+    }
+
+    /// Add a body variant, chosen when the request's `Accept` header prefers its media type.
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.inner.variants.push(variant);
+        self
+    }
+
+    /// Build the response configuration.
+    pub fn build(self) -> Response {
+        self.inner
+    }
 }
 
 #[cfg(test)]
 crate mod test {
     use super::Response;
     use crate::config::header::test::{additional_proxy_request_headers, content_type_header};
+    use crate::config::{
+        Charset, Compression, Dribble, Fault, Header, MultipartKind, MultipartResponse, Proxy,
+        ProxyCache, RedirectChain, ResponsePart, ServerSentEvent, Variant,
+    };
 
     const EMPTY_RESPONSE: &str = "{}";
     const PARTIAL_RESPONSE: &str = r#"{"status":200,"headers":[{"key":"Content-Type","value":"application/json"}],"proxy_base_url":"http://cdcproxy.kroger.com"}"#;
@@ -75,6 +598,500 @@ value = "application/json"
         response
     }
 
+    #[test]
+    fn builder() {
+        let response = Response::builder()
+            .status(200)
+            .headers(content_type_header())
+            .body_file_name("test.json")
+            .proxy_base_url("http://cdcproxy.kroger.com")
+            .additional_proxy_request_headers(additional_proxy_request_headers())
+            .build();
+        assert_eq!(response, full_response());
+    }
+
+    #[test]
+    fn validate() {
+        assert!(full_response().validate().is_ok());
+        assert!(Response::builder().body("{}").build().validate().is_ok());
+        assert!(Response::builder()
+            .body("{}")
+            .body_file_name("test.json")
+            .build()
+            .validate()
+            .is_err());
+        assert!(Response::builder()
+            .body("{}")
+            .body_base64(vec![0x00])
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn body_base64() {
+        match toml::from_str::<Response>("body_base64 = \"iVBORwD/\"") {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .body_base64(vec![0x89, 0x50, 0x4e, 0x47, 0x00, 0xff])
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn proxy_override() {
+        let proxy_toml = r#"proxy_base_url = "http://upstream.com"
+
+[proxy]
+use_proxy = true
+proxy_url = "http://corporate.proxy.com"
+no_proxy = ["localhost"]
+"#;
+        match toml::from_str::<Response>(proxy_toml) {
+            Ok(response) => assert_eq!(
+                response,
+                Response::builder()
+                    .proxy_base_url("http://upstream.com")
+                    .proxy(
+                        Proxy::builder()
+                            .proxy_url("http://corporate.proxy.com")
+                            .no_proxy("localhost")
+                            .build()
+                    )
+                    .build()
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn templated() {
+        match serde_json::from_str::<Response>(r#"{"body":"{{request.path}}","templated":true}"#) {
+            Ok(response) => {
+                assert!(*response.templated());
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .body("{{request.path}}")
+                        .templated(true)
+                        .build()
+                );
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn etag() {
+        match toml::from_str::<Response>("body = \"{}\"\netag = true") {
+            Ok(response) => {
+                assert_eq!(response, Response::builder().body("{}").etag(true).build());
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body_file_name("orders.json")
+            .stream(true)
+            .etag(true)
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn variants() {
+        let toml = r#"body = "order"
+
+[[variants]]
+media_type = "application/json"
+body = "{}"
+"#;
+        match toml::from_str::<Response>(toml) {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .body("order")
+                        .variant(Variant::body("application/json", "{}"))
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .echo(true)
+            .variant(Variant::body("application/json", "{}"))
+            .build()
+            .validate()
+            .is_err());
+        assert!(Response::builder()
+            .variant(Variant::file("json", "orders.json"))
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn echo() {
+        match toml::from_str::<Response>("echo = true") {
+            Ok(response) => {
+                assert_eq!(response, Response::builder().echo(true).build());
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body("{}")
+            .echo(true)
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn body_url() {
+        match toml::from_str::<Response>("body_url = \"https://example.com/orders.json\"") {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .body_url("https://example.com/orders.json")
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body_url("/orders.json")
+            .build()
+            .validate()
+            .is_err());
+        assert!(Response::builder()
+            .body("{}")
+            .body_url("https://example.com/orders.json")
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn multipart() {
+        let toml = r#"[multipart]
+
+[[multipart.parts]]
+content_type = "application/json"
+body = "{}"
+"#;
+        match toml::from_str::<Response>(toml) {
+            Ok(response) => {
+                let mut part = ResponsePart::body("{}");
+                let _ = part.set_content_type(Some("application/json".to_string()));
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .multipart(MultipartResponse::new(MultipartKind::Mixed, vec![part]))
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body("{}")
+            .multipart(MultipartResponse::new(
+                MultipartKind::Mixed,
+                vec![ResponsePart::body("{}")]
+            ))
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn chunked() {
+        match toml::from_str::<Response>("chunked = true\nchunk_size = 1024") {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder().chunked(true).chunk_size(1024).build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .chunked(true)
+            .chunk_size(0)
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn trailers() {
+        let toml = r#"chunked = true
+
+[[trailers]]
+key = "grpc-status"
+value = "0"
+"#;
+        match toml::from_str::<Response>(toml) {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .chunked(true)
+                        .trailers(Header::new("grpc-status", "0"))
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .trailers(Header::new("grpc-status", "0"))
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn stream() {
+        match toml::from_str::<Response>("body_file_name = \"large.bin\"\nstream = true") {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .body_file_name("large.bin")
+                        .stream(true)
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body("{}")
+            .stream(true)
+            .build()
+            .validate()
+            .is_err());
+        assert!(Response::builder()
+            .body_file_name("large.bin")
+            .stream(true)
+            .templated(true)
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn compression() {
+        match toml::from_str::<Response>("body = \"{}\"\ncompression = \"gzip\"") {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .body("{}")
+                        .compression(Compression::Gzip)
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body_file_name("large.bin")
+            .stream(true)
+            .compression(Compression::Auto)
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn charset() {
+        match toml::from_str::<Response>("body = \"café\"\ncharset = \"iso-8859-1\"") {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .body("café")
+                        .charset(Charset::Iso88591)
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body_file_name("orders.txt")
+            .stream(true)
+            .charset(Charset::Utf16)
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn fault() {
+        match toml::from_str::<Response>("status = 200\nfault = \"close_mid_body\"") {
+            Ok(response) => assert_eq!(
+                response,
+                Response::builder()
+                    .status(200)
+                    .fault(Fault::CloseMidBody)
+                    .build()
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn dribble() {
+        match toml::from_str::<Response>("[dribble]\nchunks = 10\ntotal_duration_ms = 5000") {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder().dribble(Dribble::new(10, 5000)).build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .dribble(Dribble::new(0, 5000))
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn events() {
+        match toml::from_str::<Response>(
+            "[[events]]\ndata = \"queued\"\n\n[[events]]\ndata = \"done\"\ndelay_ms = 1000",
+        ) {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .event(ServerSentEvent::new("queued", None, None, None))
+                        .event(ServerSentEvent::new("done", None, None, Some(1000)))
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .body("{}")
+            .event(ServerSentEvent::new("queued", None, None, None))
+            .build()
+            .validate()
+            .is_err());
+        assert!(Response::builder()
+            .fault(Fault::CloseMidBody)
+            .event(ServerSentEvent::new("queued", None, None, None))
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn redirect_chain() {
+        match toml::from_str::<Response>(
+            "status = 200\n\n[redirect_chain]\nhops = 2\nstatus = 307\nlocation = \"/step{{redirect.next}}\"",
+        ) {
+            Ok(response) => {
+                assert_eq!(
+                    response.redirect_chain(),
+                    &Some(
+                        RedirectChain::new(2)
+                            .with_status(307)
+                            .with_location("/step{{redirect.next}}")
+                    )
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .redirect_chain(RedirectChain::new(0))
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn proxy_cache() {
+        match toml::from_str::<Response>(
+            "proxy_base_url = \"http://upstream\"\n\n[proxy_cache]\nttl_ms = 60000\nkey_headers = [\"Accept\"]",
+        ) {
+            Ok(response) => {
+                assert_eq!(
+                    response.proxy_cache(),
+                    &Some(ProxyCache::new(60000, vec!["Accept".to_string()]))
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(Response::builder()
+            .proxy_cache(ProxyCache::new(60000, vec![]))
+            .build()
+            .validate()
+            .is_err());
+        assert!(Response::builder()
+            .proxy_base_url("http://upstream")
+            .proxy_cache(ProxyCache::new(0, vec![]))
+            .build()
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn forward_request_headers() {
+        match toml::from_str::<Response>(
+            "proxy_base_url = \"http://upstream\"\nforward_request_headers = true",
+        ) {
+            Ok(response) => {
+                assert_eq!(
+                    response,
+                    Response::builder()
+                        .proxy_base_url("http://upstream")
+                        .forward_request_headers(true)
+                        .build()
+                );
+                assert!(response.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        match serde_json::from_str::<Response>(FULL_RESPONSE_JSON) {
+            Ok(response) => assert!(!response.forward_request_headers()),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn delay_ms() {
+        match serde_json::from_str::<Response>(r#"{"delay_ms":250}"#) {
+            Ok(response) => {
+                assert_eq!(response.delay_ms(), &Some(250));
+                assert_eq!(response, Response::builder().delay_ms(250).build());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
     #[test]
     fn serialize_empty_response() {
         if let Ok(req_str) = serde_json::to_string(&Response::default()) {
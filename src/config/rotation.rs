@@ -0,0 +1,62 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response sequence rotation configuration
+use serde_derive::{Deserialize, Serialize};
+
+/// What a mapping's response `sequence` does once every response has been sent.  Without a
+/// rotation, the last response is repeated.
+///
+/// ```toml
+/// rotation = "round_robin"
+///
+/// [[sequence]]
+/// status = 200
+/// body = "{\"status\": \"queued\"}"
+///
+/// [[sequence]]
+/// status = 200
+/// body = "{\"status\": \"running\"}"
+/// ```
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    /// Start again from the first response.
+    RoundRobin,
+}
+
+impl Rotation {
+    /// The index of the response to send for the given match, counting from 0, of a sequence of
+    /// `len` responses.
+    crate fn index(self, call: usize, len: usize) -> usize {
+        match self {
+            Rotation::RoundRobin => call % len.max(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rotation;
+
+    #[test]
+    fn round_robin() {
+        let indices: Vec<usize> = (0..5)
+            .map(|call| Rotation::RoundRobin.index(call, 3))
+            .collect();
+        assert_eq!(indices, vec![0, 1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn deserialize() {
+        match serde_json::from_str::<Rotation>(r#""round_robin""#) {
+            Ok(rotation) => assert_eq!(rotation, Rotation::RoundRobin),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
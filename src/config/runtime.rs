@@ -7,12 +7,26 @@
 // modified, or distributed except according to those terms.
 
 //! Runtime environment configuration
+use crate::config::{
+    Cors, DefaultResponse, Format, Listener, Mappings, ServerCert, Strict, TagFilter, VirtualHost,
+};
+use crate::error::Error::{self, InvalidListenerConfig, InvalidRuntimeConfig};
+use crate::util;
+use clap::ArgMatches;
 use getset::Getters;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::env;
+use std::fs;
+use std::path::Path;
 use tomlenv::Environment;
 
 const ENV: &str = "env";
+/// The configuration file used when none is given on the command line.
+const DEFAULT_CONFIG: &str = "deadmock.toml";
+/// The prefix of the environment variables that override the configuration file.
+const ENV_PREFIX: &str = "DEADMOCK_";
 
 /// The runtime environment configuration for deadmock.
 #[derive(Clone, Debug, Default, Deserialize, Getters, Hash, Eq, PartialEq, Serialize)]
@@ -26,9 +40,145 @@ pub struct Runtime {
     /// The path to the mappings and templates
     #[get = "pub"]
     path: Option<String>,
+    /// An optional glob pattern, i.e. `mappings/**/orders-*.json`, used to load a subset of the
+    /// mappings instead of everything under `path`.
+    #[get = "pub"]
+    mappings_glob: Option<String>,
+    /// An optional tag expression, i.e. `orders,!slow`, used to select which mappings are active.
+    /// See [`TagFilter`](crate::config::TagFilter).
+    #[get = "pub"]
+    tags: Option<String>,
+    /// The path to a PEM encoded certificate.  When set along with `tls_key`, the server speaks
+    /// HTTPS.
+    #[get = "pub"]
+    tls_cert: Option<String>,
+    /// The path to the PEM encoded private key (PKCS8 or RSA) for `tls_cert`.
+    #[get = "pub"]
+    tls_key: Option<String>,
+    /// An optional path to the PEM encoded CA certificates that complete the chain for `tls_cert`.
+    #[get = "pub"]
+    tls_ca: Option<String>,
+    /// An optional path to the PEM encoded CA certificates used to verify client certificates.
+    /// When set, the server asks TLS clients for a certificate, which mappings can match on with
+    /// `client_cert`.  Clients without a certificate are still accepted, unless
+    /// `tls_client_auth_required` is set.
+    #[get = "pub"]
+    tls_client_ca: Option<String>,
+    /// Reject TLS clients that don't present a certificate signed by `tls_client_ca`.
+    #[get = "pub"]
+    tls_client_auth_required: Option<bool>,
+    /// The protocols offered to TLS clients with ALPN, in order of preference.  Defaults to `h2`
+    /// then `http/1.1`.
+    #[get = "pub"]
+    #[serde(default)]
+    tls_alpn: Vec<String>,
+    /// Certificates chosen by the SNI server name the client asks for, so one TLS listener can
+    /// serve several host names.
+    #[get = "pub"]
+    #[serde(default)]
+    server_certs: Vec<ServerCert>,
+    /// Additional listeners, i.e. an HTTP and an HTTPS port, served by the same process.
+    #[get = "pub"]
+    #[serde(default)]
+    listeners: Vec<Listener>,
+    /// Separate sets of mappings for the requests sent to a host name or under a path prefix.
+    #[get = "pub"]
+    #[serde(default)]
+    virtual_hosts: Vec<VirtualHost>,
+    /// Populate the proxy configuration from the `HTTP_PROXY`, `HTTPS_PROXY`, and `NO_PROXY`
+    /// environment variables.
+    #[get = "pub"]
+    proxy_from_env: Option<bool>,
+    /// The response sent when no mapping matches a request.
+    #[get = "pub"]
+    default_response: Option<DefaultResponse>,
+    /// Strict matching for every mapping that doesn't configure its own.
+    #[get = "pub"]
+    strict: Option<Strict>,
+    /// Evaluate the mappings for a request in parallel once there are at least this many.
+    #[get = "pub"]
+    parallel_threshold: Option<usize>,
+    /// The request matchers to check first, in order, named as they are displayed, i.e.
+    /// `Exact Match On Method`.  The rest are checked cheapest first.
+    #[get = "pub"]
+    #[serde(default)]
+    matcher_order: Vec<String>,
+    /// Save every proxied request, with the upstream response, as a new mapping in this
+    /// directory, laid out like `path` so it can be served as is.
+    #[get = "pub"]
+    record_path: Option<String>,
+    /// Content types for body files, by file extension, i.e. `json = "application/vnd.api+json"`.
+    /// These are checked before the built in types when a response with a `body_file_name`
+    /// doesn't set its own `Content-Type`.
+    #[get = "pub"]
+    #[serde(default)]
+    mime_types: BTreeMap<String, String>,
+    /// Answer CORS preflight requests, and add the CORS headers to every response.
+    #[get = "pub"]
+    cors: Option<Cors>,
 }
 
 impl Runtime {
+    /// Create a new runtime configuration builder.
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::default()
+    }
+
+    /// Is TLS configured for this runtime?
+    pub fn is_tls(&self) -> bool {
+        self.tls_cert.is_some() && self.tls_key.is_some()
+    }
+
+    /// All of the listeners for this runtime.
+    ///
+    /// This is the listener described by `ip` and `port`, if a port is configured, followed by
+    /// any additional `listeners`.
+    pub fn all_listeners(&self) -> Result<Vec<Listener>, Error> {
+        let mut listeners = Vec::new();
+
+        if let Some(port) = self.port {
+            let port = u16::try_from(port)
+                .map_err(|_| InvalidListenerConfig(format!("invalid port '{}'", port)))?;
+            listeners.push(Listener::new(self.ip.clone(), port, self.is_tls()));
+        }
+
+        listeners.extend(self.listeners.iter().cloned());
+
+        if listeners.is_empty() {
+            Err(InvalidListenerConfig("no listeners configured".to_string()))
+        } else if listeners.iter().any(|listener| *listener.tls()) && !self.is_tls() {
+            Err(InvalidListenerConfig(
+                "a TLS listener requires 'tls_cert' and 'tls_key'".to_string(),
+            ))
+        } else {
+            Ok(listeners)
+        }
+    }
+
+    /// Load the mappings of every virtual host from `<mappings_path>/mappings`, keeping only the
+    /// mappings selected by `tags`, as for the root mappings.  In strict mode, any ambiguity
+    /// among the mappings of a virtual host is an error.
+    pub fn virtual_host_mappings(
+        &self,
+        strict: bool,
+    ) -> Result<Vec<(VirtualHost, Mappings)>, Error> {
+        self.virtual_hosts
+            .iter()
+            .map(|virtual_host| {
+                virtual_host.validate()?;
+                let mut mappings =
+                    Mappings::from_path(&Path::new(virtual_host.mappings_path()).join("mappings"))?;
+
+                if let Some(tags) = &self.tags {
+                    mappings = mappings.select(&TagFilter::new(tags));
+                }
+
+                let _ = mappings.check_ambiguities(strict)?;
+                Ok((virtual_host.clone(), mappings))
+            })
+            .collect()
+    }
+
     /// Get the `env` environment variable, setting it to `local` if the variable is not found or set already.
     ///
     /// # Example
@@ -47,14 +197,359 @@ impl Runtime {
             env_str
         })
     }
+
+    /// Overlay `other` on this configuration.  Anything set in `other` wins.
+    pub fn merge(mut self, other: Self) -> Self {
+        self.ip = other.ip.or(self.ip);
+        self.port = other.port.or(self.port);
+        self.path = other.path.or(self.path);
+        self.mappings_glob = other.mappings_glob.or(self.mappings_glob);
+        self.tags = other.tags.or(self.tags);
+        self.tls_cert = other.tls_cert.or(self.tls_cert);
+        self.tls_key = other.tls_key.or(self.tls_key);
+        self.tls_ca = other.tls_ca.or(self.tls_ca);
+        self.tls_client_ca = other.tls_client_ca.or(self.tls_client_ca);
+        self.tls_client_auth_required = other
+            .tls_client_auth_required
+            .or(self.tls_client_auth_required);
+        if !other.tls_alpn.is_empty() {
+            self.tls_alpn = other.tls_alpn;
+        }
+        if !other.server_certs.is_empty() {
+            self.server_certs = other.server_certs;
+        }
+        if !other.listeners.is_empty() {
+            self.listeners = other.listeners;
+        }
+        if !other.virtual_hosts.is_empty() {
+            self.virtual_hosts = other.virtual_hosts;
+        }
+        self.proxy_from_env = other.proxy_from_env.or(self.proxy_from_env);
+        self.default_response = other.default_response.or(self.default_response);
+        self.strict = other.strict.or(self.strict);
+        self.parallel_threshold = other.parallel_threshold.or(self.parallel_threshold);
+        if !other.matcher_order.is_empty() {
+            self.matcher_order = other.matcher_order;
+        }
+        self.record_path = other.record_path.or(self.record_path);
+        self.mime_types.extend(other.mime_types);
+        self.cors = other.cors.or(self.cors);
+        self
+    }
+
+    /// Read the runtime configuration from the `DEADMOCK_*` environment variables, i.e.
+    /// `DEADMOCK_PORT=8080`.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_vars(|name| env::var(format!("{}{}", ENV_PREFIX, name)).ok())
+    }
+
+    fn from_vars<F>(var: F) -> Result<Self, Error>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Ok(Self {
+            ip: var("IP"),
+            port: var("PORT").map(|port| parse_port(&port)).transpose()?,
+            path: var("PATH"),
+            mappings_glob: var("MAPPINGS_GLOB"),
+            tags: var("TAGS"),
+            tls_cert: var("TLS_CERT"),
+            tls_key: var("TLS_KEY"),
+            tls_ca: var("TLS_CA"),
+            tls_client_ca: var("TLS_CLIENT_CA"),
+            tls_client_auth_required: var("TLS_CLIENT_AUTH_REQUIRED")
+                .map(|value| parse_bool("TLS_CLIENT_AUTH_REQUIRED", &value))
+                .transpose()?,
+            tls_alpn: var("TLS_ALPN").map_or_else(Vec::new, |alpn| parse_list(&alpn)),
+            server_certs: vec![],
+            listeners: vec![],
+            virtual_hosts: vec![],
+            proxy_from_env: var("PROXY_FROM_ENV")
+                .map(|value| parse_bool("PROXY_FROM_ENV", &value))
+                .transpose()?,
+            default_response: None,
+            strict: None,
+            parallel_threshold: None,
+            matcher_order: vec![],
+            record_path: var("RECORD_PATH"),
+            mime_types: BTreeMap::new(),
+            cors: None,
+        })
+    }
+}
+
+fn parse_port(port: &str) -> Result<u32, Error> {
+    port.parse()
+        .map_err(|_| InvalidRuntimeConfig(format!("invalid port '{}'", port)))
+}
+
+/// Parse a flag: `true` or `false` in any case, or `1` or `0`.
+fn parse_bool(name: &str, value: &str) -> Result<bool, Error> {
+    match value {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        _ if value.eq_ignore_ascii_case("true") => Ok(true),
+        _ if value.eq_ignore_ascii_case("false") => Ok(false),
+        _ => Err(InvalidRuntimeConfig(format!(
+            "invalid value '{}' for {}{}, expected true or false",
+            value, ENV_PREFIX, name
+        ))),
+    }
+}
+
+fn parse_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl<'a> TryFrom<&'a Path> for Runtime {
+    type Error = Error;
+
+    /// Read the runtime configuration from a file in any supported
+    /// [`Format`](crate::config::Format).
+    fn try_from(path: &'a Path) -> Result<Self, Error> {
+        let contents = util::interpolate_env(&fs::read_to_string(path)?)?;
+        Format::from_path(path).from_slice(contents.as_bytes())
+    }
+}
+
+impl<'a> TryFrom<&'a ArgMatches<'a>> for Runtime {
+    type Error = Error;
+
+    /// Resolve the runtime configuration in layers.  The configuration file, given by the
+    /// `config` argument or `deadmock.toml` if it exists, is overridden by the `DEADMOCK_*`
+    /// environment variables, which are overridden by the command line arguments.
+    fn try_from(matches: &'a ArgMatches<'a>) -> Result<Self, Error> {
+        let file = if let Some(config) = matches.value_of("config") {
+            Self::try_from(Path::new(config))?
+        } else if Path::new(DEFAULT_CONFIG).is_file() {
+            Self::try_from(Path::new(DEFAULT_CONFIG))?
+        } else {
+            Self::default()
+        };
+
+        let value = |name: &str| matches.value_of(name).map(|value| value.to_string());
+        // A flag can only switch a setting on, so an absent flag leaves the other layers alone.
+        let flag = |name: &str| {
+            if matches.is_present(name) {
+                Some(true)
+            } else {
+                None
+            }
+        };
+        let cli = Self {
+            ip: value("ip"),
+            port: matches.value_of("port").map(parse_port).transpose()?,
+            path: value("mappings_path"),
+            mappings_glob: value("mappings_glob"),
+            tags: value("tags"),
+            tls_cert: value("tls_cert"),
+            tls_key: value("tls_key"),
+            tls_ca: value("tls_ca"),
+            tls_client_ca: value("tls_client_ca"),
+            tls_client_auth_required: flag("tls-client-auth-required"),
+            tls_alpn: matches
+                .value_of("tls_alpn")
+                .map_or_else(Vec::new, parse_list),
+            server_certs: vec![],
+            listeners: vec![],
+            virtual_hosts: vec![],
+            proxy_from_env: flag("proxy-from-env"),
+            default_response: None,
+            strict: None,
+            parallel_threshold: None,
+            matcher_order: vec![],
+            record_path: value("record_path"),
+            mime_types: BTreeMap::new(),
+            cors: None,
+        };
+
+        Ok(file.merge(Self::from_env()?).merge(cli))
+    }
+}
+
+/// A builder for [`Runtime`](crate::config::Runtime) configuration.
+///
+/// # Example
+///
+/// ```
+/// # use libdeadmock::config::{Listener, Runtime};
+/// #
+/// # fn main() {
+/// let runtime = Runtime::builder()
+///     .ip("127.0.0.1")
+///     .port(8080)
+///     .listener(Listener::new(None, 8081, false))
+///     .build();
+/// assert_eq!(runtime.port(), &Some(8080));
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeBuilder {
+    inner: Runtime,
+}
+
+impl RuntimeBuilder {
+    /// The IP address to listen on.
+    pub fn ip<S: Into<String>>(mut self, ip: S) -> Self {
+        self.inner.ip = Some(ip.into());
+        self
+    }
+
+    /// The port to listen on.
+    pub fn port(mut self, port: u32) -> Self {
+        self.inner.port = Some(port);
+        self
+    }
+
+    /// The path to the mappings and templates.
+    pub fn path<S: Into<String>>(mut self, path: S) -> Self {
+        self.inner.path = Some(path.into());
+        self
+    }
+
+    /// A glob pattern used to load a subset of the mappings.
+    pub fn mappings_glob<S: Into<String>>(mut self, mappings_glob: S) -> Self {
+        self.inner.mappings_glob = Some(mappings_glob.into());
+        self
+    }
+
+    /// A tag expression used to select which mappings are active.
+    pub fn tags<S: Into<String>>(mut self, tags: S) -> Self {
+        self.inner.tags = Some(tags.into());
+        self
+    }
+
+    /// The paths to the PEM encoded certificate and private key used for TLS.
+    pub fn tls<C: Into<String>, K: Into<String>>(mut self, tls_cert: C, tls_key: K) -> Self {
+        self.inner.tls_cert = Some(tls_cert.into());
+        self.inner.tls_key = Some(tls_key.into());
+        self
+    }
+
+    /// The path to the PEM encoded CA certificates that complete the certificate chain.
+    pub fn tls_ca<S: Into<String>>(mut self, tls_ca: S) -> Self {
+        self.inner.tls_ca = Some(tls_ca.into());
+        self
+    }
+
+    /// The path to the PEM encoded CA certificates used to verify client certificates.
+    pub fn tls_client_ca<S: Into<String>>(mut self, tls_client_ca: S) -> Self {
+        self.inner.tls_client_ca = Some(tls_client_ca.into());
+        self
+    }
+
+    /// Reject TLS clients that don't present a certificate signed by the client CA.
+    pub fn tls_client_auth_required(mut self, tls_client_auth_required: bool) -> Self {
+        self.inner.tls_client_auth_required = Some(tls_client_auth_required);
+        self
+    }
+
+    /// Offer a protocol to TLS clients with ALPN.  Protocols are preferred in the order they are
+    /// added.
+    pub fn tls_alpn<S: Into<String>>(mut self, protocol: S) -> Self {
+        self.inner.tls_alpn.push(protocol.into());
+        self
+    }
+
+    /// Add a certificate chosen by the SNI server name.
+    pub fn server_cert(mut self, server_cert: ServerCert) -> Self {
+        self.inner.server_certs.push(server_cert);
+        self
+    }
+
+    /// Populate the proxy configuration from the environment.
+    pub fn proxy_from_env(mut self, proxy_from_env: bool) -> Self {
+        self.inner.proxy_from_env = Some(proxy_from_env);
+        self
+    }
+
+    /// Add a listener.
+    pub fn listener(mut self, listener: Listener) -> Self {
+        self.inner.listeners.push(listener);
+        self
+    }
+
+    /// Add a virtual host with its own mappings.
+    pub fn virtual_host(mut self, virtual_host: VirtualHost) -> Self {
+        self.inner.virtual_hosts.push(virtual_host);
+        self
+    }
+
+    /// Strict matching for every mapping that doesn't configure its own.
+    pub fn strict(mut self, strict: Strict) -> Self {
+        self.inner.strict = Some(strict);
+        self
+    }
+
+    /// Evaluate the mappings for a request in parallel once there are at least this many.
+    pub fn parallel_threshold(mut self, parallel_threshold: usize) -> Self {
+        self.inner.parallel_threshold = Some(parallel_threshold);
+        self
+    }
+
+    /// Check the named request matcher before any that aren't named.
+    pub fn matcher(mut self, name: &str) -> Self {
+        self.inner.matcher_order.push(name.to_string());
+        self
+    }
+
+    /// Save every proxied request, with the upstream response, as a new mapping in this
+    /// directory.
+    pub fn record_path<S: Into<String>>(mut self, record_path: S) -> Self {
+        self.inner.record_path = Some(record_path.into());
+        self
+    }
+
+    /// The content type for body files with the given extension.
+    pub fn mime_type<E: Into<String>, C: Into<String>>(
+        mut self,
+        extension: E,
+        content_type: C,
+    ) -> Self {
+        let _ = self
+            .inner
+            .mime_types
+            .insert(extension.into(), content_type.into());
+        self
+    }
+
+    /// Answer CORS preflight requests, and add the CORS headers to every response.
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.inner.cors = Some(cors);
+        self
+    }
+
+    /// Build the runtime configuration.
+    pub fn build(self) -> Runtime {
+        self.inner
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::{Runtime, ENV};
+    use crate::config::{Listener, VirtualHost};
+    use crate::error::Error;
+    use clap::{App, Arg};
+    use std::convert::TryFrom;
     use std::env;
+    use std::path::Path;
     use tomlenv::Environment;
 
+    const LISTENERS_TOML: &str = r#"ip = "127.0.0.1"
+port = 8080
+tls_cert = "tests/tls/cert.pem"
+tls_key = "tests/tls/key.pem"
+
+[[listeners]]
+port = 8443
+tls = true
+"#;
+
     fn validate_env(currenv: &str) {
         env::set_var(ENV, &currenv);
         assert_eq!(Runtime::env(), currenv);
@@ -75,4 +570,210 @@ mod test {
         validate_env(&Environment::Stage.to_string());
         validate_env(&Environment::Prod.to_string());
     }
+
+    #[test]
+    fn all_listeners() {
+        match toml::from_str::<Runtime>(LISTENERS_TOML) {
+            Ok(runtime) => match runtime.all_listeners() {
+                Ok(listeners) => assert_eq!(
+                    listeners,
+                    vec![
+                        Listener::new(Some("127.0.0.1".to_string()), 8080, true),
+                        Listener::new(None, 8443, true),
+                    ]
+                ),
+                Err(e) => assert!(false, e.to_string()),
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn builder() {
+        let runtime = Runtime::builder()
+            .ip("127.0.0.1")
+            .port(8080)
+            .tls("tests/tls/cert.pem", "tests/tls/key.pem")
+            .listener(Listener::new(None, 8443, true))
+            .build();
+        match toml::from_str::<Runtime>(LISTENERS_TOML) {
+            Ok(expected) => assert_eq!(runtime, expected),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn from_path() {
+        match Runtime::try_from(Path::new("tests/runtime/deadmock.toml")) {
+            Ok(runtime) => {
+                assert_eq!(runtime.ip(), &Some("127.0.0.1".to_string()));
+                assert_eq!(runtime.port(), &Some(8080));
+                assert_eq!(runtime.path(), &Some("tests".to_string()));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn virtual_host_mappings() {
+        let runtime = Runtime::builder()
+            .virtual_host(VirtualHost::host("orders.example.com", "tests"))
+            .build();
+        match runtime.virtual_host_mappings(false) {
+            Ok(virtual_hosts) => {
+                assert_eq!(virtual_hosts.len(), 1);
+                assert!(virtual_hosts[0].1.inner().len() > 1);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(runtime.virtual_host_mappings(true).is_err());
+
+        let runtime = Runtime::builder()
+            .tags("tagged")
+            .virtual_host(VirtualHost::host("orders.example.com", "tests"))
+            .build();
+        match runtime.virtual_host_mappings(true) {
+            Ok(virtual_hosts) => {
+                assert_eq!(virtual_hosts.len(), 1);
+                assert_eq!(virtual_hosts[0].1.inner().len(), 1);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        let runtime = Runtime::builder()
+            .virtual_host(VirtualHost::path_prefix("payments", "tests"))
+            .build();
+        assert!(runtime.virtual_host_mappings(false).is_err());
+    }
+
+    #[test]
+    fn layers() {
+        let file = Runtime::builder()
+            .ip("127.0.0.1")
+            .port(8080)
+            .path("tests")
+            .tls_client_auth_required(true)
+            .proxy_from_env(true)
+            .build();
+        let env = Runtime::from_vars(|name| match name {
+            "PORT" => Some("9090".to_string()),
+            "TAGS" => Some("orders".to_string()),
+            "TLS_ALPN" => Some("h2, http/1.1".to_string()),
+            "TLS_CLIENT_AUTH_REQUIRED" => Some("false".to_string()),
+            _ => None,
+        });
+        let cli = Runtime::builder().tags("payments").build();
+
+        match env {
+            Ok(env) => {
+                let runtime = file.merge(env).merge(cli);
+                assert_eq!(runtime.ip(), &Some("127.0.0.1".to_string()));
+                assert_eq!(runtime.port(), &Some(9090));
+                assert_eq!(runtime.path(), &Some("tests".to_string()));
+                assert_eq!(runtime.tags(), &Some("payments".to_string()));
+                assert_eq!(
+                    runtime.tls_alpn(),
+                    &vec!["h2".to_string(), "http/1.1".to_string()]
+                );
+                assert_eq!(runtime.tls_client_auth_required(), &Some(false));
+                assert_eq!(runtime.proxy_from_env(), &Some(true));
+
+                let runtime = runtime.merge(Runtime::builder().proxy_from_env(false).build());
+                assert_eq!(runtime.proxy_from_env(), &Some(false));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn mime_types() {
+        let file = Runtime::builder()
+            .mime_type("json", "application/vnd.api+json")
+            .mime_type("hal", "application/hal+json")
+            .build();
+        let cli = Runtime::builder()
+            .mime_type("json", "application/json")
+            .build();
+
+        let runtime = file.merge(cli);
+        assert_eq!(runtime.mime_types().len(), 2);
+        assert_eq!(
+            runtime.mime_types().get("json"),
+            Some(&"application/json".to_string())
+        );
+    }
+
+    #[test]
+    fn bad_env_port() {
+        assert!(Runtime::from_vars(|name| if name == "PORT" {
+            Some("abc".to_string())
+        } else {
+            None
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn env_flags() {
+        let runtime = Runtime::from_vars(|name| match name {
+            "TLS_CLIENT_AUTH_REQUIRED" => Some("TRUE".to_string()),
+            "PROXY_FROM_ENV" => Some("0".to_string()),
+            _ => None,
+        });
+
+        match runtime {
+            Ok(runtime) => {
+                assert_eq!(runtime.tls_client_auth_required(), &Some(true));
+                assert_eq!(runtime.proxy_from_env(), &Some(false));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match Runtime::from_vars(|name| {
+            if name == "PROXY_FROM_ENV" {
+                Some("yes".to_string())
+            } else {
+                None
+            }
+        }) {
+            Ok(_) => assert!(false, "Not expected to succeed!"),
+            Err(Error::InvalidRuntimeConfig(message)) => {
+                assert!(message.contains("DEADMOCK_PROXY_FROM_ENV"))
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn from_args() {
+        let matches = App::new("runtime-config-test")
+            .arg(Arg::with_name("config").long("config").takes_value(true))
+            .arg(Arg::with_name("port").long("port").takes_value(true))
+            .get_matches_from(vec![
+                "test-cli",
+                "--config",
+                "tests/runtime/deadmock.toml",
+                "--port",
+                "8081",
+            ]);
+        match Runtime::try_from(&matches) {
+            Ok(runtime) => {
+                assert_eq!(runtime.ip(), &Some("127.0.0.1".to_string()));
+                assert_eq!(runtime.port(), &Some(8081));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn no_listeners() {
+        assert!(Runtime::default().all_listeners().is_err());
+    }
+
+    #[test]
+    fn tls_listener_requires_cert() {
+        let runtime = toml::from_str::<Runtime>("[[listeners]]\nport = 8443\ntls = true\n")
+            .expect("Unable to deserialize runtime!");
+        assert!(runtime.all_listeners().is_err());
+    }
 }
@@ -0,0 +1,52 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! SNI server certificate configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// A certificate served to TLS clients that ask for `server_name` with SNI.
+///
+/// Clients that ask for any other name, or don't use SNI, are served the `tls_cert` configured on
+/// the `Runtime`.
+///
+/// ```toml
+/// [[server_certs]]
+/// server_name = "api.example.com"
+/// tls_cert = "certs/api.example.com.pem"
+/// tls_key = "certs/api.example.com.key"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct ServerCert {
+    /// The DNS name the client asks for.
+    #[get = "pub"]
+    server_name: String,
+    /// The path to a PEM encoded certificate, followed by any CA certificates that complete the
+    /// chain.
+    #[get = "pub"]
+    tls_cert: String,
+    /// The path to the PEM encoded private key (PKCS8 or RSA) for `tls_cert`.
+    #[get = "pub"]
+    tls_key: String,
+}
+
+impl ServerCert {
+    /// Create a new SNI server certificate.
+    pub fn new<N, C, K>(server_name: N, tls_cert: C, tls_key: K) -> Self
+    where
+        N: Into<String>,
+        C: Into<String>,
+        K: Into<String>,
+    {
+        Self {
+            server_name: server_name.into(),
+            tls_cert: tls_cert.into(),
+            tls_key: tls_key.into(),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Server-sent event configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// One event of a `text/event-stream` response.  The events of a response are sent in order,
+/// each after its delay, and the stream ends after the last one.
+///
+/// ```toml
+/// [[response.events]]
+/// event = "status"
+/// id = "1"
+/// data = "{\"status\": \"queued\"}"
+///
+/// [[response.events]]
+/// event = "status"
+/// id = "2"
+/// data = "{\"status\": \"done\"}"
+/// delay_ms = 1000
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct ServerSentEvent {
+    /// The event data.  Each line is sent as its own `data` field.
+    #[get = "pub"]
+    data: String,
+    /// The event type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    event: Option<String>,
+    /// The event id, which clients send back as `Last-Event-ID` when they reconnect.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    id: Option<String>,
+    /// How long to wait, in milliseconds, after the previous event, or the headers for the first
+    /// event, before sending this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    delay_ms: Option<u64>,
+}
+
+impl ServerSentEvent {
+    /// Create a new event.
+    pub fn new<S: Into<String>>(
+        data: S,
+        event: Option<String>,
+        id: Option<String>,
+        delay_ms: Option<u64>,
+    ) -> Self {
+        Self {
+            data: data.into(),
+            event,
+            id,
+            delay_ms,
+        }
+    }
+
+    /// The event in the `text/event-stream` format.
+    crate fn encode(&self) -> String {
+        let mut encoded = String::new();
+
+        if let Some(id) = &self.id {
+            encoded.push_str(&format!("id: {}\n", id));
+        }
+        if let Some(event) = &self.event {
+            encoded.push_str(&format!("event: {}\n", event));
+        }
+        for line in self.data.lines() {
+            encoded.push_str(&format!("data: {}\n", line));
+        }
+        if self.data.is_empty() {
+            encoded.push_str("data:\n");
+        }
+        encoded.push('\n');
+        encoded
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ServerSentEvent;
+
+    #[test]
+    fn encode() {
+        let event = ServerSentEvent::new(
+            "{\"status\":\n\"done\"}",
+            Some("status".to_string()),
+            Some("2".to_string()),
+            None,
+        );
+        assert_eq!(
+            event.encode(),
+            "id: 2\nevent: status\ndata: {\"status\":\ndata: \"done\"}\n\n"
+        );
+        assert_eq!(
+            ServerSentEvent::new("", None, None, None).encode(),
+            "data:\n\n"
+        );
+    }
+}
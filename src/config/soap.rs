@@ -0,0 +1,50 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! SOAP request matching configuration
+use crate::config::XPath;
+use getset::{Getters, Setters};
+use serde_derive::{Deserialize, Serialize};
+
+/// SOAP 1.1 or 1.2 request matching.
+///
+/// The request body must be a SOAP envelope.  `action` is compared with the `SOAPAction` header
+/// (SOAP 1.1) or the `action` parameter of the `Content-Type` header (SOAP 1.2), without quotes.
+/// `operation` is the local name of the first element in the SOAP body.  The `xpath` expressions
+/// are evaluated with the SOAP body element as the context node, so relative paths start at the
+/// operation.  The `soap` (1.1) and `soap12` (1.2) envelope namespace prefixes are declared, along
+/// with any `xml_namespaces` on the request.
+///
+/// ```toml
+/// [request.soap]
+/// action = "http://example.com/orders/GetOrder"
+/// operation = "GetOrder"
+///
+/// [[request.soap.xpath]]
+/// expression = "GetOrder/id"
+/// equal_to = "12345"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize, Setters)]
+pub struct Soap {
+    /// The SOAP action.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    /// The local name of the operation element in the SOAP body.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<String>,
+    /// XPath expressions evaluated against the SOAP body.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    xpath: Vec<XPath>,
+}
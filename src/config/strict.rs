@@ -0,0 +1,63 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Strict request matching configuration
+use crate::util;
+use getset::{Getters, Setters};
+use serde_derive::{Deserialize, Serialize};
+
+/// Strict matching fails a request that has something the mapping doesn't match on.
+///
+/// By default, every query parameter on the request must be matched by name (exact or pattern),
+/// and a request body must be matched by one of the body matchers.  The listed `headers` must be
+/// matched by one of the header matchers when they are present.  Strict matching is set on the
+/// request configuration of a mapping, or for every mapping on the runtime configuration.
+///
+/// ```toml
+/// [request.strict]
+/// ignore_body = true
+/// headers = ["X-Api-Version", "X-Tenant"]
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize, Setters)]
+pub struct Strict {
+    /// Allow query parameters the mapping doesn't match on.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    ignore_query: bool,
+    /// Allow a request body the mapping doesn't match on.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "util::is_false")]
+    ignore_body: bool,
+    /// The headers the mapping must match on when they are present on the request.
+    #[get = "pub"]
+    #[set = "pub"]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::Strict;
+
+    #[test]
+    fn deserialize_strict() {
+        match toml::from_str::<Strict>("headers = [\"X-Api-Version\"]") {
+            Ok(strict) => {
+                assert!(!*strict.ignore_query());
+                assert!(!*strict.ignore_body());
+                assert_eq!(strict.headers(), &vec!["X-Api-Version".to_string()]);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Mapping tag selection
+use getset::Getters;
+use std::fmt;
+
+/// Select mappings by tag.
+///
+/// A tag filter is built from a comma separated expression of tags.  Tags prefixed with `!` are
+/// excluded, the rest are included.  A set of tags matches when it contains at least one of the
+/// included tags (if any were given) and none of the excluded tags.
+///
+/// # Example
+///
+/// ```
+/// # use libdeadmock::config::TagFilter;
+/// #
+/// # fn main() {
+/// let filter = TagFilter::new("orders,payments,!slow");
+/// assert!(filter.is_match(&["orders".to_string()]));
+/// assert!(!filter.is_match(&["orders".to_string(), "slow".to_string()]));
+/// assert!(!filter.is_match(&["users".to_string()]));
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Eq, Getters, Hash, PartialEq)]
+pub struct TagFilter {
+    /// The tags to include.
+    #[get = "pub"]
+    include: Vec<String>,
+    /// The tags to exclude.
+    #[get = "pub"]
+    exclude: Vec<String>,
+}
+
+impl TagFilter {
+    /// Create a new tag filter from a comma separated expression, i.e. `orders,!slow`.
+    pub fn new(expression: &str) -> Self {
+        let mut filter = Self::default();
+
+        for tag in expression
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+        {
+            if tag.starts_with('!') {
+                filter.exclude.push(tag[1..].to_string());
+            } else {
+                filter.include.push(tag.to_string());
+            }
+        }
+
+        filter
+    }
+
+    /// Do the given tags satisfy this filter?
+    pub fn is_match(&self, tags: &[String]) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|tag| tags.contains(tag));
+        let excluded = self.exclude.iter().any(|tag| tags.contains(tag));
+        included && !excluded
+    }
+}
+
+impl fmt::Display for TagFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exclude: Vec<String> = self.exclude.iter().map(|tag| format!("!{}", tag)).collect();
+        let tags: Vec<&str> = self
+            .include
+            .iter()
+            .chain(exclude.iter())
+            .map(String::as_str)
+            .collect();
+        write!(f, "{}", tags.join(","))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TagFilter;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn empty_matches_everything() {
+        let filter = TagFilter::new("");
+        assert!(filter.is_match(&[]));
+        assert!(filter.is_match(&tags(&["orders"])));
+    }
+
+    #[test]
+    fn include() {
+        let filter = TagFilter::new("orders, payments");
+        assert!(filter.is_match(&tags(&["orders"])));
+        assert!(filter.is_match(&tags(&["payments", "slow"])));
+        assert!(!filter.is_match(&tags(&["users"])));
+        assert!(!filter.is_match(&[]));
+    }
+
+    #[test]
+    fn exclude() {
+        let filter = TagFilter::new("!slow");
+        assert!(filter.is_match(&[]));
+        assert!(filter.is_match(&tags(&["orders"])));
+        assert!(!filter.is_match(&tags(&["orders", "slow"])));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            TagFilter::new("orders, !slow,payments").to_string(),
+            "orders,payments,!slow"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Glob style url patterns
+use crate::error::Error;
+use regex::Regex;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A glob style url pattern, i.e. `/api/*/items/**`.
+///
+/// * `*` matches any characters within a single path segment.
+/// * `**` matches any characters, across path segments.  `**/` also matches no segments at all,
+/// so `/api/**/items` matches `/api/items`.
+/// * `?` matches a single character within a path segment.
+///
+/// Everything else matches literally, and the whole path must match.  The glob is compiled once,
+/// when the configuration is deserialized.
+#[derive(Clone, Debug)]
+pub struct UrlGlob {
+    glob: String,
+    regex: Regex,
+}
+
+impl UrlGlob {
+    /// Compile the given glob.
+    pub fn new<S: Into<String>>(glob: S) -> Result<Self, Error> {
+        let glob = glob.into();
+        let regex = Regex::new(&to_regex(&glob))?;
+        Ok(Self { glob, regex })
+    }
+
+    /// The glob as written in the configuration.
+    pub fn glob(&self) -> &str {
+        &self.glob
+    }
+
+    /// Does the given path match the glob?
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+fn to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' if chars.peek() == Some(&'*') => {
+                let _ = chars.next();
+                if chars.peek() == Some(&'/') {
+                    let _ = chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            _ => regex.push_str(&regex::escape(&ch.to_string())),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+impl PartialEq for UrlGlob {
+    fn eq(&self, other: &Self) -> bool {
+        self.glob == other.glob
+    }
+}
+
+impl Eq for UrlGlob {}
+
+impl Hash for UrlGlob {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.glob.hash(state);
+    }
+}
+
+impl Serialize for UrlGlob {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.glob)
+    }
+}
+
+struct UrlGlobVisitor;
+
+impl<'de> Visitor<'de> for UrlGlobVisitor {
+    type Value = UrlGlob;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a url glob, i.e. '/api/*/items/**'")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<UrlGlob, E>
+    where
+        E: de::Error,
+    {
+        UrlGlob::new(value).map_err(|e| E::custom(format!("invalid url glob: {}", e)))
+    }
+}
+
+impl<'de> Deserialize<'de> for UrlGlob {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(UrlGlobVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UrlGlob;
+
+    fn is_match(glob: &str, path: &str) -> bool {
+        match UrlGlob::new(glob) {
+            Ok(glob) => glob.is_match(path),
+            Err(e) => {
+                assert!(false, e.to_string());
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn single_segment() {
+        assert!(is_match("/api/*/items", "/api/v1/items"));
+        assert!(!is_match("/api/*/items", "/api/v1/beta/items"));
+        assert!(is_match("/files/*.csv", "/files/report.csv"));
+        assert!(!is_match("/files/*.csv", "/files/report.csvx"));
+        assert!(is_match("/v?/orders", "/v2/orders"));
+        assert!(!is_match("/v?/orders", "/v22/orders"));
+    }
+
+    #[test]
+    fn any_segments() {
+        assert!(is_match("/api/*/items/**", "/api/v1/items/12/parts"));
+        assert!(is_match("/api/**/items", "/api/items"));
+        assert!(is_match("/api/**/items", "/api/a/b/items"));
+        assert!(!is_match("/api/**/items", "/api/a/b/items/1"));
+    }
+
+    #[test]
+    fn literal() {
+        assert!(is_match("/search.json", "/search.json"));
+        assert!(!is_match("/search.json", "/searchxjson"));
+        assert!(is_match("/a+(b)", "/a+(b)"));
+    }
+
+    #[test]
+    fn round_trip() {
+        match serde_json::from_str::<UrlGlob>(r#""/api/*/items/**""#) {
+            Ok(glob) => {
+                assert_eq!(glob.glob(), "/api/*/items/**");
+                match serde_json::to_string(&glob) {
+                    Ok(json) => assert_eq!(json, r#""/api/*/items/**""#),
+                    Err(e) => assert!(false, e.to_string()),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Mapping validation
+use crate::config::{HeaderPattern, Mapping, Request};
+use crate::error::Error::{self, InvalidRequestConfig};
+#[cfg(feature = "protobuf")]
+use crate::matcher::protobuf;
+use crate::util;
+use getset::Getters;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A problem found with a mapping file.
+#[derive(Clone, Debug, Eq, Getters, Hash, PartialEq)]
+pub struct Problem {
+    /// The mapping file with the problem.
+    #[get = "pub"]
+    path: PathBuf,
+    /// A description of the problem.
+    #[get = "pub"]
+    message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// The result of validating a set of mappings with
+/// [`Mappings::validate`](crate::config::Mappings::validate).
+#[derive(Clone, Debug, Default, Eq, Getters, PartialEq)]
+pub struct ValidationReport {
+    /// The number of mappings that were loaded.
+    #[get = "pub"]
+    mappings: usize,
+    /// The problems found.
+    #[get = "pub"]
+    problems: Vec<Problem>,
+}
+
+impl ValidationReport {
+    /// Were the mappings free of problems?
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    crate fn problem(&mut self, path: &Path, message: String) {
+        self.problems.push(Problem {
+            path: path.to_path_buf(),
+            message,
+        });
+    }
+
+    /// Check a loaded mapping, recording any problems found.
+    crate fn check(&mut self, path: &Path, mapping: &Mapping, body_files: &HashSet<String>) {
+        self.mappings += 1;
+
+        for (field, pattern) in patterns_of(mapping.request()) {
+            if let Err(e) = Regex::new(pattern) {
+                self.problem(
+                    path,
+                    format!("invalid regex in field `{}` '{}': {}", field, pattern, e),
+                );
+            }
+        }
+
+        if let Some(remote_addr) = mapping.request().remote_addr() {
+            if let Err(e) = util::cidr_contains(remote_addr, [127, 0, 0, 1].into()) {
+                self.problem(path, format!("invalid field `remote_addr`: {}", e));
+            }
+        }
+
+        self.check_protobuf(path, mapping.request());
+
+        for response in mapping.all_responses() {
+            if let Some(body_file_name) = response.body_file_name() {
+                if !body_files.contains(body_file_name) {
+                    self.problem(path, format!("body file '{}' not found", body_file_name));
+                }
+            }
+        }
+    }
+}
+
+impl ValidationReport {
+    #[cfg(feature = "protobuf")]
+    fn check_protobuf(&mut self, path: &Path, request: &Request) {
+        if let Some(body_protobuf) = request.body_protobuf() {
+            match protobuf::descriptors(body_protobuf.descriptor_set()) {
+                Ok(descriptors) => {
+                    if !descriptors.has_message(body_protobuf.message()) {
+                        self.problem(
+                            path,
+                            format!(
+                                "invalid field `body_protobuf`: message type '{}' not found in '{}'",
+                                body_protobuf.message(),
+                                body_protobuf.descriptor_set()
+                            ),
+                        );
+                    }
+                }
+                Err(e) => self.problem(
+                    path,
+                    format!(
+                        "invalid field `body_protobuf`: descriptor set '{}': {}",
+                        body_protobuf.descriptor_set(),
+                        e
+                    ),
+                ),
+            }
+        }
+    }
+
+    #[cfg(not(feature = "protobuf"))]
+    fn check_protobuf(&mut self, _path: &Path, _request: &Request) {}
+}
+
+/// Compile every regex in the mapping's request configuration, failing on the first that is
+/// invalid.  This only checks the patterns as the mapping is loaded: the matchers compile them
+/// again through the shared, size limited, regex cache.
+crate fn compile_patterns(mapping: &Mapping) -> Result<(), Error> {
+    for (field, pattern) in patterns_of(mapping.request()) {
+        let _ = util::regex(pattern).map_err(|e| {
+            InvalidRequestConfig(format!(
+                "mapping '{}': invalid regex in field `{}` '{}': {}",
+                mapping.name(),
+                field,
+                pattern,
+                e
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+/// Every regex in the given request configuration, including nested `all_of`, `any_of` and `not`
+/// configurations, with the name of its field.
+fn patterns_of(request: &Request) -> Vec<(&'static str, &String)> {
+    let mut patterns = Vec::new();
+
+    if let Some(method_pattern) = request.method_pattern() {
+        patterns.push(("method_pattern", method_pattern));
+    }
+    if let Some(url_pattern) = request.url_pattern() {
+        patterns.push(("url_pattern", url_pattern));
+    }
+    if let Some(url_not_pattern) = request.url_not_pattern() {
+        patterns.push(("url_not_pattern", url_not_pattern));
+    }
+    if let Some(host_pattern) = request.host_pattern() {
+        patterns.push(("host_pattern", host_pattern));
+    }
+    for header_pattern in request.headers_pattern() {
+        patterns.extend(header_patterns("headers_pattern", header_pattern));
+    }
+    if let Some(header_pattern) = request.header_pattern() {
+        patterns.extend(header_patterns("header_pattern", header_pattern));
+    }
+    for value_pattern in request.query_params_pattern().values() {
+        patterns.push(("query_params_pattern", value_pattern));
+    }
+    for value_pattern in request.form_params_pattern().values() {
+        patterns.push(("form_params_pattern", value_pattern));
+    }
+    for part in request.multipart() {
+        if let Some(body_pattern) = part.body_pattern() {
+            patterns.push(("multipart", body_pattern));
+        }
+    }
+    if let Some(body_pattern) = request.body_pattern() {
+        patterns.push(("body_pattern", body_pattern));
+    }
+    for json_path in request.body_json_path() {
+        if let Some(value_pattern) = json_path.matches() {
+            patterns.push(("body_json_path", value_pattern));
+        }
+    }
+    for nested in request.all_of().iter().chain(request.any_of()) {
+        patterns.extend(patterns_of(nested));
+    }
+    if let Some(not) = request.not() {
+        patterns.extend(patterns_of(not));
+    }
+    patterns
+}
+
+fn header_patterns<'a>(
+    field: &'static str,
+    header_pattern: &'a HeaderPattern,
+) -> Vec<(&'static str, &'a String)> {
+    vec![header_pattern.key(), header_pattern.value()]
+        .into_iter()
+        .filter_map(|either| either.right_ref().ok())
+        .map(|pattern| (field, pattern))
+        .collect()
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} mappings loaded, {} problems found",
+            self.mappings,
+            self.problems.len()
+        )?;
+        for problem in &self.problems {
+            writeln!(f, "  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::Mappings;
+    use std::path::Path;
+
+    #[test]
+    fn validate() {
+        match Mappings::validate(Path::new("tests/validation")) {
+            Ok(report) => {
+                assert!(!report.is_valid());
+                assert_eq!(*report.mappings(), 3);
+                assert_eq!(report.problems().len(), 3);
+
+                let messages: Vec<String> = report
+                    .problems()
+                    .iter()
+                    .map(|problem| problem.message().clone())
+                    .collect();
+                assert!(messages
+                    .iter()
+                    .any(|message| message.contains("field `url_pattern`")));
+                assert!(messages
+                    .iter()
+                    .any(|message| message.contains("'missing-response.json' not found")));
+                assert!(messages
+                    .iter()
+                    .any(|message| message.starts_with("invalid mapping")));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn validate_missing_path() {
+        assert!(Mappings::validate(Path::new("tests/not-there")).is_err());
+    }
+}
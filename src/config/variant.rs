@@ -0,0 +1,215 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Content negotiation configuration
+use crate::error::Error::{self, InvalidResponseConfig};
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// A representation of a response body in one media type.  The variant sent is chosen by the
+/// request's `Accept` header.
+///
+/// ```toml
+/// [[response.variants]]
+/// media_type = "application/json"
+/// body = "{\"id\": 1}"
+///
+/// [[response.variants]]
+/// media_type = "application/xml"
+/// body_file_name = "order.xml"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct Variant {
+    /// The media type of this variant, sent as the `Content-Type`.
+    #[get = "pub"]
+    media_type: String,
+    /// The body, inline.  Mutually exclusive with `body_file_name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body: Option<String>,
+    /// The file to use as the body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body_file_name: Option<String>,
+}
+
+impl Variant {
+    /// A variant with an inline body.
+    pub fn body<M: Into<String>, B: Into<String>>(media_type: M, body: B) -> Self {
+        Self {
+            media_type: media_type.into(),
+            body: Some(body.into()),
+            body_file_name: None,
+        }
+    }
+
+    /// A variant with a file as the body.
+    pub fn file<M: Into<String>, F: Into<String>>(media_type: M, body_file_name: F) -> Self {
+        Self {
+            media_type: media_type.into(),
+            body: None,
+            body_file_name: Some(body_file_name.into()),
+        }
+    }
+
+    /// Validate the parts of the variant configuration that can't be checked by
+    /// deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        let mut parts = self.media_type.splitn(2, '/');
+        match (parts.next(), parts.next()) {
+            (Some(kind), Some(subtype)) if !kind.is_empty() && !subtype.is_empty() => {}
+            _ => {
+                return Err(InvalidResponseConfig(format!(
+                    "invalid variant media type '{}'",
+                    self.media_type
+                )));
+            }
+        }
+
+        if self.body.is_some() == self.body_file_name.is_some() {
+            Err(InvalidResponseConfig(format!(
+                "variant '{}' needs exactly one of `body` or `body_file_name`",
+                self.media_type
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// How much a media range from an `Accept` header prefers this variant, ranked by how
+    /// specific the range is: an exact match beats `type/*`, which beats `*/*`.
+    fn specificity(&self, range: &str) -> Option<u8> {
+        let media_type = self
+            .media_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let range = range.to_ascii_lowercase();
+
+        if range == media_type {
+            Some(2)
+        } else if range.ends_with("/*") && media_type.starts_with(&range[..range.len() - 1]) {
+            Some(1)
+        } else if range == "*/*" {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Choose the variant to send for a request with the given `Accept` header.  The variant with
+/// the highest quality wins, with ties going to the first configured.  A request without an
+/// `Accept` header gets the first variant, and `None` means no variant is acceptable.
+crate fn negotiate<'a>(variants: &'a [Variant], accept: Option<&str>) -> Option<&'a Variant> {
+    let accept = match accept {
+        Some(accept) if !accept.trim().is_empty() => accept,
+        _ => return variants.first(),
+    };
+
+    let ranges: Vec<(String, f32)> = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let name = params.next()?.trim().to_string();
+            let quality = params
+                .filter_map(|param| {
+                    let mut pair = param.splitn(2, '=');
+                    match (pair.next(), pair.next()) {
+                        (Some(key), Some(value)) if key.trim() == "q" => {
+                            value.trim().parse::<f32>().ok()
+                        }
+                        _ => None,
+                    }
+                })
+                .next()
+                .unwrap_or(1.0);
+
+            if name.is_empty() {
+                None
+            } else {
+                Some((name, quality))
+            }
+        })
+        .collect();
+
+    let quality = |variant: &Variant| {
+        ranges
+            .iter()
+            .filter_map(|(range, quality)| {
+                variant
+                    .specificity(range)
+                    .map(|specificity| (specificity, *quality))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map_or(0.0, |(_, quality)| quality)
+    };
+
+    let mut best: Option<(&Variant, f32)> = None;
+    for variant in variants {
+        let q = quality(variant);
+        if q > 0.0 && best.map_or(true, |(_, best_q)| q > best_q) {
+            best = Some((variant, q));
+        }
+    }
+    best.map(|(variant, _)| variant)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{negotiate, Variant};
+
+    #[test]
+    fn deserialize() {
+        match toml::from_str::<Variant>(
+            "media_type = \"application/xml\"\nbody_file_name = \"order.xml\"",
+        ) {
+            Ok(variant) => {
+                assert_eq!(variant, Variant::file("application/xml", "order.xml"));
+                assert!(variant.validate().is_ok());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert!(Variant::body("json", "{}").validate().is_err());
+        assert!(Variant::default().validate().is_err());
+        assert!(Variant {
+            body_file_name: Some("order.json".to_string()),
+            ..Variant::body("application/json", "{}")
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn negotiation() {
+        let json = Variant::body("application/json", "{}");
+        let xml = Variant::body("application/xml", "<order/>");
+        let text = Variant::body("text/plain", "order");
+        let variants = vec![json.clone(), xml.clone(), text.clone()];
+
+        assert_eq!(negotiate(&variants, Some("application/xml")), Some(&xml));
+        assert_eq!(negotiate(&variants, Some("text/*")), Some(&text));
+        assert_eq!(negotiate(&variants, Some("*/*")), Some(&json));
+        assert_eq!(
+            negotiate(&variants, Some("*/*;q=0.1, application/json;q=0")),
+            Some(&xml)
+        );
+        assert_eq!(
+            negotiate(&variants, Some("application/*;q=0.5, text/plain;q=0.8")),
+            Some(&text)
+        );
+        assert_eq!(negotiate(&variants, Some("")), Some(&json));
+        assert_eq!(negotiate(&variants, Some("image/png")), None);
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Virtual host configuration
+use crate::error::Error::{self, InvalidRuntimeConfig};
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A separate set of mappings, with its own state, for the requests sent to a host name or under
+/// a path prefix, so one server can stand in for several services.
+///
+/// The host is matched case-insensitively, ignoring the port, and `*.example.com` matches any
+/// subdomain of `example.com`.  The path prefix is removed from the request path before the
+/// mappings are matched.  When both are given, a request must match both.
+///
+/// ```toml
+/// [[virtual_hosts]]
+/// host = "orders.example.com"
+/// mappings_path = "services/orders"
+///
+/// [[virtual_hosts]]
+/// path_prefix = "/payments"
+/// mappings_path = "services/payments"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct VirtualHost {
+    /// The host name requests are sent to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    host: Option<String>,
+    /// The path prefix requests are sent under, i.e. `/payments`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    path_prefix: Option<String>,
+    /// The path to the `mappings` and `files` directories for this virtual host, as with the
+    /// root `path`.
+    #[get = "pub"]
+    mappings_path: String,
+}
+
+impl VirtualHost {
+    /// A virtual host for the requests sent to a host name.
+    pub fn host<H: Into<String>, P: Into<String>>(host: H, mappings_path: P) -> Self {
+        Self {
+            host: Some(host.into()),
+            path_prefix: None,
+            mappings_path: mappings_path.into(),
+        }
+    }
+
+    /// A virtual host for the requests sent under a path prefix.
+    pub fn path_prefix<R: Into<String>, P: Into<String>>(path_prefix: R, mappings_path: P) -> Self {
+        Self {
+            host: None,
+            path_prefix: Some(path_prefix.into()),
+            mappings_path: mappings_path.into(),
+        }
+    }
+
+    /// Also require the path prefix on a virtual host for a host name.
+    pub fn with_path_prefix<R: Into<String>>(mut self, path_prefix: R) -> Self {
+        self.path_prefix = Some(path_prefix.into());
+        self
+    }
+
+    /// The path to the response files for this virtual host, `<mappings_path>/files`.
+    pub fn files_path(&self) -> PathBuf {
+        Path::new(&self.mappings_path).join("files")
+    }
+
+    /// Validate the parts of the virtual host configuration that can't be checked by
+    /// deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        if self.host.is_none() && self.path_prefix.is_none() {
+            Err(InvalidRuntimeConfig(format!(
+                "virtual host '{}' needs a `host` or a `path_prefix`",
+                self.mappings_path
+            )))
+        } else if self
+            .path_prefix
+            .as_ref()
+            .map_or(false, |prefix| !prefix.starts_with('/') || prefix.len() < 2)
+        {
+            Err(InvalidRuntimeConfig(format!(
+                "virtual host '{}' has an invalid `path_prefix`",
+                self.mappings_path
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Is a request for the given host, if known, and path sent to this virtual host?
+    pub fn matches(&self, host: Option<&str>, path: &str) -> bool {
+        let host_matches = self.host.as_ref().map_or(true, |expected| {
+            host.map(strip_port).map_or(false, |actual| {
+                if expected.starts_with("*.") {
+                    let suffix = &expected[1..];
+                    actual.len() > suffix.len()
+                        && actual[actual.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+                } else {
+                    actual.eq_ignore_ascii_case(expected)
+                }
+            })
+        });
+
+        host_matches && self.strip_prefix(path).is_some()
+    }
+
+    /// The path with the path prefix removed, or `None` if the path isn't under it.  A path that
+    /// is just the prefix becomes `/`.
+    crate fn strip_prefix<'a>(&self, path: &'a str) -> Option<&'a str> {
+        match &self.path_prefix {
+            None => Some(path),
+            Some(prefix) => {
+                let prefix = prefix.trim_end_matches('/');
+                if !path.starts_with(prefix) {
+                    None
+                } else if path.len() == prefix.len() {
+                    Some("/")
+                } else if path[prefix.len()..].starts_with('/') {
+                    Some(&path[prefix.len()..])
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+fn strip_port(host: &str) -> &str {
+    match host.rfind(':') {
+        Some(idx) if !host[idx + 1..].contains(']') => &host[..idx],
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VirtualHost;
+    use std::path::PathBuf;
+
+    #[test]
+    fn deserialize() {
+        match toml::from_str::<VirtualHost>(
+            "host = \"orders.example.com\"\nmappings_path = \"services/orders\"",
+        ) {
+            Ok(virtual_host) => {
+                assert_eq!(
+                    virtual_host,
+                    VirtualHost::host("orders.example.com", "services/orders")
+                );
+                assert!(virtual_host.validate().is_ok());
+                assert_eq!(
+                    virtual_host.files_path(),
+                    PathBuf::from("services/orders/files")
+                );
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert!(VirtualHost::default().validate().is_err());
+        assert!(VirtualHost::path_prefix("payments", "services/payments")
+            .validate()
+            .is_err());
+        assert!(VirtualHost::path_prefix("/", "services/payments")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn matches() {
+        let orders = VirtualHost::host("orders.example.com", "services/orders");
+        assert!(orders.matches(Some("ORDERS.example.com:8080"), "/orders"));
+        assert!(!orders.matches(Some("payments.example.com"), "/orders"));
+        assert!(!orders.matches(None, "/orders"));
+
+        let wildcard = VirtualHost::host("*.example.com", "services/any");
+        assert!(wildcard.matches(Some("orders.example.com"), "/"));
+        assert!(!wildcard.matches(Some("example.com"), "/"));
+
+        let payments = VirtualHost::path_prefix("/payments", "services/payments");
+        assert!(payments.matches(None, "/payments/123"));
+        assert!(!payments.matches(None, "/paymentsx"));
+        assert_eq!(payments.strip_prefix("/payments/123"), Some("/123"));
+        assert_eq!(payments.strip_prefix("/payments"), Some("/"));
+        assert_eq!(payments.strip_prefix("/orders"), None);
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Webhook configuration
+use crate::config::Header;
+use crate::error::Error::{self, InvalidWebhook};
+use getset::Getters;
+use http::Method;
+use serde_derive::{Deserialize, Serialize};
+
+/// An outbound HTTP request sent after a mapping's response is served, i.e. to mock an
+/// asynchronous API that calls the client back.
+///
+/// The `url`, header values and `body` are rendered as [templates](crate::template) against the
+/// request that matched the mapping.
+///
+/// ```toml
+/// [[webhooks]]
+/// url = "{{request.header \"X-Callback-Url\"}}"
+/// delay_ms = 500
+/// body = "{\"order\": \"{{request.pathSegment 1}}\", \"status\": \"shipped\"}"
+///
+/// [[webhooks.headers]]
+/// key = "Content-Type"
+/// value = "application/json"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct Webhook {
+    /// The http method of the request.  Defaults to `POST`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    method: Option<String>,
+    /// The url to send the request to.
+    #[get = "pub"]
+    url: String,
+    /// The http headers to send with the request.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[get = "pub"]
+    headers: Vec<Header>,
+    /// The request body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    body: Option<String>,
+    /// How long to wait, in milliseconds, after the response is served before sending the
+    /// request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    delay_ms: Option<u64>,
+}
+
+impl Webhook {
+    /// Create a new webhook builder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use libdeadmock::config::{Header, Webhook};
+    /// #
+    /// # fn main() {
+    /// let webhook = Webhook::builder()
+    ///     .url("http://localhost:9000/callback")
+    ///     .headers(Header::new("Content-Type", "application/json"))
+    ///     .body("{\"status\": \"shipped\"}")
+    ///     .delay_ms(500)
+    ///     .build();
+    /// assert_eq!(webhook.delay_ms(), &Some(500));
+    /// # }
+    /// ```
+    pub fn builder() -> WebhookBuilder {
+        WebhookBuilder::default()
+    }
+
+    /// Validate the parts of the webhook configuration that can't be checked by deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        if self.url.is_empty() {
+            Err(InvalidWebhook("field `url` is required".to_string()))
+        } else if let Some(Err(e)) = self
+            .method
+            .as_ref()
+            .map(|method| Method::from_bytes(method.as_bytes()))
+        {
+            Err(InvalidWebhook(format!("invalid method: {}", e)))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A builder for a [`Webhook`](crate::config::Webhook).
+#[derive(Clone, Debug, Default)]
+pub struct WebhookBuilder {
+    inner: Webhook,
+}
+
+impl WebhookBuilder {
+    /// The http method of the request.
+    pub fn method<S: Into<String>>(mut self, method: S) -> Self {
+        self.inner.method = Some(method.into());
+        self
+    }
+
+    /// The url to send the request to.
+    pub fn url<S: Into<String>>(mut self, url: S) -> Self {
+        self.inner.url = url.into();
+        self
+    }
+
+    /// Add an http header to send with the request.
+    pub fn headers(mut self, header: Header) -> Self {
+        self.inner.headers.push(header);
+        self
+    }
+
+    /// The request body.
+    pub fn body<S: Into<String>>(mut self, body: S) -> Self {
+        self.inner.body = Some(body.into());
+        self
+    }
+
+    /// How long to wait, in milliseconds, before sending the request.
+    pub fn delay_ms(mut self, delay_ms: u64) -> Self {
+        self.inner.delay_ms = Some(delay_ms);
+        self
+    }
+
+    /// Build the webhook.
+    pub fn build(self) -> Webhook {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Webhook;
+    use crate::config::Header;
+
+    #[test]
+    fn deserialize() {
+        let toml = "url = \"http://localhost:9000/callback\"\nmethod = \"PUT\"\ndelay_ms = 250\n\n[[headers]]\nkey = \"Content-Type\"\nvalue = \"application/json\"\n";
+        match toml::from_str::<Webhook>(toml) {
+            Ok(webhook) => assert_eq!(
+                webhook,
+                Webhook::builder()
+                    .method("PUT")
+                    .url("http://localhost:9000/callback")
+                    .headers(Header::new("Content-Type", "application/json"))
+                    .delay_ms(250)
+                    .build()
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert!(Webhook::builder()
+            .url("http://localhost:9000/callback")
+            .build()
+            .validate()
+            .is_ok());
+        assert!(Webhook::builder().build().validate().is_err());
+        assert!(Webhook::builder()
+            .method("NOT A METHOD")
+            .url("http://localhost:9000/callback")
+            .build()
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! WebSocket configuration
+use crate::error::Error::{self, InvalidWebSocket};
+use getset::Getters;
+use regex::Regex;
+use serde_derive::{Deserialize, Serialize};
+
+/// Upgrade a matching request to a WebSocket, then run a scripted exchange with the client.
+///
+/// The steps are run in order.  Each `send` and `close` step is run straight away, once its
+/// delay has passed, and each `expect` or `expect_pattern` step waits for the next message from
+/// the client.  If that message doesn't match, the connection is closed with status `1008`
+/// (policy violation).  Pings are always answered with a pong.
+///
+/// ```toml
+/// [[websocket.steps]]
+/// expect = "{\"subscribe\": \"prices\"}"
+///
+/// [[websocket.steps]]
+/// send = "{\"price\": 101}"
+///
+/// [[websocket.steps]]
+/// send = "{\"price\": 102}"
+/// delay_ms = 1000
+///
+/// [[websocket.steps]]
+/// close = 1000
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct WebSocket {
+    /// The scripted exchange.
+    #[get = "pub"]
+    steps: Vec<WebSocketStep>,
+}
+
+impl WebSocket {
+    /// Run the given steps once the connection is upgraded.
+    pub fn new(steps: Vec<WebSocketStep>) -> Self {
+        Self { steps }
+    }
+
+    /// Validate the parts of the websocket configuration that can't be checked by
+    /// deserialization.
+    crate fn validate(&self) -> Result<(), Error> {
+        for step in &self.steps {
+            step.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// One step of a scripted WebSocket exchange.  Exactly one of `expect`, `expect_pattern`, `send`
+/// and `close` must be set.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct WebSocketStep {
+    /// Wait for a message from the client equal to this text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    expect: Option<String>,
+    /// Wait for a message from the client matching this regex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    expect_pattern: Option<String>,
+    /// Send this text message to the client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    send: Option<String>,
+    /// Close the connection with this status code, i.e. `1000` for a normal closure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    close: Option<u16>,
+    /// How long to wait, in milliseconds, before a `send` or `close` step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    delay_ms: Option<u64>,
+}
+
+impl WebSocketStep {
+    /// Wait for a message from the client equal to `text`.
+    pub fn expect<S: Into<String>>(text: S) -> Self {
+        Self {
+            expect: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Wait for a message from the client matching the regex `pattern`.
+    pub fn expect_pattern<S: Into<String>>(pattern: S) -> Self {
+        Self {
+            expect_pattern: Some(pattern.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Send `text` to the client, after `delay_ms` milliseconds if given.
+    pub fn send<S: Into<String>>(text: S, delay_ms: Option<u64>) -> Self {
+        Self {
+            send: Some(text.into()),
+            delay_ms,
+            ..Self::default()
+        }
+    }
+
+    /// Close the connection with status `code`, after `delay_ms` milliseconds if given.
+    pub fn close(code: u16, delay_ms: Option<u64>) -> Self {
+        Self {
+            close: Some(code),
+            delay_ms,
+            ..Self::default()
+        }
+    }
+
+    /// Does this step wait for a message from the client?
+    crate fn is_expect(&self) -> bool {
+        self.expect.is_some() || self.expect_pattern.is_some()
+    }
+
+    /// Does the client's message satisfy this step?  Steps that don't wait for a message never
+    /// match.
+    crate fn matches(&self, message: &str) -> bool {
+        if let Some(expect) = &self.expect {
+            expect == message
+        } else if let Some(pattern) = &self.expect_pattern {
+            Regex::new(pattern)
+                .map(|regex| regex.is_match(message))
+                .unwrap_or(false)
+        } else {
+            false
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let actions = [
+            self.expect.is_some(),
+            self.expect_pattern.is_some(),
+            self.send.is_some(),
+            self.close.is_some(),
+        ];
+        if actions.iter().filter(|set| **set).count() != 1 {
+            return Err(InvalidWebSocket(
+                "each step needs exactly one of `expect`, `expect_pattern`, `send` or `close`"
+                    .to_string(),
+            ));
+        }
+
+        if self.is_expect() && self.delay_ms.is_some() {
+            return Err(InvalidWebSocket(
+                "field `delay_ms` can only be used with `send` or `close`".to_string(),
+            ));
+        }
+
+        if let Some(pattern) = &self.expect_pattern {
+            let _ = Regex::new(pattern).map_err(|e| InvalidWebSocket(e.to_string()))?;
+        }
+
+        match self.close {
+            Some(code) if code < 1000 || code >= 5000 => Err(InvalidWebSocket(format!(
+                "close code {} must be between 1000 and 4999",
+                code
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{WebSocket, WebSocketStep};
+
+    #[test]
+    fn deserialize() {
+        let toml = "[[steps]]\nexpect = \"ping\"\n\n[[steps]]\nsend = \"pong\"\ndelay_ms = 100\n\n[[steps]]\nclose = 1000\n";
+        match toml::from_str::<WebSocket>(toml) {
+            Ok(websocket) => assert_eq!(
+                websocket,
+                WebSocket::new(vec![
+                    WebSocketStep::expect("ping"),
+                    WebSocketStep::send("pong", Some(100)),
+                    WebSocketStep::close(1000, None),
+                ])
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn matches() {
+        assert!(WebSocketStep::expect("ping").matches("ping"));
+        assert!(!WebSocketStep::expect("ping").matches("pong"));
+        assert!(WebSocketStep::expect_pattern("^sub.*prices").matches("subscribe prices"));
+        assert!(!WebSocketStep::send("ping", None).matches("ping"));
+    }
+
+    #[test]
+    fn validate() {
+        assert!(WebSocket::new(vec![
+            WebSocketStep::expect_pattern("^ping$"),
+            WebSocketStep::close(1000, Some(100)),
+        ])
+        .validate()
+        .is_ok());
+        assert!(WebSocket::new(vec![WebSocketStep::default()])
+            .validate()
+            .is_err());
+        assert!(WebSocket::new(vec![WebSocketStep::expect_pattern("(")])
+            .validate()
+            .is_err());
+        assert!(WebSocket::new(vec![WebSocketStep::close(999, None)])
+            .validate()
+            .is_err());
+        assert!(WebSocket::new(vec![WebSocketStep {
+            delay_ms: Some(100),
+            ..WebSocketStep::expect("ping")
+        }])
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Weighted response configuration
+use crate::config::Response;
+use crate::error::Error;
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// One of several candidate responses on a mapping.  A candidate is chosen at random for each
+/// request, in proportion to its weight.
+///
+/// ```toml
+/// [[responses]]
+/// weight = 90
+///
+/// [responses.response]
+/// status = 200
+/// body_file_name = "orders.json"
+///
+/// [[responses]]
+/// weight = 10
+///
+/// [responses.response]
+/// status = 503
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct WeightedResponse {
+    /// The relative weight of this response.
+    #[get = "pub"]
+    weight: u32,
+    /// The response configuration.
+    #[get = "pub"]
+    response: Response,
+}
+
+impl WeightedResponse {
+    /// Create a new weighted response.
+    pub fn new(weight: u32, response: Response) -> Self {
+        Self { weight, response }
+    }
+
+    /// Resolve the `${ENV_VAR}` placeholders in the response.
+    crate fn interpolate_env(&mut self) -> Result<(), Error> {
+        self.response.interpolate_env()
+    }
+}
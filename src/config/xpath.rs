@@ -0,0 +1,47 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! XPath request body matching configuration
+use getset::Getters;
+use serde_derive::{Deserialize, Serialize};
+
+/// An XPath 1.0 expression evaluated against an XML request body.
+///
+/// The body matches if the expression selects at least one node (or evaluates to `true`, a
+/// non-empty string, or a non-zero number).  With `equal_to`, the string value of one of the
+/// selected nodes must equal it.  Namespace prefixes used in the expression are declared with
+/// `xml_namespaces` on the request.
+///
+/// ```toml
+/// [request.xml_namespaces]
+/// soap = "http://schemas.xmlsoap.org/soap/envelope/"
+///
+/// [[request.xpath]]
+/// expression = "/soap:Envelope/soap:Body/GetOrder/id"
+/// equal_to = "12345"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, Hash, PartialEq, Serialize)]
+pub struct XPath {
+    /// The XPath expression, i.e. `/order/id`.
+    #[get = "pub"]
+    expression: String,
+    /// The value the string value of a selected node must equal.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    equal_to: Option<String>,
+}
+
+impl XPath {
+    /// Create a new XPath expression, optionally with an expected value.
+    pub fn new<S: Into<String>>(expression: S, equal_to: Option<String>) -> Self {
+        Self {
+            expression: expression.into(),
+            equal_to,
+        }
+    }
+}
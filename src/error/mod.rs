@@ -7,49 +7,197 @@
 // modified, or distributed except according to those terms.
 
 //! Errors generated by the library
+use crate::config::Ambiguity;
 use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 /// Errors generated by the library
 pub enum Error {
+    ///
+    AddrParse(std::net::AddrParseError),
+    ///
+    AmbiguousMappings(Vec<Ambiguity>),
+    ///
+    EnvVarNotFound(String),
+    ///
+    FragmentCycle(PathBuf),
+    ///
+    GlobPattern(glob::PatternError),
     ///
     InvalidHeaderName(http::header::InvalidHeaderName),
     ///
     InvalidHeaderValue(http::header::InvalidHeaderValue),
     ///
+    InvalidImport(String),
+    ///
+    InvalidListenerConfig(String),
+    ///
+    InvalidMapping {
+        /// The path to the mapping file that failed to load.
+        path: PathBuf,
+        /// The 1-based line of the error, if known.
+        line: Option<usize>,
+        /// The 1-based column of the error, if known.
+        column: Option<usize>,
+        /// The field at fault, if known.
+        field: Option<String>,
+        /// The underlying deserialization error message.
+        message: String,
+    },
+    ///
     InvalidProxyConfig,
     ///
+    InvalidRequestConfig(String),
+    ///
+    InvalidResponseConfig(String),
+    ///
+    InvalidRuntimeConfig(String),
+    ///
+    InvalidTlsConfig(String),
+    ///
+    InvalidWebSocket(String),
+    ///
+    InvalidWebhook(String),
+    ///
     IO(std::io::Error),
     ///
+    Json(serde_json::Error),
+    ///
     MappingKeyCollision,
     ///
     MappingNotFound,
     ///
+    Regex(regex::Error),
+    ///
+    Template(handlebars::TemplateRenderError),
+    ///
     TomlDe(toml::de::Error),
+    ///
+    TomlSer(toml::ser::Error),
+    ///
+    Yaml(serde_yaml::Error),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Error::AddrParse(e) => write!(f, "{}", e),
+            Error::AmbiguousMappings(ambiguities) => {
+                write!(f, "{} ambiguous mappings", ambiguities.len())?;
+                for ambiguity in ambiguities {
+                    write!(f, "\n  {}", ambiguity)?;
+                }
+                Ok(())
+            }
+            Error::EnvVarNotFound(name) => write!(f, "environment variable '{}' not found!", name),
+            Error::InvalidImport(e) => write!(f, "invalid import: {}", e),
+            Error::InvalidListenerConfig(e) => write!(f, "invalid listener configuration: {}", e),
+            Error::InvalidMapping {
+                path,
+                line,
+                column,
+                field,
+                message,
+            } => {
+                write!(f, "invalid mapping '{}'", path.display())?;
+                if let (Some(line), Some(column)) = (line, column) {
+                    write!(f, " at line {}, column {}", line, column)?;
+                }
+                if let Some(field) = field {
+                    write!(f, " (field '{}')", field)?;
+                }
+                write!(f, ": {}", message)
+            }
             Error::InvalidProxyConfig => write!(f, "invalid proxy configuration!"),
+            Error::InvalidRequestConfig(e) => write!(f, "invalid request configuration: {}", e),
+            Error::InvalidResponseConfig(e) => write!(f, "invalid response configuration: {}", e),
+            Error::InvalidRuntimeConfig(e) => write!(f, "invalid runtime configuration: {}", e),
+            Error::InvalidTlsConfig(e) => write!(f, "invalid TLS configuration: {}", e),
+            Error::InvalidWebSocket(e) => write!(f, "invalid websocket configuration: {}", e),
+            Error::InvalidWebhook(e) => write!(f, "invalid webhook: {}", e),
+            Error::FragmentCycle(path) => {
+                write!(f, "fragment '{}' includes itself!", path.display())
+            }
+            Error::GlobPattern(e) => write!(f, "{}", e),
             Error::IO(e) => write!(f, "{}", e),
+            Error::Json(e) => write!(f, "{}", e),
+            Error::Regex(e) => write!(f, "{}", e),
+            Error::Template(e) => write!(f, "{}", e),
+            Error::TomlDe(e) => write!(f, "{}", e),
+            Error::TomlSer(e) => write!(f, "{}", e),
+            Error::Yaml(e) => write!(f, "{}", e),
             _ => write!(f, ""),
         }
     }
 }
 
+impl Error {
+    /// The 1-based line and column of a deserialization error, if known.
+    crate fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Json(e) => Some((e.line(), e.column())),
+            Error::TomlDe(e) => e.line_col().map(|(line, col)| (line + 1, col + 1)),
+            Error::Yaml(e) => e.location().map(|loc| (loc.line(), loc.column() + 1)),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::net::AddrParseError> for Error {
+    fn from(e: std::net::AddrParseError) -> Self {
+        Error::AddrParse(e)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Error::IO(e)
     }
 }
 
+impl From<glob::PatternError> for Error {
+    fn from(e: glob::PatternError) -> Self {
+        Error::GlobPattern(e)
+    }
+}
+
+impl From<regex::Error> for Error {
+    fn from(e: regex::Error) -> Self {
+        Error::Regex(e)
+    }
+}
+
+impl From<handlebars::TemplateRenderError> for Error {
+    fn from(e: handlebars::TemplateRenderError) -> Self {
+        Error::Template(e)
+    }
+}
+
 impl From<toml::de::Error> for Error {
     fn from(e: toml::de::Error) -> Self {
         Error::TomlDe(e)
     }
 }
 
+impl From<toml::ser::Error> for Error {
+    fn from(e: toml::ser::Error) -> Self {
+        Error::TomlSer(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Yaml(e)
+    }
+}
+
 impl From<http::header::InvalidHeaderName> for Error {
     fn from(e: http::header::InvalidHeaderName) -> Self {
         Error::InvalidHeaderName(e)
@@ -46,6 +46,7 @@
 
 // Library Modules
 mod util;
+mod x509;
 
 // Public API
 pub mod config;
@@ -53,3 +54,4 @@ pub mod error;
 pub mod logging;
 pub mod matcher;
 pub mod server;
+pub mod template;
@@ -0,0 +1,132 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP basic authentication matching
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use http::header::AUTHORIZATION;
+use http::Request;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// Decode the `(username, password)` from the `Authorization: Basic` header of a request.
+fn credentials(request: &Request<Vec<u8>>) -> Option<(String, String)> {
+    let authorization = request.headers().get(AUTHORIZATION)?.to_str().ok()?.trim();
+    let mut parts = authorization.splitn(2, ' ');
+    let auth_scheme = parts.next()?;
+
+    if !auth_scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+
+    let decoded = base64::decode(parts.next()?.trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let mut credentials = decoded.splitn(2, ':');
+    let username = credentials.next()?.to_string();
+    let password = credentials.next()?.to_string();
+    Some((username, password))
+}
+
+/// Match the credentials in the `Authorization: Basic` header of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct ExactMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for ExactMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(basic_auth) = request_config.basic_auth() {
+            if let Some((username, password)) = credentials(request) {
+                // The password is deliberately left out of the log.
+                try_trace!(
+                    self.stdout,
+                    "Exact Match (Basic Auth) - Checking user {} against {}",
+                    basic_auth.username(),
+                    username
+                );
+                Ok(Some(
+                    &username == basic_auth.username() && &password == basic_auth.password(),
+                ))
+            } else {
+                try_trace!(
+                    self.stdout,
+                    "Exact Match (Basic Auth) - No basic credentials on the request"
+                );
+                Ok(Some(false))
+            }
+        } else {
+            try_trace!(self.stdout, "Exact Match (Basic Auth) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for ExactMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Exact Match On Basic Auth")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::credentials;
+    use http::Request;
+
+    fn decode(authorization: &str) -> Option<(String, String)> {
+        match Request::builder()
+            .header("Authorization", authorization)
+            .body(vec![])
+        {
+            Ok(request) => credentials(&request),
+            Err(e) => {
+                assert!(false, e.to_string());
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn decode_credentials() {
+        assert_eq!(
+            decode("Basic YWRtaW46czNjcjN0"),
+            Some(("admin".to_string(), "s3cr3t".to_string()))
+        );
+        assert_eq!(
+            decode("basic dXNlcjpwYTpzcw=="),
+            Some(("user".to_string(), "pa:ss".to_string()))
+        );
+        assert_eq!(decode("Bearer YWRtaW46czNjcjN0"), None);
+        assert_eq!(decode("Basic not-base64!"), None);
+        assert_eq!(decode("Basic YWRtaW4="), None);
+    }
+}
@@ -0,0 +1,539 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request body matching
+use crate::config::{self, Request as RequestConfig};
+use crate::error::Error;
+use crate::matcher::json_path;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use crate::util;
+use http::header::CONTENT_LENGTH;
+use http::Request;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// Exactly match the body of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct ExactMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for ExactMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for ExactMatch {
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(body) = request_config.body() {
+            try_trace!(
+                self.stdout,
+                "Exact Match (Body) - Checking {} against {}",
+                body,
+                String::from_utf8_lossy(request.body())
+            );
+            Ok(Some(request.body().as_slice() == body.as_bytes()))
+        } else {
+            try_trace!(self.stdout, "Exact Match (Body) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for ExactMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Exact Match On Body")
+    }
+}
+
+/// Pattern match the body of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct PatternMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for PatternMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for PatternMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(body_pattern) = request_config.body_pattern() {
+            let body = String::from_utf8_lossy(request.body());
+            try_trace!(
+                self.stdout,
+                "Pattern Match (Body) - Checking {} against {}",
+                body_pattern,
+                body
+            );
+            let regex = util::pattern(body_pattern)?;
+            Ok(Some(regex.is_match(&body)))
+        } else {
+            try_trace!(self.stdout, "Pattern Match (Body) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for PatternMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pattern Match On Body")
+    }
+}
+
+/// Match JSONPath expressions against the JSON body of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct JsonPath {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl JsonPath {
+    fn is_json_path_match(
+        &self,
+        body: &Value,
+        json_path: &config::JsonPath,
+    ) -> Result<bool, Error> {
+        let selected = match json_path::select(body, json_path.expression()) {
+            Ok(selected) => selected,
+            Err(e) => {
+                try_trace!(self.stdout, "JSONPath Match (Body) - {}", e);
+                return Ok(false);
+            }
+        };
+        let regex = json_path
+            .matches()
+            .as_ref()
+            .map(|value_pattern| util::pattern(value_pattern))
+            .transpose()?;
+
+        Ok(selected.iter().any(|value| {
+            let actual = match value {
+                Value::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+            json_path
+                .equal_to()
+                .as_ref()
+                .map_or(true, |expected| &actual == expected)
+                && regex.as_ref().map_or(true, |regex| regex.is_match(&actual))
+        }))
+    }
+}
+
+impl Slogger for JsonPath {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for JsonPath {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.body_json_path().is_empty() {
+            try_trace!(self.stdout, "JSONPath Match (Body) - No check performed");
+            Ok(None)
+        } else if let Ok(body) = serde_json::from_slice::<Value>(request.body()) {
+            try_trace!(
+                self.stdout,
+                "JSONPath Match (Body) - Checking {:?} against {}",
+                request_config.body_json_path(),
+                body
+            );
+            for json_path in request_config.body_json_path() {
+                if !self.is_json_path_match(&body, json_path)? {
+                    return Ok(Some(false));
+                }
+            }
+            Ok(Some(true))
+        } else {
+            try_trace!(self.stdout, "JSONPath Match (Body) - Body is not JSON");
+            Ok(Some(false))
+        }
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JSONPath Match On Body")
+    }
+}
+
+/// Structurally match the JSON body of a HTTP request, ignoring key order and whitespace.
+#[derive(Clone, Debug, Default)]
+pub struct EqualToJson {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for EqualToJson {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for EqualToJson {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(equal_to_json) = request_config.equal_to_json() {
+            let expected = serde_json::from_str::<Value>(equal_to_json.json());
+            let actual = serde_json::from_slice::<Value>(request.body());
+            try_trace!(
+                self.stdout,
+                "Equal To JSON (Body) - Checking {:?} against {:?}",
+                expected,
+                actual
+            );
+
+            if let (Ok(expected), Ok(actual)) = (expected, actual) {
+                Ok(Some(json_equal(&expected, &actual, equal_to_json)))
+            } else {
+                Ok(Some(false))
+            }
+        } else {
+            try_trace!(self.stdout, "Equal To JSON (Body) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for EqualToJson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Equal To JSON On Body")
+    }
+}
+
+/// Match the size of the body of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct Size {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for Size {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for Size {
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(body_size) = request_config.body_size() {
+            let size = request
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|content_length| content_length.to_str().ok())
+                .and_then(|content_length| content_length.trim().parse::<u64>().ok())
+                .unwrap_or(request.body().len() as u64);
+            try_trace!(
+                self.stdout,
+                "Size Match (Body) - Checking {} against {:?}",
+                size,
+                body_size
+            );
+            Ok(Some(body_size.contains(size)))
+        } else {
+            try_trace!(self.stdout, "Size Match (Body) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Size Match On Body")
+    }
+}
+
+/// Match the SHA-256 or MD5 hash of the body of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct Hash {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for Hash {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for Hash {
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.body_sha256().is_none() && request_config.body_md5().is_none() {
+            try_trace!(self.stdout, "Hash Match (Body) - No check performed");
+            return Ok(None);
+        }
+
+        let sha256_match = request_config
+            .body_sha256()
+            .as_ref()
+            .map_or(true, |expected| {
+                let actual = format!("{:x}", Sha256::digest(request.body()));
+                try_trace!(
+                    self.stdout,
+                    "Hash Match (Body) - Checking SHA-256 {} against {}",
+                    expected,
+                    actual
+                );
+                expected.eq_ignore_ascii_case(&actual)
+            });
+        let md5_match = request_config.body_md5().as_ref().map_or(true, |expected| {
+            let actual = format!("{:x}", md5::compute(request.body()));
+            try_trace!(
+                self.stdout,
+                "Hash Match (Body) - Checking MD5 {} against {}",
+                expected,
+                actual
+            );
+            expected.eq_ignore_ascii_case(&actual)
+        });
+
+        Ok(Some(sha256_match && md5_match))
+    }
+}
+
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hash Match On Body")
+    }
+}
+
+/// Match a HTTP request body that contains every one of a list of substrings.
+#[derive(Clone, Debug, Default)]
+pub struct Contains {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for Contains {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for Contains {
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.body_contains().is_empty() {
+            try_trace!(self.stdout, "Contains Match (Body) - No check performed");
+            Ok(None)
+        } else {
+            let body = String::from_utf8_lossy(request.body());
+            try_trace!(
+                self.stdout,
+                "Contains Match (Body) - Checking {:?} against {}",
+                request_config.body_contains(),
+                body
+            );
+            Ok(Some(
+                request_config
+                    .body_contains()
+                    .iter()
+                    .all(|substring| body.contains(&substring[..])),
+            ))
+        }
+    }
+}
+
+impl fmt::Display for Contains {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Contains Match On Body")
+    }
+}
+
+fn json_equal(expected: &Value, actual: &Value, options: &config::EqualToJson) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            (*options.ignore_extra_fields() || expected.len() == actual.len())
+                && expected.iter().all(|(key, expected)| {
+                    actual
+                        .get(key)
+                        .map_or(false, |actual| json_equal(expected, actual, options))
+                })
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                false
+            } else if *options.ignore_array_order() {
+                // Pair each expected element with the first unused equal actual element.
+                let mut used = vec![false; actual.len()];
+                expected.iter().all(|expected| {
+                    if let Some(idx) = actual.iter().enumerate().position(|(idx, actual)| {
+                        !used[idx] && json_equal(expected, actual, options)
+                    }) {
+                        used[idx] = true;
+                        true
+                    } else {
+                        false
+                    }
+                })
+            } else {
+                expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| json_equal(expected, actual, options))
+            }
+        }
+        _ => expected == actual,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::json_equal;
+    use crate::config::EqualToJson;
+    use serde_json::json;
+
+    #[test]
+    fn key_order() {
+        let options = EqualToJson::default();
+        assert!(json_equal(
+            &json!({"a": 1, "b": [1, 2]}),
+            &json!({"b": [1, 2], "a": 1}),
+            &options
+        ));
+        assert!(!json_equal(
+            &json!({"a": 1}),
+            &json!({"a": 1, "b": 2}),
+            &options
+        ));
+        assert!(!json_equal(
+            &json!({"b": [1, 2]}),
+            &json!({"b": [2, 1]}),
+            &options
+        ));
+    }
+
+    #[test]
+    fn ignore_extra_fields() {
+        let options = EqualToJson::new("", true, false);
+        assert!(json_equal(
+            &json!({"a": {"b": 1}}),
+            &json!({"a": {"b": 1, "c": 2}, "d": 3}),
+            &options
+        ));
+        assert!(!json_equal(
+            &json!({"a": {"b": 1}}),
+            &json!({"a": {"c": 2}}),
+            &options
+        ));
+    }
+
+    #[test]
+    fn ignore_array_order() {
+        let options = EqualToJson::new("", false, true);
+        assert!(json_equal(
+            &json!([1, {"a": 2}, 1]),
+            &json!([{"a": 2}, 1, 1]),
+            &options
+        ));
+        assert!(!json_equal(&json!([1, 1, 2]), &json!([1, 2, 2]), &options));
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request TLS client certificate matching
+use crate::config::{ClientCert, Request as RequestConfig};
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use crate::x509::ClientCertificate;
+use http::Request;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// Match the certificate the client presented during the TLS handshake on the subject, a subject
+/// alternative name, or the fingerprint.
+#[derive(Clone, Debug, Default)]
+pub struct ExactMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for ExactMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(client_cert) = request_config.client_cert() {
+            let actual = request.extensions().get::<ClientCertificate>();
+            try_trace!(
+                self.stdout,
+                "Exact Match (Client Certificate) - Checking {:?} against {:?}",
+                client_cert,
+                actual
+            );
+            Ok(Some(
+                actual.map_or(false, |actual| is_match(client_cert, actual)),
+            ))
+        } else {
+            try_trace!(
+                self.stdout,
+                "Exact Match (Client Certificate) - No check performed"
+            );
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for ExactMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Exact Match On Client Certificate")
+    }
+}
+
+fn is_match(client_cert: &ClientCert, actual: &ClientCertificate) -> bool {
+    client_cert
+        .subject()
+        .as_ref()
+        .map_or(true, |subject| *subject == actual.subject)
+        && client_cert
+            .san()
+            .as_ref()
+            .map_or(true, |san| actual.subject_alt_names.contains(san))
+        && client_cert
+            .fingerprint()
+            .as_ref()
+            .map_or(true, |fingerprint| {
+                fingerprint.replace(':', "").to_ascii_lowercase() == actual.fingerprint
+            })
+}
+
+#[cfg(test)]
+mod test {
+    use super::is_match;
+    use crate::config::ClientCert;
+    use crate::x509::test::test_client_certificate;
+
+    #[test]
+    fn subject_san_and_fingerprint() {
+        let actual = test_client_certificate();
+        let mut client_cert = ClientCert::default();
+        assert!(is_match(&client_cert, &actual));
+
+        let _ = client_cert.set_subject(Some("CN=localhost".to_string()));
+        let _ = client_cert.set_san(Some("127.0.0.1".to_string()));
+        let _ = client_cert.set_fingerprint(Some(
+            "2A:FD:6A:D4:E8:29:0C:F0:7B:BC:53:76:38:20:03:15:E8:C3:40:53:17:8C:B2:A7:61:DE:CE:E2:76:31:51:64"
+                .to_string(),
+        ));
+        assert!(is_match(&client_cert, &actual));
+
+        let _ = client_cert.set_san(Some("billing.acme.internal".to_string()));
+        assert!(!is_match(&client_cert, &actual));
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `all_of`, `any_of` and `not` request matching
+//!
+//! Each nested request configuration is checked with the same enabled matchers as the mapping it
+//! belongs to, combinators included, so they can be nested to any depth.  A nested
+//! configuration with nothing to check never matches.
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{Enabled, MatchCost, Matcher, RequestMatch, Slogger};
+use http::Request;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// Match a HTTP request if it matches every one of the nested request configurations.
+#[derive(Clone, Debug)]
+pub struct AllOf {
+    enabled: Enabled,
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl AllOf {
+    /// Create a new `all_of` matcher, checking nested configurations with the given matchers.
+    pub fn new(enabled: Enabled) -> Self {
+        Self {
+            enabled,
+            stdout: None,
+            stderr: None,
+        }
+    }
+}
+
+impl Slogger for AllOf {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for AllOf {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.all_of().is_empty() {
+            try_trace!(self.stdout, "All Of - No check performed");
+            Ok(None)
+        } else {
+            try_trace!(
+                self.stdout,
+                "All Of - Checking {} nested configurations",
+                request_config.all_of().len()
+            );
+            let matcher = Matcher::new(self.enabled, self.stdout.clone(), self.stderr.clone());
+            Ok(Some(request_config.all_of().iter().all(|nested| {
+                matcher.evaluate(request, nested) == Some(true)
+            })))
+        }
+    }
+}
+
+impl fmt::Display for AllOf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "All Of")
+    }
+}
+
+/// Match a HTTP request if it matches at least one of the nested request configurations.
+#[derive(Clone, Debug)]
+pub struct AnyOf {
+    enabled: Enabled,
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl AnyOf {
+    /// Create a new `any_of` matcher, checking nested configurations with the given matchers.
+    pub fn new(enabled: Enabled) -> Self {
+        Self {
+            enabled,
+            stdout: None,
+            stderr: None,
+        }
+    }
+}
+
+impl Slogger for AnyOf {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for AnyOf {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.any_of().is_empty() {
+            try_trace!(self.stdout, "Any Of - No check performed");
+            Ok(None)
+        } else {
+            try_trace!(
+                self.stdout,
+                "Any Of - Checking {} nested configurations",
+                request_config.any_of().len()
+            );
+            let matcher = Matcher::new(self.enabled, self.stdout.clone(), self.stderr.clone());
+            Ok(Some(request_config.any_of().iter().any(|nested| {
+                matcher.evaluate(request, nested) == Some(true)
+            })))
+        }
+    }
+}
+
+impl fmt::Display for AnyOf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Any Of")
+    }
+}
+
+/// Match a HTTP request if it doesn't match the nested request configuration.
+#[derive(Clone, Debug)]
+pub struct Not {
+    enabled: Enabled,
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Not {
+    /// Create a new `not` matcher, checking the nested configuration with the given matchers.
+    pub fn new(enabled: Enabled) -> Self {
+        Self {
+            enabled,
+            stdout: None,
+            stderr: None,
+        }
+    }
+}
+
+impl Slogger for Not {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for Not {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(nested) = request_config.not() {
+            try_trace!(self.stdout, "Not - Checking the nested configuration");
+            let matcher = Matcher::new(self.enabled, self.stdout.clone(), self.stderr.clone());
+            Ok(Some(matcher.evaluate(request, nested) == Some(false)))
+        } else {
+            try_trace!(self.stdout, "Not - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for Not {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Not")
+    }
+}
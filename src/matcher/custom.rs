@@ -0,0 +1,142 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! User provided request matching
+//!
+//! Embedding applications register their own [`RequestMatch`](crate::matcher::RequestMatch)
+//! implementations by name with [`register_matcher`](crate::matcher::register_matcher), and a
+//! mapping opts in with the `custom_matcher` field of its request configuration.
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use http::Request;
+use lazy_static::lazy_static;
+use slog::{error, trace, Logger};
+use slog_try::{try_error, try_trace};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+type Registry = HashMap<String, Arc<dyn RequestMatch + Send + Sync>>;
+
+lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(HashMap::new());
+}
+
+/// Register a request matcher under the given name, replacing any matcher already registered
+/// with that name.
+///
+/// The matcher is only checked for mappings that name it in `custom_matcher`.  It is given the
+/// full request configuration of the mapping, and a matcher with nothing to check (returning
+/// `None`) is treated as matching.
+///
+/// # Example
+///
+/// ```
+/// # use http::Request;
+/// # use libdeadmock::config::Request as RequestConfig;
+/// # use libdeadmock::error::Error;
+/// # use libdeadmock::matcher::{register_matcher, RequestMatch};
+/// # use std::fmt;
+/// #
+/// #[derive(Debug)]
+/// struct Signed;
+///
+/// impl RequestMatch for Signed {
+///     fn is_match(
+///         &self,
+///         request: &Request<Vec<u8>>,
+///         _request_config: &RequestConfig,
+///     ) -> Result<Option<bool>, Error> {
+///         Ok(Some(request.headers().contains_key("X-Signature")))
+///     }
+/// }
+///
+/// impl fmt::Display for Signed {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "Signed")
+///     }
+/// }
+///
+/// # fn main() {
+/// register_matcher("signed", Signed);
+/// # }
+/// ```
+pub fn register_matcher<S, M>(name: S, matcher: M)
+where
+    S: Into<String>,
+    M: RequestMatch + Send + Sync + 'static,
+{
+    let mut registry = match REGISTRY.write() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let _ = registry.insert(name.into(), Arc::new(matcher));
+}
+
+fn registered(name: &str) -> Option<Arc<dyn RequestMatch + Send + Sync>> {
+    let registry = match REGISTRY.read() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    registry.get(name).cloned()
+}
+
+/// Match a HTTP request with the registered matcher named by the request configuration.
+#[derive(Clone, Debug, Default)]
+pub struct CustomMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for CustomMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for CustomMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(name) = request_config.custom_matcher() {
+            if let Some(matcher) = registered(name) {
+                try_trace!(self.stdout, "Custom Match - Checking with '{}'", name);
+                Ok(Some(
+                    matcher.is_match(request, request_config)?.unwrap_or(true),
+                ))
+            } else {
+                try_error!(self.stderr, "Custom Match - '{}' is not registered", name);
+                Ok(Some(false))
+            }
+        } else {
+            try_trace!(self.stdout, "Custom Match - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for CustomMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Custom Match")
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request `application/x-www-form-urlencoded` body matching
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{RequestMatch, Slogger};
+use crate::util;
+use http::header::CONTENT_TYPE;
+use http::Request;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// Parse the urlencoded form body of a request into decoded `(name, value)` pairs.
+///
+/// Returns `None` if the request doesn't have an `application/x-www-form-urlencoded` body.
+crate fn form_params(request: &Request<Vec<u8>>) -> Option<Vec<(String, String)>> {
+    let content_type = request.headers().get(CONTENT_TYPE)?.to_str().ok()?;
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+
+    if essence.eq_ignore_ascii_case("application/x-www-form-urlencoded") {
+        Some(util::parse_urlencoded(&String::from_utf8_lossy(
+            request.body(),
+        )))
+    } else {
+        None
+    }
+}
+
+/// Exactly match the form fields of a HTTP request body.
+#[derive(Clone, Debug, Default)]
+pub struct ExactMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for ExactMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for ExactMatch {
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.form_params().is_empty() {
+            try_trace!(self.stdout, "Exact Match (Form) - No check performed");
+            Ok(None)
+        } else if let Some(actual) = form_params(request) {
+            try_trace!(
+                self.stdout,
+                "Exact Match (Form) - Checking {:?} against {:?}",
+                request_config.form_params(),
+                actual
+            );
+            Ok(Some(request_config.form_params().iter().all(
+                |(expected_name, expected_value)| {
+                    actual
+                        .iter()
+                        .any(|(name, value)| name == expected_name && value == expected_value)
+                },
+            )))
+        } else {
+            try_trace!(self.stdout, "Exact Match (Form) - Body is not a form");
+            Ok(Some(false))
+        }
+    }
+}
+
+impl fmt::Display for ExactMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Exact Match On Form")
+    }
+}
+
+/// Pattern match the form fields of a HTTP request body.
+#[derive(Clone, Debug, Default)]
+pub struct PatternMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for PatternMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for PatternMatch {
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.form_params_pattern().is_empty() {
+            try_trace!(self.stdout, "Pattern Match (Form) - No check performed");
+            Ok(None)
+        } else if let Some(actual) = form_params(request) {
+            try_trace!(
+                self.stdout,
+                "Pattern Match (Form) - Checking {:?} against {:?}",
+                request_config.form_params_pattern(),
+                actual
+            );
+            for (expected_name, value_pattern) in request_config.form_params_pattern() {
+                let regex = util::pattern(value_pattern)?;
+                if !actual
+                    .iter()
+                    .any(|(name, value)| name == expected_name && regex.is_match(value))
+                {
+                    return Ok(Some(false));
+                }
+            }
+            Ok(Some(true))
+        } else {
+            try_trace!(self.stdout, "Pattern Match (Form) - Body is not a form");
+            Ok(Some(false))
+        }
+    }
+}
+
+impl fmt::Display for PatternMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pattern Match On Form")
+    }
+}
@@ -9,11 +9,10 @@
 //! HTTP request single header matching
 use crate::config::{self, HeaderPattern, Request as RequestConfig};
 use crate::error::Error;
-use crate::matcher::{self, RequestMatch, Slogger};
-use cached::{cached_key_result, UnboundCache};
+use crate::matcher::{self, MatchCost, RequestMatch, Slogger};
+use crate::util;
 use http::Request;
 use libeither::Either;
-use regex::Regex;
 use slog::{trace, Logger};
 use slog_try::try_trace;
 use std::fmt;
@@ -46,9 +45,13 @@ impl fmt::Display for ExactMatch {
 }
 
 impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &config::Request,
     ) -> Result<Option<bool>, Error> {
         if let Some(header) = request_config.header() {
@@ -62,7 +65,9 @@ impl RequestMatch for ExactMatch {
                 let results: Vec<bool> = request
                     .headers()
                     .iter()
-                    .map(|actual| matcher::equal_headers(actual, expected))
+                    .map(|actual| {
+                        matcher::equal_headers(actual, expected, *header.value_case_insensitive())
+                    })
                     .filter(|v| *v)
                     .collect();
                 try_trace!(self.stdout, "Found {} header matches", results.len());
@@ -94,31 +99,39 @@ impl PatternMatch {
         actual: &str,
         either: &Either<String, String>,
         case_insensitive: bool,
-    ) -> bool {
+    ) -> Result<bool, Error> {
         if let Ok(expected) = either.left_ref() {
-            if case_insensitive {
-                actual == expected.to_lowercase()
+            Ok(if case_insensitive {
+                actual.eq_ignore_ascii_case(expected)
             } else {
                 actual == expected
-            }
+            })
         } else if let Ok(expected) = either.right_ref() {
-            try_trace!(self.stdout, "Checking {} against {}", actual, expected);
-            if let Ok(regex) = generate_regex(expected) {
-                try_trace!(self.stdout, "Regex: {:?}", regex);
-                regex.is_match(actual)
+            let pattern = if case_insensitive {
+                format!("(?i){}", expected)
             } else {
-                false
-            }
+                expected.clone()
+            };
+            try_trace!(self.stdout, "Checking {} against {}", actual, pattern);
+            let regex = util::pattern(&pattern)?;
+            try_trace!(self.stdout, "Regex: {:?}", regex);
+            Ok(regex.is_match(actual))
         } else {
-            false
+            Ok(false)
         }
     }
 
-    fn is_header_match(&self, actual: &(&str, &str), expected: &HeaderPattern) -> Option<bool> {
-        Some(
-            self.is_match_either(actual.0, expected.key(), true)
-                && self.is_match_either(actual.1, expected.value(), false),
-        )
+    fn is_header_match(
+        &self,
+        actual: &(&str, &str),
+        expected: &HeaderPattern,
+    ) -> Result<bool, Error> {
+        Ok(self.is_match_either(actual.0, expected.key(), true)?
+            && self.is_match_either(
+                actual.1,
+                expected.value(),
+                *expected.value_case_insensitive(),
+            )?)
     }
 }
 
@@ -136,23 +149,14 @@ impl Slogger for PatternMatch {
     }
 }
 
-cached_key_result! {
-    REGEX: UnboundCache<String, Regex> = UnboundCache::new();
-    Key = { header_pattern.to_string() };
-    fn generate_regex(header_pattern: &str) -> Result<Regex, String> = {
-        let regex_result = Regex::new(header_pattern);
-
-        match regex_result {
-            Ok(regex) => Ok(regex),
-            Err(e) => Err(e.to_string()),
-        }
+impl RequestMatch for PatternMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
     }
-}
 
-impl RequestMatch for PatternMatch {
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &RequestConfig,
     ) -> Result<Option<bool>, Error> {
         if let Some(header_pattern) = request_config.header_pattern() {
@@ -169,7 +173,9 @@ impl RequestMatch for PatternMatch {
                     Ok(value) => Some((key, value)),
                     Err(_) => None,
                 })
-                .filter_map(|actual_header| self.is_header_match(&actual_header, header_pattern))
+                .map(|actual_header| self.is_header_match(&actual_header, header_pattern))
+                .collect::<Result<Vec<bool>, Error>>()?
+                .into_iter()
                 .filter(|x| *x)
                 .collect();
 
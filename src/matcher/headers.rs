@@ -9,11 +9,10 @@
 //! HTTP request headers matching
 use crate::config;
 use crate::error::Error;
-use crate::matcher::{self, RequestMatch, Slogger};
-use cached::{cached_key_result, UnboundCache};
+use crate::matcher::{self, MatchCost, RequestMatch, Slogger};
+use crate::util;
 use http::Request;
 use libeither::Either;
-use regex::Regex;
 use slog::{trace, Logger};
 use slog_try::try_trace;
 use std::fmt;
@@ -26,14 +25,20 @@ pub struct ExactMatch {
 }
 
 impl ExactMatch {
-    fn actual_has_match(&self, request: &Request<()>, header: &config::Header) -> Option<bool> {
+    fn actual_has_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        header: &config::Header,
+    ) -> Option<bool> {
         if let Ok((ref expected_name, ref expected_value)) = matcher::to_header_tuple(header) {
             let expected = (expected_name, expected_value);
             Some(
                 request
                     .headers()
                     .iter()
-                    .map(|actual| matcher::equal_headers(actual, expected))
+                    .map(|actual| {
+                        matcher::equal_headers(actual, expected, *header.value_case_insensitive())
+                    })
                     .any(|x| x),
             )
         } else {
@@ -63,9 +68,13 @@ impl fmt::Display for ExactMatch {
 }
 
 impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &config::Request,
     ) -> Result<Option<bool>, Error> {
         if request_config.headers().is_empty() {
@@ -97,21 +106,22 @@ impl PatternMatch {
         actual: &str,
         either: &Either<String, String>,
         case_insensitive: bool,
-    ) -> bool {
+    ) -> Result<bool, Error> {
         if let Ok(expected) = either.left_ref() {
-            if case_insensitive {
-                actual == expected.to_lowercase()
+            Ok(if case_insensitive {
+                actual.eq_ignore_ascii_case(expected)
             } else {
                 actual == expected
-            }
+            })
         } else if let Ok(expected) = either.right_ref() {
-            if let Ok(regex) = generate_regex(expected) {
-                regex.is_match(actual)
+            let pattern = if case_insensitive {
+                format!("(?i){}", expected)
             } else {
-                false
-            }
+                expected.clone()
+            };
+            Ok(util::pattern(&pattern)?.is_match(actual))
         } else {
-            false
+            Ok(false)
         }
     }
 
@@ -119,11 +129,13 @@ impl PatternMatch {
         &self,
         actual: &(&str, &str),
         expected: &config::HeaderPattern,
-    ) -> Option<bool> {
-        Some(
-            self.is_match_either(actual.0, expected.key(), true)
-                && self.is_match_either(actual.1, expected.value(), false),
-        )
+    ) -> Result<bool, Error> {
+        Ok(self.is_match_either(actual.0, expected.key(), true)?
+            && self.is_match_either(
+                actual.1,
+                expected.value(),
+                *expected.value_case_insensitive(),
+            )?)
     }
 }
 
@@ -147,23 +159,14 @@ impl fmt::Display for PatternMatch {
     }
 }
 
-cached_key_result! {
-    REGEX: UnboundCache<String, Regex> = UnboundCache::new();
-    Key = { header_pattern.to_string() };
-    fn generate_regex(header_pattern: &str) -> Result<Regex, String> = {
-        let regex_result = Regex::new(header_pattern);
-
-        match regex_result {
-            Ok(regex) => Ok(regex),
-            Err(e) => Err(e.to_string()),
-        }
+impl RequestMatch for PatternMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
     }
-}
 
-impl RequestMatch for PatternMatch {
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &config::Request,
     ) -> Result<Option<bool>, Error> {
         if request_config.headers_pattern().is_empty() {
@@ -177,7 +180,7 @@ impl RequestMatch for PatternMatch {
             let headers_pattern_match: Vec<bool> = request_config
                 .headers_pattern()
                 .iter()
-                .map(|header_pattern| {
+                .map(|header_pattern| -> Result<bool, Error> {
                     // For every header pattern, loop through the actual
                     // headers looking for one match.
                     let matched_header: Vec<bool> = request
@@ -188,15 +191,15 @@ impl RequestMatch for PatternMatch {
                             Ok(value) => Some((key, value)),
                             Err(_) => None,
                         })
-                        .filter_map(|actual_header| {
-                            self.is_header_match(&actual_header, header_pattern)
-                        })
+                        .map(|actual_header| self.is_header_match(&actual_header, header_pattern))
+                        .collect::<Result<Vec<bool>, Error>>()?
+                        .into_iter()
                         .filter(|x| *x)
                         .collect();
 
-                    matched_header.len() == 1 && matched_header[0]
+                    Ok(matched_header.len() == 1 && matched_header[0])
                 })
-                .collect();
+                .collect::<Result<_, Error>>()?;
 
             let all_header_patterns_match =
                 !headers_pattern_match.is_empty() && headers_pattern_match.iter().all(|v| *v);
@@ -205,3 +208,56 @@ impl RequestMatch for PatternMatch {
         }
     }
 }
+
+/// Match the headers that must be absent from an HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct NegateMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for NegateMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for NegateMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &config::Request,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.absent_headers().is_empty() {
+            try_trace!(self.stdout, "Negate Match (Headers) - No check performed");
+            Ok(None)
+        } else {
+            try_trace!(
+                self.stdout,
+                "Negate Match (Headers) - Checking {:?} are absent",
+                request_config.absent_headers()
+            );
+            Ok(Some(request_config.absent_headers().iter().all(|name| {
+                !request.headers().contains_key(name.as_str())
+            })))
+        }
+    }
+}
+
+impl fmt::Display for NegateMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Negate Match On Headers")
+    }
+}
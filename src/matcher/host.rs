@@ -0,0 +1,221 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request host matching
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use crate::util;
+use http::header::HOST;
+use http::Request;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// The host a request was sent to, from an absolute-form request uri or the `Host` header.
+///
+/// The port, if any, is included as given (i.e. `localhost:8080`).
+crate fn request_host(request: &Request<Vec<u8>>) -> Option<String> {
+    request
+        .uri()
+        .authority_part()
+        .map(|authority| authority.as_str().to_string())
+        .or_else(|| {
+            request
+                .headers()
+                .get(HOST)
+                .and_then(|host| host.to_str().ok())
+                .map(str::to_string)
+        })
+        .map(|host| match host.rfind('@') {
+            Some(idx) => host[idx + 1..].to_string(),
+            None => host,
+        })
+}
+
+/// Does the actual host match the expected host?
+///
+/// Host names are case-insensitive.  If the expected host has no port, the port of the actual
+/// host is ignored.
+fn is_host_match(actual: &str, expected: &str) -> bool {
+    if expected.contains(':') {
+        actual.eq_ignore_ascii_case(expected)
+    } else {
+        strip_port(actual).eq_ignore_ascii_case(expected)
+    }
+}
+
+fn strip_port(host: &str) -> &str {
+    match host.rfind(':') {
+        Some(idx) if !host[idx + 1..].contains(']') => &host[..idx],
+        _ => host,
+    }
+}
+
+/// Exactly match the host of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct ExactMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for ExactMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(host) = request_config.host() {
+            let actual = request_host(request);
+            try_trace!(
+                self.stdout,
+                "Exact Match (Host) - Checking {} against {:?}",
+                host,
+                actual
+            );
+            Ok(Some(
+                actual.map_or(false, |actual| is_host_match(&actual, host)),
+            ))
+        } else {
+            try_trace!(self.stdout, "Exact Match (Host) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for ExactMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Exact Match On Host")
+    }
+}
+
+/// Pattern match the host of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct PatternMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for PatternMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for PatternMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(host_pattern) = request_config.host_pattern() {
+            let actual = request_host(request);
+            try_trace!(
+                self.stdout,
+                "Pattern Match (Host) - Checking {:?} against {}",
+                actual,
+                host_pattern
+            );
+            let regex = util::pattern(host_pattern)?;
+            Ok(Some(actual.map_or(false, |actual| {
+                regex.is_match(strip_port(&actual))
+            })))
+        } else {
+            try_trace!(self.stdout, "Pattern Match (Host) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for PatternMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pattern Match On Host")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_host_match, request_host, strip_port};
+    use http::Request;
+
+    #[test]
+    fn host_from_uri_or_header() {
+        match Request::builder()
+            .uri("http://api.example.com:8080/orders")
+            .header("Host", "ignored.example.com")
+            .body(vec![])
+        {
+            Ok(request) => assert_eq!(
+                request_host(&request),
+                Some("api.example.com:8080".to_string())
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match Request::builder()
+            .uri("/orders")
+            .header("Host", "api.example.com")
+            .body(vec![])
+        {
+            Ok(request) => assert_eq!(request_host(&request), Some("api.example.com".to_string())),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match Request::builder().uri("/orders").body(vec![]) {
+            Ok(request) => assert_eq!(request_host(&request), None),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn host_match() {
+        assert!(is_host_match("API.example.com", "api.example.com"));
+        assert!(is_host_match("api.example.com:8080", "api.example.com"));
+        assert!(is_host_match(
+            "api.example.com:8080",
+            "api.example.com:8080"
+        ));
+        assert!(!is_host_match(
+            "api.example.com:8081",
+            "api.example.com:8080"
+        ));
+        assert!(!is_host_match("www.example.com", "api.example.com"));
+        assert_eq!(strip_port("[::1]:8080"), "[::1]");
+        assert_eq!(strip_port("[::1]"), "[::1]");
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A JSONPath evaluator for request body matching and response templating.
+//!
+//! The supported syntax is `$` followed by any number of:
+//!
+//! * `.name` or `['name']` - A child of an object.
+//! * `[0]` - An element of an array.  Negative indices count from the end.
+//! * `.*` or `[*]` - Every child of an object or array.
+//! * `..name` or `..*` - A descendant at any depth.
+//!
+//! Filter and script expressions are not supported.
+use serde_json::Value;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Child(String),
+    Index(i64),
+    Wildcard,
+    Descendant(Option<String>),
+}
+
+/// Select the values at the given path.
+crate fn select<'a>(value: &'a Value, path: &str) -> Result<Vec<&'a Value>, String> {
+    let mut selected = vec![value];
+
+    for segment in parse(path)? {
+        selected = selected
+            .into_iter()
+            .flat_map(|value| apply(value, &segment))
+            .collect();
+    }
+
+    Ok(selected)
+}
+
+fn apply<'a>(value: &'a Value, segment: &Segment) -> Vec<&'a Value> {
+    match (segment, value) {
+        (Segment::Child(name), Value::Object(map)) => map.get(name).into_iter().collect(),
+        (Segment::Index(index), Value::Array(array)) => {
+            let index = if *index < 0 {
+                array.len() as i64 + index
+            } else {
+                *index
+            };
+            if index < 0 {
+                vec![]
+            } else {
+                array.get(index as usize).into_iter().collect()
+            }
+        }
+        (Segment::Wildcard, _) => children(value),
+        (Segment::Descendant(name), _) => {
+            let mut found = vec![];
+            descendants(value, name, &mut found);
+            found
+        }
+        _ => vec![],
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Object(map) => map.values().collect(),
+        Value::Array(array) => array.iter().collect(),
+        _ => vec![],
+    }
+}
+
+fn descendants<'a>(value: &'a Value, name: &Option<String>, found: &mut Vec<&'a Value>) {
+    match (name, value) {
+        (Some(name), Value::Object(map)) => found.extend(map.get(name)),
+        (None, _) => found.extend(children(value)),
+        _ => {}
+    }
+
+    for child in children(value) {
+        descendants(child, name, found);
+    }
+}
+
+fn parse(path: &str) -> Result<Vec<Segment>, String> {
+    let chars: Vec<char> = path.trim().chars().collect();
+    if chars.first() != Some(&'$') {
+        return Err(format!("JSONPath '{}' must start with '$'", path));
+    }
+
+    let mut segments = vec![];
+    let mut i = 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                let (name, next) = name(&chars, i + 2);
+                if name.is_empty() {
+                    return Err(format!("JSONPath '{}' has an empty descendant name", path));
+                }
+                segments.push(Segment::Descendant(if name == "*" {
+                    None
+                } else {
+                    Some(name)
+                }));
+                i = next;
+            }
+            '.' => {
+                let (name, next) = name(&chars, i + 1);
+                if name.is_empty() {
+                    return Err(format!("JSONPath '{}' has an empty child name", path));
+                }
+                segments.push(if name == "*" {
+                    Segment::Wildcard
+                } else {
+                    Segment::Child(name)
+                });
+                i = next;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|end| i + end)
+                    .ok_or_else(|| format!("JSONPath '{}' has an unclosed '['", path))?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+
+                segments.push(if inner == "*" {
+                    Segment::Wildcard
+                } else if inner.len() >= 2
+                    && (inner.starts_with('\'') && inner.ends_with('\'')
+                        || inner.starts_with('"') && inner.ends_with('"'))
+                {
+                    Segment::Child(inner[1..inner.len() - 1].to_string())
+                } else {
+                    Segment::Index(inner.parse().map_err(|_| {
+                        format!("JSONPath '{}' has an invalid index '{}'", path, inner)
+                    })?)
+                });
+                i = end + 1;
+            }
+            c => return Err(format!("JSONPath '{}' has an unexpected '{}'", path, c)),
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Read a dot notation name, stopping at the next `.` or `[`.
+fn name(chars: &[char], start: usize) -> (String, usize) {
+    let end = chars[start.min(chars.len())..]
+        .iter()
+        .position(|c| *c == '.' || *c == '[')
+        .map_or(chars.len(), |end| start + end);
+    (chars[start.min(chars.len())..end].iter().collect(), end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::select;
+    use serde_json::{json, Value};
+
+    fn order() -> Value {
+        json!({
+            "id": 12345,
+            "customer": { "name": "Yoda", "tier": "gold" },
+            "items": [
+                { "sku": "A1", "qty": 1 },
+                { "sku": "B2", "qty": 3 }
+            ]
+        })
+    }
+
+    fn check(path: &str, expected: Vec<Value>) {
+        let order = order();
+        match select(&order, path) {
+            Ok(selected) => assert_eq!(
+                selected.into_iter().cloned().collect::<Vec<Value>>(),
+                expected
+            ),
+            Err(e) => assert!(false, e),
+        }
+    }
+
+    #[test]
+    fn children() {
+        check("$", vec![order()]);
+        check("$.id", vec![json!(12345)]);
+        check("$.customer.name", vec![json!("Yoda")]);
+        check("$['customer']['tier']", vec![json!("gold")]);
+        check("$.missing", vec![]);
+    }
+
+    #[test]
+    fn indices() {
+        check("$.items[0].sku", vec![json!("A1")]);
+        check("$.items[-1].qty", vec![json!(3)]);
+        check("$.items[5]", vec![]);
+        check("$.items[*].sku", vec![json!("A1"), json!("B2")]);
+    }
+
+    #[test]
+    fn descendants() {
+        check("$..qty", vec![json!(1), json!(3)]);
+        check("$.customer.*", vec![json!("Yoda"), json!("gold")]);
+    }
+
+    #[test]
+    fn invalid() {
+        let order = order();
+        assert!(select(&order, "id").is_err());
+        assert!(select(&order, "$.items[").is_err());
+        assert!(select(&order, "$.items[x]").is_err());
+        assert!(select(&order, "$.").is_err());
+    }
+}
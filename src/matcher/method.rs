@@ -9,10 +9,9 @@
 //! HTTP request method matching
 use crate::config::{self, Request as RequestConfig};
 use crate::error::Error;
-use crate::matcher::{RequestMatch, Slogger};
-use cached::{cached_key_result, UnboundCache};
+use crate::matcher::{MatchCost, MatchOutcome, RequestMatch, Slogger};
+use crate::util;
 use http::Request;
-use regex::Regex;
 use slog::{trace, Logger};
 use slog_try::try_trace;
 use std::fmt;
@@ -39,24 +38,68 @@ impl Slogger for ExactMatch {
 }
 
 impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &config::Request,
     ) -> Result<Option<bool>, Error> {
-        if let Some(method) = request_config.method() {
+        if let Some(expected) = expected_methods(request_config) {
+            let method = request.method().as_str();
             try_trace!(
                 self.stdout,
                 "Exact Match (Method) - Checking {} against {}",
-                method,
-                request.method().as_str()
+                expected,
+                method
             );
-            Ok(Some(request.method().as_str() == &method[..]))
+            Ok(Some(
+                request_config
+                    .allowed_methods()
+                    .map_or(true, |allowed| allowed.contains(&method)),
+            ))
         } else {
             try_trace!(self.stdout, "Exact Match (Method) - No check performed");
             Ok(None)
         }
     }
+
+    fn outcome(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<MatchOutcome>, Error> {
+        Ok(
+            match (
+                self.is_match(request, request_config)?,
+                expected_methods(request_config),
+            ) {
+                (Some(matched), Some(expected)) => Some(
+                    MatchOutcome::new(self.to_string(), matched)
+                        .with_values(expected, request.method().as_str()),
+                ),
+                _ => None,
+            },
+        )
+    }
+}
+
+/// The `method` and `methods` on the request configuration, i.e. `GET, HEAD`, if any are set.
+fn expected_methods(request_config: &RequestConfig) -> Option<String> {
+    let methods: Vec<&str> = request_config
+        .method()
+        .iter()
+        .chain(request_config.methods())
+        .map(String::as_str)
+        .collect();
+
+    if methods.is_empty() {
+        None
+    } else {
+        Some(methods.join(", "))
+    }
 }
 
 impl fmt::Display for ExactMatch {
@@ -86,23 +129,14 @@ impl Slogger for PatternMatch {
     }
 }
 
-cached_key_result! {
-    REGEX: UnboundCache<String, Regex> = UnboundCache::new();
-    Key = { method_pattern.to_string() };
-    fn generate_regex(method_pattern: &str) -> Result<Regex, String> = {
-        let regex_result = Regex::new(method_pattern);
-
-        match regex_result {
-            Ok(regex) => Ok(regex),
-            Err(e) => Err(e.to_string()),
-        }
+impl RequestMatch for PatternMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
     }
-}
 
-impl RequestMatch for PatternMatch {
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &RequestConfig,
     ) -> Result<Option<bool>, Error> {
         if let Some(method_pattern) = request_config.method_pattern() {
@@ -113,16 +147,32 @@ impl RequestMatch for PatternMatch {
                 method,
                 method_pattern
             );
-            if let Ok(regex) = generate_regex(method_pattern) {
-                Ok(Some(regex.is_match(method)))
-            } else {
-                Ok(Some(false))
-            }
+            let regex = util::pattern(method_pattern)?;
+            Ok(Some(regex.is_match(method)))
         } else {
             try_trace!(self.stdout, "Pattern Match (Method) - No check performed");
             Ok(None)
         }
     }
+
+    fn outcome(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<MatchOutcome>, Error> {
+        Ok(
+            match (
+                self.is_match(request, request_config)?,
+                request_config.method_pattern(),
+            ) {
+                (Some(matched), Some(expected)) => Some(
+                    MatchOutcome::new(self.to_string(), matched)
+                        .with_values(expected.clone(), request.method().as_str()),
+                ),
+                _ => None,
+            },
+        )
+    }
 }
 
 impl fmt::Display for PatternMatch {
@@ -130,3 +180,55 @@ impl fmt::Display for PatternMatch {
         write!(f, "Pattern Match On Method")
     }
 }
+
+/// Match an HTTP method the request must not have.
+#[derive(Clone, Debug, Default)]
+pub struct NegateMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for NegateMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for NegateMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &config::Request,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(method_not) = request_config.method_not() {
+            try_trace!(
+                self.stdout,
+                "Negate Match (Method) - Checking {} is not {}",
+                request.method().as_str(),
+                method_not
+            );
+            Ok(Some(request.method().as_str() != &method_not[..]))
+        } else {
+            try_trace!(self.stdout, "Negate Match (Method) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for NegateMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Negate Match On Method")
+    }
+}
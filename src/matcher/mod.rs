@@ -9,76 +9,279 @@
 //! HTTP request matching for the server.
 #[cfg(feature = "headers")]
 use crate::config::Header;
-use crate::config::{Mapping, Mappings, Request as RequestConfig};
+use crate::config::{Mapping, Mappings, Request as RequestConfig, Strict};
 use crate::error::Error::{self, MappingNotFound};
 use bitflags::bitflags;
+use getset::Getters;
 #[cfg(feature = "headers")]
 use http::header::{HeaderName, HeaderValue};
 use http::Request;
-use slog::{trace, Logger};
-use slog_try::try_trace;
+use rayon::prelude::*;
+use serde_derive::Serialize;
+use slog::{error, trace, Logger};
+use slog_try::{try_error, try_trace};
 use std::fmt;
-
+use std::iter;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use uuid::Uuid;
+
+/// The maximum number of near misses reported for a request.
+const MAX_NEAR_MISSES: usize = 5;
+
+#[cfg(feature = "basic_auth")]
+crate mod basic_auth;
+#[cfg(feature = "body")]
+crate mod body;
+#[cfg(feature = "client_cert")]
+crate mod client_cert;
+crate mod combinator;
+crate mod custom;
+#[cfg(feature = "form")]
+crate mod form;
 #[cfg(feature = "header")]
 crate mod header;
 #[cfg(feature = "headers")]
 crate mod headers;
+#[cfg(feature = "host")]
+crate mod host;
+crate mod json_path;
 #[cfg(feature = "method")]
 crate mod method;
+#[cfg(feature = "multipart")]
+crate mod multipart;
+crate mod outcome;
+#[cfg(feature = "protobuf")]
+crate mod protobuf;
+#[cfg(feature = "query")]
+crate mod query;
+#[cfg(feature = "remote_addr")]
+crate mod remote_addr;
+crate mod request;
+crate mod scenario;
+#[cfg(feature = "scheme")]
+crate mod scheme;
+#[cfg(feature = "script")]
+crate mod script;
+#[cfg(feature = "sni")]
+crate mod sni;
+crate mod stats;
+crate mod strict;
 #[cfg(feature = "url")]
 crate mod url;
-
+#[cfg(feature = "xml")]
+crate mod xml;
+
+#[cfg(all(feature = "exact_match", feature = "basic_auth"))]
+pub use self::basic_auth::ExactMatch as ExactMatchBasicAuth;
+#[cfg(all(feature = "exact_match", feature = "body"))]
+pub use self::body::Contains as ContainsMatchBody;
+#[cfg(all(feature = "exact_match", feature = "body"))]
+pub use self::body::EqualToJson as EqualToJsonMatchBody;
+#[cfg(all(feature = "exact_match", feature = "body"))]
+pub use self::body::ExactMatch as ExactMatchBody;
+#[cfg(all(feature = "exact_match", feature = "body"))]
+pub use self::body::Hash as HashMatchBody;
+#[cfg(all(feature = "pattern_match", feature = "body"))]
+pub use self::body::JsonPath as JsonPathMatchBody;
+#[cfg(all(feature = "pattern_match", feature = "body"))]
+pub use self::body::PatternMatch as PatternMatchBody;
+#[cfg(all(feature = "exact_match", feature = "body"))]
+pub use self::body::Size as SizeMatchBody;
+#[cfg(all(feature = "exact_match", feature = "client_cert"))]
+pub use self::client_cert::ExactMatch as ExactMatchClientCert;
+pub use self::combinator::{AllOf, AnyOf, Not};
+pub use self::custom::{register_matcher, CustomMatch};
+#[cfg(all(feature = "exact_match", feature = "form"))]
+pub use self::form::ExactMatch as ExactMatchForm;
+#[cfg(all(feature = "pattern_match", feature = "form"))]
+pub use self::form::PatternMatch as PatternMatchForm;
 #[cfg(all(feature = "exact_match", feature = "header"))]
 pub use self::header::ExactMatch as ExactMatchHeader;
 #[cfg(all(feature = "pattern_match", feature = "header"))]
 pub use self::header::PatternMatch as PatternMatchHeader;
 #[cfg(all(feature = "exact_match", feature = "headers"))]
 pub use self::headers::ExactMatch as ExactMatchHeaders;
+#[cfg(all(feature = "negate_match", feature = "headers"))]
+pub use self::headers::NegateMatch as NegateMatchHeaders;
 #[cfg(all(feature = "pattern_match", feature = "headers"))]
 pub use self::headers::PatternMatch as PatternMatchHeaders;
+#[cfg(all(feature = "exact_match", feature = "host"))]
+pub use self::host::ExactMatch as ExactMatchHost;
+#[cfg(all(feature = "pattern_match", feature = "host"))]
+pub use self::host::PatternMatch as PatternMatchHost;
 #[cfg(all(feature = "exact_match", feature = "method"))]
 pub use self::method::ExactMatch as ExactMatchMethod;
+#[cfg(all(feature = "negate_match", feature = "method"))]
+pub use self::method::NegateMatch as NegateMatchMethod;
 #[cfg(all(feature = "pattern_match", feature = "method"))]
 pub use self::method::PatternMatch as PatternMatchMethod;
+#[cfg(all(feature = "pattern_match", feature = "multipart"))]
+pub use self::multipart::PartMatch as MultipartMatchBody;
+pub use self::outcome::{MatchOutcome, MatchReport};
+#[cfg(all(feature = "exact_match", feature = "protobuf"))]
+pub use self::protobuf::FieldMatch as ProtobufMatchBody;
+#[cfg(all(feature = "exact_match", feature = "query"))]
+pub use self::query::ExactMatch as ExactMatchQuery;
+#[cfg(all(feature = "pattern_match", feature = "query"))]
+pub use self::query::PatternMatch as PatternMatchQuery;
+#[cfg(all(feature = "exact_match", feature = "remote_addr"))]
+pub use self::remote_addr::ExactMatch as ExactMatchRemoteAddr;
+pub use self::request::RequestExt;
+pub use self::scenario::{Scenarios, STARTED};
+#[cfg(all(feature = "exact_match", feature = "scheme"))]
+pub use self::scheme::ExactMatch as ExactMatchScheme;
+#[cfg(feature = "script")]
+pub use self::script::ScriptMatch;
+#[cfg(all(feature = "exact_match", feature = "sni"))]
+pub use self::sni::ExactMatch as ExactMatchSni;
+pub use self::stats::{Counters, MappingCounters, Stats};
+pub use self::strict::StrictMatch;
 #[cfg(all(feature = "exact_match", feature = "url"))]
 pub use self::url::ExactMatch as ExactMatchUrl;
 #[cfg(all(feature = "pattern_match", feature = "url"))]
+pub use self::url::GlobMatch as GlobMatchUrl;
+#[cfg(all(feature = "negate_match", feature = "url"))]
+pub use self::url::NegateMatch as NegateMatchUrl;
+#[cfg(all(feature = "pattern_match", feature = "url"))]
 pub use self::url::PatternMatch as PatternMatchUrl;
+#[cfg(all(feature = "pattern_match", feature = "xml"))]
+pub use self::xml::Soap as SoapMatch;
+#[cfg(all(feature = "pattern_match", feature = "xml"))]
+pub use self::xml::XPath as XPathMatchBody;
 
 bitflags! {
     /// Enabled flags for request matching types
-    pub struct Enabled: u32 {
+    pub struct Enabled: u64 {
         /// Enable the exact matching on url
         #[cfg(all(feature = "exact_match", feature = "url"))]
-        const EXACT_URL       = 0b0000_0000_0001;
+        const EXACT_URL        = 0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0001;
         /// Enable the exact matching on method
         #[cfg(all(feature = "exact_match", feature = "method"))]
-        const EXACT_METHOD    = 0b0000_0000_0010;
+        const EXACT_METHOD     = 0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0010;
         /// Enable the exact matching on all headers
         #[cfg(all(feature = "exact_match", feature = "headers"))]
-        const EXACT_HEADERS   = 0b0000_0000_0100;
+        const EXACT_HEADERS    = 0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0100;
         /// Enable the exact matching on one header
         #[cfg(all(feature = "exact_match", feature = "header"))]
-        const EXACT_HEADER    = 0b0000_0000_1000;
+        const EXACT_HEADER     = 0b0000_0000_0000_0000_0000_0000_0000_0000_0000_1000;
         /// Enable the pattern matching on url
         #[cfg(all(feature = "pattern_match", feature = "url"))]
-        const PATTERN_URL     = 0b0000_0001_0000;
+        const PATTERN_URL      = 0b0000_0000_0000_0000_0000_0000_0000_0000_0001_0000;
+        /// Enable the exact matching on query parameters
+        #[cfg(all(feature = "exact_match", feature = "query"))]
+        const EXACT_QUERY      = 0b0000_0000_0000_0000_0000_0000_0000_0000_0010_0000;
+        /// Enable the pattern matching on query parameters
+        #[cfg(all(feature = "pattern_match", feature = "query"))]
+        const PATTERN_QUERY    = 0b0000_0000_0000_0000_0000_0000_0000_0000_0100_0000;
         /// Enable the pattern matching on one header
         #[cfg(all(feature = "pattern_match", feature = "header"))]
-        const PATTERN_HEADER  = 0b0000_1000_0000;
+        const PATTERN_HEADER   = 0b0000_0000_0000_0000_0000_0000_0000_0000_1000_0000;
         /// Enable the pattern matching on method
         #[cfg(all(feature = "pattern_match", feature = "method"))]
-        const PATTERN_METHOD  = 0b0001_0000_0000;
+        const PATTERN_METHOD   = 0b0000_0000_0000_0000_0000_0000_0000_0001_0000_0000;
         /// Enable the pattern matching on all headers
         #[cfg(all(feature = "pattern_match", feature = "headers"))]
-        const PATTERN_HEADERS = 0b0010_0000_0000;
+        const PATTERN_HEADERS  = 0b0000_0000_0000_0000_0000_0000_0000_0010_0000_0000;
+        /// Enable the exact matching on the body
+        #[cfg(all(feature = "exact_match", feature = "body"))]
+        const EXACT_BODY       = 0b0000_0000_0000_0000_0000_0000_0000_0100_0000_0000;
+        /// Enable the negated matching on headers (absent headers)
+        #[cfg(all(feature = "negate_match", feature = "headers"))]
+        const NEGATE_HEADERS   = 0b0000_0000_0000_0000_0000_0000_0000_1000_0000_0000;
+        /// Enable the negated matching on method
+        #[cfg(all(feature = "negate_match", feature = "method"))]
+        const NEGATE_METHOD    = 0b0000_0000_0000_0000_0000_0000_0001_0000_0000_0000;
+        /// Enable the negated matching on url
+        #[cfg(all(feature = "negate_match", feature = "url"))]
+        const NEGATE_URL       = 0b0000_0000_0000_0000_0000_0000_0010_0000_0000_0000;
+        /// Enable the JSONPath matching on the body
+        #[cfg(all(feature = "pattern_match", feature = "body"))]
+        const JSON_PATH_BODY   = 0b0000_0000_0000_0000_0000_0000_0100_0000_0000_0000;
+        /// Enable the structural JSON matching on the body
+        #[cfg(all(feature = "exact_match", feature = "body"))]
+        const EQUAL_JSON_BODY  = 0b0000_0000_0000_0000_0000_0000_1000_0000_0000_0000;
+        /// Enable the XPath matching on an XML body
+        #[cfg(all(feature = "pattern_match", feature = "xml"))]
+        const XPATH_BODY       = 0b0000_0000_0000_0000_0000_0001_0000_0000_0000_0000;
+        /// Enable the exact matching on host
+        #[cfg(all(feature = "exact_match", feature = "host"))]
+        const EXACT_HOST       = 0b0000_0000_0000_0000_0000_0010_0000_0000_0000_0000;
+        /// Enable the pattern matching on host
+        #[cfg(all(feature = "pattern_match", feature = "host"))]
+        const PATTERN_HOST     = 0b0000_0000_0000_0000_0000_0100_0000_0000_0000_0000;
+        /// Enable the exact matching on scheme
+        #[cfg(all(feature = "exact_match", feature = "scheme"))]
+        const EXACT_SCHEME     = 0b0000_0000_0000_0000_0000_1000_0000_0000_0000_0000;
+        /// Enable the exact matching on basic authentication credentials
+        #[cfg(all(feature = "exact_match", feature = "basic_auth"))]
+        const EXACT_BASIC_AUTH = 0b0000_0000_0000_0000_0001_0000_0000_0000_0000_0000;
+        /// Enable the part matching on a multipart body
+        #[cfg(all(feature = "pattern_match", feature = "multipart"))]
+        const MULTIPART_BODY   = 0b0000_0000_0000_0000_0010_0000_0000_0000_0000_0000;
+        /// Enable the exact matching on form fields
+        #[cfg(all(feature = "exact_match", feature = "form"))]
+        const EXACT_FORM       = 0b0000_0000_0000_0000_0100_0000_0000_0000_0000_0000;
+        /// Enable the pattern matching on form fields
+        #[cfg(all(feature = "pattern_match", feature = "form"))]
+        const PATTERN_FORM     = 0b0000_0000_0000_0000_1000_0000_0000_0000_0000_0000;
+        /// Enable the size matching on the body
+        #[cfg(all(feature = "exact_match", feature = "body"))]
+        const BODY_SIZE        = 0b0000_0000_0000_0001_0000_0000_0000_0000_0000_0000;
+        /// Enable the exact matching on the remote address
+        #[cfg(all(feature = "exact_match", feature = "remote_addr"))]
+        const REMOTE_ADDR      = 0b0000_0000_0000_0010_0000_0000_0000_0000_0000_0000;
+        /// Enable the glob matching on url
+        #[cfg(all(feature = "pattern_match", feature = "url"))]
+        const GLOB_URL         = 0b0000_0000_0000_0100_0000_0000_0000_0000_0000_0000;
+        /// Enable the pattern matching on the body
+        #[cfg(all(feature = "pattern_match", feature = "body"))]
+        const PATTERN_BODY     = 0b0000_0000_0000_1000_0000_0000_0000_0000_0000_0000;
+        /// Enable the substring matching on the body
+        #[cfg(all(feature = "exact_match", feature = "body"))]
+        const BODY_CONTAINS    = 0b0000_0000_0001_0000_0000_0000_0000_0000_0000_0000;
+        /// Enable the field matching on a protobuf body
+        #[cfg(all(feature = "exact_match", feature = "protobuf"))]
+        const PROTOBUF_BODY    = 0b0000_0000_0010_0000_0000_0000_0000_0000_0000_0000;
+        /// Enable the SOAP action, operation and body matching
+        #[cfg(all(feature = "pattern_match", feature = "xml"))]
+        const SOAP             = 0b0000_0000_0100_0000_0000_0000_0000_0000_0000_0000;
+        /// Enable the scripted matching
+        #[cfg(feature = "script")]
+        const SCRIPT           = 0b0000_0000_1000_0000_0000_0000_0000_0000_0000_0000;
+        /// Enable the hash matching on the body
+        #[cfg(all(feature = "exact_match", feature = "body"))]
+        const BODY_HASH        = 0b0000_0001_0000_0000_0000_0000_0000_0000_0000_0000;
+        /// Enable the exact matching on the TLS client certificate
+        #[cfg(all(feature = "exact_match", feature = "client_cert"))]
+        const CLIENT_CERT      = 0b0000_0010_0000_0000_0000_0000_0000_0000_0000_0000;
+        /// Enable the exact matching on the SNI server name
+        #[cfg(all(feature = "exact_match", feature = "sni"))]
+        const EXACT_SNI        = 0b0000_0100_0000_0000_0000_0000_0000_0000_0000_0000;
     }
 }
 
 impl Enabled {
     /// Enable all of the exact matching.
     pub fn exact() -> Self {
-        Self::exact_url() | Self::exact_method() | Self::exact_header() | Self::exact_headers()
+        Self::exact_url()
+            | Self::exact_method()
+            | Self::exact_header()
+            | Self::exact_headers()
+            | Self::exact_query()
+            | Self::exact_body()
+            | Self::equal_json_body()
+            | Self::exact_host()
+            | Self::exact_scheme()
+            | Self::exact_basic_auth()
+            | Self::exact_form()
+            | Self::body_size()
+            | Self::remote_addr()
+            | Self::body_contains()
+            | Self::protobuf_body()
+            | Self::body_hash()
+            | Self::client_cert()
+            | Self::exact_sni()
     }
 
     /// Enable all of the pattern matching.
@@ -87,6 +290,21 @@ impl Enabled {
             | Self::pattern_method()
             | Self::pattern_header()
             | Self::pattern_headers()
+            | Self::pattern_query()
+            | Self::json_path_body()
+            | Self::xpath_body()
+            | Self::pattern_host()
+            | Self::multipart_body()
+            | Self::pattern_form()
+            | Self::glob_url()
+            | Self::pattern_body()
+            | Self::soap()
+            | Self::script()
+    }
+
+    /// Enable all of the negated matching.
+    pub fn negate() -> Self {
+        Self::negate_headers() | Self::negate_method() | Self::negate_url()
     }
 
     #[cfg(all(feature = "exact_match", feature = "url"))]
@@ -129,6 +347,26 @@ impl Enabled {
         Self::empty()
     }
 
+    #[cfg(all(feature = "exact_match", feature = "query"))]
+    fn exact_query() -> Self {
+        Self::EXACT_QUERY
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "query")))]
+    fn exact_query() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "body"))]
+    fn exact_body() -> Self {
+        Self::EXACT_BODY
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "body")))]
+    fn exact_body() -> Self {
+        Self::empty()
+    }
+
     #[cfg(all(feature = "pattern_match", feature = "url"))]
     fn pattern_url() -> Self {
         Self::PATTERN_URL
@@ -139,6 +377,96 @@ impl Enabled {
         Self::empty()
     }
 
+    #[cfg(all(feature = "pattern_match", feature = "url"))]
+    fn glob_url() -> Self {
+        Self::GLOB_URL
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "url")))]
+    fn glob_url() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "body"))]
+    fn body_hash() -> Self {
+        Self::BODY_HASH
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "body")))]
+    fn body_hash() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "sni"))]
+    fn exact_sni() -> Self {
+        Self::EXACT_SNI
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "sni")))]
+    fn exact_sni() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "client_cert"))]
+    fn client_cert() -> Self {
+        Self::CLIENT_CERT
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "client_cert")))]
+    fn client_cert() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "body"))]
+    fn body_contains() -> Self {
+        Self::BODY_CONTAINS
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "body")))]
+    fn body_contains() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "protobuf"))]
+    fn protobuf_body() -> Self {
+        Self::PROTOBUF_BODY
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "protobuf")))]
+    fn protobuf_body() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(feature = "script")]
+    fn script() -> Self {
+        Self::SCRIPT
+    }
+
+    #[cfg(not(feature = "script"))]
+    fn script() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "pattern_match", feature = "xml"))]
+    fn soap() -> Self {
+        Self::SOAP
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "xml")))]
+    fn soap() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "pattern_match", feature = "body"))]
+    fn pattern_body() -> Self {
+        Self::PATTERN_BODY
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "body")))]
+    fn pattern_body() -> Self {
+        Self::empty()
+    }
+
     #[cfg(all(feature = "pattern_match", feature = "method"))]
     fn pattern_method() -> Self {
         Self::PATTERN_METHOD
@@ -168,6 +496,166 @@ impl Enabled {
     fn pattern_headers() -> Self {
         Self::empty()
     }
+
+    #[cfg(all(feature = "pattern_match", feature = "query"))]
+    fn pattern_query() -> Self {
+        Self::PATTERN_QUERY
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "query")))]
+    fn pattern_query() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "body"))]
+    fn equal_json_body() -> Self {
+        Self::EQUAL_JSON_BODY
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "body")))]
+    fn equal_json_body() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "body"))]
+    fn body_size() -> Self {
+        Self::BODY_SIZE
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "body")))]
+    fn body_size() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "pattern_match", feature = "body"))]
+    fn json_path_body() -> Self {
+        Self::JSON_PATH_BODY
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "body")))]
+    fn json_path_body() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "pattern_match", feature = "xml"))]
+    fn xpath_body() -> Self {
+        Self::XPATH_BODY
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "xml")))]
+    fn xpath_body() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "host"))]
+    fn exact_host() -> Self {
+        Self::EXACT_HOST
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "host")))]
+    fn exact_host() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "pattern_match", feature = "host"))]
+    fn pattern_host() -> Self {
+        Self::PATTERN_HOST
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "host")))]
+    fn pattern_host() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "scheme"))]
+    fn exact_scheme() -> Self {
+        Self::EXACT_SCHEME
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "scheme")))]
+    fn exact_scheme() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "basic_auth"))]
+    fn exact_basic_auth() -> Self {
+        Self::EXACT_BASIC_AUTH
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "basic_auth")))]
+    fn exact_basic_auth() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "pattern_match", feature = "multipart"))]
+    fn multipart_body() -> Self {
+        Self::MULTIPART_BODY
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "multipart")))]
+    fn multipart_body() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "form"))]
+    fn exact_form() -> Self {
+        Self::EXACT_FORM
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "form")))]
+    fn exact_form() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "pattern_match", feature = "form"))]
+    fn pattern_form() -> Self {
+        Self::PATTERN_FORM
+    }
+
+    #[cfg(not(all(feature = "pattern_match", feature = "form")))]
+    fn pattern_form() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "exact_match", feature = "remote_addr"))]
+    fn remote_addr() -> Self {
+        Self::REMOTE_ADDR
+    }
+
+    #[cfg(not(all(feature = "exact_match", feature = "remote_addr")))]
+    fn remote_addr() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "negate_match", feature = "headers"))]
+    fn negate_headers() -> Self {
+        Self::NEGATE_HEADERS
+    }
+
+    #[cfg(not(all(feature = "negate_match", feature = "headers")))]
+    fn negate_headers() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "negate_match", feature = "method"))]
+    fn negate_method() -> Self {
+        Self::NEGATE_METHOD
+    }
+
+    #[cfg(not(all(feature = "negate_match", feature = "method")))]
+    fn negate_method() -> Self {
+        Self::empty()
+    }
+
+    #[cfg(all(feature = "negate_match", feature = "url"))]
+    fn negate_url() -> Self {
+        Self::NEGATE_URL
+    }
+
+    #[cfg(not(all(feature = "negate_match", feature = "url")))]
+    fn negate_url() -> Self {
+        Self::empty()
+    }
 }
 
 impl fmt::Display for Enabled {
@@ -190,8 +678,39 @@ crate fn to_header_tuple(header: &Header) -> Result<HeaderTuple, Error> {
 }
 
 #[cfg(feature = "headers")]
-crate fn equal_headers(actual: HeaderTupleRef<'_>, expected: HeaderTupleRef<'_>) -> bool {
-    actual == expected
+crate fn equal_headers(
+    actual: HeaderTupleRef<'_>,
+    expected: HeaderTupleRef<'_>,
+    value_case_insensitive: bool,
+) -> bool {
+    if value_case_insensitive {
+        actual.0 == expected.0
+            && actual
+                .1
+                .as_bytes()
+                .eq_ignore_ascii_case(expected.1.as_bytes())
+    } else {
+        actual == expected
+    }
+}
+
+/// A mapping that nearly matched a request.  At least one of the configured matchers matched,
+/// and at least one didn't.
+#[derive(Clone, Debug, Eq, Getters, PartialEq, Serialize)]
+pub struct NearMiss {
+    /// The name of the mapping.
+    #[get = "pub"]
+    name: String,
+    /// The matchers that matched.
+    #[get = "pub"]
+    matched: Vec<String>,
+    /// The matchers that didn't match.
+    #[get = "pub"]
+    failed: Vec<String>,
+    /// The outcomes of the matchers that didn't match, with the expected and actual values where
+    /// the matcher supplies them.
+    #[get = "pub"]
+    failures: Vec<MatchOutcome>,
 }
 
 /// A struct that supports slog logging
@@ -202,7 +721,25 @@ pub trait Slogger {
     fn set_stderr(self, stderr: Option<Logger>) -> Self;
 }
 
+/// How expensive a request matcher is to check.
+///
+/// Cheaper matchers are checked first, so a request that fails on its method or url never has
+/// its body parsed.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum MatchCost {
+    /// Compares the request line, the headers or the connection.
+    Cheap,
+    /// Reads the body, without parsing it.
+    Moderate,
+    /// Parses the body, runs a script, or checks nested request configurations.
+    Expensive,
+}
+
 /// A request matcher
+///
+/// Matchers are given the whole request, with the body already read.  The connection details
+/// the server recorded for the request, i.e. the client address and certificate, are read with
+/// [`RequestExt`](crate::matcher::RequestExt).
 pub trait RequestMatch: fmt::Debug + fmt::Display {
     /// Does the incoming request match the request configuration from a mapping.
     ///
@@ -210,16 +747,42 @@ pub trait RequestMatch: fmt::Debug + fmt::Display {
     /// Otherwise, `is_match` must return `None`
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &RequestConfig,
     ) -> Result<Option<bool>, Error>;
+
+    /// Check the incoming request against the request configuration from a mapping, and explain
+    /// the result.
+    ///
+    /// Returns `None` if the matcher has no configuration.  The default implementation wraps
+    /// `is_match`, so matchers only need to override this to supply the values they compared.
+    fn outcome(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<MatchOutcome>, Error> {
+        Ok(self
+            .is_match(request, request_config)?
+            .map(|matched| MatchOutcome::new(self.to_string(), matched)))
+    }
+
+    /// How expensive this matcher is to check, relative to the others.
+    fn cost(&self) -> MatchCost {
+        MatchCost::Moderate
+    }
 }
 
 /// Try to match an incoming request to a mapping.
 #[allow(box_pointers)]
 pub struct Matcher {
     /// The matchers setup for request matching.
-    matchers: Vec<Box<dyn RequestMatch>>,
+    matchers: Vec<Box<dyn RequestMatch + Send + Sync>>,
+    /// The strict matcher, checked after every other matcher.
+    strict: StrictMatch,
+    /// Evaluate the candidate mappings in parallel once there are at least this many.
+    parallel_threshold: Option<usize>,
+    /// Statistics for every request checked, shared between matchers.
+    stats: Option<Arc<Mutex<Stats>>>,
     /// stdout slog logger
     stdout: Option<Logger>,
     /// stderr slog logger
@@ -229,8 +792,7 @@ pub struct Matcher {
 #[allow(box_pointers)]
 impl fmt::Debug for Matcher {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.matchers
-            .iter()
+        self.matchers()
             .map(|matcher| write!(f, "{:?},", matcher))
             .collect()
     }
@@ -252,6 +814,14 @@ fn enable_pattern_match_url(enabled: Enabled, matcher: &mut Matcher) {
 #[cfg(not(all(feature = "pattern_match", feature = "url")))]
 fn enable_pattern_match_url(_enabled: Enabled, _matcher: &mut Matcher) {}
 
+#[cfg(all(feature = "pattern_match", feature = "url"))]
+fn enable_glob_match_url(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<GlobMatchUrl>(enabled, Enabled::GLOB_URL, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "url")))]
+fn enable_glob_match_url(_enabled: Enabled, _matcher: &mut Matcher) {}
+
 #[cfg(all(feature = "exact_match", feature = "method"))]
 fn enable_exact_match_method(enabled: Enabled, matcher: &mut Matcher) {
     enable_matcher::<ExactMatchMethod>(enabled, Enabled::EXACT_METHOD, matcher);
@@ -300,9 +870,217 @@ fn enable_pattern_match_headers(enabled: Enabled, matcher: &mut Matcher) {
 #[cfg(not(all(feature = "pattern_match", feature = "headers")))]
 fn enable_pattern_match_headers(_enabled: Enabled, _matcher: &mut Matcher) {}
 
+#[cfg(all(feature = "exact_match", feature = "query"))]
+fn enable_exact_match_query(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchQuery>(enabled, Enabled::EXACT_QUERY, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "query")))]
+fn enable_exact_match_query(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "query"))]
+fn enable_pattern_match_query(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<PatternMatchQuery>(enabled, Enabled::PATTERN_QUERY, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "query")))]
+fn enable_pattern_match_query(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "body"))]
+fn enable_exact_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchBody>(enabled, Enabled::EXACT_BODY, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "body")))]
+fn enable_exact_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "body"))]
+fn enable_equal_to_json_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<EqualToJsonMatchBody>(enabled, Enabled::EQUAL_JSON_BODY, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "body")))]
+fn enable_equal_to_json_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "body"))]
+fn enable_size_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<SizeMatchBody>(enabled, Enabled::BODY_SIZE, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "body")))]
+fn enable_size_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "body"))]
+fn enable_hash_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<HashMatchBody>(enabled, Enabled::BODY_HASH, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "body")))]
+fn enable_hash_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "body"))]
+fn enable_contains_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ContainsMatchBody>(enabled, Enabled::BODY_CONTAINS, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "body")))]
+fn enable_contains_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "protobuf"))]
+fn enable_protobuf_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ProtobufMatchBody>(enabled, Enabled::PROTOBUF_BODY, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "protobuf")))]
+fn enable_protobuf_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(feature = "script")]
+fn enable_script_match(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ScriptMatch>(enabled, Enabled::SCRIPT, matcher);
+}
+
+#[cfg(not(feature = "script"))]
+fn enable_script_match(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "xml"))]
+fn enable_soap_match(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<SoapMatch>(enabled, Enabled::SOAP, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "xml")))]
+fn enable_soap_match(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "body"))]
+fn enable_pattern_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<PatternMatchBody>(enabled, Enabled::PATTERN_BODY, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "body")))]
+fn enable_pattern_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "body"))]
+fn enable_json_path_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<JsonPathMatchBody>(enabled, Enabled::JSON_PATH_BODY, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "body")))]
+fn enable_json_path_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "xml"))]
+fn enable_xpath_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<XPathMatchBody>(enabled, Enabled::XPATH_BODY, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "xml")))]
+fn enable_xpath_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "host"))]
+fn enable_exact_match_host(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchHost>(enabled, Enabled::EXACT_HOST, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "host")))]
+fn enable_exact_match_host(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "host"))]
+fn enable_pattern_match_host(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<PatternMatchHost>(enabled, Enabled::PATTERN_HOST, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "host")))]
+fn enable_pattern_match_host(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "scheme"))]
+fn enable_exact_match_scheme(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchScheme>(enabled, Enabled::EXACT_SCHEME, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "scheme")))]
+fn enable_exact_match_scheme(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "basic_auth"))]
+fn enable_exact_match_basic_auth(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchBasicAuth>(enabled, Enabled::EXACT_BASIC_AUTH, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "basic_auth")))]
+fn enable_exact_match_basic_auth(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "multipart"))]
+fn enable_multipart_match_body(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<MultipartMatchBody>(enabled, Enabled::MULTIPART_BODY, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "multipart")))]
+fn enable_multipart_match_body(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "form"))]
+fn enable_exact_match_form(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchForm>(enabled, Enabled::EXACT_FORM, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "form")))]
+fn enable_exact_match_form(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "pattern_match", feature = "form"))]
+fn enable_pattern_match_form(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<PatternMatchForm>(enabled, Enabled::PATTERN_FORM, matcher);
+}
+
+#[cfg(not(all(feature = "pattern_match", feature = "form")))]
+fn enable_pattern_match_form(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "remote_addr"))]
+fn enable_exact_match_remote_addr(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchRemoteAddr>(enabled, Enabled::REMOTE_ADDR, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "remote_addr")))]
+fn enable_exact_match_remote_addr(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "client_cert"))]
+fn enable_exact_match_client_cert(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchClientCert>(enabled, Enabled::CLIENT_CERT, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "client_cert")))]
+fn enable_exact_match_client_cert(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "exact_match", feature = "sni"))]
+fn enable_exact_match_sni(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<ExactMatchSni>(enabled, Enabled::EXACT_SNI, matcher);
+}
+
+#[cfg(not(all(feature = "exact_match", feature = "sni")))]
+fn enable_exact_match_sni(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "negate_match", feature = "headers"))]
+fn enable_negate_match_headers(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<NegateMatchHeaders>(enabled, Enabled::NEGATE_HEADERS, matcher);
+}
+
+#[cfg(not(all(feature = "negate_match", feature = "headers")))]
+fn enable_negate_match_headers(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "negate_match", feature = "method"))]
+fn enable_negate_match_method(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<NegateMatchMethod>(enabled, Enabled::NEGATE_METHOD, matcher);
+}
+
+#[cfg(not(all(feature = "negate_match", feature = "method")))]
+fn enable_negate_match_method(_enabled: Enabled, _matcher: &mut Matcher) {}
+
+#[cfg(all(feature = "negate_match", feature = "url"))]
+fn enable_negate_match_url(enabled: Enabled, matcher: &mut Matcher) {
+    enable_matcher::<NegateMatchUrl>(enabled, Enabled::NEGATE_URL, matcher);
+}
+
+#[cfg(not(all(feature = "negate_match", feature = "url")))]
+fn enable_negate_match_url(_enabled: Enabled, _matcher: &mut Matcher) {}
+
 fn enable_matcher<T>(enabled: Enabled, contains: Enabled, matcher: &mut Matcher)
 where
-    T: 'static + RequestMatch + Default + Slogger,
+    T: 'static + RequestMatch + Default + Send + Slogger + Sync,
 {
     if enabled.contains(contains) {
         let _ = matcher.push(
@@ -319,34 +1097,178 @@ impl Matcher {
     pub fn new(enabled: Enabled, stdout: Option<Logger>, stderr: Option<Logger>) -> Self {
         let mut matcher = Self {
             matchers: vec![],
+            strict: StrictMatch::default()
+                .set_stdout(stdout.clone())
+                .set_stderr(stderr.clone()),
+            parallel_threshold: None,
+            stats: None,
             stdout,
             stderr,
         };
 
         enable_exact_match_url(enabled, &mut matcher);
         enable_pattern_match_url(enabled, &mut matcher);
+        enable_glob_match_url(enabled, &mut matcher);
         enable_exact_match_method(enabled, &mut matcher);
         enable_pattern_match_method(enabled, &mut matcher);
+        enable_exact_match_host(enabled, &mut matcher);
+        enable_pattern_match_host(enabled, &mut matcher);
+        enable_exact_match_scheme(enabled, &mut matcher);
+        enable_exact_match_basic_auth(enabled, &mut matcher);
+        enable_exact_match_remote_addr(enabled, &mut matcher);
+        enable_exact_match_client_cert(enabled, &mut matcher);
+        enable_exact_match_sni(enabled, &mut matcher);
         enable_exact_match_header(enabled, &mut matcher);
         enable_pattern_match_header(enabled, &mut matcher);
         enable_exact_match_headers(enabled, &mut matcher);
         enable_pattern_match_headers(enabled, &mut matcher);
+        enable_exact_match_query(enabled, &mut matcher);
+        enable_pattern_match_query(enabled, &mut matcher);
+        enable_exact_match_form(enabled, &mut matcher);
+        enable_pattern_match_form(enabled, &mut matcher);
+        enable_exact_match_body(enabled, &mut matcher);
+        enable_equal_to_json_match_body(enabled, &mut matcher);
+        enable_size_match_body(enabled, &mut matcher);
+        enable_pattern_match_body(enabled, &mut matcher);
+        enable_contains_match_body(enabled, &mut matcher);
+        enable_hash_match_body(enabled, &mut matcher);
+        enable_protobuf_match_body(enabled, &mut matcher);
+        enable_soap_match(enabled, &mut matcher);
+        enable_script_match(enabled, &mut matcher);
+        enable_json_path_match_body(enabled, &mut matcher);
+        enable_xpath_match_body(enabled, &mut matcher);
+        enable_multipart_match_body(enabled, &mut matcher);
+        enable_negate_match_headers(enabled, &mut matcher);
+        enable_negate_match_method(enabled, &mut matcher);
+        enable_negate_match_url(enabled, &mut matcher);
+
+        let all_of = AllOf::new(enabled)
+            .set_stdout(matcher.stdout.clone())
+            .set_stderr(matcher.stderr.clone());
+        let any_of = AnyOf::new(enabled)
+            .set_stdout(matcher.stdout.clone())
+            .set_stderr(matcher.stderr.clone());
+        let not = Not::new(enabled)
+            .set_stdout(matcher.stdout.clone())
+            .set_stderr(matcher.stderr.clone());
+        let custom = CustomMatch::default()
+            .set_stdout(matcher.stdout.clone())
+            .set_stderr(matcher.stderr.clone());
+        let _ = matcher.push(all_of).push(any_of).push(not).push(custom);
+
+        matcher.set_matcher_order(&[])
+    }
+
+    /// Use the given strict matching for request configurations that don't configure their own.
+    pub fn set_strict(mut self, strict: Option<Strict>) -> Self {
+        self.strict = StrictMatch::new(strict)
+            .set_stdout(self.stdout.clone())
+            .set_stderr(self.stderr.clone());
+        self
+    }
+
+    /// Evaluate the candidate mappings for a request in parallel once there are at least
+    /// `parallel_threshold` of them.  Smaller sets of mappings are scanned in order.
+    pub fn set_parallel_threshold(mut self, parallel_threshold: Option<usize>) -> Self {
+        self.parallel_threshold = parallel_threshold;
+        self
+    }
+
+    /// Record statistics for every request checked.
+    pub fn set_stats(mut self, stats: Option<Arc<Mutex<Stats>>>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Check the named matchers first, in the given order, and then the rest, cheapest first.
+    ///
+    /// Matchers are named as they are displayed, i.e. `Exact Match On Method`.  The strict
+    /// matcher is always checked last.
+    pub fn set_matcher_order(mut self, order: &[String]) -> Self {
+        self.matchers.sort_by_key(|matcher| {
+            let name = matcher.to_string();
+            let position = order
+                .iter()
+                .position(|ordered| *ordered == name)
+                .unwrap_or_else(|| order.len());
+            (position, matcher.cost())
+        });
+        self
+    }
 
-        matcher
+    /// Every request matcher, in the order they are checked.
+    fn matchers(&self) -> impl Iterator<Item = &dyn RequestMatch> {
+        self.matchers
+            .iter()
+            .map(|matcher| &**matcher)
+            .chain(iter::once(&self.strict as &dyn RequestMatch))
     }
 
     /// Add a request matcher to the list.
-    fn push<T: RequestMatch + 'static>(&mut self, request_match: T) -> &mut Self {
+    fn push<T: RequestMatch + Send + Sync + 'static>(&mut self, request_match: T) -> &mut Self {
         self.matchers.push(Box::new(request_match));
         self
     }
 
-    /// Get a mapping that matches the given request.
-    pub fn get_match(&self, request: &Request<()>, mappings: &Mappings) -> Result<Mapping, Error> {
-        mappings
+    /// Get a mapping that matches the given request, with every scenario in its starting state.
+    pub fn get_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        mappings: &Mappings,
+    ) -> Result<Mapping, Error> {
+        self.get_match_in_state(request, mappings, &Scenarios::default())
+            .map(|(_, mapping)| mapping)
+    }
+
+    /// Get a mapping that matches the given request, given the current state of every scenario,
+    /// along with the id it is stored under in the mappings.  Mapping names needn't be unique,
+    /// so per-mapping state is kept by id.
+    pub fn get_match_in_state(
+        &self,
+        request: &Request<Vec<u8>>,
+        mappings: &Mappings,
+        scenarios: &Scenarios,
+    ) -> Result<(Uuid, Mapping), Error> {
+        let candidates: Vec<(&Uuid, &Mapping)> = mappings
             .inner()
             .iter()
-            .inspect(|(_uuid, mapping)| {
+            .filter(|(_, mapping)| *mapping.enabled() && scenarios.allows(mapping))
+            .collect();
+
+        // Statistics are collected for this request alone, and added to the shared statistics
+        // once, so the matching threads don't contend for the lock.
+        let (matched, local_stats) = if self
+            .parallel_threshold
+            .map_or(false, |threshold| candidates.len() >= threshold)
+        {
+            try_trace!(
+                self.stdout,
+                "Checking {} mappings in parallel",
+                candidates.len()
+            );
+            candidates
+                .par_iter()
+                .fold(
+                    || (None, Stats::default()),
+                    |(matched, mut local_stats), (id, mapping)| {
+                        if self.is_match(request, **id, mapping, &mut local_stats) {
+                            (best_match(matched, Some((**id, *mapping))), local_stats)
+                        } else {
+                            (matched, local_stats)
+                        }
+                    },
+                )
+                .reduce(
+                    || (None, Stats::default()),
+                    |(left, mut local_stats), (right, other_stats)| {
+                        local_stats.merge(other_stats);
+                        (best_match(left, right), local_stats)
+                    },
+                )
+        } else {
+            let mut local_stats = Stats::default();
+            let mut matched = None;
+            for (id, mapping) in &candidates {
                 try_trace!(self.stdout, "");
                 try_trace!(
                     self.stdout,
@@ -354,39 +1276,189 @@ impl Matcher {
                     format!(" Checking '{}' ", mapping.name()),
                     80
                 );
-            })
-            .filter_map(|(_uuid, mapping)| self.is_match(request, mapping))
-            .min()
+                if self.is_match(request, **id, mapping, &mut local_stats) {
+                    matched = best_match(matched, Some((**id, *mapping)));
+                }
+            }
+            (matched, local_stats)
+        };
+
+        if let Some(stats) = &self.stats {
+            match stats.lock() {
+                Ok(mut guard) => guard.merge(local_stats),
+                Err(poisoned) => poisoned.into_inner().merge(local_stats),
+            }
+        }
+
+        matched
+            .map(|(id, mapping)| (id, mapping.clone()))
             .ok_or_else(|| MappingNotFound)
     }
 
-    fn is_match(&self, request: &Request<()>, mapping: &Mapping) -> Option<Mapping> {
-        let matches = self
-            .matchers
-            .iter()
-            // Generate a list of matches
+    /// Get a report of how the given request fared against every enabled mapping.
+    pub fn reports(&self, request: &Request<Vec<u8>>, mappings: &Mappings) -> Vec<MatchReport> {
+        mappings
+            .inner()
+            .values()
+            .filter(|mapping| *mapping.enabled())
+            .map(|mapping| self.report(request, mapping))
+            .collect()
+    }
+
+    /// Get a report of how the given request fared against a mapping.
+    pub fn report(&self, request: &Request<Vec<u8>>, mapping: &Mapping) -> MatchReport {
+        let outcomes = self
+            .matchers()
+            .filter_map(
+                |matcher| match matcher.outcome(request, mapping.request()) {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        try_error!(self.stderr, "{} - {}", matcher, e);
+                        Some(
+                            MatchOutcome::new(matcher.to_string(), false)
+                                .with_reason(e.to_string()),
+                        )
+                    }
+                },
+            )
+            .collect();
+
+        MatchReport::new(mapping.name().clone(), outcomes)
+    }
+
+    /// Get the mappings that nearly matched the given request, closest first.
+    pub fn near_misses(&self, request: &Request<Vec<u8>>, mappings: &Mappings) -> Vec<NearMiss> {
+        let mut near_misses: Vec<NearMiss> = self
+            .reports(request, mappings)
+            .into_iter()
+            .filter_map(|report| {
+                let matched: Vec<String> = report
+                    .matched()
+                    .map(|outcome| outcome.matcher().clone())
+                    .collect();
+                let failures: Vec<MatchOutcome> = report.failed().cloned().collect();
+
+                if matched.is_empty() || failures.is_empty() {
+                    None
+                } else {
+                    Some(NearMiss {
+                        name: report.name().clone(),
+                        matched,
+                        failed: failures
+                            .iter()
+                            .map(|outcome| outcome.matcher().clone())
+                            .collect(),
+                        failures,
+                    })
+                }
+            })
+            .collect();
+
+        near_misses.sort_by(|left, right| {
+            right
+                .matched
+                .len()
+                .cmp(&left.matched.len())
+                .then_with(|| left.name.cmp(&right.name))
+        });
+        near_misses.truncate(MAX_NEAR_MISSES);
+        near_misses
+    }
+
+    /// Does the request match the mapping?  The checks are recorded in `local_stats` if this
+    /// matcher keeps statistics.
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        id: Uuid,
+        mapping: &Mapping,
+        local_stats: &mut Stats,
+    ) -> bool {
+        if self.stats.is_some() {
+            let start = Instant::now();
+            let matched = self.evaluate_recording(request, mapping.request(), Some(local_stats));
+            local_stats.record_mapping(id, mapping.name(), matched == Some(true), start.elapsed());
+            matched == Some(true)
+        } else {
+            self.evaluate(request, mapping.request()) == Some(true)
+        }
+    }
+
+    /// Check a request against a request configuration.
+    ///
+    /// Returns `None` if none of the matchers had anything to check, otherwise whether every
+    /// configured matcher matched.  The remaining matchers are skipped once one doesn't match.
+    crate fn evaluate(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Option<bool> {
+        self.evaluate_recording(request, request_config, None)
+    }
+
+    /// Check a request against a request configuration, recording each matcher that had
+    /// something to check in the given statistics.
+    fn evaluate_recording(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+        stats: Option<&mut Stats>,
+    ) -> Option<bool> {
+        let mut checked = vec![];
+        let mut all_true = true;
+
+        for matcher in self.matchers() {
+            let start = Instant::now();
             // * If the matcher was configured and matches, returns `Some(true)`
             // * If the matcher was configured and doesn't match, returns `Some(false)`
             // * If the matcher was not configured, returns `None`
-            .map(|matcher| matcher.is_match(request, mapping.request()))
-            // Filter out any Errors
-            .filter_map(|res| res.ok())
-            // Filter out the `None` from matchers that weren't configured
-            .filter_map(|x| x)
-            .collect::<Vec<bool>>();
-
-        let all_true = matches.iter().all(|x| *x);
-        try_trace!(self.stdout, "Matches: {:?}, All: {}", matches, all_true);
-
-        // Is the remaining list non-empty and all true?
-        if !matches.is_empty() && all_true {
-            Some(mapping.clone())
-        } else {
+            // * If the configuration is invalid, i.e. a regex doesn't compile, the error is
+            //   reported and the request can't match
+            let matched = match matcher.is_match(request, request_config) {
+                Ok(matched) => matched,
+                Err(e) => {
+                    try_error!(self.stderr, "{} - {}", matcher, e);
+                    Some(false)
+                }
+            };
+            if let Some(matched) = matched {
+                checked.push((matcher, matched, start.elapsed()));
+
+                if !matched {
+                    try_trace!(self.stdout, "Failed: {}", matcher);
+                    all_true = false;
+                    break;
+                }
+            }
+        }
+
+        try_trace!(self.stdout, "Checked: {}, All: {}", checked.len(), all_true);
+
+        if let Some(stats) = stats {
+            for (matcher, matched, elapsed) in &checked {
+                stats.record_matcher(&matcher.to_string(), *matched, *elapsed);
+            }
+        }
+
+        if checked.is_empty() {
             None
+        } else {
+            Some(all_true)
         }
     }
 }
 
+/// The better of two matches, the one whose mapping orders first, i.e. with the highest priority.
+fn best_match<'a>(
+    left: Option<(Uuid, &'a Mapping)>,
+    right: Option<(Uuid, &'a Mapping)>,
+) -> Option<(Uuid, &'a Mapping)> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(if right.1 < left.1 { right } else { left }),
+        (left, right) => left.or(right),
+    }
+}
+
 impl Slogger for Matcher {
     /// Add a stdout logger
     fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
@@ -403,12 +1475,20 @@ impl Slogger for Matcher {
 
 #[cfg(test)]
 mod test {
-    use super::Matcher;
+    use super::{register_matcher, MatchCost, Matcher, RequestMatch, Scenarios, Stats};
     use crate::config::files::test::test_files;
     use crate::config::mappings::test::test_mappings;
+    use crate::config::Request as RequestConfig;
+    use crate::error::Error;
     use crate::matcher::Enabled;
+    use crate::util::ServerName;
+    use crate::x509::test::test_client_certificate;
     use http::request::Builder;
+    use http::uri::Scheme;
     use http::Request;
+    use std::fmt;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
     // use slog::{o, Drain};
     // use slog_term;
 
@@ -421,11 +1501,34 @@ mod test {
                 | Enabled::PATTERN_METHOD
                 | Enabled::PATTERN_HEADER
                 | Enabled::PATTERN_HEADERS
+                | Enabled::PATTERN_QUERY
+                | Enabled::JSON_PATH_BODY
+                | Enabled::XPATH_BODY
+                | Enabled::PATTERN_HOST
+                | Enabled::MULTIPART_BODY
+                | Enabled::PATTERN_FORM
+                | Enabled::GLOB_URL
+                | Enabled::PATTERN_BODY
+                | Enabled::SOAP
         ));
         assert!(!all_pattern.contains(Enabled::EXACT_URL));
         assert!(!all_pattern.contains(Enabled::EXACT_METHOD));
         assert!(!all_pattern.contains(Enabled::EXACT_HEADER));
         assert!(!all_pattern.contains(Enabled::EXACT_HEADERS));
+        assert!(!all_pattern.contains(Enabled::EXACT_QUERY));
+        assert!(!all_pattern.contains(Enabled::EXACT_BODY));
+        assert!(!all_pattern.contains(Enabled::EQUAL_JSON_BODY));
+        assert!(!all_pattern.contains(Enabled::EXACT_HOST));
+        assert!(!all_pattern.contains(Enabled::EXACT_SCHEME));
+        assert!(!all_pattern.contains(Enabled::EXACT_BASIC_AUTH));
+        assert!(!all_pattern.contains(Enabled::EXACT_FORM));
+        assert!(!all_pattern.contains(Enabled::BODY_SIZE));
+        assert!(!all_pattern.contains(Enabled::REMOTE_ADDR));
+        assert!(!all_pattern.contains(Enabled::BODY_CONTAINS));
+        assert!(!all_pattern.contains(Enabled::PROTOBUF_BODY));
+        assert!(!all_pattern.contains(Enabled::BODY_HASH));
+        assert!(!all_pattern.contains(Enabled::CLIENT_CERT));
+        assert!(!all_pattern.contains(Enabled::EXACT_SNI));
     }
 
     #[test]
@@ -437,15 +1540,48 @@ mod test {
                 | Enabled::EXACT_METHOD
                 | Enabled::EXACT_HEADER
                 | Enabled::EXACT_HEADERS
+                | Enabled::EXACT_QUERY
+                | Enabled::EXACT_BODY
+                | Enabled::EQUAL_JSON_BODY
+                | Enabled::EXACT_HOST
+                | Enabled::EXACT_SCHEME
+                | Enabled::EXACT_BASIC_AUTH
+                | Enabled::EXACT_FORM
+                | Enabled::BODY_SIZE
+                | Enabled::REMOTE_ADDR
+                | Enabled::BODY_CONTAINS
+                | Enabled::PROTOBUF_BODY
+                | Enabled::BODY_HASH
+                | Enabled::CLIENT_CERT
+                | Enabled::EXACT_SNI
         ));
         assert!(!all_exact.contains(Enabled::PATTERN_URL));
         assert!(!all_exact.contains(Enabled::PATTERN_METHOD));
         assert!(!all_exact.contains(Enabled::PATTERN_HEADER));
         assert!(!all_exact.contains(Enabled::PATTERN_HEADERS));
+        assert!(!all_exact.contains(Enabled::PATTERN_QUERY));
+        assert!(!all_exact.contains(Enabled::JSON_PATH_BODY));
+        assert!(!all_exact.contains(Enabled::XPATH_BODY));
+        assert!(!all_exact.contains(Enabled::PATTERN_HOST));
+        assert!(!all_exact.contains(Enabled::MULTIPART_BODY));
+        assert!(!all_exact.contains(Enabled::PATTERN_FORM));
+        assert!(!all_exact.contains(Enabled::GLOB_URL));
+        assert!(!all_exact.contains(Enabled::PATTERN_BODY));
+        assert!(!all_exact.contains(Enabled::SOAP));
     }
 
-    #[allow(box_pointers)]
     fn check_request(enabled: Enabled, request_builder: &mut Builder, priority: u8, name: &str) {
+        check_request_body(enabled, request_builder, vec![], priority, name);
+    }
+
+    #[allow(box_pointers)]
+    fn check_request_body(
+        enabled: Enabled,
+        request_builder: &mut Builder,
+        body: Vec<u8>,
+        priority: u8,
+        name: &str,
+    ) {
         let mappings = test_mappings().expect("Unable to setup mappings!");
         // let decorator = slog_term::PlainDecorator::new(std::io::stderr());
         // let drain = slog_term::CompactFormat::new(decorator).build().fuse();
@@ -455,7 +1591,7 @@ mod test {
         let matcher = Matcher::new(enabled, None, None);
         assert!(!matcher.matchers.is_empty());
 
-        if let Ok(request) = request_builder.body(()) {
+        if let Ok(request) = request_builder.body(body) {
             if let Ok(mapping) = matcher.get_match(&request, &mappings) {
                 assert_eq!(mapping.name(), name);
                 assert_eq!(*mapping.priority(), priority);
@@ -468,13 +1604,17 @@ mod test {
         }
     }
 
-    #[allow(box_pointers)]
     fn check_no_match(enabled: Enabled, request_builder: &mut Builder) {
+        check_no_match_body(enabled, request_builder, vec![]);
+    }
+
+    #[allow(box_pointers)]
+    fn check_no_match_body(enabled: Enabled, request_builder: &mut Builder, body: Vec<u8>) {
         let mappings = test_mappings().expect("Unable to setup mappings!");
         let matcher = Matcher::new(enabled, None, None);
         assert!(!matcher.matchers.is_empty());
 
-        if let Ok(request) = request_builder.body(()) {
+        if let Ok(request) = request_builder.body(body) {
             assert!(matcher.get_match(&request, &mappings).is_err());
         } else {
             assert!(false, "Unable to build the request to test!");
@@ -530,6 +1670,43 @@ mod test {
         );
     }
 
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_methods() {
+        for method in &["GET", "HEAD"] {
+            let mut request_builder = Request::builder();
+            let _ = request_builder.method(*method).uri("/health");
+
+            check_request(
+                Enabled::EXACT_URL | Enabled::EXACT_METHOD,
+                &mut request_builder,
+                1,
+                "Exact Match - Methods & URL",
+            );
+        }
+
+        let mut post = Request::builder();
+        let _ = post.method("POST").uri("/health");
+
+        check_no_match(Enabled::EXACT_URL | Enabled::EXACT_METHOD, &mut post);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_any_method() {
+        for method in &["GET", "DELETE", "OPTIONS"] {
+            let mut request_builder = Request::builder();
+            let _ = request_builder.method(*method).uri("/ping");
+
+            check_request(
+                Enabled::EXACT_URL | Enabled::EXACT_METHOD,
+                &mut request_builder,
+                3,
+                "Exact Match - Any Method & URL",
+            );
+        }
+    }
+
     #[test]
     #[allow(box_pointers)]
     fn exact_match_url() {
@@ -544,6 +1721,57 @@ mod test {
         );
     }
 
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_url_json_mapping() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/json-mapping");
+
+        check_request(
+            Enabled::EXACT_URL,
+            &mut request_builder,
+            1,
+            "Exact Match - URL (JSON)",
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_url_yaml_mapping() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/yaml-mapping");
+
+        check_request(
+            Enabled::EXACT_URL,
+            &mut request_builder,
+            1,
+            "Exact Match - URL (YAML)",
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_url_fragment_mapping() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/fragment");
+
+        check_request(
+            Enabled::EXACT_URL,
+            &mut request_builder,
+            1,
+            "Exact Match - URL (Fragment)",
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn disabled_mapping() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/disabled");
+
+        check_no_match(Enabled::EXACT_URL, &mut request_builder);
+    }
+
     #[test]
     #[allow(box_pointers)]
     fn exact_match_method_and_url() {
@@ -675,6 +1903,996 @@ mod test {
         );
     }
 
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_query() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/search?page=2&q=exact+match");
+
+        check_request(
+            Enabled::EXACT_QUERY,
+            &mut request_builder,
+            1,
+            "Exact Match - Query",
+        );
+
+        let mut wrong_value = Request::builder();
+        let _ = wrong_value.uri("/search?page=3&q=exact+match");
+
+        check_no_match(Enabled::EXACT_QUERY, &mut wrong_value);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn pattern_match_query() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/search?id=12345");
+
+        check_request(
+            Enabled::PATTERN_QUERY,
+            &mut request_builder,
+            1,
+            "Pattern Match - Query",
+        );
+
+        let mut bad_id = Request::builder();
+        let _ = bad_id.uri("/search?id=abc");
+
+        check_no_match(Enabled::PATTERN_QUERY, &mut bad_id);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_body() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+
+        check_request_body(
+            Enabled::EXACT_BODY,
+            &mut request_builder,
+            br#"{"id": 12345}"#.to_vec(),
+            1,
+            "Exact Match - Body",
+        );
+
+        let mut other_body = Request::builder();
+        let _ = other_body.method("POST");
+
+        check_no_match_body(
+            Enabled::EXACT_BODY,
+            &mut other_body,
+            br#"{"id": 54321}"#.to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn pattern_match_body() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST").uri("/orders");
+
+        check_request_body(
+            Enabled::EXACT_URL | Enabled::PATTERN_BODY,
+            &mut request_builder,
+            br#"{"order_id": "ORD-20190412-0042", "items": []}"#.to_vec(),
+            1,
+            "Pattern Match - Body & URL",
+        );
+
+        let mut other_body = Request::builder();
+        let _ = other_body.method("POST").uri("/orders");
+
+        check_no_match_body(
+            Enabled::EXACT_URL | Enabled::PATTERN_BODY,
+            &mut other_body,
+            br#"{"order_id": "42", "items": []}"#.to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn contains_match_body() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST").uri("/shipments");
+
+        check_request_body(
+            Enabled::EXACT_URL | Enabled::BODY_CONTAINS,
+            &mut request_builder,
+            br#"{"shipment": {"order_id": "ORD-7731", "carrier": "ups"}}"#.to_vec(),
+            1,
+            "Contains Match - Body & URL",
+        );
+
+        let mut other_body = Request::builder();
+        let _ = other_body.method("POST").uri("/shipments");
+
+        check_no_match_body(
+            Enabled::EXACT_URL | Enabled::BODY_CONTAINS,
+            &mut other_body,
+            br#"{"shipment": {"order_id": "ORD-7731", "carrier": "fedex"}}"#.to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn protobuf_match_body() {
+        // orders.CreateOrder { order_id: "ORD-1", customer: { id: 42 }, status: PAID }
+        let body = b"\x0a\x05ORD-1\x12\x02\x08\x2a\x18\x01";
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST").uri("/v1/orders");
+        let _ = request_builder.header("Content-Type", "application/x-protobuf");
+
+        check_request_body(
+            Enabled::EXACT_URL | Enabled::PROTOBUF_BODY,
+            &mut request_builder,
+            body.to_vec(),
+            1,
+            "Protobuf Match - Body & URL",
+        );
+
+        // status: PENDING is the default, so it isn't encoded.
+        let mut pending = Request::builder();
+        let _ = pending.method("POST").uri("/v1/orders");
+        let _ = pending.header("Content-Type", "application/x-protobuf");
+
+        check_no_match_body(
+            Enabled::EXACT_URL | Enabled::PROTOBUF_BODY,
+            &mut pending,
+            body[..body.len() - 2].to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_header_case_insensitive() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.header("X-Case-Match", "YODA");
+
+        check_request(
+            Enabled::EXACT_HEADER,
+            &mut request_builder,
+            1,
+            "Case Insensitive - Header",
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn pattern_match_header_case_insensitive() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.header("X-Case-Pattern", "YODA RULES");
+
+        check_request(
+            Enabled::PATTERN_HEADER,
+            &mut request_builder,
+            1,
+            "Case Insensitive - Header",
+        );
+
+        let mut other_case = Request::builder();
+        let _ = other_case.header("X-Pattern-Match", "YODA RULES");
+
+        check_no_match(Enabled::PATTERN_HEADER, &mut other_case);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn negate_match_headers() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/secure");
+
+        check_request(
+            Enabled::NEGATE_HEADERS,
+            &mut request_builder,
+            1,
+            "Negate Match - Headers",
+        );
+
+        let mut authorized = Request::builder();
+        let _ = authorized.uri("/secure");
+        let _ = authorized.header("Authorization", "Bearer abc");
+
+        check_no_match(Enabled::NEGATE_HEADERS, &mut authorized);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn negate_match_method_and_url() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+        let _ = request_builder.uri("/orders");
+
+        check_request(
+            Enabled::NEGATE_METHOD | Enabled::NEGATE_URL,
+            &mut request_builder,
+            1,
+            "Negate Match - Method & URL",
+        );
+
+        let mut get = Request::builder();
+        let _ = get.method("GET");
+        let _ = get.uri("/orders");
+
+        check_no_match(Enabled::NEGATE_METHOD | Enabled::NEGATE_URL, &mut get);
+
+        let mut admin = Request::builder();
+        let _ = admin.method("POST");
+        let _ = admin.uri("/admin/users");
+
+        check_no_match(Enabled::NEGATE_METHOD | Enabled::NEGATE_URL, &mut admin);
+    }
+
+    #[test]
+    fn enable_negate() {
+        let all_negate = Enabled::negate();
+        assert!(all_negate
+            .contains(Enabled::NEGATE_HEADERS | Enabled::NEGATE_METHOD | Enabled::NEGATE_URL));
+        assert!(!all_negate.contains(Enabled::EXACT_URL));
+        assert!(!all_negate.contains(Enabled::PATTERN_URL));
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn parallel_match() {
+        let mappings = test_mappings().expect("Unable to setup mappings!");
+        let matcher = Matcher::new(Enabled::EXACT_URL | Enabled::EXACT_METHOD, None, None)
+            .set_parallel_threshold(Some(1));
+
+        match Request::builder().uri("/json").method("GET").body(vec![]) {
+            Ok(request) => match matcher.get_match(&request, &mappings) {
+                Ok(mapping) => assert_eq!(mapping.name(), "Exact Match - Method & URL"),
+                Err(e) => assert!(false, e.to_string()),
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match Request::builder().uri("/json").method("POST").body(vec![]) {
+            Ok(request) => assert!(matcher.get_match(&request, &mappings).is_err()),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn stats() {
+        let mappings = test_mappings().expect("Unable to setup mappings!");
+        let stats = Arc::new(Mutex::new(Stats::default()));
+        let matcher = Matcher::new(Enabled::EXACT_URL | Enabled::EXACT_METHOD, None, None)
+            .set_stats(Some(stats.clone()));
+
+        match Request::builder().uri("/json").method("GET").body(vec![]) {
+            Ok(request) => assert!(matcher.get_match(&request, &mappings).is_ok()),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match stats.lock() {
+            Ok(stats) => {
+                match stats
+                    .mappings()
+                    .values()
+                    .find(|mapping| mapping.name() == "Exact Match - Method & URL")
+                {
+                    Some(mapping) => {
+                        assert_eq!(*mapping.counters().evaluations(), 1);
+                        assert_eq!(*mapping.counters().matches(), 1);
+                    }
+                    None => assert!(false, "No counters for the mapping!"),
+                }
+                match stats.matchers().get("Exact Match On Url") {
+                    Some(counters) => assert!(*counters.evaluations() > 0),
+                    None => assert!(false, "No counters for the matcher!"),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn matcher_order() {
+        let matcher = Matcher::new(Enabled::all(), None, None);
+        let costs: Vec<MatchCost> = matcher.matchers.iter().map(|m| m.cost()).collect();
+        let mut sorted = costs.clone();
+        sorted.sort();
+        assert_eq!(costs, sorted);
+        assert_eq!(costs.first(), Some(&MatchCost::Cheap));
+        assert_eq!(costs.last(), Some(&MatchCost::Expensive));
+
+        let matcher = matcher.set_matcher_order(&["Not".to_string(), "All Of".to_string()]);
+        let names: Vec<String> = matcher.matchers().map(|m| m.to_string()).collect();
+        assert_eq!(names[0], "Not");
+        assert_eq!(names[1], "All Of");
+        assert_eq!(names.last().map(String::as_str), Some("Strict Match"));
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn scenario_states() {
+        let mappings = test_mappings().expect("Unable to setup mappings!");
+        let matcher = Matcher::new(Enabled::EXACT_URL | Enabled::EXACT_METHOD, None, None);
+        let mut scenarios = Scenarios::default();
+        let get = Request::builder()
+            .method("GET")
+            .uri("/scenario/order")
+            .body(vec![])
+            .expect("Unable to build the request to test!");
+        let delete = Request::builder()
+            .method("DELETE")
+            .uri("/scenario/order")
+            .body(vec![])
+            .expect("Unable to build the request to test!");
+
+        match matcher.get_match_in_state(&get, &mappings, &scenarios) {
+            Ok(mapping) => assert_eq!(mapping.name(), "Scenario - Order"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match matcher.get_match_in_state(&delete, &mappings, &scenarios) {
+            Ok(mapping) => {
+                assert_eq!(mapping.name(), "Scenario - Delete Order");
+                scenarios.transition(&mapping);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match matcher.get_match_in_state(&get, &mappings, &scenarios) {
+            Ok(mapping) => assert_eq!(mapping.name(), "Scenario - Order Deleted"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(matcher
+            .get_match_in_state(&delete, &mappings, &scenarios)
+            .is_err());
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn equal_to_json_match_body() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+
+        check_request_body(
+            Enabled::EQUAL_JSON_BODY,
+            &mut request_builder,
+            br#"{ "tags": ["b", "a"], "order": 54321, "note": "extra" }"#.to_vec(),
+            1,
+            "Equal To JSON - Body",
+        );
+
+        let mut other_body = Request::builder();
+        let _ = other_body.method("POST");
+
+        check_no_match_body(
+            Enabled::EQUAL_JSON_BODY,
+            &mut other_body,
+            br#"{"order": 54321, "tags": ["a"]}"#.to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn json_path_match_body() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+
+        check_request_body(
+            Enabled::JSON_PATH_BODY,
+            &mut request_builder,
+            br#"{"customer": {"tier": "gold"}, "items": [{"sku": "A1", "qty": 2}]}"#.to_vec(),
+            1,
+            "JSONPath Match - Body",
+        );
+
+        let mut other_tier = Request::builder();
+        let _ = other_tier.method("POST");
+
+        check_no_match_body(
+            Enabled::JSON_PATH_BODY,
+            &mut other_tier,
+            br#"{"customer": {"tier": "silver"}, "items": [{"sku": "A1", "qty": 2}]}"#.to_vec(),
+        );
+
+        let mut not_json = Request::builder();
+        let _ = not_json.method("POST");
+
+        check_no_match_body(
+            Enabled::JSON_PATH_BODY,
+            &mut not_json,
+            b"tier=gold".to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn xpath_match_body() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+
+        check_request_body(
+            Enabled::XPATH_BODY,
+            &mut request_builder,
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><GetOrder><id>12345</id></GetOrder></s:Body></s:Envelope>"#.to_vec(),
+            1,
+            "XPath Match - Body",
+        );
+
+        let mut other_id = Request::builder();
+        let _ = other_id.method("POST");
+
+        check_no_match_body(
+            Enabled::XPATH_BODY,
+            &mut other_id,
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><GetOrder><id>54321</id></GetOrder></s:Body></s:Envelope>"#.to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn soap_match() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST").uri("/services/inventory");
+        let _ = request_builder.header("SOAPAction", "\"http://example.com/inventory/CheckStock\"");
+
+        check_request_body(
+            Enabled::EXACT_URL | Enabled::SOAP,
+            &mut request_builder,
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><CheckStock><sku>WID-1</sku></CheckStock></s:Body></s:Envelope>"#.to_vec(),
+            1,
+            "SOAP Match - URL",
+        );
+
+        let mut other_action = Request::builder();
+        let _ = other_action.method("POST").uri("/services/inventory");
+        let _ = other_action.header("SOAPAction", "\"http://example.com/inventory/Reserve\"");
+
+        check_no_match_body(
+            Enabled::EXACT_URL | Enabled::SOAP,
+            &mut other_action,
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><CheckStock><sku>WID-1</sku></CheckStock></s:Body></s:Envelope>"#.to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn strict_match() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/strict/orders?id=1");
+        let _ = request_builder.header("X-Request-Id", "abc");
+
+        check_request(
+            Enabled::EXACT_URL | Enabled::EXACT_QUERY,
+            &mut request_builder,
+            1,
+            "Strict Match - Query & URL",
+        );
+
+        let mut extra_param = Request::builder();
+        let _ = extra_param.uri("/strict/orders?id=1&debug=true");
+
+        check_no_match(Enabled::EXACT_URL | Enabled::EXACT_QUERY, &mut extra_param);
+
+        let mut extra_header = Request::builder();
+        let _ = extra_header.uri("/strict/orders?id=1");
+        let _ = extra_header.header("X-Api-Version", "2");
+
+        check_no_match(Enabled::EXACT_URL | Enabled::EXACT_QUERY, &mut extra_header);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn hash_match_body() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("PUT").uri("/archive/reports");
+
+        check_request_body(
+            Enabled::EXACT_URL | Enabled::BODY_HASH,
+            &mut request_builder,
+            b"%PDF-1.4 quarterly report".to_vec(),
+            1,
+            "Hash Match - Body & URL",
+        );
+
+        let mut other_body = Request::builder();
+        let _ = other_body.method("PUT").uri("/archive/reports");
+
+        check_no_match_body(
+            Enabled::EXACT_URL | Enabled::BODY_HASH,
+            &mut other_body,
+            b"%PDF-1.4 annual report".to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_host() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/orders");
+        let _ = request_builder.header("Host", "API.example.com:8080");
+
+        check_request(
+            Enabled::EXACT_HOST,
+            &mut request_builder,
+            1,
+            "Exact Match - Host",
+        );
+
+        let mut absolute = Request::builder();
+        let _ = absolute.uri("http://api.example.com/orders");
+
+        check_request(Enabled::EXACT_HOST, &mut absolute, 1, "Exact Match - Host");
+
+        let mut other_host = Request::builder();
+        let _ = other_host.uri("/orders");
+        let _ = other_host.header("Host", "admin.example.com");
+
+        check_no_match(Enabled::EXACT_HOST, &mut other_host);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn pattern_match_host() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/");
+        let _ = request_builder.header("Host", "www.example.org:8443");
+
+        check_request(
+            Enabled::PATTERN_HOST,
+            &mut request_builder,
+            1,
+            "Pattern Match - Host",
+        );
+
+        let mut other_host = Request::builder();
+        let _ = other_host.uri("/");
+        let _ = other_host.header("Host", "www.example.net");
+
+        check_no_match(Enabled::PATTERN_HOST, &mut other_host);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_scheme() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/secure");
+        let _ = request_builder.extension(Scheme::HTTPS);
+
+        check_request(
+            Enabled::EXACT_URL | Enabled::EXACT_SCHEME,
+            &mut request_builder,
+            1,
+            "Exact Match - Scheme & URL",
+        );
+
+        let mut absolute = Request::builder();
+        let _ = absolute.uri("https://localhost/secure");
+
+        check_request(
+            Enabled::EXACT_SCHEME,
+            &mut absolute,
+            1,
+            "Exact Match - Scheme & URL",
+        );
+
+        let mut plain = Request::builder();
+        let _ = plain.uri("/secure");
+
+        check_no_match(Enabled::EXACT_URL | Enabled::EXACT_SCHEME, &mut plain);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_basic_auth() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/admin");
+        let _ = request_builder.header("Authorization", "Basic YWRtaW46czNjcjN0");
+
+        check_request(
+            Enabled::EXACT_URL | Enabled::EXACT_BASIC_AUTH,
+            &mut request_builder,
+            1,
+            "Exact Match - Basic Auth & URL",
+        );
+
+        let mut wrong_password = Request::builder();
+        let _ = wrong_password.uri("/admin");
+        let _ = wrong_password.header("Authorization", "Basic YWRtaW46d3Jvbmc=");
+
+        check_no_match(
+            Enabled::EXACT_URL | Enabled::EXACT_BASIC_AUTH,
+            &mut wrong_password,
+        );
+
+        let mut anonymous = Request::builder();
+        let _ = anonymous.uri("/admin");
+
+        check_no_match(
+            Enabled::EXACT_URL | Enabled::EXACT_BASIC_AUTH,
+            &mut anonymous,
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn multipart_match_body() {
+        let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nQuarterly report\r\n--XyZ\r\nContent-Disposition: form-data; name=\"upload\"; filename=\"report.csv\"\r\nContent-Type: text/csv\r\n\r\nid,name\r\n1,widget\r\n--XyZ--\r\n";
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+        let _ = request_builder.header("Content-Type", "multipart/form-data; boundary=XyZ");
+
+        check_request_body(
+            Enabled::MULTIPART_BODY,
+            &mut request_builder,
+            body.to_vec(),
+            1,
+            "Multipart Match - Body",
+        );
+
+        let mut other_boundary = Request::builder();
+        let _ = other_boundary.method("POST");
+        let _ = other_boundary.header("Content-Type", "multipart/form-data; boundary=AbC");
+
+        check_no_match_body(Enabled::MULTIPART_BODY, &mut other_boundary, body.to_vec());
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_form() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+        let _ = request_builder.header("Content-Type", "application/x-www-form-urlencoded");
+
+        check_request_body(
+            Enabled::EXACT_FORM,
+            &mut request_builder,
+            b"username=jozias&remember=on&note=hello+world%21".to_vec(),
+            1,
+            "Exact Match - Form",
+        );
+
+        let mut not_form = Request::builder();
+        let _ = not_form.method("POST");
+        let _ = not_form.header("Content-Type", "text/plain");
+
+        check_no_match_body(
+            Enabled::EXACT_FORM,
+            &mut not_form,
+            b"username=jozias&remember=on".to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn pattern_match_form() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.method("POST");
+        let _ = request_builder.header(
+            "Content-Type",
+            "application/x-www-form-urlencoded; charset=utf-8",
+        );
+
+        check_request_body(
+            Enabled::PATTERN_FORM,
+            &mut request_builder,
+            b"email=jozias%40example.com&age=42".to_vec(),
+            1,
+            "Pattern Match - Form",
+        );
+
+        let mut bad_email = Request::builder();
+        let _ = bad_email.method("POST");
+        let _ = bad_email.header("Content-Type", "application/x-www-form-urlencoded");
+
+        check_no_match_body(
+            Enabled::PATTERN_FORM,
+            &mut bad_email,
+            b"email=jozias&age=42".to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn combinator_match() {
+        let enabled = Enabled::EXACT_URL | Enabled::EXACT_HEADERS;
+        let mut header_a = Request::builder();
+        let _ = header_a.uri("/combinator");
+        let _ = header_a.header("X-Client", "a");
+
+        check_request(enabled, &mut header_a, 1, "Combinator Match - Any Of & Not");
+
+        let mut header_b = Request::builder();
+        let _ = header_b.uri("/combinator");
+        let _ = header_b.header("X-Client", "b");
+
+        check_request(enabled, &mut header_b, 1, "Combinator Match - Any Of & Not");
+
+        let mut header_c = Request::builder();
+        let _ = header_c.uri("/combinator");
+        let _ = header_c.header("X-Client", "c");
+
+        check_no_match(enabled, &mut header_c);
+
+        let mut blocked = Request::builder();
+        let _ = blocked.uri("/combinator");
+        let _ = blocked.header("X-Client", "a");
+        let _ = blocked.header("X-Blocked", "true");
+
+        check_no_match(enabled, &mut blocked);
+    }
+
+    #[derive(Debug)]
+    struct Signed;
+
+    impl RequestMatch for Signed {
+        fn is_match(
+            &self,
+            request: &Request<Vec<u8>>,
+            _request_config: &RequestConfig,
+        ) -> Result<Option<bool>, Error> {
+            Ok(Some(
+                request
+                    .headers()
+                    .get("X-Signature")
+                    .map_or(false, |signature| signature == "s1gn3d"),
+            ))
+        }
+    }
+
+    impl fmt::Display for Signed {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Signed")
+        }
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn custom_match() {
+        register_matcher("signature-check", Signed);
+
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/signed");
+        let _ = request_builder.header("X-Signature", "s1gn3d");
+
+        check_request(
+            Enabled::EXACT_URL,
+            &mut request_builder,
+            1,
+            "Custom Match - URL",
+        );
+
+        let mut unsigned = Request::builder();
+        let _ = unsigned.uri("/signed");
+
+        check_no_match(Enabled::EXACT_URL, &mut unsigned);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn size_match_body() {
+        let mut too_large = Request::builder();
+        let _ = too_large.method("PUT").uri("/upload");
+        let _ = too_large.header("Content-Length", "2097152");
+
+        check_request(
+            Enabled::EXACT_URL | Enabled::BODY_SIZE,
+            &mut too_large,
+            1,
+            "Size Match - Body & URL",
+        );
+
+        let mut small = Request::builder();
+        let _ = small.method("PUT").uri("/upload");
+
+        check_no_match_body(
+            Enabled::EXACT_URL | Enabled::BODY_SIZE,
+            &mut small,
+            b"tiny".to_vec(),
+        );
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_remote_addr() {
+        let mut staging = Request::builder();
+        let _ = staging.uri("/environment");
+        let _ = staging.extension(SocketAddr::from(([10, 1, 20, 30], 54321)));
+
+        check_request(
+            Enabled::EXACT_URL | Enabled::REMOTE_ADDR,
+            &mut staging,
+            1,
+            "Exact Match - Remote Address & URL",
+        );
+
+        let mut other = Request::builder();
+        let _ = other.uri("/environment");
+        let _ = other.extension(SocketAddr::from(([10, 2, 20, 30], 54321)));
+
+        check_no_match(Enabled::EXACT_URL | Enabled::REMOTE_ADDR, &mut other);
+
+        let mut unknown = Request::builder();
+        let _ = unknown.uri("/environment");
+
+        check_no_match(Enabled::EXACT_URL | Enabled::REMOTE_ADDR, &mut unknown);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_client_cert() {
+        let mut billing = Request::builder();
+        let _ = billing.uri("/clients/statements");
+        let _ = billing.extension(test_client_certificate());
+
+        check_request(
+            Enabled::EXACT_URL | Enabled::CLIENT_CERT,
+            &mut billing,
+            1,
+            "Exact Match - Client Certificate & URL",
+        );
+
+        let mut anonymous = Request::builder();
+        let _ = anonymous.uri("/clients/statements");
+
+        check_no_match(Enabled::EXACT_URL | Enabled::CLIENT_CERT, &mut anonymous);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_sni() {
+        let mut tenant = Request::builder();
+        let _ = tenant.uri("/tenants/current");
+        let _ = tenant.extension(ServerName {
+            name: "Acme.Example.com".to_string(),
+        });
+
+        check_request(
+            Enabled::EXACT_URL | Enabled::EXACT_SNI,
+            &mut tenant,
+            1,
+            "Exact Match - SNI & URL",
+        );
+
+        let mut other = Request::builder();
+        let _ = other.uri("/tenants/current");
+        let _ = other.extension(ServerName {
+            name: "globex.example.com".to_string(),
+        });
+
+        check_no_match(Enabled::EXACT_URL | Enabled::EXACT_SNI, &mut other);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_url_case_insensitive() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/Legacy/ORDERS");
+
+        check_request(
+            Enabled::EXACT_URL,
+            &mut request_builder,
+            1,
+            "Exact Match - URL Case Insensitive",
+        );
+
+        let mut other = Request::builder();
+        let _ = other.uri("/legacy/order");
+
+        check_no_match(Enabled::EXACT_URL, &mut other);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn glob_match_url() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/catalog/v2/items/42/parts");
+
+        check_request(
+            Enabled::GLOB_URL,
+            &mut request_builder,
+            1,
+            "Glob Match - URL",
+        );
+
+        let mut other = Request::builder();
+        let _ = other.uri("/catalog/v2/beta/items/42");
+
+        check_no_match(Enabled::GLOB_URL, &mut other);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn exact_match_url_normalized() {
+        let mut request_builder = Request::builder();
+        let _ = request_builder.uri("/reports//2019/./q1%2Fsummary");
+
+        check_request(
+            Enabled::EXACT_URL,
+            &mut request_builder,
+            1,
+            "Exact Match - URL Normalized",
+        );
+
+        let mut other = Request::builder();
+        let _ = other.uri("/reports/2019/q1/summary/..");
+
+        check_no_match(Enabled::EXACT_URL, &mut other);
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn near_misses() {
+        let mappings = test_mappings().expect("Unable to setup mappings!");
+        let matcher = Matcher::new(Enabled::EXACT_URL | Enabled::EXACT_METHOD, None, None);
+
+        match Request::builder().uri("/json").method("POST").body(vec![]) {
+            Ok(request) => {
+                assert!(matcher.get_match(&request, &mappings).is_err());
+                let near_misses = matcher.near_misses(&request, &mappings);
+                assert!(near_misses.iter().any(|near_miss| {
+                    near_miss.name() == "Exact Match - Method & URL"
+                        && near_miss.matched() == &vec!["Exact Match On Url".to_string()]
+                }));
+                assert!(near_misses.len() <= super::MAX_NEAR_MISSES);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn match_report() {
+        let mappings = test_mappings().expect("Unable to setup mappings!");
+        let matcher = Matcher::new(Enabled::EXACT_URL | Enabled::EXACT_METHOD, None, None);
+
+        match Request::builder().uri("/json").method("POST").body(vec![]) {
+            Ok(request) => {
+                let reports = matcher.reports(&request, &mappings);
+                assert!(reports.iter().all(|report| !report.is_match()));
+
+                match reports
+                    .iter()
+                    .find(|report| report.name() == "Exact Match - Method & URL")
+                {
+                    Some(report) => {
+                        let failed: Vec<_> = report.failed().collect();
+                        assert_eq!(failed.len(), 1);
+                        assert_eq!(failed[0].matcher(), "Exact Match On Method");
+                        assert_eq!(failed[0].expected(), &Some("GET".to_string()));
+                        assert_eq!(failed[0].actual(), &Some("POST".to_string()));
+                        assert_eq!(
+                            failed[0].reason(),
+                            &Some("expected 'GET', but was 'POST'".to_string())
+                        );
+                    }
+                    None => assert!(false, "No report for 'Exact Match - Method & URL'"),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    #[allow(box_pointers)]
+    fn invalid_pattern() {
+        let request_config = RequestConfig::builder().url_pattern("/orders/(").build();
+        let matcher = Matcher::new(Enabled::PATTERN_URL, None, None);
+
+        match Request::builder().uri("/orders/(").body(vec![]) {
+            Ok(request) => {
+                match matcher.matchers().next() {
+                    Some(pattern_match) => {
+                        match pattern_match.is_match(&request, &request_config) {
+                            Ok(_) => assert!(false, "Expected an invalid regex error!"),
+                            Err(Error::InvalidRequestConfig(message)) => {
+                                assert!(message.contains("invalid regex '/orders/('"))
+                            }
+                            Err(e) => assert!(false, e.to_string()),
+                        }
+                    }
+                    None => assert!(false, "No pattern url matcher!"),
+                }
+                assert_eq!(matcher.evaluate(&request, &request_config), Some(false));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
     #[test]
     #[allow(box_pointers)]
     fn mixed_match_header() {
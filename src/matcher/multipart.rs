@@ -0,0 +1,290 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request `multipart/form-data` body matching
+use crate::config::{MultipartPart, Request as RequestConfig};
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use crate::util;
+use http::header::CONTENT_TYPE;
+use http::Request;
+use regex::Regex;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// The maximum number of headers on a single part.
+const MAX_PART_HEADERS: usize = 16;
+
+/// A single part of a multipart body.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+crate struct Part<'a> {
+    name: Option<String>,
+    filename: Option<String>,
+    content_type: Option<String>,
+    body: &'a [u8],
+}
+
+/// An iterator over the parts of a multipart body.
+///
+/// Parts are parsed as they are requested, so matching stops reading the body at the first part
+/// that satisfies the configuration.  A malformed or truncated part ends the iteration.
+#[derive(Clone, Debug)]
+crate struct Parts<'a> {
+    body: &'a [u8],
+    delimiter: Vec<u8>,
+    pos: Option<usize>,
+}
+
+impl<'a> Parts<'a> {
+    crate fn new(body: &'a [u8], boundary: &str) -> Self {
+        let mut delimiter = b"--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        let pos = find(body, &delimiter).map(|idx| idx + delimiter.len());
+        Self {
+            body,
+            delimiter,
+            pos,
+        }
+    }
+}
+
+impl<'a> Iterator for Parts<'a> {
+    type Item = Part<'a>;
+
+    fn next(&mut self) -> Option<Part<'a>> {
+        let mut pos = self.pos.take()?;
+        let body = self.body;
+
+        // The close delimiter, `--boundary--`, ends the body.
+        if body[pos..].starts_with(b"--") {
+            return None;
+        }
+
+        // Skip any transport padding and the line break after the delimiter.
+        while pos < body.len() && (body[pos] == b' ' || body[pos] == b'\t') {
+            pos += 1;
+        }
+        if body[pos..].starts_with(b"\r\n") {
+            pos += 2;
+        } else if body[pos..].starts_with(b"\n") {
+            pos += 1;
+        }
+
+        let mut headers = [httparse::EMPTY_HEADER; MAX_PART_HEADERS];
+        let (consumed, headers) = match httparse::parse_headers(&body[pos..], &mut headers) {
+            Ok(httparse::Status::Complete((consumed, headers))) => (consumed, headers),
+            _ => return None,
+        };
+        let content = pos + consumed;
+
+        let mut next_delimiter = b"\r\n".to_vec();
+        next_delimiter.extend_from_slice(&self.delimiter);
+        let end = find(&body[content..], &next_delimiter)?;
+        self.pos = Some(content + end + next_delimiter.len());
+
+        let mut part = Part {
+            body: &body[content..content + end],
+            ..Part::default()
+        };
+        for header in headers.iter() {
+            let value = String::from_utf8_lossy(header.value);
+            if header.name.eq_ignore_ascii_case("content-disposition") {
+                part.name = util::header_parameter(&value, "name");
+                part.filename = util::header_parameter(&value, "filename");
+            } else if header.name.eq_ignore_ascii_case("content-type") {
+                part.content_type = Some(value.trim().to_string());
+            }
+        }
+        Some(part)
+    }
+}
+
+/// Find the first occurence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The multipart boundary from the `Content-Type` header of a request.
+crate fn boundary(request: &Request<Vec<u8>>) -> Option<String> {
+    let content_type = request.headers().get(CONTENT_TYPE)?.to_str().ok()?;
+
+    if essence(content_type).starts_with("multipart/") {
+        util::header_parameter(content_type, "boundary")
+    } else {
+        None
+    }
+}
+
+/// The media type without parameters, lower cased.
+fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn is_part_match(part: &Part<'_>, expected: &MultipartPart, body_regex: Option<&Regex>) -> bool {
+    part.name.as_ref() == Some(expected.name())
+        && expected
+            .filename()
+            .as_ref()
+            .map_or(true, |filename| part.filename.as_ref() == Some(filename))
+        && expected
+            .content_type()
+            .as_ref()
+            .map_or(true, |content_type| {
+                part.content_type
+                    .as_ref()
+                    .map_or(false, |actual| essence(actual) == essence(content_type))
+            })
+        && expected
+            .body()
+            .as_ref()
+            .map_or(true, |body| part.body == body.as_bytes())
+        && body_regex.map_or(true, |regex| {
+            regex.is_match(&String::from_utf8_lossy(part.body))
+        })
+}
+
+/// Match the parts of a `multipart/form-data` HTTP request body.
+#[derive(Clone, Debug, Default)]
+pub struct PartMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for PartMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for PartMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.multipart().is_empty() {
+            try_trace!(self.stdout, "Part Match (Multipart) - No check performed");
+            return Ok(None);
+        }
+
+        if let Some(boundary) = boundary(request) {
+            try_trace!(
+                self.stdout,
+                "Part Match (Multipart) - Checking {:?} with boundary {}",
+                request_config.multipart(),
+                boundary
+            );
+            for expected in request_config.multipart() {
+                let body_regex = expected
+                    .body_pattern()
+                    .as_ref()
+                    .map(|body_pattern| util::pattern(body_pattern))
+                    .transpose()?;
+                if !Parts::new(request.body(), &boundary)
+                    .any(|part| is_part_match(&part, expected, body_regex.as_ref()))
+                {
+                    return Ok(Some(false));
+                }
+            }
+            Ok(Some(true))
+        } else {
+            try_trace!(
+                self.stdout,
+                "Part Match (Multipart) - Body is not multipart"
+            );
+            Ok(Some(false))
+        }
+    }
+}
+
+impl fmt::Display for PartMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Part Match On Multipart Body")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{boundary, Part, Parts};
+    use crate::util;
+    use http::Request;
+
+    const BODY: &[u8] = b"preamble\r\n--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nQuarterly report\r\n--XyZ\r\nContent-Disposition: form-data; name=\"upload\"; filename=\"report.csv\"\r\nContent-Type: text/csv\r\n\r\nid,name\r\n1,widget\r\n--XyZ--\r\n";
+
+    #[test]
+    fn parse_parts() {
+        let parts: Vec<Part<'_>> = Parts::new(BODY, "XyZ").collect();
+        assert_eq!(
+            parts,
+            vec![
+                Part {
+                    name: Some("title".to_string()),
+                    filename: None,
+                    content_type: None,
+                    body: b"Quarterly report",
+                },
+                Part {
+                    name: Some("upload".to_string()),
+                    filename: Some("report.csv".to_string()),
+                    content_type: Some("text/csv".to_string()),
+                    body: b"id,name\r\n1,widget",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_body() {
+        assert_eq!(Parts::new(&BODY[..60], "XyZ").count(), 0);
+        assert_eq!(Parts::new(BODY, "AbC").count(), 0);
+    }
+
+    #[test]
+    fn content_type_boundary() {
+        assert_eq!(
+            util::header_parameter("form-data; name=\"a\"; filename=\"b.txt\"", "filename"),
+            Some("b.txt".to_string())
+        );
+
+        match Request::builder()
+            .header("Content-Type", "multipart/form-data; boundary=\"XyZ\"")
+            .body(vec![])
+        {
+            Ok(request) => assert_eq!(boundary(&request), Some("XyZ".to_string())),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match Request::builder()
+            .header("Content-Type", "application/json; boundary=XyZ")
+            .body(vec![])
+        {
+            Ok(request) => assert_eq!(boundary(&request), None),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
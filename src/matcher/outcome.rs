@@ -0,0 +1,156 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Structured request matching results
+use getset::Getters;
+use serde_derive::Serialize;
+use std::fmt;
+
+/// The result of checking a request with a single matcher.
+#[derive(Clone, Debug, Eq, Getters, PartialEq, Serialize)]
+pub struct MatchOutcome {
+    /// The matcher, i.e. `Exact Match On Url`.
+    #[get = "pub"]
+    matcher: String,
+    /// Did the request match?
+    #[get = "pub"]
+    matched: bool,
+    /// Why the request did or didn't match.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    /// What the matcher expected, from the request configuration.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    /// What the matcher found on the request.
+    #[get = "pub"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<String>,
+}
+
+impl MatchOutcome {
+    /// Create a new outcome for the given matcher.
+    pub fn new<S: Into<String>>(matcher: S, matched: bool) -> Self {
+        Self {
+            matcher: matcher.into(),
+            matched,
+            reason: None,
+            expected: None,
+            actual: None,
+        }
+    }
+
+    /// Record the expected and actual values that were compared.  If the request didn't match,
+    /// and no reason has been given, a reason is generated from the values.
+    pub fn with_values<E, A>(mut self, expected: E, actual: A) -> Self
+    where
+        E: Into<String>,
+        A: Into<String>,
+    {
+        let expected = expected.into();
+        let actual = actual.into();
+
+        if !self.matched && self.reason.is_none() {
+            self.reason = Some(format!("expected '{}', but was '{}'", expected, actual));
+        }
+        self.expected = Some(expected);
+        self.actual = Some(actual);
+        self
+    }
+
+    /// Record why the request did or didn't match.
+    pub fn with_reason<S: Into<String>>(mut self, reason: S) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+impl fmt::Display for MatchOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            self.matcher,
+            if self.matched { "matched" } else { "failed" }
+        )?;
+        if let Some(reason) = &self.reason {
+            write!(f, " ({})", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// The results of checking a request against the request configuration of a mapping.
+#[derive(Clone, Debug, Eq, Getters, PartialEq, Serialize)]
+pub struct MatchReport {
+    /// The name of the mapping.
+    #[get = "pub"]
+    name: String,
+    /// The outcome of every matcher that had something to check.
+    #[get = "pub"]
+    outcomes: Vec<MatchOutcome>,
+}
+
+impl MatchReport {
+    crate fn new(name: String, outcomes: Vec<MatchOutcome>) -> Self {
+        Self { name, outcomes }
+    }
+
+    /// Did the request match the mapping?  At least one matcher must have had something to
+    /// check, and every one of them must have matched.
+    pub fn is_match(&self) -> bool {
+        !self.outcomes.is_empty() && self.outcomes.iter().all(|outcome| outcome.matched)
+    }
+
+    /// The outcomes of the matchers that matched.
+    pub fn matched(&self) -> impl Iterator<Item = &MatchOutcome> {
+        self.outcomes.iter().filter(|outcome| outcome.matched)
+    }
+
+    /// The outcomes of the matchers that didn't match.
+    pub fn failed(&self) -> impl Iterator<Item = &MatchOutcome> {
+        self.outcomes.iter().filter(|outcome| !outcome.matched)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MatchOutcome, MatchReport};
+
+    #[test]
+    fn generated_reason() {
+        let outcome = MatchOutcome::new("Exact Match On Url", false).with_values("/a", "/b");
+        assert_eq!(
+            outcome.reason(),
+            &Some("expected '/a', but was '/b'".to_string())
+        );
+        assert_eq!(
+            outcome.to_string(),
+            "Exact Match On Url: failed (expected '/a', but was '/b')"
+        );
+
+        let outcome = MatchOutcome::new("Exact Match On Url", true).with_values("/a", "/a");
+        assert_eq!(outcome.reason(), &None);
+    }
+
+    #[test]
+    fn report() {
+        let report = MatchReport::new(
+            "mapping".to_string(),
+            vec![
+                MatchOutcome::new("Exact Match On Url", true),
+                MatchOutcome::new("Exact Match On Method", false),
+            ],
+        );
+        assert!(!report.is_match());
+        assert_eq!(report.matched().count(), 1);
+        assert_eq!(report.failed().count(), 1);
+        assert!(!MatchReport::new("empty".to_string(), vec![]).is_match());
+    }
+}
@@ -0,0 +1,429 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request protobuf body matching
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use cached::{cached_key_result, UnboundCache};
+use http::Request;
+use protobuf::descriptor::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto_Type as Type, FileDescriptorSet,
+};
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::sync::Arc;
+
+/// A field of a message type.
+#[derive(Clone, Debug)]
+struct Field {
+    name: String,
+    kind: Type,
+    type_name: String,
+}
+
+/// The message and enum types from a descriptor set, by fully qualified name (with a leading
+/// `.`, as used by `type_name` in field descriptors).
+#[derive(Clone, Debug, Default)]
+crate struct Descriptors {
+    messages: HashMap<String, HashMap<u32, Field>>,
+    enums: HashMap<String, HashMap<i32, String>>,
+}
+
+impl Descriptors {
+    fn from_set(set: &FileDescriptorSet) -> Self {
+        let mut descriptors = Self::default();
+
+        for file in set.get_file() {
+            let prefix = if file.get_package().is_empty() {
+                String::new()
+            } else {
+                format!(".{}", file.get_package())
+            };
+
+            for message in file.get_message_type() {
+                descriptors.add_message(&prefix, message);
+            }
+            for enum_type in file.get_enum_type() {
+                descriptors.add_enum(&prefix, enum_type);
+            }
+        }
+
+        descriptors
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn add_message(&mut self, prefix: &str, message: &DescriptorProto) {
+        let name = format!("{}.{}", prefix, message.get_name());
+        let fields = message
+            .get_field()
+            .iter()
+            .map(|field| {
+                (
+                    field.get_number() as u32,
+                    Field {
+                        name: field.get_name().to_string(),
+                        kind: field.get_field_type(),
+                        type_name: field.get_type_name().to_string(),
+                    },
+                )
+            })
+            .collect();
+
+        for nested in message.get_nested_type() {
+            self.add_message(&name, nested);
+        }
+        for enum_type in message.get_enum_type() {
+            self.add_enum(&name, enum_type);
+        }
+        let _ = self.messages.insert(name, fields);
+    }
+
+    fn add_enum(&mut self, prefix: &str, enum_type: &EnumDescriptorProto) {
+        let values = enum_type
+            .get_value()
+            .iter()
+            .map(|value| (value.get_number(), value.get_name().to_string()))
+            .collect();
+        let _ = self
+            .enums
+            .insert(format!("{}.{}", prefix, enum_type.get_name()), values);
+    }
+
+    /// Does the descriptor set contain the given message type?
+    crate fn has_message(&self, message: &str) -> bool {
+        self.messages.contains_key(&qualified(message))
+    }
+
+    /// Decode a message of the given type.
+    #[allow(clippy::cast_possible_truncation)]
+    fn decode(&self, message: &str, bytes: &[u8]) -> Result<Vec<(String, Value)>, String> {
+        let fields = self
+            .messages
+            .get(message)
+            .ok_or_else(|| format!("unknown message type '{}'", message))?;
+        let mut reader = Reader { bytes, pos: 0 };
+        let mut values = Vec::new();
+
+        while !reader.is_empty() {
+            let key = reader.varint()?;
+            let wire = match key & 0x7 {
+                0 => Wire::Varint(reader.varint()?),
+                1 => Wire::Fixed64(reader.fixed64()?),
+                2 => Wire::Bytes(reader.length_delimited()?),
+                5 => Wire::Fixed32(reader.fixed32()?),
+                wire_type => return Err(format!("unsupported wire type {}", wire_type)),
+            };
+
+            // Unknown fields are skipped.
+            if let Some(field) = fields.get(&((key >> 3) as u32)) {
+                self.push_values(field, wire, &mut values)?;
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Decode the value(s) of a field, and add them to `values`.
+    fn push_values(
+        &self,
+        field: &Field,
+        wire: Wire<'_>,
+        values: &mut Vec<(String, Value)>,
+    ) -> Result<(), String> {
+        match (field.kind, wire) {
+            (Type::TYPE_STRING, Wire::Bytes(bytes)) => values.push((
+                field.name.clone(),
+                Value::Scalar(String::from_utf8_lossy(bytes).into_owned()),
+            )),
+            (Type::TYPE_BYTES, Wire::Bytes(bytes)) => {
+                values.push((field.name.clone(), Value::Scalar(base64::encode(bytes))))
+            }
+            (Type::TYPE_MESSAGE, Wire::Bytes(bytes)) => values.push((
+                field.name.clone(),
+                Value::Message(self.decode(&field.type_name, bytes)?),
+            )),
+            (Type::TYPE_GROUP, _) => return Err("groups are not supported".to_string()),
+            (_, Wire::Bytes(bytes)) => {
+                // A packed repeated scalar field.
+                let mut reader = Reader { bytes, pos: 0 };
+                while !reader.is_empty() {
+                    let wire = match field.kind {
+                        Type::TYPE_DOUBLE | Type::TYPE_FIXED64 | Type::TYPE_SFIXED64 => {
+                            Wire::Fixed64(reader.fixed64()?)
+                        }
+                        Type::TYPE_FLOAT | Type::TYPE_FIXED32 | Type::TYPE_SFIXED32 => {
+                            Wire::Fixed32(reader.fixed32()?)
+                        }
+                        _ => Wire::Varint(reader.varint()?),
+                    };
+                    self.push_values(field, wire, values)?;
+                }
+            }
+            (kind, wire) => values.push((
+                field.name.clone(),
+                Value::Scalar(self.scalar(kind, &field.type_name, wire)?),
+            )),
+        }
+
+        Ok(())
+    }
+
+    /// The string form of a numeric scalar value.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn scalar(&self, kind: Type, type_name: &str, wire: Wire<'_>) -> Result<String, String> {
+        let value = match (kind, wire) {
+            (Type::TYPE_INT32, Wire::Varint(v)) => (v as i32).to_string(),
+            (Type::TYPE_INT64, Wire::Varint(v)) => (v as i64).to_string(),
+            (Type::TYPE_UINT32, Wire::Varint(v)) => (v as u32).to_string(),
+            (Type::TYPE_UINT64, Wire::Varint(v)) => v.to_string(),
+            (Type::TYPE_SINT32, Wire::Varint(v)) => {
+                (((v >> 1) as i32) ^ -((v & 1) as i32)).to_string()
+            }
+            (Type::TYPE_SINT64, Wire::Varint(v)) => {
+                (((v >> 1) as i64) ^ -((v & 1) as i64)).to_string()
+            }
+            (Type::TYPE_BOOL, Wire::Varint(v)) => (v != 0).to_string(),
+            (Type::TYPE_ENUM, Wire::Varint(v)) => self
+                .enums
+                .get(type_name)
+                .and_then(|values| values.get(&(v as i32)))
+                .cloned()
+                .unwrap_or_else(|| (v as i32).to_string()),
+            (Type::TYPE_FIXED64, Wire::Fixed64(v)) => v.to_string(),
+            (Type::TYPE_SFIXED64, Wire::Fixed64(v)) => (v as i64).to_string(),
+            (Type::TYPE_DOUBLE, Wire::Fixed64(v)) => f64::from_bits(v).to_string(),
+            (Type::TYPE_FIXED32, Wire::Fixed32(v)) => v.to_string(),
+            (Type::TYPE_SFIXED32, Wire::Fixed32(v)) => (v as i32).to_string(),
+            (Type::TYPE_FLOAT, Wire::Fixed32(v)) => f32::from_bits(v).to_string(),
+            (kind, wire) => return Err(format!("unexpected {:?} for a {:?} field", wire, kind)),
+        };
+        Ok(value)
+    }
+}
+
+/// A wire format value.
+#[derive(Clone, Copy, Debug)]
+enum Wire<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    Bytes(&'a [u8]),
+}
+
+/// A decoded field value.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Scalar(String),
+    Message(Vec<(String, Value)>),
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.pos < len {
+            Err("truncated message".to_string())
+        } else {
+            let taken = &self.bytes[self.pos..self.pos + len];
+            self.pos += len;
+            Ok(taken)
+        }
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err("varint is too long".to_string())
+    }
+
+    fn fixed64(&mut self) -> Result<u64, String> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn fixed32(&mut self) -> Result<u32, String> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn length_delimited(&mut self) -> Result<&'a [u8], String> {
+        let len = self.varint()?;
+        self.take(len as usize)
+    }
+}
+
+/// Prefix a message type name with a `.`, if it isn't already.
+fn qualified(message: &str) -> String {
+    if message.starts_with('.') {
+        message.to_string()
+    } else {
+        format!(".{}", message)
+    }
+}
+
+/// The values selected by a dotted field path.
+fn select<'a>(values: &'a [(String, Value)], path: &str) -> Vec<&'a str> {
+    let mut parts = path.splitn(2, '.');
+    let head = parts.next().unwrap_or("");
+    let rest = parts.next();
+
+    values
+        .iter()
+        .filter(|(name, _)| name == head)
+        .flat_map(|(_, value)| match (value, rest) {
+            (Value::Scalar(value), None) => vec![&value[..]],
+            (Value::Message(values), Some(rest)) => select(values, rest),
+            _ => vec![],
+        })
+        .collect()
+}
+
+cached_key_result! {
+    DESCRIPTORS: UnboundCache<String, Arc<Descriptors>> = UnboundCache::new();
+    Key = { descriptor_set.to_string() };
+    fn load(descriptor_set: &str) -> Result<Arc<Descriptors>, String> = {
+        fs::read(descriptor_set)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                protobuf::parse_from_bytes::<FileDescriptorSet>(&bytes).map_err(|e| e.to_string())
+            })
+            .map(|set| Arc::new(Descriptors::from_set(&set)))
+    }
+}
+
+/// Load the descriptor set at the given path.  Descriptor sets are cached once loaded.
+crate fn descriptors(descriptor_set: &str) -> Result<Arc<Descriptors>, String> {
+    load(descriptor_set)
+}
+
+/// Match field values in the protobuf body of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct FieldMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for FieldMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for FieldMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(body_protobuf) = request_config.body_protobuf() {
+            let decoded = descriptors(body_protobuf.descriptor_set()).and_then(|descriptors| {
+                descriptors.decode(&qualified(body_protobuf.message()), request.body())
+            });
+
+            match decoded {
+                Ok(values) => {
+                    try_trace!(
+                        self.stdout,
+                        "Protobuf Match (Body) - Checking {:?} against {:?}",
+                        body_protobuf.fields(),
+                        values
+                    );
+                    Ok(Some(body_protobuf.fields().iter().all(
+                        |(path, expected)| select(&values, path).contains(&&expected[..]),
+                    )))
+                }
+                Err(e) => {
+                    try_trace!(self.stdout, "Protobuf Match (Body) - {}", e);
+                    Ok(Some(false))
+                }
+            }
+        } else {
+            try_trace!(self.stdout, "Protobuf Match (Body) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for FieldMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Protobuf Match On Body")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{descriptors, select};
+
+    // orders.CreateOrder {
+    //   order_id: "ORD-1", customer: { id: 42, name: "Ada" }, status: PAID, quantities: [1, 300]
+    // }
+    const CREATE_ORDER: &[u8] = &[
+        0x0a, 0x05, b'O', b'R', b'D', b'-', b'1', 0x12, 0x07, 0x08, 0x2a, 0x12, 0x03, b'A', b'd',
+        b'a', 0x18, 0x01, 0x22, 0x03, 0x01, 0xac, 0x02,
+    ];
+
+    #[test]
+    fn decode() {
+        match descriptors("tests/protobuf/orders.pb") {
+            Ok(descriptors) => {
+                assert!(descriptors.has_message("orders.CreateOrder"));
+                assert!(descriptors.has_message(".orders.CreateOrder.Customer"));
+
+                match descriptors.decode(".orders.CreateOrder", CREATE_ORDER) {
+                    Ok(values) => {
+                        assert_eq!(select(&values, "order_id"), vec!["ORD-1"]);
+                        assert_eq!(select(&values, "customer.id"), vec!["42"]);
+                        assert_eq!(select(&values, "customer.name"), vec!["Ada"]);
+                        assert_eq!(select(&values, "status"), vec!["PAID"]);
+                        assert_eq!(select(&values, "quantities"), vec!["1", "300"]);
+                        assert!(select(&values, "customer").is_empty());
+                    }
+                    Err(e) => assert!(false, e),
+                }
+                assert!(descriptors
+                    .decode(".orders.CreateOrder", &CREATE_ORDER[..4])
+                    .is_err());
+            }
+            Err(e) => assert!(false, e),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request query parameter matching
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use crate::util;
+use http::Request;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// Parse the query string of a request into decoded `(name, value)` pairs.
+crate fn query_params(request: &Request<Vec<u8>>) -> Vec<(String, String)> {
+    request
+        .uri()
+        .query()
+        .map(util::parse_urlencoded)
+        .unwrap_or_default()
+}
+
+/// Exactly match the query parameters on a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct ExactMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for ExactMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.query_params().is_empty() {
+            try_trace!(self.stdout, "Exact Match (Query) - No check performed");
+            Ok(None)
+        } else {
+            let actual = query_params(request);
+            try_trace!(
+                self.stdout,
+                "Exact Match (Query) - Checking {:?} against {:?}",
+                request_config.query_params(),
+                actual
+            );
+            Ok(Some(request_config.query_params().iter().all(
+                |(expected_name, expected_value)| {
+                    actual
+                        .iter()
+                        .any(|(name, value)| name == expected_name && value == expected_value)
+                },
+            )))
+        }
+    }
+}
+
+impl fmt::Display for ExactMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Exact Match On Query")
+    }
+}
+
+/// Pattern match the query parameters on a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct PatternMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for PatternMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for PatternMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.query_params_pattern().is_empty() {
+            try_trace!(self.stdout, "Pattern Match (Query) - No check performed");
+            Ok(None)
+        } else {
+            let actual = query_params(request);
+            try_trace!(
+                self.stdout,
+                "Pattern Match (Query) - Checking {:?} against {:?}",
+                request_config.query_params_pattern(),
+                actual
+            );
+            for (expected_name, value_pattern) in request_config.query_params_pattern() {
+                let regex = util::pattern(value_pattern)?;
+                if !actual
+                    .iter()
+                    .any(|(name, value)| name == expected_name && regex.is_match(value))
+                {
+                    return Ok(Some(false));
+                }
+            }
+            Ok(Some(true))
+        }
+    }
+}
+
+impl fmt::Display for PatternMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pattern Match On Query")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::query_params;
+    use http::Request;
+
+    #[test]
+    fn parse_query() {
+        match Request::builder()
+            .uri("/search?q=rust+lang&page=2&flag")
+            .body(vec![])
+        {
+            Ok(request) => assert_eq!(
+                query_params(&request),
+                vec![
+                    ("q".to_string(), "rust lang".to_string()),
+                    ("page".to_string(), "2".to_string()),
+                    ("flag".to_string(), "".to_string()),
+                ]
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The connection details of a request
+//!
+//! Request matchers are given the whole request, with the body already read.  The server also
+//! records what it knows about the connection in the request extensions, and this trait reads
+//! them back, so custom matchers can match on the same things as the built in ones.
+use crate::util;
+use crate::x509::ClientCertificate;
+use http::Request;
+use std::net::SocketAddr;
+
+/// The connection details of a request received by the server.
+///
+/// # Example
+///
+/// ```
+/// # use http::Request;
+/// # use libdeadmock::matcher::RequestExt;
+/// #
+/// # fn main() {
+/// let request = Request::builder()
+///     .uri("/orders")
+///     .body(vec![])
+///     .expect("Unable to build the request!");
+/// assert_eq!(request.scheme(), "http");
+/// assert!(request.remote_addr().is_none());
+/// assert!(!request.is_tls());
+/// # }
+/// ```
+pub trait RequestExt {
+    /// The scheme the request was received over, `http` or `https`.
+    fn scheme(&self) -> &str;
+
+    /// Was the request received over TLS?
+    fn is_tls(&self) -> bool {
+        self.scheme() == "https"
+    }
+
+    /// The address of the client that sent the request.
+    fn remote_addr(&self) -> Option<SocketAddr>;
+
+    /// The SNI server name the client asked for.
+    fn server_name(&self) -> Option<&str>;
+
+    /// The subject distinguished name of the client certificate, i.e. `O=Acme, CN=client`.
+    fn client_cert_subject(&self) -> Option<&str>;
+
+    /// The subject alternative names of the client certificate.
+    fn client_cert_subject_alt_names(&self) -> &[String];
+
+    /// The lowercase hex encoded SHA-256 fingerprint of the client certificate.
+    fn client_cert_fingerprint(&self) -> Option<&str>;
+}
+
+impl RequestExt for Request<Vec<u8>> {
+    fn scheme(&self) -> &str {
+        util::request_scheme(self)
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        util::remote_addr(self)
+    }
+
+    fn server_name(&self) -> Option<&str> {
+        util::server_name(self)
+    }
+
+    fn client_cert_subject(&self) -> Option<&str> {
+        self.extensions()
+            .get::<ClientCertificate>()
+            .map(|client_cert| client_cert.subject.as_str())
+    }
+
+    fn client_cert_subject_alt_names(&self) -> &[String] {
+        self.extensions()
+            .get::<ClientCertificate>()
+            .map_or(&[], |client_cert| &client_cert.subject_alt_names[..])
+    }
+
+    fn client_cert_fingerprint(&self) -> Option<&str> {
+        self.extensions()
+            .get::<ClientCertificate>()
+            .map(|client_cert| client_cert.fingerprint.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RequestExt;
+    use crate::util::ServerName;
+    use crate::x509::test::test_client_certificate;
+    use http::uri::Scheme;
+    use http::Request;
+    use std::net::SocketAddr;
+
+    #[test]
+    fn connection_details() {
+        match Request::builder()
+            .uri("/orders")
+            .extension(Scheme::HTTPS)
+            .extension(SocketAddr::from(([10, 1, 20, 30], 54321)))
+            .extension(ServerName {
+                name: "api.example.com".to_string(),
+            })
+            .extension(test_client_certificate())
+            .body(vec![])
+        {
+            Ok(request) => {
+                assert!(request.is_tls());
+                assert_eq!(
+                    request.remote_addr(),
+                    Some(SocketAddr::from(([10, 1, 20, 30], 54321)))
+                );
+                assert_eq!(request.server_name(), Some("api.example.com"));
+                assert_eq!(request.client_cert_subject(), Some("CN=localhost"));
+                assert_eq!(
+                    request.client_cert_subject_alt_names(),
+                    &["localhost".to_string(), "127.0.0.1".to_string()]
+                );
+                assert!(request.client_cert_fingerprint().is_some());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
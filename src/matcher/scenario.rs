@@ -0,0 +1,91 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Scenario state for stateful stubbing
+use crate::config::Mapping;
+use std::collections::HashMap;
+
+/// The state every scenario starts in.
+pub const STARTED: &str = "Started";
+
+/// The current state of every scenario.
+///
+/// A mapping in a `scenario` with a `required_state` only matches while the scenario is in that
+/// state.  When a mapping with a `new_state` is matched, its scenario moves to that state.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Scenarios {
+    states: HashMap<String, String>,
+}
+
+impl Scenarios {
+    /// The current state of the given scenario.
+    pub fn state(&self, scenario: &str) -> &str {
+        self.states.get(scenario).map_or(STARTED, String::as_str)
+    }
+
+    /// Move every scenario back to the `Started` state.
+    pub fn reset(&mut self) {
+        self.states.clear();
+    }
+
+    /// Can the given mapping be matched in the current state?
+    crate fn allows(&self, mapping: &Mapping) -> bool {
+        match (mapping.scenario(), mapping.required_state()) {
+            (Some(scenario), Some(required_state)) => self.state(scenario) == required_state,
+            _ => true,
+        }
+    }
+
+    /// Move the scenario of the given (matched) mapping to its new state.
+    crate fn transition(&mut self, mapping: &Mapping) {
+        if let (Some(scenario), Some(new_state)) = (mapping.scenario(), mapping.new_state()) {
+            let _ = self.states.insert(scenario.clone(), new_state.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Scenarios, STARTED};
+    use crate::config::Mapping;
+
+    #[test]
+    fn transition() {
+        let mut scenarios = Scenarios::default();
+        let delete = Mapping::builder()
+            .scenario("order")
+            .required_state(STARTED)
+            .new_state("Deleted")
+            .build();
+        let get_deleted = Mapping::builder()
+            .scenario("order")
+            .required_state("Deleted")
+            .build();
+
+        assert_eq!(scenarios.state("order"), STARTED);
+        assert!(scenarios.allows(&delete));
+        assert!(!scenarios.allows(&get_deleted));
+
+        scenarios.transition(&delete);
+        assert_eq!(scenarios.state("order"), "Deleted");
+        assert!(!scenarios.allows(&delete));
+        assert!(scenarios.allows(&get_deleted));
+
+        scenarios.reset();
+        assert_eq!(scenarios.state("order"), STARTED);
+    }
+
+    #[test]
+    fn no_scenario() {
+        let mut scenarios = Scenarios::default();
+        let mapping = Mapping::builder().new_state("Ignored").build();
+        assert!(scenarios.allows(&mapping));
+        scenarios.transition(&mapping);
+        assert_eq!(scenarios, Scenarios::default());
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Scripted HTTP request matching
+//!
+//! A mapping gives a [rhai](https://github.com/jonathandturner/rhai) script in the `script`
+//! field of its request configuration.  The script is evaluated with the request in scope, and
+//! must return a `bool`.
+//!
+//! * `method`, `path`, `query` and `body` are strings.
+//! * `header(name)` returns the value of a header, or `""` if it isn't present.
+//! * `query_param(name)` returns the decoded value of a query parameter, or `""` if it isn't
+//!   present.
+//!
+//! ```toml
+//! [request]
+//! url_pattern = "^/orders/"
+//! script = 'header("X-Tenant") == "acme" && body != ""'
+//! ```
+use crate::config::Request as RequestConfig;
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use crate::util;
+use http::Request;
+use rhai::{Engine, RegisterFn, Scope};
+use slog::{error, trace, Logger};
+use slog_try::{try_error, try_trace};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Match a HTTP request with a script.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for ScriptMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for ScriptMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(script) = request_config.script() {
+            try_trace!(self.stdout, "Script Match - Evaluating {}", script);
+
+            match evaluate(request, script) {
+                Ok(matched) => Ok(Some(matched)),
+                Err(e) => {
+                    try_error!(self.stderr, "Script Match - {}", e);
+                    Ok(Some(false))
+                }
+            }
+        } else {
+            try_trace!(self.stdout, "Script Match - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for ScriptMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Script Match")
+    }
+}
+
+/// Evaluate a script against a request.
+#[allow(box_pointers)]
+fn evaluate(request: &Request<Vec<u8>>, script: &str) -> Result<bool, String> {
+    let headers: HashMap<String, String> = request
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    let query_params: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(util::parse_urlencoded)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    let mut engine = Engine::new();
+    engine.register_fn("header", move |name: String| {
+        headers
+            .get(&name.to_ascii_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    });
+    engine.register_fn("query_param", move |name: String| {
+        query_params.get(&name).cloned().unwrap_or_default()
+    });
+
+    let mut scope: Scope = Vec::new();
+    scope.push(("method".to_string(), Box::new(request.method().to_string())));
+    scope.push((
+        "path".to_string(),
+        Box::new(request.uri().path().to_string()),
+    ));
+    scope.push((
+        "query".to_string(),
+        Box::new(request.uri().query().unwrap_or("").to_string()),
+    ));
+    scope.push((
+        "body".to_string(),
+        Box::new(String::from_utf8_lossy(request.body()).into_owned()),
+    ));
+
+    engine
+        .eval_with_scope::<bool>(&mut scope, script)
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::evaluate;
+    use http::Request;
+
+    fn check(script: &str) -> Result<bool, String> {
+        match Request::builder()
+            .method("POST")
+            .uri("/orders?tenant=acme")
+            .header("X-Api-Version", "2")
+            .body(b"{\"id\": 1}".to_vec())
+        {
+            Ok(request) => evaluate(&request, script),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    #[test]
+    fn request_in_scope() {
+        assert_eq!(check(r#"method == "POST" && path == "/orders""#), Ok(true));
+        assert_eq!(check(r#"header("x-api-version") == "2""#), Ok(true));
+        assert_eq!(check(r#"query_param("tenant") == "acme""#), Ok(true));
+        assert_eq!(check(r#"header("X-Missing") == """#), Ok(true));
+        assert_eq!(check(r#"query == "tenant=globex""#), Ok(false));
+    }
+
+    #[test]
+    fn invalid_script() {
+        assert!(check("method ==").is_err());
+        assert!(check(r#""not a bool""#).is_err());
+    }
+}
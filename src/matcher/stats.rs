@@ -0,0 +1,195 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Request matching statistics
+use getset::Getters;
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How often something was checked against a request, how often it matched, and how long the
+/// checks took.
+#[derive(Clone, Copy, Debug, Default, Eq, Getters, PartialEq, Serialize)]
+pub struct Counters {
+    /// The number of requests checked.
+    #[get = "pub"]
+    evaluations: u64,
+    /// The number of requests that matched.
+    #[get = "pub"]
+    matches: u64,
+    /// The number of requests that didn't match.
+    #[get = "pub"]
+    failures: u64,
+    /// The total time spent checking requests.
+    #[get = "pub"]
+    elapsed: Duration,
+}
+
+impl Counters {
+    fn record(&mut self, matched: bool, elapsed: Duration) {
+        self.evaluations += 1;
+        if matched {
+            self.matches += 1;
+        } else {
+            self.failures += 1;
+        }
+        self.elapsed += elapsed;
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.evaluations += other.evaluations;
+        self.matches += other.matches;
+        self.failures += other.failures;
+        self.elapsed += other.elapsed;
+    }
+}
+
+/// The counters for a mapping, along with its name.
+#[derive(Clone, Debug, Default, Eq, Getters, PartialEq, Serialize)]
+pub struct MappingCounters {
+    /// The name of the mapping.  Names needn't be unique, so the counters are kept by id.
+    #[get = "pub"]
+    name: String,
+    /// The counters for the mapping.
+    #[get = "pub"]
+    #[serde(flatten)]
+    counters: Counters,
+}
+
+/// Request matching statistics, per request matcher and per mapping.
+///
+/// Only the matchers that had something to check are counted, so a mapping that doesn't
+/// configure a body is never counted against the body matchers.
+#[derive(Clone, Debug, Default, Eq, Getters, PartialEq, Serialize)]
+pub struct Stats {
+    /// Counters by request matcher, i.e. `Exact Match On Url`.
+    #[get = "pub"]
+    matchers: BTreeMap<String, Counters>,
+    /// Counters by mapping id.
+    #[get = "pub"]
+    mappings: BTreeMap<Uuid, MappingCounters>,
+}
+
+impl Stats {
+    /// Record a check by the given request matcher.
+    crate fn record_matcher(&mut self, matcher: &str, matched: bool, elapsed: Duration) {
+        self.matchers
+            .entry(matcher.to_string())
+            .or_default()
+            .record(matched, elapsed);
+    }
+
+    /// Record a check against the mapping with the given id and name.
+    crate fn record_mapping(
+        &mut self,
+        id: Uuid,
+        name: &str,
+        matched: bool,
+        elapsed: Duration,
+    ) {
+        self.mappings
+            .entry(id)
+            .or_insert_with(|| MappingCounters {
+                name: name.to_string(),
+                counters: Counters::default(),
+            })
+            .counters
+            .record(matched, elapsed);
+    }
+
+    /// Add the counters of `other`, i.e. those collected while matching one request.
+    crate fn merge(&mut self, other: Self) {
+        for (matcher, counters) in other.matchers {
+            self.matchers.entry(matcher).or_default().add(&counters);
+        }
+        for (id, mapping) in other.mappings {
+            self.mappings
+                .entry(id)
+                .or_insert_with(|| MappingCounters {
+                    name: mapping.name.clone(),
+                    counters: Counters::default(),
+                })
+                .counters
+                .add(&mapping.counters);
+        }
+    }
+
+    /// Clear every counter.
+    pub fn reset(&mut self) {
+        self.matchers.clear();
+        self.mappings.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Stats;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[test]
+    fn record() {
+        let mut stats = Stats::default();
+        let orders = Uuid::new_v4();
+        stats.record_matcher("Exact Match On Url", true, Duration::from_millis(1));
+        stats.record_matcher("Exact Match On Url", false, Duration::from_millis(2));
+        stats.record_mapping(orders, "Orders", false, Duration::from_millis(3));
+
+        match stats.matchers().get("Exact Match On Url") {
+            Some(counters) => {
+                assert_eq!(*counters.evaluations(), 2);
+                assert_eq!(*counters.matches(), 1);
+                assert_eq!(*counters.failures(), 1);
+                assert_eq!(*counters.elapsed(), Duration::from_millis(3));
+            }
+            None => assert!(false, "No counters for the matcher!"),
+        }
+        assert_eq!(
+            stats
+                .mappings()
+                .get(&orders)
+                .map(|c| *c.counters().failures()),
+            Some(1)
+        );
+
+        stats.reset();
+        assert!(stats.matchers().is_empty());
+        assert!(stats.mappings().is_empty());
+    }
+
+    #[test]
+    fn merge() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut stats = Stats::default();
+        stats.record_mapping(first, "", true, Duration::from_millis(1));
+
+        let mut request = Stats::default();
+        request.record_matcher("Exact Match On Url", true, Duration::from_millis(1));
+        request.record_mapping(first, "", false, Duration::from_millis(2));
+        request.record_mapping(second, "", true, Duration::from_millis(3));
+        stats.merge(request);
+
+        // Mappings with the same (empty) name are still counted apart.
+        assert_eq!(stats.mappings().len(), 2);
+        match stats.mappings().get(&first) {
+            Some(mapping) => {
+                assert_eq!(*mapping.counters().evaluations(), 2);
+                assert_eq!(*mapping.counters().elapsed(), Duration::from_millis(3));
+            }
+            None => assert!(false, "No counters for the mapping!"),
+        }
+        assert_eq!(
+            stats
+                .matchers()
+                .get("Exact Match On Url")
+                .map(|c| *c.matches()),
+            Some(1)
+        );
+    }
+}
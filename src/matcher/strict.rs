@@ -0,0 +1,261 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Strict HTTP request matching
+use crate::config::{Request as RequestConfig, Strict};
+use crate::error::Error;
+use crate::matcher::{MatchOutcome, RequestMatch, Slogger};
+use crate::util;
+use http::header::{AUTHORIZATION, HOST};
+use http::Request;
+use libeither::Either;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::fmt;
+
+/// Fail a request that has query parameters, headers or a body that the request configuration
+/// doesn't match on.
+///
+/// The request configuration's own `strict` setting is used if it has one, otherwise the
+/// default, i.e. from the runtime configuration.
+#[derive(Clone, Debug, Default)]
+pub struct StrictMatch {
+    default: Option<Strict>,
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl StrictMatch {
+    /// Create a new strict matcher with the given default.
+    pub fn new(default: Option<Strict>) -> Self {
+        Self {
+            default,
+            ..Self::default()
+        }
+    }
+
+    /// The first thing on the request that the request configuration doesn't match on.
+    fn uncovered(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+        strict: &Strict,
+    ) -> Option<String> {
+        if !*strict.ignore_query() {
+            let params = request
+                .uri()
+                .query()
+                .map(util::parse_urlencoded)
+                .unwrap_or_default();
+
+            if let Some((name, _)) = params
+                .iter()
+                .find(|(name, _)| !covers_query_param(request_config, name))
+            {
+                return Some(format!("unexpected query parameter '{}'", name));
+            }
+        }
+
+        if !*strict.ignore_body() && !request.body().is_empty() && !covers_body(request_config) {
+            return Some("unexpected body".to_string());
+        }
+
+        strict
+            .headers()
+            .iter()
+            .find(|name| {
+                request.headers().contains_key(&name[..]) && !covers_header(request_config, name)
+            })
+            .map(|name| format!("unexpected header '{}'", name))
+    }
+}
+
+impl Slogger for StrictMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for StrictMatch {
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        Ok(self
+            .outcome(request, request_config)?
+            .map(|outcome| *outcome.matched()))
+    }
+
+    fn outcome(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<MatchOutcome>, Error> {
+        if let Some(strict) = request_config
+            .strict()
+            .as_ref()
+            .or_else(|| self.default.as_ref())
+        {
+            let uncovered = self.uncovered(request, request_config, strict);
+            try_trace!(self.stdout, "Strict Match - {:?}", uncovered);
+
+            Ok(Some(match uncovered {
+                Some(reason) => MatchOutcome::new(self.to_string(), false).with_reason(reason),
+                None => MatchOutcome::new(self.to_string(), true),
+            }))
+        } else {
+            try_trace!(self.stdout, "Strict Match - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for StrictMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Strict Match")
+    }
+}
+
+/// The request configuration and the nested configurations that must, or may, match.
+fn configs(request_config: &RequestConfig) -> Vec<&RequestConfig> {
+    let mut configs = vec![request_config];
+    for nested in request_config
+        .all_of()
+        .iter()
+        .chain(request_config.any_of())
+    {
+        configs.extend(self::configs(nested));
+    }
+    configs
+}
+
+fn covers_query_param(request_config: &RequestConfig, name: &str) -> bool {
+    configs(request_config).iter().any(|config| {
+        config.query_params().contains_key(name) || config.query_params_pattern().contains_key(name)
+    })
+}
+
+fn covers_body(request_config: &RequestConfig) -> bool {
+    configs(request_config).iter().any(|config| {
+        config.body().is_some()
+            || config.body_pattern().is_some()
+            || !config.body_contains().is_empty()
+            || config.body_sha256().is_some()
+            || config.body_md5().is_some()
+            || config.body_size().is_some()
+            || !config.body_json_path().is_empty()
+            || config.equal_to_json().is_some()
+            || !config.xpath().is_empty()
+            || config.soap().is_some()
+            || !config.multipart().is_empty()
+            || config.body_protobuf().is_some()
+            || !config.form_params().is_empty()
+            || !config.form_params_pattern().is_empty()
+    })
+}
+
+fn covers_header(request_config: &RequestConfig, name: &str) -> bool {
+    configs(request_config).iter().any(|config| {
+        config
+            .headers()
+            .iter()
+            .chain(config.header())
+            .any(|header| header.key().eq_ignore_ascii_case(name))
+            || config
+                .headers_pattern()
+                .iter()
+                .chain(config.header_pattern())
+                .any(|header_pattern| covers_key(header_pattern.key(), name))
+            || (name.eq_ignore_ascii_case(AUTHORIZATION.as_str()) && config.basic_auth().is_some())
+            || (name.eq_ignore_ascii_case(HOST.as_str())
+                && (config.host().is_some() || config.host_pattern().is_some()))
+    })
+}
+
+fn covers_key(key: &Either<String, String>, name: &str) -> bool {
+    if let Ok(key) = key.left_ref() {
+        key.eq_ignore_ascii_case(name)
+    } else if let Ok(key_pattern) = key.right_ref() {
+        util::regex(key_pattern)
+            .map(|regex| regex.is_match(name))
+            .unwrap_or(false)
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StrictMatch;
+    use crate::config::{Header, Request as RequestConfig, Strict};
+    use crate::matcher::RequestMatch;
+    use http::Request;
+
+    fn check(request_config: &RequestConfig, uri: &str, body: &[u8]) -> Option<bool> {
+        let mut strict = Strict::default();
+        let _ = strict.set_headers(vec!["X-Api-Version".to_string()]);
+
+        match Request::builder()
+            .uri(uri)
+            .header("X-Api-Version", "2")
+            .header("X-Request-Id", "abc")
+            .body(body.to_vec())
+        {
+            Ok(request) => {
+                match StrictMatch::new(Some(strict)).is_match(&request, request_config) {
+                    Ok(matched) => matched,
+                    Err(e) => {
+                        assert!(false, e.to_string());
+                        None
+                    }
+                }
+            }
+            Err(e) => {
+                assert!(false, e.to_string());
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn query_params() {
+        let config = RequestConfig::builder()
+            .header(Header::new("X-Api-Version", "2"))
+            .query_param("id", "1")
+            .build();
+        assert_eq!(check(&config, "/orders?id=1", b""), Some(true));
+        assert_eq!(check(&config, "/orders?id=1&debug=true", b""), Some(false));
+    }
+
+    #[test]
+    fn headers_and_body() {
+        let config = RequestConfig::builder().build();
+        assert_eq!(check(&config, "/orders", b""), Some(false));
+
+        let config = RequestConfig::builder()
+            .header(Header::new("X-Api-Version", "2"))
+            .build();
+        assert_eq!(check(&config, "/orders", b""), Some(true));
+        assert_eq!(check(&config, "/orders", b"{}"), Some(false));
+
+        let config = RequestConfig::builder()
+            .header(Header::new("X-Api-Version", "2"))
+            .all_of(RequestConfig::builder().body("{}").build())
+            .build();
+        assert_eq!(check(&config, "/orders", b"{}"), Some(true));
+    }
+}
@@ -9,14 +9,46 @@
 //! HTTP request URL matching
 use crate::config::Request as RequestConfig;
 use crate::error::Error;
-use crate::matcher::{RequestMatch, Slogger};
-use cached::{cached_key_result, UnboundCache};
+use crate::matcher::{MatchCost, MatchOutcome, RequestMatch, Slogger};
+use crate::util;
 use http::Request;
-use regex::Regex;
 use slog::{trace, Logger};
 use slog_try::try_trace;
+use std::borrow::Cow;
 use std::fmt;
 
+/// Normalize a request path: percent-decode it, collapse repeated slashes and resolve `.` and
+/// `..` segments.  A trailing slash is kept.
+crate fn normalize_path(path: &str) -> String {
+    let decoded = util::percent_decode(path);
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                let _ = segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    if normalized.len() > 1 && (decoded.ends_with('/') || decoded.ends_with("/.")) {
+        normalized.push('/');
+    }
+    normalized
+}
+
+/// The request path the url matchers check, normalized if the request configuration asks for it.
+fn request_path<'a>(request: &'a Request<Vec<u8>>, request_config: &RequestConfig) -> Cow<'a, str> {
+    if *request_config.normalize_url() {
+        Cow::Owned(normalize_path(request.uri().path()))
+    } else {
+        Cow::Borrowed(request.uri().path())
+    }
+}
+
 /// Exactly match a url
 #[derive(Clone, Debug, Default)]
 pub struct ExactMatch {
@@ -39,24 +71,54 @@ impl Slogger for ExactMatch {
 }
 
 impl RequestMatch for ExactMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &RequestConfig,
     ) -> Result<Option<bool>, Error> {
         if let Some(url) = request_config.url() {
+            let path = request_path(request, request_config);
             try_trace!(
                 self.stdout,
                 "Exact Match (URL) - Checking {} against {}",
                 url,
-                request.uri().path()
+                path
             );
-            Ok(Some(request.uri().path() == &url[..]))
+            if *request_config.url_case_insensitive() {
+                Ok(Some(path.eq_ignore_ascii_case(url)))
+            } else {
+                Ok(Some(path == &url[..]))
+            }
         } else {
             try_trace!(self.stdout, "Exact Match (URL) - No check performed");
             Ok(None)
         }
     }
+
+    fn outcome(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<MatchOutcome>, Error> {
+        Ok(
+            match (
+                self.is_match(request, request_config)?,
+                request_config.url(),
+            ) {
+                (Some(matched), Some(expected)) => {
+                    Some(MatchOutcome::new(self.to_string(), matched).with_values(
+                        expected.clone(),
+                        request_path(request, request_config).into_owned(),
+                    ))
+                }
+                _ => None,
+            },
+        )
+    }
 }
 
 impl fmt::Display for ExactMatch {
@@ -65,14 +127,14 @@ impl fmt::Display for ExactMatch {
     }
 }
 
-/// Pattern match a url
+/// Glob match a url
 #[derive(Clone, Debug, Default)]
-pub struct PatternMatch {
+pub struct GlobMatch {
     stdout: Option<Logger>,
     stderr: Option<Logger>,
 }
 
-impl Slogger for PatternMatch {
+impl Slogger for GlobMatch {
     /// Add a stdout logger
     fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
         self.stdout = stdout;
@@ -86,43 +148,105 @@ impl Slogger for PatternMatch {
     }
 }
 
-cached_key_result! {
-    REGEX: UnboundCache<String, Regex> = UnboundCache::new();
-    Key = { path.to_string() };
-    fn generate_regex(path: &str, url_pattern: &str) -> Result<Regex, String> = {
-        let regex_result = Regex::new(url_pattern);
+impl RequestMatch for GlobMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
 
-        match regex_result {
-            Ok(regex) => Ok(regex),
-            Err(e) => Err(e.to_string()),
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(url_glob) = request_config.url_glob() {
+            let path = request_path(request, request_config);
+            try_trace!(
+                self.stdout,
+                "Glob Match (URL) - Checking {} against {}",
+                path,
+                url_glob.glob()
+            );
+            Ok(Some(url_glob.is_match(&path)))
+        } else {
+            try_trace!(self.stdout, "Glob Match (URL) - No check performed");
+            Ok(None)
         }
     }
 }
 
+impl fmt::Display for GlobMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Glob Match On Url")
+    }
+}
+
+/// Pattern match a url
+#[derive(Clone, Debug, Default)]
+pub struct PatternMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for PatternMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
 impl RequestMatch for PatternMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
     fn is_match(
         &self,
-        request: &Request<()>,
+        request: &Request<Vec<u8>>,
         request_config: &RequestConfig,
     ) -> Result<Option<bool>, Error> {
         if let Some(url_pattern) = request_config.url_pattern() {
-            let path = request.uri().path();
+            let path = request_path(request, request_config);
             try_trace!(
                 self.stdout,
                 "Pattern Match (URL) - Checking {} against {}",
                 url_pattern,
                 path
             );
-            if let Ok(regex) = generate_regex(path, url_pattern) {
-                Ok(Some(regex.is_match(path)))
-            } else {
-                Ok(Some(false))
-            }
+            let regex = util::pattern(url_pattern)?;
+            Ok(Some(regex.is_match(&path)))
         } else {
             try_trace!(self.stdout, "Patten Match (URL) - No check performed");
             Ok(None)
         }
     }
+
+    fn outcome(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<MatchOutcome>, Error> {
+        Ok(
+            match (
+                self.is_match(request, request_config)?,
+                request_config.url_pattern(),
+            ) {
+                (Some(matched), Some(expected)) => {
+                    Some(MatchOutcome::new(self.to_string(), matched).with_values(
+                        expected.clone(),
+                        request_path(request, request_config).into_owned(),
+                    ))
+                }
+                _ => None,
+            },
+        )
+    }
 }
 
 impl fmt::Display for PatternMatch {
@@ -130,3 +254,74 @@ impl fmt::Display for PatternMatch {
         write!(f, "Pattern Match On Url")
     }
 }
+
+/// Match a url pattern the request must not match.
+#[derive(Clone, Debug, Default)]
+pub struct NegateMatch {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for NegateMatch {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for NegateMatch {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Cheap
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if let Some(url_not_pattern) = request_config.url_not_pattern() {
+            let path = request_path(request, request_config);
+            try_trace!(
+                self.stdout,
+                "Negate Match (URL) - Checking {} does not match {}",
+                path,
+                url_not_pattern
+            );
+            let regex = util::pattern(url_not_pattern)?;
+            Ok(Some(!regex.is_match(&path)))
+        } else {
+            try_trace!(self.stdout, "Negate Match (URL) - No check performed");
+            Ok(None)
+        }
+    }
+}
+
+impl fmt::Display for NegateMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Negate Match On Url")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::normalize_path;
+
+    #[test]
+    fn normalize() {
+        assert_eq!(normalize_path("/a/b"), "/a/b");
+        assert_eq!(normalize_path("/a%2Fb"), "/a/b");
+        assert_eq!(normalize_path("//a///b/"), "/a/b/");
+        assert_eq!(normalize_path("/a/./b/../c"), "/a/c");
+        assert_eq!(normalize_path("/../../a"), "/a");
+        assert_eq!(normalize_path("/a/b/."), "/a/b/");
+        assert_eq!(normalize_path("/"), "/");
+        assert_eq!(normalize_path("/%7Euser/docs%20new"), "/~user/docs new");
+    }
+}
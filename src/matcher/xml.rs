@@ -0,0 +1,360 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP request XML body matching
+use crate::config::{self, Request as RequestConfig};
+use crate::error::Error;
+use crate::matcher::{MatchCost, RequestMatch, Slogger};
+use crate::util;
+use http::header::CONTENT_TYPE;
+use http::Request;
+use slog::{trace, Logger};
+use slog_try::try_trace;
+use std::collections::BTreeMap;
+use std::fmt;
+use sxd_document::dom::{Document, Element};
+use sxd_document::parser;
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{Context, Factory, Value};
+
+/// The SOAP 1.1 envelope namespace.
+const SOAP_11: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+/// The SOAP 1.2 envelope namespace.
+const SOAP_12: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+/// Match XPath expressions against the XML body of a HTTP request.
+#[derive(Clone, Debug, Default)]
+pub struct XPath {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for XPath {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for XPath {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        if request_config.xpath().is_empty() {
+            try_trace!(self.stdout, "XPath Match (Body) - No check performed");
+            return Ok(None);
+        }
+
+        let body = String::from_utf8_lossy(request.body());
+        if let Ok(package) = parser::parse(&body) {
+            try_trace!(
+                self.stdout,
+                "XPath Match (Body) - Checking {:?} against {}",
+                request_config.xpath(),
+                body
+            );
+            let document = package.as_document();
+            Ok(Some(request_config.xpath().iter().all(|xpath| {
+                is_xpath_match(
+                    document.root().into(),
+                    xpath,
+                    request_config.xml_namespaces(),
+                )
+            })))
+        } else {
+            try_trace!(self.stdout, "XPath Match (Body) - Body is not XML");
+            Ok(Some(false))
+        }
+    }
+}
+
+impl fmt::Display for XPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "XPath Match On Body")
+    }
+}
+
+/// Match SOAP requests on the SOAP action, the operation, and XPath expressions evaluated against
+/// the SOAP body.
+#[derive(Clone, Debug, Default)]
+pub struct Soap {
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+}
+
+impl Slogger for Soap {
+    /// Add a stdout logger
+    fn set_stdout(mut self, stdout: Option<Logger>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Add a stderr logger
+    fn set_stderr(mut self, stderr: Option<Logger>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl RequestMatch for Soap {
+    fn cost(&self) -> MatchCost {
+        MatchCost::Expensive
+    }
+
+    fn is_match(
+        &self,
+        request: &Request<Vec<u8>>,
+        request_config: &RequestConfig,
+    ) -> Result<Option<bool>, Error> {
+        let soap = if let Some(soap) = request_config.soap() {
+            soap
+        } else {
+            try_trace!(self.stdout, "SOAP Match - No check performed");
+            return Ok(None);
+        };
+
+        if let Some(expected) = soap.action() {
+            let action = soap_action(request);
+            try_trace!(
+                self.stdout,
+                "SOAP Match - Checking action {} against {:?}",
+                expected,
+                action
+            );
+            if action.as_ref() != Some(expected) {
+                return Ok(Some(false));
+            }
+        }
+
+        if soap.operation().is_none() && soap.xpath().is_empty() {
+            return Ok(Some(true));
+        }
+
+        let body = String::from_utf8_lossy(request.body());
+        let package = if let Ok(package) = parser::parse(&body) {
+            package
+        } else {
+            try_trace!(self.stdout, "SOAP Match - Body is not XML");
+            return Ok(Some(false));
+        };
+        let document = package.as_document();
+        let soap_body = if let Some(soap_body) = soap_body(&document) {
+            soap_body
+        } else {
+            try_trace!(self.stdout, "SOAP Match - Body is not a SOAP envelope");
+            return Ok(Some(false));
+        };
+
+        if let Some(expected) = soap.operation() {
+            let operation = soap_body
+                .children()
+                .into_iter()
+                .find_map(|child| child.element())
+                .map(|operation| operation.name().local_part().to_string());
+            try_trace!(
+                self.stdout,
+                "SOAP Match - Checking operation {} against {:?}",
+                expected,
+                operation
+            );
+            if operation.as_ref() != Some(expected) {
+                return Ok(Some(false));
+            }
+        }
+
+        let mut namespaces = request_config.xml_namespaces().clone();
+        let _ = namespaces
+            .entry("soap".to_string())
+            .or_insert_with(|| SOAP_11.to_string());
+        let _ = namespaces
+            .entry("soap12".to_string())
+            .or_insert_with(|| SOAP_12.to_string());
+        try_trace!(
+            self.stdout,
+            "SOAP Match - Checking {:?} against {}",
+            soap.xpath(),
+            body
+        );
+        Ok(Some(soap.xpath().iter().all(|xpath| {
+            is_xpath_match(soap_body.into(), xpath, &namespaces)
+        })))
+    }
+}
+
+impl fmt::Display for Soap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SOAP Match")
+    }
+}
+
+/// The SOAP action, from the `SOAPAction` header (SOAP 1.1) or the `action` parameter of the
+/// `Content-Type` header (SOAP 1.2).
+fn soap_action(request: &Request<Vec<u8>>) -> Option<String> {
+    if let Some(action) = request.headers().get("SOAPAction") {
+        action
+            .to_str()
+            .ok()
+            .map(|action| action.trim().trim_matches('"').to_string())
+    } else {
+        let content_type = request.headers().get(CONTENT_TYPE)?.to_str().ok()?;
+        util::header_parameter(content_type, "action")
+    }
+}
+
+/// The `Body` element of a SOAP 1.1 or 1.2 envelope.
+fn soap_body<'d>(document: &Document<'d>) -> Option<Element<'d>> {
+    let envelope = document
+        .root()
+        .children()
+        .into_iter()
+        .find_map(|child| child.element())?;
+    let namespace = envelope.name().namespace_uri()?;
+
+    if (namespace != SOAP_11 && namespace != SOAP_12) || envelope.name().local_part() != "Envelope"
+    {
+        return None;
+    }
+
+    envelope
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+        .find(|element| {
+            element.name().namespace_uri() == Some(namespace)
+                && element.name().local_part() == "Body"
+        })
+}
+
+fn is_xpath_match(
+    node: Node<'_>,
+    xpath: &config::XPath,
+    namespaces: &BTreeMap<String, String>,
+) -> bool {
+    let compiled = match Factory::new().build(xpath.expression()) {
+        Ok(Some(compiled)) => compiled,
+        _ => return false,
+    };
+    let mut context = Context::new();
+    for (prefix, uri) in namespaces {
+        context.set_namespace(prefix, uri);
+    }
+
+    match compiled.evaluate(&context, node) {
+        Ok(Value::Nodeset(nodes)) => match xpath.equal_to() {
+            Some(expected) => nodes.iter().any(|node| &node.string_value() == expected),
+            None => nodes.size() > 0,
+        },
+        Ok(value) => match xpath.equal_to() {
+            Some(expected) => &value.string() == expected,
+            None => value.boolean(),
+        },
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_xpath_match, soap_action, soap_body};
+    use crate::config::XPath;
+    use http::Request;
+    use std::collections::BTreeMap;
+    use sxd_document::parser;
+
+    const ENVELOPE: &str = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <GetOrder><id>12345</id><express/></GetOrder>
+  </soap:Body>
+</soap:Envelope>"#;
+
+    fn check(expression: &str, equal_to: Option<&str>) -> bool {
+        let mut namespaces = BTreeMap::new();
+        let _ = namespaces.insert(
+            "soap".to_string(),
+            "http://schemas.xmlsoap.org/soap/envelope/".to_string(),
+        );
+        let package = parser::parse(ENVELOPE).expect("Unable to parse the test XML!");
+        is_xpath_match(
+            package.as_document().root().into(),
+            &XPath::new(expression, equal_to.map(str::to_string)),
+            &namespaces,
+        )
+    }
+
+    #[test]
+    fn presence() {
+        assert!(check("/soap:Envelope/soap:Body/GetOrder/express", None));
+        assert!(!check("/soap:Envelope/soap:Body/GetOrder/standard", None));
+        assert!(check("count(//id) = 1", None));
+    }
+
+    #[test]
+    fn value() {
+        assert!(check("//GetOrder/id", Some("12345")));
+        assert!(!check("//GetOrder/id", Some("54321")));
+        assert!(check("string(//GetOrder/id)", Some("12345")));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(!check("//GetOrder[", None));
+        assert!(!check("/undeclared:Envelope", None));
+    }
+
+    #[test]
+    fn envelope_body() {
+        let package = parser::parse(ENVELOPE).expect("Unable to parse the test XML!");
+        let document = package.as_document();
+        match soap_body(&document) {
+            Some(body) => assert!(is_xpath_match(
+                body.into(),
+                &XPath::new("GetOrder/id", Some("12345".to_string())),
+                &BTreeMap::new(),
+            )),
+            None => assert!(false, "No SOAP body found"),
+        }
+
+        let package =
+            parser::parse("<Envelope><Body/></Envelope>").expect("Unable to parse the test XML!");
+        assert!(soap_body(&package.as_document()).is_none());
+    }
+
+    #[test]
+    fn action() {
+        match Request::builder()
+            .header("SOAPAction", "\"urn:GetOrder\"")
+            .body(vec![])
+        {
+            Ok(request) => assert_eq!(soap_action(&request), Some("urn:GetOrder".to_string())),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match Request::builder()
+            .header(
+                "Content-Type",
+                "application/soap+xml; charset=utf-8; action=\"urn:GetOrder\"",
+            )
+            .body(vec![])
+        {
+            Ok(request) => assert_eq!(soap_action(&request), Some("urn:GetOrder".to_string())),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
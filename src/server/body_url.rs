@@ -0,0 +1,132 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response bodies downloaded from a url when the server starts.
+use crate::config::Mappings;
+use futures::{future, stream, Future, Stream};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Uri};
+use hyper_tls::HttpsConnector;
+use sha2::{Digest, Sha256};
+use slog::{error, info, Logger};
+use slog_try::{try_error, try_info};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::prelude::FutureExt;
+
+/// The directory, under the files path, that downloaded bodies are cached in.
+const CACHE_DIR: &str = "body_url_cache";
+
+/// Where the body downloaded from `url` is cached.
+crate fn cache_path(files_path: &Path, url: &str) -> PathBuf {
+    let name: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    files_path.join(CACHE_DIR).join(name)
+}
+
+/// Download the `body_url` of every response in the given mappings, each cached under its own
+/// files path, that isn't cached on disk yet.  Failures are logged, never returned, and leave
+/// that body missing.
+crate fn fetch_all(
+    sources: &[(&Mappings, &Path)],
+    stdout: Option<Logger>,
+    stderr: Option<Logger>,
+) -> impl Future<Item = (), Error = ()> + Send {
+    let mut pending: Vec<(String, PathBuf)> = sources
+        .iter()
+        .flat_map(|(mappings, files_path)| {
+            mappings
+                .inner()
+                .values()
+                .flat_map(|mapping| mapping.all_responses())
+                .filter_map(|response| response.body_url().clone())
+                .map(move |url| {
+                    let path = cache_path(files_path, &url);
+                    (url, path)
+                })
+        })
+        .filter(|(_, path)| !path.exists())
+        .collect();
+    pending.sort();
+    pending.dedup();
+    let connector_stderr = stderr.clone();
+
+    future::result(HttpsConnector::new(4))
+        .map_err(move |e| try_error!(connector_stderr, "Unable to fetch body urls: {}", e))
+        .and_then(move |connector| {
+            let client = Client::builder().build::<_, Body>(connector);
+            stream::iter_ok(pending).for_each(move |(url, path)| {
+                let stdout = stdout.clone();
+                let stderr = stderr.clone();
+                fetch(&client, &url, path).then(move |result| {
+                    match result {
+                        Ok(length) => try_info!(stdout, "Fetched {} bytes from {}", length, url),
+                        Err(e) => try_error!(stderr, "Unable to fetch {}: {}", url, e),
+                    }
+                    Ok(())
+                })
+            })
+        })
+}
+
+/// Download `url` to `path`, returning the length of the body.
+fn fetch(
+    client: &Client<HttpsConnector<HttpConnector>, Body>,
+    url: &str,
+    path: PathBuf,
+) -> impl Future<Item = usize, Error = String> + Send {
+    future::result(url.parse::<Uri>().map_err(|e| e.to_string()))
+        .and_then({
+            let client = client.clone();
+            move |uri| {
+                client
+                    .get(uri)
+                    .timeout(Duration::from_secs(30))
+                    .map_err(|e| e.to_string())
+            }
+        })
+        .and_then(|response| {
+            let status = response.status();
+            if status.is_success() {
+                future::Either::A(response.into_body().concat2().map_err(|e| e.to_string()))
+            } else {
+                future::Either::B(future::err(format!("unexpected status {}", status)))
+            }
+        })
+        .and_then(move |body| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::write(&path, &body).map_err(|e| e.to_string())?;
+            Ok(body.len())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::cache_path;
+    use std::path::Path;
+
+    #[test]
+    fn cache_path_per_url() {
+        let files_path = Path::new("files");
+        let path = cache_path(files_path, "https://example.com/orders.json");
+        assert!(path.starts_with("files/body_url_cache"));
+        assert_eq!(
+            path,
+            cache_path(files_path, "https://example.com/orders.json")
+        );
+        assert_ne!(
+            path,
+            cache_path(files_path, "https://example.com/users.json")
+        );
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response body transcoding.
+use crate::config::Charset;
+use std::convert::TryFrom;
+
+/// The `Content-Type` used for a transcoded body that doesn't have one.
+crate const DEFAULT_CONTENT_TYPE: &str = "text/plain";
+
+/// Transcode a UTF-8 response body to a character set.  Invalid UTF-8 is replaced first.
+crate fn transcode(charset: Charset, body: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(body);
+
+    match charset {
+        Charset::Utf8 => text.into_owned().into_bytes(),
+        Charset::Iso88591 => text
+            .chars()
+            .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+            .collect(),
+        Charset::Utf16 => {
+            let mut transcoded = vec![0xFE, 0xFF];
+            transcoded.extend(
+                text.encode_utf16()
+                    .flat_map(|unit| unit.to_be_bytes().to_vec()),
+            );
+            transcoded
+        }
+        Charset::Utf16Le => text
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes().to_vec())
+            .collect(),
+        Charset::Utf16Be => text
+            .encode_utf16()
+            .flat_map(|unit| unit.to_be_bytes().to_vec())
+            .collect(),
+    }
+}
+
+/// Set the `charset` parameter of a `Content-Type`, replacing any that is already there.
+crate fn content_type(content_type: &str, charset: Charset) -> String {
+    let mut parts: Vec<&str> = content_type
+        .split(';')
+        .map(str::trim)
+        .filter(|part| {
+            !part.is_empty()
+                && !part
+                    .splitn(2, '=')
+                    .next()
+                    .map_or(false, |key| key.trim().eq_ignore_ascii_case("charset"))
+        })
+        .collect();
+    let charset = format!("charset={}", charset.name());
+    parts.push(&charset);
+    parts.join("; ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{content_type, transcode};
+    use crate::config::Charset;
+
+    #[test]
+    fn transcoding() {
+        assert_eq!(
+            transcode(Charset::Utf8, "café".as_bytes()),
+            "café".as_bytes()
+        );
+        assert_eq!(
+            transcode(Charset::Iso88591, "café €".as_bytes()),
+            b"caf\xE9 ?"
+        );
+        assert_eq!(
+            transcode(Charset::Utf16, "hé".as_bytes()),
+            vec![0xFE, 0xFF, 0x00, b'h', 0x00, 0xE9]
+        );
+        assert_eq!(
+            transcode(Charset::Utf16Le, "hé".as_bytes()),
+            vec![b'h', 0x00, 0xE9, 0x00]
+        );
+        assert_eq!(
+            transcode(Charset::Utf16Be, "hé".as_bytes()),
+            vec![0x00, b'h', 0x00, 0xE9]
+        );
+    }
+
+    #[test]
+    fn charset_param() {
+        assert_eq!(
+            content_type("text/html", Charset::Iso88591),
+            "text/html; charset=iso-8859-1"
+        );
+        assert_eq!(
+            content_type("application/json; charset=UTF-8", Charset::Utf16),
+            "application/json; charset=utf-16"
+        );
+        assert_eq!(
+            content_type("text/plain;format=flowed", Charset::Utf8),
+            "text/plain; format=flowed; charset=utf-8"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Codec for a whole connection, which starts out speaking HTTP and may be upgraded to a
+//! WebSocket.
+use super::inbound::{Frame, Http, Rejected};
+use super::websocket::{self, Message};
+use bytes::BytesMut;
+use http::{Request, StatusCode};
+use std::io;
+use tokio_io::codec::{Decoder, Encoder};
+
+/// Something received from the client.
+#[derive(Debug)]
+crate enum Inbound {
+    /// An HTTP request.
+    Request(Request<Vec<u8>>),
+    /// A WebSocket message, once the connection has been upgraded.
+    Message(Message),
+    /// A request the server refuses to read, i.e. one with a body that is too large.  It is
+    /// answered with the status, and nothing more is read from the connection.
+    Rejected(StatusCode),
+}
+
+/// Connection codec for tokio.  Requests are decoded with [`Http`](Http) until an
+/// [`Upgrade`](Frame::Upgrade) frame is encoded, after which WebSocket messages are decoded
+/// instead.
+#[derive(Clone, Copy, Debug, Default)]
+crate struct Connection {
+    websocket: bool,
+    rejected: bool,
+}
+
+impl Encoder for Connection {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Frame, dst: &mut BytesMut) -> io::Result<()> {
+        if let Frame::Upgrade(_) = item {
+            self.websocket = true;
+        }
+        Http.encode(item, dst)
+    }
+}
+
+impl Decoder for Connection {
+    type Item = Inbound;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Inbound>> {
+        if self.rejected {
+            // The rest of the connection can't be framed, so it is thrown away.
+            src.clear();
+            Ok(None)
+        } else if self.websocket {
+            Ok(websocket::decode(src)?.map(Inbound::Message))
+        } else {
+            match Http.decode(src) {
+                Ok(request) => Ok(request.map(Inbound::Request)),
+                Err(e) => match Rejected::from_error(&e) {
+                    Some(status) => {
+                        self.rejected = true;
+                        src.clear();
+                        Ok(Some(Inbound::Rejected(status)))
+                    }
+                    None => Err(e),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Connection, Inbound};
+    use crate::server::codec::{Frame, Message};
+    use bytes::BytesMut;
+    use http::{Response, StatusCode};
+    use tokio_io::codec::{Decoder, Encoder};
+
+    #[test]
+    fn upgrade() {
+        let mut connection = Connection::default();
+        let mut src = BytesMut::from(&b"GET /prices HTTP/1.1\r\nUpgrade: websocket\r\n\r\n"[..]);
+        match connection.decode(&mut src) {
+            Ok(Some(Inbound::Request(request))) => assert_eq!(request.uri().path(), "/prices"),
+            _ => assert!(false, "Expected a request"),
+        }
+
+        let mut dst = BytesMut::new();
+        if let Err(e) = connection.encode(Frame::Upgrade(Response::new(())), &mut dst) {
+            assert!(false, e.to_string());
+        }
+
+        // An unmasked frame from the client is still accepted.
+        let mut src = BytesMut::from(&[0x81, 0x02, b'h', b'i'][..]);
+        match connection.decode(&mut src) {
+            Ok(Some(Inbound::Message(message))) => {
+                assert_eq!(message, Message::Text("hi".to_string()))
+            }
+            _ => assert!(false, "Expected a message"),
+        }
+    }
+
+    #[test]
+    fn rejected() {
+        let mut connection = Connection::default();
+        let mut src = BytesMut::from(
+            &b"POST /orders HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\nGET / HTTP/1.1\r\n\r\n"
+                [..],
+        );
+        match connection.decode(&mut src) {
+            Ok(Some(Inbound::Rejected(status))) => {
+                assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE)
+            }
+            _ => assert!(false, "Expected the request to be rejected"),
+        }
+
+        src.extend_from_slice(b"GET / HTTP/1.1\r\n\r\n");
+        match connection.decode(&mut src) {
+            Ok(None) => assert!(src.is_empty()),
+            _ => assert!(false, "Expected nothing more to be read"),
+        }
+    }
+}
@@ -7,13 +7,68 @@
 // modified, or distributed except according to those terms.
 
 //! Codec for decoding inbound HTTP requests.
-use bytes::BytesMut;
+use super::websocket::{self, Message};
+use crate::config::Fault;
+use bytes::{Bytes, BytesMut};
 use chrono::Utc;
-use http::header::HeaderValue;
-use http::{Request, Response, Version};
-use std::{fmt, io};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::{Request, Response, StatusCode, Version};
+use std::error::Error;
+use std::{fmt, io, str};
 use tokio_io::codec::{Decoder, Encoder};
 
+/// The size of each chunk of a chunked response body, unless the response configures one.
+crate const DEFAULT_CHUNK_SIZE: usize = 8192;
+/// The largest request body the server reads.  A larger request is answered with
+/// `413 Payload Too Large`.
+crate const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+/// The longest chunk size line, with any chunk extensions, accepted in a chunked request body.
+const MAX_CHUNK_LINE: usize = 1024;
+
+/// A request the server refuses to read, answered with the status before the connection is
+/// closed.  The decoder returns it as the inner error of an `io::Error`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+crate struct Rejected(StatusCode);
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request rejected with {}", self.0)
+    }
+}
+
+impl Error for Rejected {}
+
+impl Rejected {
+    /// The status to answer with, if the given decoder error rejects the request.
+    crate fn from_error(e: &io::Error) -> Option<StatusCode> {
+        e.get_ref()
+            .and_then(|inner| inner.downcast_ref::<Self>())
+            .map(|rejected| rejected.0)
+    }
+}
+
+fn reject(status: StatusCode) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, Rejected(status))
+}
+
+/// How the length of a request body is given.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BodyLength {
+    /// By a `Content-Length` header, or no body at all.
+    Fixed(usize),
+    /// By `Transfer-Encoding: chunked`.
+    Chunked,
+}
+
+/// Send a response body with `Transfer-Encoding: chunked`, in chunks of at most `size` bytes,
+/// instead of with a `Content-Length`, followed by the `trailers`.  The handler adds this to the
+/// response extensions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+crate struct Chunked {
+    crate size: usize,
+    crate trailers: HeaderMap,
+}
+
 /// HTTP codec for tokio.
 #[derive(Copy, Clone, Debug)]
 crate struct Http;
@@ -35,56 +90,176 @@ impl<'a> fmt::Write for BytesWrite<'a> {
     }
 }
 
+/// A piece of an HTTP response.
+#[derive(Debug)]
+crate enum Frame {
+    /// A whole response.  The body is chunked if the response extensions contain
+    /// [`Chunked`](Chunked).
+    Response(Response<Vec<u8>>),
+    /// The status line and headers of a response whose body follows in `Data` frames, if the
+    /// content length is given, otherwise in `Chunk` frames.
+    Head {
+        response: Response<()>,
+        content_length: Option<u64>,
+    },
+    /// Part of a response body.
+    Data(Bytes),
+    /// A chunk of a chunked response body.  An empty chunk ends the body.
+    Chunk(Bytes),
+    /// The end of a chunked response body, with the trailers to send after it.
+    Trailers(HeaderMap),
+    /// A whole response, cut short by a fault.
+    Fault {
+        response: Response<Vec<u8>>,
+        fault: Fault,
+    },
+    /// The status line and headers of a `101 Switching Protocols` response, after which the
+    /// connection carries WebSocket messages.
+    Upgrade(Response<()>),
+    /// A WebSocket message.
+    Message(Message),
+    /// Close the connection.  The handler stops sending at this frame, so it is never encoded.
+    Close,
+}
+
+impl Frame {
+    /// Is this the frame that closes the connection?
+    crate fn is_close(&self) -> bool {
+        match self {
+            Frame::Close => true,
+            _ => false,
+        }
+    }
+}
+
 /// Implementation of encoding an HTTP response into a `BytesMut`, basically
 /// just writing out an HTTP/1.1 response.
 impl Encoder for Http {
-    type Item = Response<String>;
+    type Item = Frame;
     type Error = io::Error;
 
-    fn encode(&mut self, item: Response<String>, dst: &mut BytesMut) -> io::Result<()> {
-        use std::fmt::Write;
-
-        write!(
-            BytesWrite(dst),
-            "\
-             HTTP/1.1 {}\r\n\
-             Server: Example\r\n\
-             Content-Length: {}\r\n\
-             Date: {}\r\n\
-             ",
-            item.status(),
-            item.body().len(),
-            Utc::now()
-        )
-        .unwrap();
-
-        for (k, v) in item.headers() {
-            dst.extend_from_slice(k.as_str().as_bytes());
-            dst.extend_from_slice(b": ");
-            dst.extend_from_slice(v.as_bytes());
-            dst.extend_from_slice(b"\r\n");
+    fn encode(&mut self, item: Frame, dst: &mut BytesMut) -> io::Result<()> {
+        match item {
+            Frame::Response(mut response) => {
+                if let Some(chunked) = response.extensions_mut().remove::<Chunked>() {
+                    encode_head(&response, None, dst);
+                    for chunk in response.body().chunks(chunked.size.max(1)) {
+                        encode_chunk(chunk, dst);
+                    }
+                    encode_last_chunk(&chunked.trailers, dst);
+                } else {
+                    encode_head(&response, Some(response.body().len() as u64), dst);
+                    dst.extend_from_slice(response.body());
+                }
+            }
+            Frame::Head {
+                response,
+                content_length,
+            } => encode_head(&response, content_length, dst),
+            Frame::Data(data) => dst.extend_from_slice(&data),
+            Frame::Chunk(chunk) => encode_chunk(&chunk, dst),
+            Frame::Trailers(trailers) => encode_last_chunk(&trailers, dst),
+            Frame::Fault { response, fault } => {
+                let mut encoded = BytesMut::new();
+                self.encode(Frame::Response(response), &mut encoded)?;
+                dst.extend_from_slice(fault.truncate(&encoded));
+            }
+            Frame::Upgrade(response) => {
+                encode_status_line(&response, dst);
+                encode_headers(&response, dst);
+            }
+            Frame::Message(message) => websocket::encode(&message, dst),
+            Frame::Close => {}
         }
 
+        Ok(())
+    }
+}
+
+/// Write the status line and headers of a response, with a `Content-Length` if one is given,
+/// otherwise `Transfer-Encoding: chunked`.
+fn encode_head<T>(response: &Response<T>, content_length: Option<u64>, dst: &mut BytesMut) {
+    use std::fmt::Write;
+
+    encode_status_line(response, dst);
+
+    if let Some(content_length) = content_length {
+        write!(BytesWrite(dst), "Content-Length: {}\r\n", content_length).unwrap();
+    } else {
+        dst.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+    }
+
+    encode_headers(response, dst);
+}
+
+fn encode_status_line<T>(response: &Response<T>, dst: &mut BytesMut) {
+    use std::fmt::Write;
+
+    write!(
+        BytesWrite(dst),
+        "\
+         HTTP/1.1 {}\r\n\
+         Server: Example\r\n\
+         ",
+        response.status()
+    )
+    .unwrap();
+}
+
+fn encode_headers<T>(response: &Response<T>, dst: &mut BytesMut) {
+    use std::fmt::Write;
+
+    write!(BytesWrite(dst), "Date: {}\r\n", Utc::now()).unwrap();
+
+    for (k, v) in response.headers() {
+        dst.extend_from_slice(k.as_str().as_bytes());
+        dst.extend_from_slice(b": ");
+        dst.extend_from_slice(v.as_bytes());
         dst.extend_from_slice(b"\r\n");
-        dst.extend_from_slice(item.body().as_bytes());
+    }
 
-        Ok(())
+    dst.extend_from_slice(b"\r\n");
+}
+
+fn encode_chunk(chunk: &[u8], dst: &mut BytesMut) {
+    use std::fmt::Write;
+
+    write!(BytesWrite(dst), "{:x}\r\n", chunk.len()).unwrap();
+    dst.extend_from_slice(chunk);
+    dst.extend_from_slice(b"\r\n");
+}
+
+fn encode_last_chunk(trailers: &HeaderMap, dst: &mut BytesMut) {
+    dst.extend_from_slice(b"0\r\n");
+
+    for (k, v) in trailers {
+        dst.extend_from_slice(k.as_str().as_bytes());
+        dst.extend_from_slice(b": ");
+        dst.extend_from_slice(v.as_bytes());
+        dst.extend_from_slice(b"\r\n");
     }
+
+    dst.extend_from_slice(b"\r\n");
 }
 
 /// Implementation of decoding an HTTP request from the bytes we've read so far.
 /// This leverages the `httparse` crate to do the actual parsing and then we use
 /// that information to construct an instance of a `http::Request` object,
 /// trying to avoid allocations where possible.
+///
+/// If the request has a `Content-Length` header, or is sent with `Transfer-Encoding: chunked`,
+/// decoding waits until the whole body, and any trailers, have been read.  Trailers are added to
+/// the request headers.  A body over [`MAX_BODY_SIZE`](MAX_BODY_SIZE), or one that can't be
+/// framed, is [`Rejected`](Rejected).
 impl Decoder for Http {
-    type Item = Request<()>;
+    type Item = Request<Vec<u8>>;
     type Error = io::Error;
 
-    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Request<()>>> {
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Request<Vec<u8>>>> {
         // TODO: we should grow this headers array if parsing fails and asks
         //       for more headers
         let mut headers = [None; 16];
-        let (method, path, version, amt) = {
+        let (method, path, version, amt, body_length) = {
             let mut parsed_headers = [httparse::EMPTY_HEADER; 16];
             let mut r = httparse::Request::new(&mut parsed_headers);
             let status = r.parse(src).map_err(|e| {
@@ -103,7 +278,32 @@ impl Decoder for Http {
                 (start, start + a.len())
             };
 
+            let mut body_length = BodyLength::Fixed(0);
             for (i, header) in r.headers.iter().enumerate() {
+                if header.name.eq_ignore_ascii_case("transfer-encoding") {
+                    // The final coding must be chunked, or the body has no end.
+                    let chunked = str::from_utf8(header.value)
+                        .ok()
+                        .and_then(|value| value.rsplit(',').next())
+                        .map_or(false, |coding| {
+                            coding.trim().eq_ignore_ascii_case("chunked")
+                        });
+                    if !chunked {
+                        return Err(reject(StatusCode::BAD_REQUEST));
+                    }
+                    body_length = BodyLength::Chunked;
+                } else if header.name.eq_ignore_ascii_case("content-length")
+                    && body_length != BodyLength::Chunked
+                {
+                    let content_length = str::from_utf8(header.value)
+                        .ok()
+                        .and_then(|value| value.trim().parse::<usize>().ok())
+                        .ok_or_else(|| reject(StatusCode::BAD_REQUEST))?;
+                    if content_length > MAX_BODY_SIZE {
+                        return Err(reject(StatusCode::PAYLOAD_TOO_LARGE));
+                    }
+                    body_length = BodyLength::Fixed(content_length);
+                }
                 let k = toslice(header.name.as_bytes());
                 let v = toslice(header.value);
                 headers[i] = Some((k, v));
@@ -114,6 +314,7 @@ impl Decoder for Http {
                 toslice(r.path.unwrap().as_bytes()),
                 r.version.unwrap(),
                 amt,
+                body_length,
             )
         };
         if version != 1 {
@@ -122,7 +323,23 @@ impl Decoder for Http {
                 "only HTTP/1.1 accepted",
             ));
         }
+        let (body, trailers, body_end) = match body_length {
+            BodyLength::Fixed(content_length) => {
+                let body_end = amt
+                    .checked_add(content_length)
+                    .ok_or_else(|| reject(StatusCode::PAYLOAD_TOO_LARGE))?;
+                if src.len() < body_end {
+                    return Ok(None);
+                }
+                (src[amt..body_end].to_vec(), HeaderMap::new(), body_end)
+            }
+            BodyLength::Chunked => match decode_chunked(&src[amt..])? {
+                Some((body, trailers, length)) => (body, trailers, amt + length),
+                None => return Ok(None),
+            },
+        };
         let data = src.split_to(amt).freeze();
+        let _ = src.split_to(body_end - amt);
         let mut request = Request::builder();
         let _ = request.method(&data[method.0..method.1]);
         let _ = request.uri(data.slice(path.0, path.1));
@@ -136,10 +353,364 @@ impl Decoder for Http {
                 .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
             let _ = request.header(&data[k.0..k.1], value);
         }
+        for (name, value) in &trailers {
+            let _ = request.header(name, value.clone());
+        }
 
         let req = request
-            .body(())
+            .body(body)
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
         Ok(Some(req))
     }
 }
+
+/// Decode a chunked request body, returning the body, the trailers, and the number of bytes
+/// read, or `None` until the last chunk and the trailers have all arrived.
+fn decode_chunked(src: &[u8]) -> io::Result<Option<(Vec<u8>, HeaderMap, usize)>> {
+    let mut body = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        let line_end = match src[pos..].windows(2).position(|pair| pair == b"\r\n") {
+            Some(idx) if idx <= MAX_CHUNK_LINE => pos + idx,
+            None if src.len() - pos <= MAX_CHUNK_LINE => return Ok(None),
+            _ => return Err(reject(StatusCode::BAD_REQUEST)),
+        };
+        let size = str::from_utf8(&src[pos..line_end])
+            .ok()
+            .and_then(|line| line.split(';').next())
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+            .ok_or_else(|| reject(StatusCode::BAD_REQUEST))?;
+        pos = line_end + 2;
+
+        if size == 0 {
+            break;
+        }
+        let _ = body
+            .len()
+            .checked_add(size)
+            .filter(|length| *length <= MAX_BODY_SIZE)
+            .ok_or_else(|| reject(StatusCode::PAYLOAD_TOO_LARGE))?;
+
+        let chunk_end = pos + size;
+        if src.len() < chunk_end + 2 {
+            return Ok(None);
+        }
+        if &src[chunk_end..chunk_end + 2] != b"\r\n" {
+            return Err(reject(StatusCode::BAD_REQUEST));
+        }
+        body.extend_from_slice(&src[pos..chunk_end]);
+        pos = chunk_end + 2;
+    }
+
+    let mut parsed = [httparse::EMPTY_HEADER; 16];
+    match httparse::parse_headers(&src[pos..], &mut parsed) {
+        Ok(httparse::Status::Complete((amt, headers))) => {
+            let mut trailers = HeaderMap::new();
+            for header in headers {
+                let name = HeaderName::from_bytes(header.name.as_bytes())
+                    .map_err(|_| reject(StatusCode::BAD_REQUEST))?;
+                let value = HeaderValue::from_bytes(header.value)
+                    .map_err(|_| reject(StatusCode::BAD_REQUEST))?;
+                let _ = trailers.append(name, value);
+            }
+            Ok(Some((body, trailers, pos + amt)))
+        }
+        Ok(httparse::Status::Partial) => Ok(None),
+        Err(_) => Err(reject(StatusCode::BAD_REQUEST)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Chunked, Frame, Http, Rejected, MAX_BODY_SIZE};
+    use crate::config::Fault;
+    use crate::server::codec::websocket::Message;
+    use bytes::{Bytes, BytesMut};
+    use http::header::{HeaderMap, HeaderValue};
+    use http::{Response, StatusCode};
+    use tokio_io::codec::{Decoder, Encoder};
+
+    fn encode(frames: Vec<Frame>) -> String {
+        let mut dst = BytesMut::new();
+        for frame in frames {
+            if let Err(e) = Http.encode(frame, &mut dst) {
+                assert!(false, e.to_string());
+            }
+        }
+        String::from_utf8_lossy(&dst).into_owned()
+    }
+
+    #[test]
+    fn encode_with_content_length() {
+        let encoded = encode(vec![Frame::Response(Response::new(
+            b"hello world".to_vec(),
+        ))]);
+        assert!(encoded.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(encoded.contains("Content-Length: 11\r\n"));
+        assert!(!encoded.contains("Transfer-Encoding"));
+        assert!(encoded.ends_with("\r\n\r\nhello world"));
+    }
+
+    #[test]
+    fn encode_chunked() {
+        match Response::builder()
+            .extension(Chunked {
+                size: 5,
+                trailers: HeaderMap::new(),
+            })
+            .body(b"hello world".to_vec())
+        {
+            Ok(response) => {
+                let encoded = encode(vec![Frame::Response(response)]);
+                assert!(encoded.contains("Transfer-Encoding: chunked\r\n"));
+                assert!(!encoded.contains("Content-Length"));
+                assert!(
+                    encoded.ends_with("\r\n\r\n5\r\nhello\r\n5\r\n worl\r\n1\r\nd\r\n0\r\n\r\n")
+                );
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn encode_streamed() {
+        let encoded = encode(vec![
+            Frame::Head {
+                response: Response::new(()),
+                content_length: Some(11),
+            },
+            Frame::Data(Bytes::from(&b"hello"[..])),
+            Frame::Data(Bytes::from(&b" world"[..])),
+        ]);
+        assert!(encoded.contains("Content-Length: 11\r\n"));
+        assert!(encoded.ends_with("\r\n\r\nhello world"));
+
+        let encoded = encode(vec![
+            Frame::Head {
+                response: Response::new(()),
+                content_length: None,
+            },
+            Frame::Chunk(Bytes::from(&b"hello"[..])),
+            Frame::Chunk(Bytes::new()),
+        ]);
+        assert!(encoded.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(encoded.ends_with("\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn encode_trailers() {
+        let mut trailers = HeaderMap::new();
+        let _ = trailers.insert("grpc-status", HeaderValue::from_static("0"));
+
+        match Response::builder()
+            .extension(Chunked {
+                size: 8,
+                trailers: trailers.clone(),
+            })
+            .body(b"hello".to_vec())
+        {
+            Ok(response) => {
+                let encoded = encode(vec![Frame::Response(response)]);
+                assert!(encoded.ends_with("\r\n\r\n5\r\nhello\r\n0\r\ngrpc-status: 0\r\n\r\n"));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        let encoded = encode(vec![
+            Frame::Head {
+                response: Response::new(()),
+                content_length: None,
+            },
+            Frame::Chunk(Bytes::from(&b"hello"[..])),
+            Frame::Trailers(trailers),
+        ]);
+        assert!(encoded.ends_with("\r\n\r\n5\r\nhello\r\n0\r\ngrpc-status: 0\r\n\r\n"));
+    }
+
+    #[test]
+    fn encode_fault() {
+        let encoded = encode(vec![Frame::Fault {
+            response: Response::new(b"hello world".to_vec()),
+            fault: Fault::CloseAfterHeaders,
+        }]);
+        assert!(encoded.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(encoded.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn encode_upgrade() {
+        match Response::builder()
+            .status(101)
+            .header("Upgrade", "websocket")
+            .body(())
+        {
+            Ok(response) => {
+                let encoded = encode(vec![Frame::Upgrade(response)]);
+                assert!(encoded.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+                assert!(encoded.contains("upgrade: websocket\r\n"));
+                assert!(!encoded.contains("Content-Length"));
+                assert!(!encoded.contains("Transfer-Encoding"));
+                assert!(encoded.ends_with("\r\n\r\n"));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn encode_message() {
+        let mut dst = BytesMut::new();
+        match Http.encode(Frame::Message(Message::Text("hi".to_string())), &mut dst) {
+            Ok(()) => assert_eq!(&dst[..], &[0x81, 0x02, b'h', b'i'][..]),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn decode_without_body() {
+        let mut src = BytesMut::from(&b"GET /plaintext HTTP/1.1\r\nHost: localhost\r\n\r\n"[..]);
+        match Http.decode(&mut src) {
+            Ok(Some(request)) => {
+                assert_eq!(request.uri().path(), "/plaintext");
+                assert!(request.body().is_empty());
+                assert!(src.is_empty());
+            }
+            Ok(None) => assert!(false, "Expected a complete request"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn decode_with_body() {
+        let mut src = BytesMut::from(
+            &b"POST /orders HTTP/1.1\r\nContent-Length: 13\r\n\r\n{\"id\": 12345}"[..],
+        );
+        match Http.decode(&mut src) {
+            Ok(Some(request)) => {
+                assert_eq!(request.body(), &b"{\"id\": 12345}".to_vec());
+                assert!(src.is_empty());
+            }
+            Ok(None) => assert!(false, "Expected a complete request"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn decode_partial_body() {
+        let mut src =
+            BytesMut::from(&b"POST /orders HTTP/1.1\r\nContent-Length: 13\r\n\r\n{\"id\""[..]);
+        match Http.decode(&mut src) {
+            Ok(None) => {}
+            Ok(Some(_)) => assert!(false, "Expected an incomplete request"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        src.extend_from_slice(b": 12345}");
+        match Http.decode(&mut src) {
+            Ok(Some(request)) => assert_eq!(request.body().len(), 13),
+            Ok(None) => assert!(false, "Expected a complete request"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn decode_invalid_content_length() {
+        let mut src = BytesMut::from(&b"POST /orders HTTP/1.1\r\nContent-Length: abc\r\n\r\n"[..]);
+        match Http.decode(&mut src) {
+            Err(e) => assert_eq!(Rejected::from_error(&e), Some(StatusCode::BAD_REQUEST)),
+            Ok(_) => assert!(false, "Expected the request to be rejected"),
+        }
+    }
+
+    #[test]
+    fn decode_too_large() {
+        let mut src = BytesMut::from(
+            format!(
+                "POST /orders HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY_SIZE + 1
+            )
+            .as_bytes(),
+        );
+        match Http.decode(&mut src) {
+            Err(e) => assert_eq!(
+                Rejected::from_error(&e),
+                Some(StatusCode::PAYLOAD_TOO_LARGE)
+            ),
+            Ok(_) => assert!(false, "Expected the request to be rejected"),
+        }
+
+        let mut src = BytesMut::from(
+            &b"POST /orders HTTP/1.1\r\nContent-Length: 18446744073709551615\r\n\r\n"[..],
+        );
+        match Http.decode(&mut src) {
+            Err(e) => assert_eq!(
+                Rejected::from_error(&e),
+                Some(StatusCode::PAYLOAD_TOO_LARGE)
+            ),
+            Ok(_) => assert!(false, "Expected the request to be rejected"),
+        }
+
+        let mut src = BytesMut::from(
+            &b"POST /orders HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nffffffff\r\n"[..],
+        );
+        match Http.decode(&mut src) {
+            Err(e) => assert_eq!(
+                Rejected::from_error(&e),
+                Some(StatusCode::PAYLOAD_TOO_LARGE)
+            ),
+            Ok(_) => assert!(false, "Expected the request to be rejected"),
+        }
+    }
+
+    #[test]
+    fn decode_chunked() {
+        let mut src = BytesMut::from(
+            &b"POST /orders HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n{\"id\"\r\n"[..],
+        );
+        match Http.decode(&mut src) {
+            Ok(None) => {}
+            Ok(Some(_)) => assert!(false, "Expected an incomplete request"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        src.extend_from_slice(
+            b"8;ext=1\r\n: 12345}\r\n0\r\nX-Checksum: abc\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+        );
+        match Http.decode(&mut src) {
+            Ok(Some(request)) => {
+                assert_eq!(request.body(), &b"{\"id\": 12345}".to_vec());
+                assert_eq!(
+                    request.headers().get("x-checksum"),
+                    Some(&HeaderValue::from_static("abc"))
+                );
+            }
+            Ok(None) => assert!(false, "Expected a complete request"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        // The next request on the connection starts after the trailers.
+        match Http.decode(&mut src) {
+            Ok(Some(request)) => {
+                assert_eq!(request.uri().path(), "/");
+                assert!(src.is_empty());
+            }
+            Ok(None) => assert!(false, "Expected a complete request"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn decode_invalid_chunk() {
+        let mut src = BytesMut::from(
+            &b"POST /orders HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n"[..],
+        );
+        match Http.decode(&mut src) {
+            Err(e) => assert_eq!(Rejected::from_error(&e), Some(StatusCode::BAD_REQUEST)),
+            Ok(_) => assert!(false, "Expected the request to be rejected"),
+        }
+
+        let mut src =
+            BytesMut::from(&b"POST /orders HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n"[..]);
+        assert!(Http.decode(&mut src).is_err());
+    }
+}
@@ -7,6 +7,10 @@
 // modified, or distributed except according to those terms.
 
 //! Codecs used by the async runtime.
+crate mod connection;
 crate mod inbound;
+crate mod websocket;
 
-crate use self::inbound::Http;
+crate use self::connection::{Connection, Inbound};
+crate use self::inbound::{Chunked, Frame, DEFAULT_CHUNK_SIZE};
+crate use self::websocket::Message;
@@ -0,0 +1,256 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! WebSocket ([RFC 6455](https://tools.ietf.org/html/rfc6455)) message framing.
+//!
+//! Messages from the client are masked, and may be split into several frames.  Messages from
+//! the server are always sent unmasked, in a single frame.
+use bytes::BytesMut;
+use sha1::{Digest, Sha1};
+use std::convert::TryFrom;
+use std::io;
+
+/// The GUID appended to the client's key to form the `Sec-WebSocket-Accept` header.
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// A WebSocket message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+crate enum Message {
+    /// A UTF-8 text message.
+    Text(String),
+    /// A binary message.
+    Binary(Vec<u8>),
+    /// A ping, which is answered with a pong carrying the same data.
+    Ping(Vec<u8>),
+    /// A pong.
+    Pong(Vec<u8>),
+    /// Close the connection, with an optional status code.
+    Close(Option<u16>),
+}
+
+/// The `Sec-WebSocket-Accept` header value for the given `Sec-WebSocket-Key`.
+crate fn accept_key(key: &str) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.input(key.trim().as_bytes());
+    sha1.input(ACCEPT_GUID.as_bytes());
+    base64::encode(&sha1.result())
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// A single frame, parsed from the front of a buffer.
+struct RawFrame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+    len: usize,
+}
+
+/// Parse the frame at the front of `src`, without consuming it.
+fn parse_frame(src: &[u8]) -> io::Result<Option<RawFrame>> {
+    if src.len() < 2 {
+        return Ok(None);
+    }
+
+    let fin = src[0] & 0x80 != 0;
+    let opcode = src[0] & 0x0F;
+    let masked = src[1] & 0x80 != 0;
+    let (payload_len, mut offset) = match src[1] & 0x7F {
+        126 if src.len() >= 4 => (u64::from(u16::from_be_bytes([src[2], src[3]])), 4),
+        127 if src.len() >= 10 => {
+            let mut len = [0; 8];
+            len.copy_from_slice(&src[2..10]);
+            (u64::from_be_bytes(len), 10)
+        }
+        126 | 127 => return Ok(None),
+        len => (u64::from(len), 2),
+    };
+    let payload_len = usize::try_from(payload_len).map_err(|_| invalid("frame too large"))?;
+
+    let mask = if masked {
+        if src.len() < offset + 4 {
+            return Ok(None);
+        }
+        let mut mask = [0; 4];
+        mask.copy_from_slice(&src[offset..offset + 4]);
+        offset += 4;
+        Some(mask)
+    } else {
+        None
+    };
+
+    if src.len() < offset + payload_len {
+        return Ok(None);
+    }
+
+    let mut payload = src[offset..offset + payload_len].to_vec();
+    if let Some(mask) = mask {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok(Some(RawFrame {
+        fin,
+        opcode,
+        payload,
+        len: offset + payload_len,
+    }))
+}
+
+/// Decode the message at the front of `src`, once all of its frames have arrived.
+crate fn decode(src: &mut BytesMut) -> io::Result<Option<Message>> {
+    let first = match parse_frame(src)? {
+        Some(frame) => frame,
+        None => return Ok(None),
+    };
+    let mut consumed = first.len;
+    let mut payload = first.payload;
+    let mut fin = first.fin;
+
+    while !fin {
+        match parse_frame(&src[consumed..])? {
+            Some(ref frame) if frame.opcode != OP_CONTINUATION => {
+                return Err(invalid(
+                    "control frames between fragments are not supported",
+                ));
+            }
+            Some(frame) => {
+                consumed += frame.len;
+                payload.extend_from_slice(&frame.payload);
+                fin = frame.fin;
+            }
+            None => return Ok(None),
+        }
+    }
+    let _ = src.split_to(consumed);
+
+    let message = match first.opcode {
+        OP_TEXT => Message::Text(String::from_utf8(payload).map_err(invalid)?),
+        OP_BINARY => Message::Binary(payload),
+        OP_CLOSE => Message::Close(if payload.len() >= 2 {
+            Some(u16::from_be_bytes([payload[0], payload[1]]))
+        } else {
+            None
+        }),
+        OP_PING => Message::Ping(payload),
+        OP_PONG => Message::Pong(payload),
+        opcode => return Err(invalid(format!("unknown opcode {:#x}", opcode))),
+    };
+    Ok(Some(message))
+}
+
+/// Encode a message as a single, unmasked frame.
+crate fn encode(message: &Message, dst: &mut BytesMut) {
+    let (opcode, payload): (u8, Vec<u8>) = match message {
+        Message::Text(text) => (OP_TEXT, text.as_bytes().to_vec()),
+        Message::Binary(data) => (OP_BINARY, data.clone()),
+        Message::Ping(data) => (OP_PING, data.clone()),
+        Message::Pong(data) => (OP_PONG, data.clone()),
+        Message::Close(code) => (
+            OP_CLOSE,
+            code.map_or_else(Vec::new, |code| code.to_be_bytes().to_vec()),
+        ),
+    };
+
+    dst.extend_from_slice(&[0x80 | opcode]);
+    match (u8::try_from(payload.len()), u16::try_from(payload.len())) {
+        (Ok(len), _) if len < 126 => dst.extend_from_slice(&[len]),
+        (_, Ok(len)) => {
+            dst.extend_from_slice(&[126]);
+            dst.extend_from_slice(&len.to_be_bytes());
+        }
+        _ => {
+            let len = u64::try_from(payload.len()).unwrap_or_else(|_| u64::max_value());
+            dst.extend_from_slice(&[127]);
+            dst.extend_from_slice(&len.to_be_bytes());
+        }
+    }
+    dst.extend_from_slice(&payload);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{accept_key, decode, encode, Message};
+    use bytes::BytesMut;
+    use std::convert::TryFrom;
+
+    /// Mask a client frame with a fixed key.
+    fn client_frame(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xfa, 0x21, 0x3d];
+        let len = u8::try_from(payload.len()).unwrap_or(0);
+        let mut frame = vec![first, 0x80 | len];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn accept() {
+        // The example from RFC 6455.
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn decode_masked() {
+        let mut src = BytesMut::from(client_frame(0x81, b"Hello"));
+        match decode(&mut src) {
+            Ok(Some(message)) => assert_eq!(message, Message::Text("Hello".to_string())),
+            Ok(None) => assert!(false, "Expected a message"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn decode_fragmented() {
+        let mut src = BytesMut::from(client_frame(0x01, b"Hel"));
+        match decode(&mut src) {
+            Ok(None) => {}
+            _ => assert!(false, "Expected an incomplete message"),
+        }
+
+        src.extend_from_slice(&client_frame(0x80, b"lo"));
+        match decode(&mut src) {
+            Ok(Some(message)) => assert_eq!(message, Message::Text("Hello".to_string())),
+            Ok(None) => assert!(false, "Expected a message"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        for message in vec![
+            Message::Text("Hello".to_string()),
+            Message::Binary(vec![0; 300]),
+            Message::Ping(b"ping".to_vec()),
+            Message::Close(Some(1000)),
+        ] {
+            let mut dst = BytesMut::new();
+            encode(&message, &mut dst);
+            match decode(&mut dst) {
+                Ok(Some(decoded)) => assert_eq!(decoded, message),
+                Ok(None) => assert!(false, "Expected a message"),
+                Err(e) => assert!(false, e.to_string()),
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Cross-origin resource sharing.
+use crate::config::Cors;
+use http::header::{
+    HeaderMap, HeaderValue, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+    ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+    VARY,
+};
+use http::{Method, Request, Response, StatusCode};
+
+/// The methods a preflight request allows when none are configured.
+const DEFAULT_METHODS: &str = "GET, HEAD, POST, PUT, PATCH, DELETE, OPTIONS";
+
+/// The origin of a request, if it is allowed.
+fn allowed_origin<'a>(cors: &Cors, request: &'a Request<Vec<u8>>) -> Option<&'a HeaderValue> {
+    request.headers().get(ORIGIN).filter(|origin| {
+        origin
+            .to_str()
+            .map(|origin| cors.allows_origin(origin))
+            .unwrap_or(false)
+    })
+}
+
+/// The CORS headers for a response to the given request.  These are empty unless the request
+/// comes from an allowed origin.
+crate fn headers(cors: &Cors, request: &Request<Vec<u8>>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    if let Some(origin) = allowed_origin(cors, request) {
+        let any = cors.allowed_origins().iter().any(|allowed| allowed == "*")
+            || cors.allowed_origins().is_empty();
+        if any && !*cors.allow_credentials() {
+            let _ = headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        } else {
+            let _ = headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+            let _ = headers.insert(VARY, HeaderValue::from_static("Origin"));
+        }
+
+        if *cors.allow_credentials() {
+            let _ = headers.insert(
+                ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+
+        if !cors.exposed_headers().is_empty() {
+            if let Ok(exposed) = HeaderValue::from_str(&cors.exposed_headers().join(", ")) {
+                let _ = headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, exposed);
+            }
+        }
+    }
+
+    headers
+}
+
+/// The response to a preflight request from an allowed origin, or `None` if the request is
+/// anything else.
+crate fn preflight(cors: &Cors, request: &Request<Vec<u8>>) -> Option<Response<Vec<u8>>> {
+    if request.method() != Method::OPTIONS
+        || !request
+            .headers()
+            .contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+    {
+        return None;
+    }
+    let _ = allowed_origin(cors, request)?;
+
+    let mut response = Response::new(vec![]);
+    *response.status_mut() = StatusCode::NO_CONTENT;
+    let response_headers = response.headers_mut();
+    response_headers.extend(headers(cors, request));
+
+    let methods = if cors.allowed_methods().is_empty() {
+        DEFAULT_METHODS.to_string()
+    } else {
+        cors.allowed_methods().join(", ")
+    };
+    if let Ok(methods) = HeaderValue::from_str(&methods) {
+        let _ = response_headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+    }
+
+    if cors.allowed_headers().is_empty() {
+        if let Some(requested) = request.headers().get(ACCESS_CONTROL_REQUEST_HEADERS) {
+            let _ = response_headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, requested.clone());
+        }
+    } else if let Ok(allowed) = HeaderValue::from_str(&cors.allowed_headers().join(", ")) {
+        let _ = response_headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allowed);
+    }
+
+    if let Some(max_age_secs) = cors.max_age_secs() {
+        let _ = response_headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(*max_age_secs));
+    }
+
+    Some(response)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{headers, preflight};
+    use crate::config::Cors;
+    use http::Request;
+
+    fn request(method: &str, origin: &str) -> Request<Vec<u8>> {
+        Request::builder()
+            .method(method)
+            .uri("/orders")
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "POST")
+            .header("Access-Control-Request-Headers", "Content-Type")
+            .body(vec![])
+            .expect("Unable to build request!")
+    }
+
+    #[test]
+    fn preflight_response() {
+        let cors = Cors::builder()
+            .allowed_origin("http://localhost:3000")
+            .allow_credentials(true)
+            .max_age_secs(600)
+            .build();
+
+        match preflight(&cors, &request("OPTIONS", "http://localhost:3000")) {
+            Some(response) => {
+                assert_eq!(response.status(), 204);
+                let headers = response.headers();
+                assert_eq!(
+                    headers["access-control-allow-origin"],
+                    "http://localhost:3000"
+                );
+                assert_eq!(headers["access-control-allow-credentials"], "true");
+                assert_eq!(headers["access-control-allow-headers"], "Content-Type");
+                assert_eq!(headers["access-control-max-age"], "600");
+                assert!(headers.contains_key("access-control-allow-methods"));
+            }
+            None => assert!(false, "Expected a preflight response"),
+        }
+
+        assert!(preflight(&cors, &request("OPTIONS", "http://evil.example.com")).is_none());
+        assert!(preflight(&cors, &request("GET", "http://localhost:3000")).is_none());
+    }
+
+    #[test]
+    fn response_headers() {
+        let cors = Cors::builder().exposed_header("X-Total-Count").build();
+        let headers = headers(&cors, &request("GET", "http://localhost:3000"));
+        assert_eq!(headers["access-control-allow-origin"], "*");
+        assert_eq!(headers["access-control-expose-headers"], "X-Total-Count");
+
+        let request = Request::get("/orders")
+            .body(vec![])
+            .expect("Unable to build request!");
+        assert!(super::headers(&cors, &request).is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response body compression.
+use crate::config::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use std::io::{self, Write};
+
+/// The brotli quality, trading compression ratio for speed.
+const BROTLI_QUALITY: u32 = 5;
+/// The brotli window size, as a power of two.
+const BROTLI_WINDOW: u32 = 22;
+
+/// Compress a response body.  `Auto` must be negotiated first, and leaves the body as is.
+crate fn compress(compression: Compression, body: &[u8]) -> io::Result<Vec<u8>> {
+    match compression {
+        Compression::Auto => Ok(body.to_vec()),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        Compression::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        Compression::Brotli => {
+            let mut encoder =
+                brotli::CompressorWriter::new(Vec::new(), 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+            encoder.write_all(body)?;
+            Ok(encoder.into_inner())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::compress;
+    use crate::config::Compression;
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use std::io::Read;
+
+    const BODY: &[u8] = b"{\"orders\": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]}";
+
+    fn round_trip<R: Read>(mut decoder: R) -> Vec<u8> {
+        let mut decompressed = vec![];
+        if let Err(e) = decoder.read_to_end(&mut decompressed) {
+            assert!(false, e.to_string());
+        }
+        decompressed
+    }
+
+    #[test]
+    fn gzip() {
+        match compress(Compression::Gzip, BODY) {
+            Ok(compressed) => assert_eq!(round_trip(GzDecoder::new(&compressed[..])), BODY),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn deflate() {
+        match compress(Compression::Deflate, BODY) {
+            Ok(compressed) => assert_eq!(round_trip(ZlibDecoder::new(&compressed[..])), BODY),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn brotli() {
+        match compress(Compression::Brotli, BODY) {
+            Ok(compressed) => assert_eq!(
+                round_trip(brotli::Decompressor::new(&compressed[..], 4096)),
+                BODY
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Entity tags for conditional requests.
+
+/// A strong entity tag for a response body, i.e. `"5d41402abc4b2a76b9719d911017c592"`.
+crate fn compute(body: &[u8]) -> String {
+    format!("\"{:x}\"", md5::compute(body))
+}
+
+/// Does an `If-None-Match` header match the given entity tag?  Tags are compared weakly, so
+/// `W/"abc"` matches `"abc"`, and `*` matches any tag.
+crate fn matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+
+    if_none_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compute, matches};
+
+    #[test]
+    fn etag() {
+        let etag = compute(b"hello");
+        assert_eq!(etag, "\"5d41402abc4b2a76b9719d911017c592\"");
+        assert_ne!(etag, compute(b"hello world"));
+    }
+
+    #[test]
+    fn if_none_match() {
+        let etag = "\"abc\"";
+        assert!(matches("\"abc\"", etag));
+        assert!(matches("W/\"abc\"", etag));
+        assert!(matches("\"xyz\", \"abc\"", etag));
+        assert!(matches("*", etag));
+        assert!(!matches("\"xyz\"", etag));
+        assert!(!matches("abc", etag));
+    }
+}
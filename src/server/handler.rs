@@ -7,32 +7,70 @@
 // modified, or distributed except according to those terms.
 
 //! Request/Response handling for the async runtime.
-use crate::config;
+use crate::config::header_rewrite::rewrite;
+use crate::config::variant;
+use crate::config::{self, Compression, HeaderRewrite};
 use crate::error::Error;
-use crate::matcher::{Enabled, Matcher};
-use crate::server::codec;
+use crate::matcher::{Enabled, Matcher, NearMiss, Scenarios, Stats};
+use crate::server::body_url;
+use crate::server::charset;
+use crate::server::codec::{self, Chunked, Frame, Inbound, Message, DEFAULT_CHUNK_SIZE};
+use crate::server::cors;
+use crate::server::encoding;
+use crate::server::etag;
 use crate::server::header;
+use crate::server::http2;
+use crate::server::mime;
+use crate::server::multipart;
+use crate::server::rate_limiter::RateLimiter;
+use crate::server::recorder::Recording;
+use crate::server::response_cache::ResponseCache;
+use crate::server::sequences::Sequences;
+use crate::server::server_handle::{InFlight, ServerHandle};
+use crate::server::tls;
+use crate::server::transformer;
+use crate::server::webhook::PendingWebhook;
+use crate::server::websocket::Session;
+use crate::template;
 use crate::util::{self, FutResponse};
+use crate::x509::ClientCertificate;
+use bytes::Bytes;
 use cached::{cached_key_result, UnboundCache};
-use futures::{future, Future, Sink, Stream};
-use http::{Request, Response, StatusCode};
+use futures::sync::oneshot;
+use futures::{future, stream, Future, Sink, Stream};
+use http::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_ENCODING, CACHE_CONTROL, CONNECTION,
+    CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, ETAG, HOST, IF_NONE_MATCH, LOCATION,
+    RETRY_AFTER, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, TRAILER, UPGRADE, VARY,
+};
+use http::uri::{PathAndQuery, Scheme};
+use http::{Request, Response, StatusCode, Uri};
 use hyper::client::HttpConnector;
 use hyper::{Client, Request as HyperRequest};
 use hyper_proxy::{Intercept, Proxy, ProxyConnector};
 use hyper_tls::HttpsConnector;
+use serde_json::json;
 use slog::Logger;
 use slog::{error, info, trace};
 use slog_try::{try_error, try_info, try_trace};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, BufReader, ErrorKind, Read};
+use std::iter;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::await;
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
 use tokio::prelude::FutureExt;
-use tokio_codec::Decoder;
+use tokio::timer::Delay;
+use tokio_codec::{BytesCodec, Decoder, FramedRead};
+use tokio_rustls::TlsAcceptor;
 use typed_headers::Credentials;
+use uuid::Uuid;
 
 /// Request/Response handler for the async runtime.
 #[derive(Clone, Debug)]
@@ -44,6 +82,24 @@ pub struct Handler {
     enabled: Enabled,
     static_mappings: config::Mappings,
     dynamic_mappings: Arc<Mutex<config::Mappings>>,
+    scenarios: Arc<Mutex<Scenarios>>,
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+    sequences: Arc<Mutex<Sequences>>,
+    response_cache: Arc<Mutex<ResponseCache>>,
+    stats: Arc<Mutex<Stats>>,
+    default_response: Option<config::DefaultResponse>,
+    strict: Option<config::Strict>,
+    parallel_threshold: Option<usize>,
+    matcher_order: Vec<String>,
+    record_path: Option<PathBuf>,
+    mime_types: BTreeMap<String, String>,
+    cors: Option<config::Cors>,
+    virtual_hosts: Vec<(config::VirtualHost, Handler)>,
+    in_flight: Arc<AtomicUsize>,
+    tls: bool,
+    peer_addr: Option<SocketAddr>,
+    client_cert: Option<ClientCertificate>,
+    server_name: Option<String>,
 }
 
 impl Handler {
@@ -62,9 +118,79 @@ impl Handler {
             enabled,
             static_mappings,
             dynamic_mappings: Arc::new(Mutex::new(config::Mappings::default())),
+            scenarios: Arc::new(Mutex::new(Scenarios::default())),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::default())),
+            sequences: Arc::new(Mutex::new(Sequences::default())),
+            response_cache: Arc::new(Mutex::new(ResponseCache::default())),
+            stats: Arc::new(Mutex::new(Stats::default())),
+            default_response: None,
+            strict: None,
+            parallel_threshold: None,
+            matcher_order: vec![],
+            record_path: None,
+            mime_types: BTreeMap::new(),
+            cors: None,
+            virtual_hosts: vec![],
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            tls: false,
+            peer_addr: None,
+            client_cert: None,
+            server_name: None,
         }
     }
 
+    /// Set the response sent when no mapping matches a request.
+    pub fn default_response(mut self, default_response: Option<config::DefaultResponse>) -> Self {
+        self.default_response = default_response;
+        self
+    }
+
+    /// Set the strict matching used for mappings that don't configure their own.
+    pub fn strict(mut self, strict: Option<config::Strict>) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Evaluate the mappings for a request in parallel once there are at least this many.
+    pub fn parallel_threshold(mut self, parallel_threshold: Option<usize>) -> Self {
+        self.parallel_threshold = parallel_threshold;
+        self
+    }
+
+    /// Check the named request matchers first, in the given order.
+    pub fn matcher_order(mut self, matcher_order: Vec<String>) -> Self {
+        self.matcher_order = matcher_order;
+        self
+    }
+
+    /// Save every proxied request, with the upstream response, as a new mapping in the given
+    /// directory.  Mapping files are written to `mappings` and response bodies to `files`, so the
+    /// directory can be served as is.
+    pub fn record_path(mut self, record_path: Option<PathBuf>) -> Self {
+        self.record_path = record_path;
+        self
+    }
+
+    /// Content types for body files, by file extension, checked before the built in types when
+    /// a response doesn't set its own `Content-Type`.
+    pub fn mime_types(mut self, mime_types: BTreeMap<String, String>) -> Self {
+        self.mime_types = mime_types;
+        self
+    }
+
+    /// Answer CORS preflight requests, and add the CORS headers to every response.
+    pub fn cors(mut self, cors: Option<config::Cors>) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Answer the requests for a virtual host with another handler, which has its own mappings
+    /// and state.  Virtual hosts are checked in the order they are added.
+    pub fn virtual_host(mut self, virtual_host: config::VirtualHost, handler: Handler) -> Self {
+        self.virtual_hosts.push((virtual_host, handler));
+        self
+    }
+
     /// Add a stdout slog logger to this handler.
     pub fn stdout(mut self, stdout: Option<Logger>) -> Self {
         self.stdout = stdout;
@@ -76,25 +202,92 @@ impl Handler {
         self.stderr = stderr;
         self
     }
+
+    /// A snapshot of the request matching statistics, per request matcher and per mapping, for
+    /// every request handled so far.
+    pub fn stats(&self) -> Stats {
+        match self.stats.lock() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Clear the request matching statistics.
+    pub fn reset_stats(&self) {
+        match self.stats.lock() {
+            Ok(mut guard) => guard.reset(),
+            Err(poisoned) => poisoned.into_inner().reset(),
+        }
+    }
+
+    /// Start the response sequence of every mapping again from its first response.
+    pub fn reset_sequences(&self) {
+        match self.sequences.lock() {
+            Ok(mut guard) => guard.reset(),
+            Err(poisoned) => poisoned.into_inner().reset(),
+        }
+    }
+
+    /// Drop every cached proxy response, so the next request for each goes to the upstream.
+    pub fn clear_proxy_cache(&self) {
+        match self.response_cache.lock() {
+            Ok(mut guard) => guard.clear(),
+            Err(poisoned) => poisoned.into_inner().clear(),
+        }
+    }
 }
 
 /// Spawn a task onto the event loop to handle the request.
 #[allow(box_pointers)]
-pub fn handle(handler: Handler, stream: TcpStream) {
-    // Frame the socket using the `Http` protocol. This maps the TCP socket
-    // to a Stream + Sink of HTTP frames.
+pub fn handle<S>(handler: Handler, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    // Frame the socket using the `Connection` protocol. This maps the TCP socket
+    // to a Stream + Sink of HTTP frames, or WebSocket messages once upgraded.
     // This splits a single `Stream + Sink` value into two separate handles
     // that can be used independently (even on different tasks or threads).
-    let (tx, rx) = codec::Http.framed(stream).split();
+    let (tx, rx) = codec::Connection::default().framed(stream).split();
+    let session: Arc<Mutex<Option<Session>>> = Arc::new(Mutex::new(None));
 
     // Clone all the things....
     let response_stderr_1 = handler.stderr.clone();
 
     // Map all requests into responses and send them back to the client.
     let task = tx
-        .send_all(rx.and_then(move |req| {
-            respond(handler.clone(), &req).map_err(|e| io::Error::new(ErrorKind::Other, e))
-        }))
+        .send_all(
+            rx.map(move |inbound| -> FutFrames {
+                let mut req = match inbound {
+                    Inbound::Request(req) => req,
+                    Inbound::Message(message) => {
+                        let messages = match session.lock() {
+                            Ok(mut guard) => guard.as_mut().map(|s| s.receive(&message)),
+                            Err(poisoned) => {
+                                poisoned.into_inner().as_mut().map(|s| s.receive(&message))
+                            }
+                        };
+                        return websocket_frames(messages.unwrap_or_default());
+                    }
+                    Inbound::Rejected(status) => return rejected_frames(status),
+                };
+
+                connection_details(&handler, &mut req);
+                let session = session.clone();
+                // The request is in flight until its last frame, i.e. a dribbled one, is sent.
+                let in_flight = InFlight::start(&handler.in_flight);
+                Box::new(
+                    in_flight.hold(
+                        respond(handler.clone(), &req)
+                            .map_err(|e| io::Error::new(ErrorKind::Other, e))
+                            .map(move |response| upgrade_frames(response, &session))
+                            .flatten_stream(),
+                    ),
+                )
+            })
+            .flatten()
+            // Stopping closes the connection, once everything before the fault has been sent.
+            .take_while(|frame| Ok(!frame.is_close())),
+        )
         .then(move |res| {
             if let Err(e) = res {
                 try_error!(response_stderr_1, "failed to process the request: {}", e);
@@ -107,15 +300,162 @@ pub fn handle(handler: Handler, stream: TcpStream) {
     let _ = tokio::spawn(task);
 }
 
+/// Spawn a task onto the event loop to handle an HTTP/2 connection.  Each stream is answered
+/// on its own task.
+///
+/// Response bodies are sent whole, so streamed body files, events, dribbles and faults only
+/// behave as configured over HTTP/1.1.
+#[allow(box_pointers)]
+pub fn handle_h2<S>(handler: Handler, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let connection_stderr = handler.stderr.clone();
+
+    let task = h2::server::handshake(stream)
+        .and_then(move |connection| {
+            connection.for_each(move |(request, mut send)| {
+                let handler = handler.clone();
+                let stream_stderr = handler.stderr.clone();
+                let (parts, body) = request.into_parts();
+                let in_flight = InFlight::start(&handler.in_flight);
+
+                let task = http2::read_body(body)
+                    .map_err(|e| e.to_string())
+                    .and_then(move |body| {
+                        let mut req = Request::from_parts(parts, body);
+                        connection_details(&handler, &mut req);
+                        respond(handler, &req)
+                    })
+                    .and_then(move |response| {
+                        let (response, trailers) = h2_response(response);
+                        http2::send_response(&mut send, response, trailers)
+                            .map_err(|e| e.to_string())
+                    })
+                    .then(move |result| {
+                        drop(in_flight);
+                        result
+                    })
+                    .map_err(move |e| {
+                        try_error!(stream_stderr, "failed to process the request: {}", e)
+                    });
+                let _ = tokio::spawn(task);
+                Ok(())
+            })
+        })
+        .map_err(move |e| try_error!(connection_stderr, "HTTP/2 connection failed: {}", e));
+
+    let _ = tokio::spawn(task);
+}
+
+/// Record what the server knows about the connection in the request extensions, for the
+/// matchers, templates and [`RequestExt`](crate::matcher::RequestExt) to read.
+fn connection_details(handler: &Handler, request: &mut Request<Vec<u8>>) {
+    let _ = request.extensions_mut().insert(if handler.tls {
+        Scheme::HTTPS
+    } else {
+        Scheme::HTTP
+    });
+    if let Some(peer_addr) = handler.peer_addr {
+        let _ = request.extensions_mut().insert(peer_addr);
+    }
+    if let Some(client_cert) = &handler.client_cert {
+        let _ = request.extensions_mut().insert(client_cert.clone());
+    }
+    if let Some(server_name) = &handler.server_name {
+        let _ = request.extensions_mut().insert(util::ServerName {
+            name: server_name.clone(),
+        });
+    }
+}
+
+/// Respond to a request, handing it to the handler of its virtual host if it has one, and
+/// handling CORS first if it is configured.
+#[allow(box_pointers)]
+fn respond(handler: Handler, request: &Request<Vec<u8>>) -> FutResponse {
+    let host = request.uri().host().map(str::to_string).or_else(|| {
+        request
+            .headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(str::to_string)
+    });
+    if let Some((virtual_host, virtual_handler)) =
+        handler.virtual_hosts.iter().find(|(virtual_host, _)| {
+            virtual_host.matches(host.as_ref().map(String::as_str), request.uri().path())
+        })
+    {
+        try_trace!(
+            handler.stdout,
+            "Sending the request to virtual host '{}'",
+            virtual_host.mappings_path()
+        );
+        let virtual_handler = Handler {
+            tls: handler.tls,
+            peer_addr: handler.peer_addr,
+            client_cert: handler.client_cert.clone(),
+            server_name: handler.server_name.clone(),
+            ..virtual_handler.clone()
+        };
+        return match virtual_request(virtual_host, request) {
+            Ok(request) => respond(virtual_handler, &request),
+            Err(e) => util::error_response_fut(e, StatusCode::INTERNAL_SERVER_ERROR),
+        };
+    }
+
+    if let Some(cors_config) = handler.cors.clone() {
+        if let Some(response) = cors::preflight(&cors_config, request) {
+            try_trace!(handler.stdout, "Answered CORS preflight request");
+            return Box::new(future::ok(response));
+        }
+
+        let headers = cors::headers(&cors_config, request);
+        Box::new(match_response(handler, request).map(move |mut response| {
+            response.headers_mut().extend(headers);
+            response
+        }))
+    } else {
+        match_response(handler, request)
+    }
+}
+
+/// A copy of a request for a virtual host, with the virtual host's path prefix removed.
+fn virtual_request(
+    virtual_host: &config::VirtualHost,
+    request: &Request<Vec<u8>>,
+) -> Result<Request<Vec<u8>>, String> {
+    let mut copy = transformer::copy_request(request);
+    if let Some(path) = virtual_host.strip_prefix(request.uri().path()) {
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path.to_string(),
+        };
+        let mut parts = request.uri().clone().into_parts();
+        parts.path_and_query = Some(
+            path_and_query
+                .parse::<PathAndQuery>()
+                .map_err(|e| e.to_string())?,
+        );
+        *copy.uri_mut() = Uri::from_parts(parts).map_err(|e| e.to_string())?;
+    }
+    Ok(copy)
+}
+
 #[allow(box_pointers)]
-fn respond(handler: Handler, request: &Request<()>) -> FutResponse {
+fn match_response(handler: Handler, request: &Request<Vec<u8>>) -> FutResponse {
     let matcher = Matcher::new(
         handler.enabled,
         handler.stdout.clone(),
         handler.stderr.clone(),
-    );
+    )
+    .set_strict(handler.strict.clone())
+    .set_parallel_threshold(handler.parallel_threshold)
+    .set_matcher_order(&handler.matcher_order)
+    .set_stats(Some(handler.stats.clone()));
 
-    if let Ok(mapping) = matcher.get_match(&request, &handler.static_mappings) {
+    if let Ok((id, mapping)) =
+        match_in_state(&handler, &matcher, &request, &handler.static_mappings)
+    {
         try_trace!(handler.stdout, "");
         try_trace!(
             handler.stdout,
@@ -123,7 +463,7 @@ fn respond(handler: Handler, request: &Request<()>) -> FutResponse {
             format!(" Matched '{}' ", mapping.name()),
             80
         );
-        http_response(handler, &request, mapping.response())
+        matched_response(handler, &request, id, &mapping)
     } else {
         let dynamic_mappings = handler.dynamic_mappings.clone();
         let locked_dynamic_mappings = match dynamic_mappings.lock() {
@@ -131,7 +471,9 @@ fn respond(handler: Handler, request: &Request<()>) -> FutResponse {
             Err(poisoned) => poisoned.into_inner(),
         };
 
-        if let Ok(mapping) = matcher.get_match(&request, &locked_dynamic_mappings) {
+        if let Ok((id, mapping)) =
+            match_in_state(&handler, &matcher, &request, &locked_dynamic_mappings)
+        {
             try_trace!(handler.stdout, "");
             try_trace!(
                 handler.stdout,
@@ -139,7 +481,20 @@ fn respond(handler: Handler, request: &Request<()>) -> FutResponse {
                 format!(" Matched '{}' ", mapping.name()),
                 80
             );
-            http_response(handler, &request, mapping.response())
+            matched_response(handler, &request, id, &mapping)
+        } else if let Some(default_response) = &handler.default_response {
+            try_error!(
+                handler.stderr,
+                "No mapping found, sending the default response"
+            );
+            let near_misses = if *default_response.near_misses() {
+                let mut near_misses = matcher.near_misses(&request, &handler.static_mappings);
+                near_misses.extend(matcher.near_misses(&request, &locked_dynamic_mappings));
+                Some(near_misses)
+            } else {
+                None
+            };
+            unmatched_response(default_response, near_misses)
         } else {
             try_error!(handler.stderr, "No mapping found");
             util::error_response_fut("No mapping found".to_string(), StatusCode::NOT_FOUND)
@@ -147,20 +502,350 @@ fn respond(handler: Handler, request: &Request<()>) -> FutResponse {
     }
 }
 
+/// Match a request against a snapshot of the scenario states, so requests are matched
+/// concurrently rather than one at a time under the scenarios lock.  A match in a scenario is
+/// checked again, and its scenario moved on, under the lock.  If another request moved the
+/// scenario on first, the request is matched again.
+fn match_in_state(
+    handler: &Handler,
+    matcher: &Matcher,
+    request: &Request<Vec<u8>>,
+    mappings: &config::Mappings,
+) -> Result<(Uuid, config::Mapping), Error> {
+    loop {
+        let snapshot = match handler.scenarios.lock() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        let (id, mapping) = matcher.get_match_in_state(request, mappings, &snapshot)?;
+
+        if mapping.scenario().is_none() {
+            return Ok((id, mapping));
+        }
+
+        let mut scenarios = match handler.scenarios.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if scenarios.allows(&mapping) {
+            scenarios.transition(&mapping);
+            return Ok((id, mapping));
+        }
+    }
+}
+
+#[allow(box_pointers)]
+fn matched_response(
+    handler: Handler,
+    request: &Request<Vec<u8>>,
+    id: Uuid,
+    mapping: &config::Mapping,
+) -> FutResponse {
+    if let Some(metadata) = mapping.metadata() {
+        try_info!(
+            handler.stdout,
+            "Matched '{}', metadata: {}",
+            mapping.name(),
+            metadata
+        );
+    }
+
+    if let Some(rate_limit) = mapping.rate_limit() {
+        let rate_limiter = handler.rate_limiter.clone();
+        let mut locked_rate_limiter = match rate_limiter.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if let Err(retry_after) = locked_rate_limiter.check(id, rate_limit, Instant::now()) {
+            try_info!(
+                handler.stdout,
+                "Rate limit reached for '{}', retry after {:?}",
+                mapping.name(),
+                retry_after
+            );
+            return if let Some(response) = rate_limit.response() {
+                http_response(handler, request, response)
+            } else {
+                let mut response = util::error_response(
+                    "Rate limit exceeded".to_string(),
+                    StatusCode::TOO_MANY_REQUESTS,
+                );
+                // Round up, so clients never retry early.
+                let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                let _ = response.headers_mut().insert(RETRY_AFTER, seconds.into());
+                Box::new(future::ok(response))
+            };
+        }
+    }
+
+    let mut webhooks = Vec::new();
+    for webhook in mapping.webhooks() {
+        match PendingWebhook::new(webhook, request) {
+            Ok(pending) => webhooks.push(pending),
+            Err(e) => try_error!(handler.stderr, "Unable to render webhook: {}", e),
+        }
+    }
+    let stdout = handler.stdout.clone();
+    let stderr = handler.stderr.clone();
+
+    let response = if let Some(websocket) = mapping.websocket() {
+        websocket_response(request, websocket)
+    } else if mapping.sequence().is_empty() {
+        http_response(handler, request, mapping.pick_response())
+    } else {
+        let call = match handler.sequences.lock() {
+            Ok(mut guard) => guard.next(id),
+            Err(poisoned) => poisoned.into_inner().next(id),
+        };
+        try_trace!(
+            handler.stdout,
+            "Sending response {} of the sequence for '{}'",
+            call + 1,
+            mapping.name()
+        );
+        http_response(handler, request, mapping.nth_response(call))
+    };
+
+    if webhooks.is_empty() {
+        response
+    } else {
+        Box::new(response.map(move |response| {
+            for webhook in webhooks {
+                let _ = tokio::spawn(webhook.send(stdout.clone(), stderr.clone()));
+            }
+            response
+        }))
+    }
+}
+
+/// Accept a WebSocket upgrade, with the session that runs the script in the response
+/// extensions, or tell the client to upgrade if the request isn't one.
+#[allow(box_pointers)]
+fn websocket_response(request: &Request<Vec<u8>>, websocket: &config::WebSocket) -> FutResponse {
+    let upgrade = request
+        .headers()
+        .get(UPGRADE)
+        .and_then(|upgrade| upgrade.to_str().ok())
+        .map_or(false, |upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+    let key = request
+        .headers()
+        .get(SEC_WEBSOCKET_KEY)
+        .and_then(|key| key.to_str().ok());
+
+    let response = match key {
+        Some(key) if upgrade => Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(UPGRADE, "websocket")
+            .header(CONNECTION, "Upgrade")
+            .header(SEC_WEBSOCKET_ACCEPT, codec::websocket::accept_key(key))
+            .extension(Session::new(websocket))
+            .body(vec![]),
+        _ => Response::builder()
+            .status(StatusCode::UPGRADE_REQUIRED)
+            .header(UPGRADE, "websocket")
+            .header(CONNECTION, "Upgrade")
+            .body(b"WebSocket upgrade required".to_vec()),
+    };
+
+    match response {
+        Ok(response) => Box::new(future::ok(response)),
+        Err(e) => util::error_response_fut(format!("{}", e), StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[allow(box_pointers)]
+fn unmatched_response(
+    default_response: &config::DefaultResponse,
+    near_misses: Option<Vec<NearMiss>>,
+) -> FutResponse {
+    let mut response_builder = Response::builder();
+    for header in default_response.headers() {
+        let _ = response_builder.header(&header.key()[..], &header.value()[..]);
+    }
+
+    let status = default_response
+        .status()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::NOT_FOUND);
+    let _ = response_builder.status(status);
+
+    let message = default_response
+        .body()
+        .clone()
+        .unwrap_or_else(|| "No mapping found".to_string());
+    let body = if let Some(near_misses) = near_misses {
+        if default_response.headers().is_empty() {
+            let _ = response_builder.header(CONTENT_TYPE, "application/json");
+        }
+        match serde_json::to_vec(&json!({ "message": message, "near_misses": near_misses })) {
+            Ok(body) => body,
+            Err(e) => return util::error_response_fut(e.to_string(), status),
+        }
+    } else {
+        message.into_bytes()
+    };
+
+    match response_builder.body(body) {
+        Ok(response) => Box::new(future::ok(response)),
+        Err(e) => util::error_response_fut(format!("{}", e), StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
 #[allow(box_pointers)]
 fn http_response(
     handler: Handler,
-    request: &Request<()>,
+    request: &Request<Vec<u8>>,
+    response_config: &config::Response,
+) -> FutResponse {
+    if let Some(redirect_chain) = response_config.redirect_chain() {
+        let hop = redirect_chain.hop(request);
+        if let Some(next) = redirect_chain.next(hop) {
+            try_trace!(
+                handler.stdout,
+                "Redirecting from hop {} to hop {}",
+                hop,
+                next
+            );
+            return redirect_response(request, redirect_chain, hop, next);
+        }
+    }
+
+    let stdout = handler.stdout.clone();
+    let stderr = handler.stderr.clone();
+    let response = generate_response(handler, request, response_config);
+    let response: FutResponse = if response_config.transformers().is_empty() {
+        response
+    } else {
+        let names = response_config.transformers().clone();
+        let request = transformer::copy_request(request);
+        Box::new(response.map(move |response| {
+            match transformer::apply(&names, &request, response) {
+                Ok(response) => response,
+                Err(e) => {
+                    try_error!(stderr, "Unable to transform the response: {}", e);
+                    util::error_response(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            }
+        }))
+    };
+
+    if let Some(delay_ms) = response_config.delay_ms() {
+        try_trace!(stdout, "Delaying response by {}ms", delay_ms);
+        Box::new(
+            Delay::new(Instant::now() + Duration::from_millis(*delay_ms))
+                .map_err(|e| e.to_string())
+                .and_then(move |_| response),
+        )
+    } else {
+        response
+    }
+}
+
+/// A redirect from `hop` of a redirect chain to `next`.
+#[allow(box_pointers)]
+fn redirect_response(
+    request: &Request<Vec<u8>>,
+    redirect_chain: &config::RedirectChain,
+    hop: u32,
+    next: u32,
+) -> FutResponse {
+    let location = match redirect_chain.location() {
+        Some(location) => {
+            let redirect = json!({
+                "hop": hop,
+                "next": next,
+                "param": redirect_chain.param_name(),
+            });
+            match template::render_with(location, request, "redirect", redirect) {
+                Ok(location) => location,
+                Err(e) => {
+                    return util::error_response_fut(
+                        e.to_string(),
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    );
+                }
+            }
+        }
+        None => redirect_chain.default_location(request, next),
+    };
+    let status = redirect_chain
+        .status()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::FOUND);
+
+    match Response::builder()
+        .status(status)
+        .header(LOCATION, &location[..])
+        .body(vec![])
+    {
+        Ok(response) => Box::new(future::ok(response)),
+        Err(e) => util::error_response_fut(format!("{}", e), StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[allow(box_pointers)]
+fn generate_response(
+    handler: Handler,
+    request: &Request<Vec<u8>>,
     response_config: &config::Response,
 ) -> FutResponse {
     if let Some(proxy_base_url) = response_config.proxy_base_url() {
         let full_url = format!("{}{}", proxy_base_url, request.uri());
+        let cache_key = response_config
+            .proxy_cache()
+            .as_ref()
+            .and_then(|proxy_cache| ResponseCache::key(request, &full_url, proxy_cache));
+
+        if let Some(key) = &cache_key {
+            let cached = match handler.response_cache.lock() {
+                Ok(mut guard) => guard.get(key, Instant::now()),
+                Err(poisoned) => poisoned.into_inner().get(key, Instant::now()),
+            };
+
+            if let Some(response) = cached {
+                try_trace!(
+                    handler.stdout,
+                    "Sending the cached response for {}",
+                    full_url
+                );
+                return Box::new(future::ok(response));
+            }
+        }
+
+        let cache_ttl = response_config
+            .proxy_cache()
+            .as_ref()
+            .map(|proxy_cache| Duration::from_millis(*proxy_cache.ttl_ms()));
+        let response_cache = handler.response_cache.clone();
         let (tx, rx) = futures::sync::mpsc::unbounded();
-        let headers = response_config.additional_proxy_request_headers().clone();
-        let proxy_config = handler.proxy_config.clone();
+        let headers = match proxy_request_headers(request, response_config) {
+            Ok(headers) => headers,
+            Err(e) => {
+                try_error!(handler.stderr, "Unable to build the proxy request: {}", e);
+                return util::error_response_fut(e.to_string(), StatusCode::BAD_GATEWAY);
+            }
+        };
+        let response_rewrites = response_config.proxy_response_rewrites().clone();
+        let stdout = handler.stdout.clone();
+        let stderr = handler.stderr.clone();
+        let proxy_config = response_config
+            .proxy()
+            .clone()
+            .unwrap_or_else(|| handler.proxy_config.clone());
+        let bypass_proxy = full_url
+            .parse::<Uri>()
+            .ok()
+            .and_then(|uri| uri.host().map(|host| proxy_config.bypass(host)))
+            .unwrap_or(false);
+        let proxy_url = proxy_config.proxy_url_for(&full_url).cloned();
+        let recording = handler
+            .record_path
+            .as_ref()
+            .map(|record_path| Recording::new(record_path, request));
         tokio::spawn_async(async move {
-            if *proxy_config.use_proxy() {
-                if let Some(url_str) = proxy_config.proxy_url() {
+            if *proxy_config.use_proxy() && !bypass_proxy {
+                if let Some(url_str) = proxy_url {
                     let proxy_uri = url_str.parse().expect("Unable to parse proxy URI");
                     let mut proxy = Proxy::new(Intercept::All, proxy_uri);
                     if let Some(username) = proxy_config.proxy_username() {
@@ -218,20 +903,55 @@ fn http_response(
         });
 
         Box::new(
-            rx.fold(String::new(), |mut buffer, res| {
-                match res {
-                    Ok(val) => buffer.push_str(&val),
-                    Err(e) => buffer.push_str(&e.to_string()),
-                }
-                futures::future::ok(buffer)
-            })
-            .map_err(|_| "Error processing upstream response".to_string())
-            .map(Response::new),
+            rx.into_future()
+                .map_err(|_| "Error processing upstream response".to_string())
+                .map(move |(upstream, _)| match upstream {
+                    Some(Ok(upstream)) => {
+                        let response = proxied_response(
+                            upstream,
+                            &response_rewrites,
+                            recording,
+                            &stdout,
+                            &stderr,
+                        );
+
+                        if let (Some(key), Some(ttl)) = (cache_key, cache_ttl) {
+                            match response_cache.lock() {
+                                Ok(mut guard) => guard.insert(key, &response, ttl, Instant::now()),
+                                Err(poisoned) => poisoned.into_inner().insert(
+                                    key,
+                                    &response,
+                                    ttl,
+                                    Instant::now(),
+                                ),
+                            }
+                        }
+                        response
+                    }
+                    Some(Err(e)) => Response::new(e.into_bytes()),
+                    None => Response::new(vec![]),
+                }),
         )
     } else {
+        let templated = *response_config.templated();
         let mut response_builder = Response::builder();
         for header in response_config.headers() {
-            let _ = response_builder.header(&header.key()[..], &header.value()[..]);
+            if templated {
+                match template::render(header.value(), request) {
+                    Ok(value) => {
+                        let _ = response_builder.header(&header.key()[..], &value[..]);
+                    }
+                    Err(e) => {
+                        try_error!(handler.stderr, "Unable to render header template: {}", e);
+                        return util::error_response_fut(
+                            e.to_string(),
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                        );
+                    }
+                }
+            } else {
+                let _ = response_builder.header(&header.key()[..], &header.value()[..]);
+            }
         }
 
         if let Some(status) = response_config.status() {
@@ -244,15 +964,247 @@ fn http_response(
             let _ = response_builder.status(StatusCode::OK);
         }
 
-        let body = if let Some(body_file_name) = response_config.body_file_name() {
-            match load(handler.files_path, body_file_name) {
+        let echo = *response_config.echo();
+        let has_content_type = response_config
+            .headers()
+            .iter()
+            .any(|header| header.key().eq_ignore_ascii_case(CONTENT_TYPE.as_str()));
+        let negotiated = if response_config.variants().is_empty() {
+            None
+        } else {
+            let _ = response_builder.header(VARY, ACCEPT.as_str());
+            variant::negotiate(
+                response_config.variants(),
+                request
+                    .headers()
+                    .get(ACCEPT)
+                    .and_then(|value| value.to_str().ok()),
+            )
+        };
+        let body = if let Some(negotiated) = negotiated {
+            let _ = response_builder.header(CONTENT_TYPE, &negotiated.media_type()[..]);
+            match (negotiated.body(), negotiated.body_file_name()) {
+                (Some(body), _) => body.clone().into_bytes(),
+                (None, Some(body_file_name)) => match load(handler.files_path, body_file_name) {
+                    Ok(body) => body,
+                    Err(e) => e.as_bytes().to_vec(),
+                },
+                (None, None) => b"Unable to process body".to_vec(),
+            }
+        } else if echo {
+            if !has_content_type {
+                let _ = response_builder.header(CONTENT_TYPE, "application/json");
+            }
+            match serde_json::to_vec(&template::request_json(request)) {
                 Ok(body) => body,
-                Err(e) => e.to_string(),
+                Err(e) => {
+                    return util::error_response_fut(
+                        e.to_string(),
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    );
+                }
+            }
+        } else if let Some(body) = response_config.body() {
+            body.clone().into_bytes()
+        } else if let Some(body_base64) = response_config.body_base64() {
+            body_base64.to_vec()
+        } else if let Some(body_file_name) = response_config.body_file_name() {
+            if !has_content_type {
+                if let Some(content_type) = mime::content_type(body_file_name, &handler.mime_types)
+                {
+                    let _ = response_builder.header(CONTENT_TYPE, content_type);
+                }
+            }
+            if *response_config.stream() {
+                match body_file(&handler.files_path, body_file_name) {
+                    Ok(body_file) => {
+                        let _ = response_builder.extension(body_file);
+                        vec![]
+                    }
+                    Err(e) => e.as_bytes().to_vec(),
+                }
+            } else {
+                match load(handler.files_path, body_file_name) {
+                    Ok(body) => body,
+                    Err(e) => e.as_bytes().to_vec(),
+                }
+            }
+        } else if let Some(body_url) = response_config.body_url() {
+            match std::fs::read(body_url::cache_path(&handler.files_path, body_url)) {
+                Ok(body) => body,
+                Err(_) => b"Body url not fetched!".to_vec(),
+            }
+        } else if let Some(multipart) = response_config.multipart() {
+            let mut parts = Vec::new();
+            for part in multipart.parts() {
+                if let Some(body) = part.body() {
+                    parts.push((part, None, body.clone().into_bytes()));
+                } else if let Some(body_file_name) = part.body_file_name() {
+                    let body = match load(handler.files_path.clone(), body_file_name) {
+                        Ok(body) => body,
+                        Err(e) => e.as_bytes().to_vec(),
+                    };
+                    let content_type = mime::content_type(body_file_name, &handler.mime_types);
+                    parts.push((part, content_type, body));
+                }
+            }
+
+            let boundary = multipart::boundary();
+            let _ = response_builder.header(
+                CONTENT_TYPE,
+                &multipart::content_type(*multipart.kind(), &boundary)[..],
+            );
+            multipart::encode(*multipart.kind(), &boundary, &parts)
+        } else if !response_config.events().is_empty() {
+            if !has_content_type {
+                let _ = response_builder.header(CONTENT_TYPE, "text/event-stream");
+            }
+            let _ = response_builder.header(CACHE_CONTROL, "no-cache");
+
+            let mut events = Vec::new();
+            for event in response_config.events() {
+                let encoded = event.encode();
+                if templated {
+                    match template::render(&encoded, request) {
+                        Ok(rendered) => events.push((event.delay_ms().unwrap_or(0), rendered)),
+                        Err(e) => {
+                            try_error!(handler.stderr, "Unable to render event template: {}", e);
+                            return util::error_response_fut(
+                                e.to_string(),
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                            );
+                        }
+                    }
+                } else {
+                    events.push((event.delay_ms().unwrap_or(0), encoded));
+                }
+            }
+            let _ = response_builder.extension(EventStream { events });
+            vec![]
+        } else {
+            b"Unable to process body".to_vec()
+        };
+
+        let body = if templated && !echo && response_config.body_base64().is_none() {
+            match template::render(&String::from_utf8_lossy(&body), request) {
+                Ok(rendered) => rendered.into_bytes(),
+                Err(e) => {
+                    try_error!(handler.stderr, "Unable to render template: {}", e);
+                    return util::error_response_fut(
+                        e.to_string(),
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    );
+                }
+            }
+        } else {
+            body
+        };
+
+        let body = if let Some(charset) = response_config.charset() {
+            if let Some(headers) = response_builder.headers_mut() {
+                let content_type = charset::content_type(
+                    headers
+                        .get(CONTENT_TYPE)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or(charset::DEFAULT_CONTENT_TYPE),
+                    *charset,
+                );
+                if let Ok(value) = HeaderValue::from_str(&content_type) {
+                    let _ = headers.insert(CONTENT_TYPE, value);
+                }
+            }
+            charset::transcode(*charset, &body)
+        } else {
+            body
+        };
+
+        let body = if let Some(compression) = response_config.compression() {
+            let accept_encoding = request
+                .headers()
+                .get(ACCEPT_ENCODING)
+                .and_then(|value| value.to_str().ok());
+
+            if *compression == Compression::Auto {
+                let _ = response_builder.header(VARY, ACCEPT_ENCODING.as_str());
+            }
+
+            if let Some(compression) = compression.negotiate(accept_encoding) {
+                match encoding::compress(compression, &body) {
+                    Ok(compressed) => {
+                        if let Some(content_encoding) = compression.content_encoding() {
+                            let _ = response_builder.header(CONTENT_ENCODING, content_encoding);
+                        }
+                        compressed
+                    }
+                    Err(e) => {
+                        try_error!(handler.stderr, "Unable to compress the body: {}", e);
+                        return util::error_response_fut(
+                            e.to_string(),
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                        );
+                    }
+                }
+            } else {
+                body
             }
         } else {
-            "Unable to process body".to_string()
+            body
         };
 
+        if *response_config.etag() {
+            let etag = etag::compute(&body);
+            let not_modified = request
+                .headers()
+                .get(IF_NONE_MATCH)
+                .and_then(|value| value.to_str().ok())
+                .map_or(false, |if_none_match| etag::matches(if_none_match, &etag));
+            let _ = response_builder.header(ETAG, &etag[..]);
+
+            if not_modified {
+                try_trace!(handler.stdout, "ETag {} matched, sending 304", etag);
+                let _ = response_builder.status(StatusCode::NOT_MODIFIED);
+                return match response_builder.body(vec![]) {
+                    Ok(response) => Box::new(future::ok(response)),
+                    Err(e) => util::error_response_fut(
+                        format!("{}", e),
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    ),
+                };
+            }
+        }
+
+        if let Some(fault) = response_config.fault() {
+            let _ = response_builder.extension(*fault);
+        }
+
+        if let Some(dribble) = response_config.dribble() {
+            let _ = response_builder.extension(*dribble);
+        }
+
+        if *response_config.chunked() {
+            let mut trailers = HeaderMap::new();
+            for trailer in response_config.trailers() {
+                match (
+                    HeaderName::from_bytes(trailer.key().as_bytes()),
+                    HeaderValue::from_str(trailer.value()),
+                ) {
+                    (Ok(name), Ok(value)) => {
+                        let _ = trailers.append(name, value);
+                    }
+                    _ => try_error!(handler.stderr, "Invalid trailer: {}", trailer.key()),
+                }
+            }
+            if !trailers.is_empty() {
+                let names: Vec<&str> = trailers.keys().map(HeaderName::as_str).collect();
+                let _ = response_builder.header(TRAILER, &names.join(", ")[..]);
+            }
+
+            let _ = response_builder.extension(Chunked {
+                size: response_config.chunk_size().unwrap_or(DEFAULT_CHUNK_SIZE),
+                trailers,
+            });
+        }
+
         match response_builder.body(body) {
             Ok(response) => Box::new(future::ok(response)),
             Err(e) => util::error_response_fut(format!("{}", e), StatusCode::INTERNAL_SERVER_ERROR),
@@ -260,45 +1212,111 @@ fn http_response(
     }
 }
 
+/// Headers that only apply to a single connection, so are never passed through the proxy.
+const HOP_BY_HOP_HEADERS: [&str; 6] = [
+    "connection",
+    "keep-alive",
+    "proxy-authorization",
+    "te",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// The headers sent to `proxy_base_url`.  These are the `additional_proxy_request_headers`, with
+/// the `proxy_request_rewrites` applied.  The headers of the incoming request are only forwarded
+/// as well when `forward_request_headers` is set on the mapping.
+fn proxy_request_headers(
+    request: &Request<Vec<u8>>,
+    response_config: &config::Response,
+) -> Result<HeaderMap, Error> {
+    let mut headers = HeaderMap::new();
+
+    if *response_config.forward_request_headers() {
+        for (key, value) in request.headers() {
+            // The upstream request has its own host, and no body.
+            if !HOP_BY_HOP_HEADERS.contains(&key.as_str()) && key != HOST && key != CONTENT_LENGTH {
+                let _ = headers.append(key.clone(), value.clone());
+            }
+        }
+    }
+
+    for header in response_config.additional_proxy_request_headers() {
+        let _ = headers.insert(
+            HeaderName::from_bytes(header.key().as_bytes())?,
+            HeaderValue::from_str(header.value())?,
+        );
+    }
+
+    rewrite(response_config.proxy_request_rewrites(), &mut headers)?;
+    Ok(headers)
+}
+
+/// Prepare an upstream response to be sent back to the client.  The headers that describe how
+/// it was framed are dropped, the `proxy_response_rewrites` are applied, and it is recorded if
+/// record mode is on.
+fn proxied_response(
+    mut upstream: Response<Vec<u8>>,
+    rewrites: &[HeaderRewrite],
+    recording: Option<Recording>,
+    stdout: &Option<Logger>,
+    stderr: &Option<Logger>,
+) -> Response<Vec<u8>> {
+    for key in HOP_BY_HOP_HEADERS
+        .iter()
+        .chain(&["content-length", "date", "server"])
+    {
+        let _ = upstream.headers_mut().remove(*key);
+    }
+
+    if let Err(e) = rewrite(rewrites, upstream.headers_mut()) {
+        try_error!(
+            stderr,
+            "Unable to rewrite the upstream response headers: {}",
+            e
+        );
+        return util::error_response(e.to_string(), StatusCode::BAD_GATEWAY);
+    }
+
+    if let Some(recording) = recording {
+        match recording.save(upstream.status(), upstream.headers(), upstream.body()) {
+            Ok(path) => try_info!(stdout, "Recorded '{}'", path.display()),
+            Err(e) => try_error!(stderr, "Unable to record the upstream response: {}", e),
+        }
+    }
+    upstream
+}
+
 async fn run_request<C>(
     client: Client<C, hyper::Body>,
-    tx: futures::sync::mpsc::UnboundedSender<Result<String, String>>,
+    tx: futures::sync::mpsc::UnboundedSender<Result<Response<Vec<u8>>, String>>,
     url: String,
     stdout: Option<Logger>,
     stderr: Option<Logger>,
-    headers: Vec<config::Header>,
+    headers: HeaderMap,
 ) where
     C: hyper::client::connect::Connect + Sync + 'static,
 {
     match await!({
         try_trace!(stdout, "Making request to {}", url);
-        let mut request_builder = HyperRequest::get(url);
-
-        for header in headers {
-            let _ = request_builder.header(&header.key()[..], &header.value()[..]);
-        }
-
-        let body = request_builder
+        let mut upstream_request = HyperRequest::get(url)
             .body(hyper::Body::empty())
             .expect("Unable to create upstream request");
+        *upstream_request.headers_mut() = headers;
         client
-            .request(body)
+            .request(upstream_request)
             .timeout(std::time::Duration::from_secs(10))
     }) {
         Ok(response) => {
+            let (parts, body) = response.into_parts();
             let body = await!({
-                response
-                    .into_body()
-                    .map_err(|_| ())
-                    .fold(Vec::new(), |mut v, chunk| {
-                        v.extend_from_slice(&chunk);
-                        futures::future::ok(v)
-                    })
+                body.map_err(|_| ()).fold(Vec::new(), |mut v, chunk| {
+                    v.extend_from_slice(&chunk);
+                    futures::future::ok(v)
+                })
             });
 
             if let Ok(body) = body {
-                let body_str = String::from_utf8_lossy(&body).into_owned();
-                tx.unbounded_send(Ok(body_str))
+                tx.unbounded_send(Ok(Response::from_parts(parts, body)))
                     .expect("Unable to send upstream response!");
             } else {
                 try_error!(stderr, "Unable to process upstream response!");
@@ -315,18 +1333,19 @@ async fn run_request<C>(
 }
 
 cached_key_result! {
-    STATIC_RESPONSE: UnboundCache<String, String> = UnboundCache::new();
-    Key = { filename.to_string() };
-    fn load(files_path: PathBuf, filename: &str) -> Result<String, &str> = {
-        let mut buffer = String::new();
+    // Virtual hosts have their own files path, so the same file name can be another body.
+    STATIC_RESPONSE: UnboundCache<(PathBuf, String), Vec<u8>> = UnboundCache::new();
+    Key = { (files_path.clone(), filename.to_string()) };
+    fn load(files_path: PathBuf, filename: &str) -> Result<Vec<u8>, &str> = {
+        let mut buffer = Vec::new();
         let mut found = false;
 
-        util::visit_dirs(&files_path, &mut |entry| -> Result<(), Error> {
-            if let Some(fname) = entry.path().file_name() {
+        util::visit_dirs(&files_path, &mut |path| -> Result<(), Error> {
+            if let Some(fname) = path.file_name() {
                 if fname.to_string_lossy() == filename {
-                    let f = File::open(entry.path())?;
+                    let f = File::open(path)?;
                     let mut reader = BufReader::new(f);
-                    let _ = reader.read_to_string(&mut buffer)?;
+                    let _ = reader.read_to_end(&mut buffer)?;
                     found = true;
                 }
             }
@@ -341,27 +1360,497 @@ cached_key_result! {
     }
 }
 
-/// Start the async runtime handling.
-pub fn run(socket_addr: &SocketAddr, handler: Handler) -> Result<(), Error> {
-    let listener = TcpListener::bind(&socket_addr)?;
+/// The encoded events of a `text/event-stream` response, each with the delay, in milliseconds,
+/// after the one before.  The response itself has an empty body.
+#[derive(Clone, Debug)]
+struct EventStream {
+    events: Vec<(u64, String)>,
+}
 
-    // Run the server.
-    // try_trace!(handler.stdout, "{:?}", current);
-    try_info!(handler.stdout, "Listening on '{}'", socket_addr);
+/// A body file that is streamed from disk as the response is sent, rather than read into
+/// memory.  The response itself has an empty body.
+#[derive(Clone, Debug)]
+struct BodyFile {
+    path: PathBuf,
+    length: u64,
+}
+
+fn body_file(files_path: &Path, filename: &str) -> Result<BodyFile, &'static str> {
+    let mut found = None;
+
+    util::visit_dirs(files_path, &mut |path| -> Result<(), Error> {
+        if let Some(fname) = path.file_name() {
+            if fname.to_string_lossy() == filename {
+                found = Some(BodyFile {
+                    path: path.to_path_buf(),
+                    length: path.metadata()?.len(),
+                });
+            }
+        }
+        Ok(())
+    })
+    .map_err(|_| "Body file not found!")?;
+
+    found.ok_or("Body file not found!")
+}
 
+#[allow(box_pointers)]
+type FutFrames = Box<dyn Stream<Item = Frame, Error = io::Error> + Send>;
+
+/// The frames to send for a response.  A body file is read from disk a block at a time, as the
+/// connection is ready for more.
+#[allow(box_pointers)]
+fn frames(mut response: Response<Vec<u8>>) -> FutFrames {
+    if let Some(fault) = response.extensions_mut().remove::<config::Fault>() {
+        Box::new(stream::iter_ok(vec![
+            Frame::Fault { response, fault },
+            Frame::Close,
+        ]))
+    } else if let Some(body_file) = response.extensions_mut().remove::<BodyFile>() {
+        let chunked = response.extensions_mut().remove::<Chunked>();
+        let chunk_size = chunked.as_ref().map(|chunked| chunked.size.max(1));
+        let (parts, _) = response.into_parts();
+        let head = Frame::Head {
+            response: Response::from_parts(parts, ()),
+            content_length: if chunk_size.is_some() {
+                None
+            } else {
+                Some(body_file.length)
+            },
+        };
+
+        let body = tokio::fs::File::open(body_file.path)
+            .map(|file| FramedRead::new(file, BytesCodec::new()))
+            .flatten_stream()
+            .map(move |block| {
+                let block = block.freeze();
+                let frames: Vec<Frame> = if let Some(chunk_size) = chunk_size {
+                    (0..block.len())
+                        .step_by(chunk_size)
+                        .map(|start| {
+                            Frame::Chunk(block.slice(start, (start + chunk_size).min(block.len())))
+                        })
+                        .collect()
+                } else {
+                    vec![Frame::Data(block)]
+                };
+                stream::iter_ok(frames)
+            })
+            .flatten();
+        let end = chunked.map(|chunked| Frame::Trailers(chunked.trailers));
+
+        Box::new(
+            stream::once(Ok(head))
+                .chain(body)
+                .chain(stream::iter_ok(end)),
+        )
+    } else if let Some(dribble) = response.extensions_mut().remove::<config::Dribble>() {
+        let trailers = response
+            .extensions_mut()
+            .remove::<Chunked>()
+            .map(|chunked| chunked.trailers);
+        let chunked = trailers.is_some();
+        let (parts, body) = response.into_parts();
+        let head = Frame::Head {
+            response: Response::from_parts(parts, ()),
+            content_length: if chunked {
+                None
+            } else {
+                Some(body.len() as u64)
+            },
+        };
+
+        let start = Instant::now();
+        let pieces: Vec<(Duration, Bytes)> = dribble
+            .split(&body)
+            .into_iter()
+            .map(|(delay, piece)| (delay, Bytes::from(piece)))
+            .collect();
+        let body = stream::iter_ok(pieces).and_then(move |(delay, piece)| {
+            Delay::new(start + delay)
+                .map_err(|e| io::Error::new(ErrorKind::Other, e))
+                .map(move |_| {
+                    if chunked {
+                        Frame::Chunk(piece)
+                    } else {
+                        Frame::Data(piece)
+                    }
+                })
+        });
+        let end = trailers.map(Frame::Trailers);
+
+        Box::new(
+            stream::once(Ok(head))
+                .chain(body)
+                .chain(stream::iter_ok(end)),
+        )
+    } else if let Some(event_stream) = response.extensions_mut().remove::<EventStream>() {
+        let trailers = response
+            .extensions_mut()
+            .remove::<Chunked>()
+            .map_or_else(HeaderMap::new, |chunked| chunked.trailers);
+        let (parts, _) = response.into_parts();
+        let head = Frame::Head {
+            response: Response::from_parts(parts, ()),
+            content_length: None,
+        };
+
+        // Each delay starts once the event before has been sent.
+        let events = stream::iter_ok(event_stream.events).and_then(|(delay_ms, event)| {
+            Delay::new(Instant::now() + Duration::from_millis(delay_ms))
+                .map_err(|e| io::Error::new(ErrorKind::Other, e))
+                .map(move |_| Frame::Chunk(Bytes::from(event)))
+        });
+
+        Box::new(
+            stream::once(Ok(head))
+                .chain(events)
+                .chain(stream::once(Ok(Frame::Trailers(trailers)))),
+        )
+    } else {
+        Box::new(stream::once(Ok(Frame::Response(response))))
+    }
+}
+
+/// Read a streamed body file or events into the body of a response sent over HTTP/2, along with
+/// the trailers to send after it.
+fn h2_response(mut response: Response<Vec<u8>>) -> (Response<Vec<u8>>, HeaderMap) {
+    let trailers = response
+        .extensions_mut()
+        .remove::<Chunked>()
+        .map_or_else(HeaderMap::new, |chunked| chunked.trailers);
+
+    if let Some(body_file) = response.extensions_mut().remove::<BodyFile>() {
+        match std::fs::read(&body_file.path) {
+            Ok(body) => *response.body_mut() = body,
+            Err(e) => *response.body_mut() = e.to_string().into_bytes(),
+        }
+    } else if let Some(event_stream) = response.extensions_mut().remove::<EventStream>() {
+        *response.body_mut() = event_stream
+            .events
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect::<String>()
+            .into_bytes();
+    }
+    (response, trailers)
+}
+
+/// The frames to send for a response, switching the connection over to WebSocket messages if
+/// the response accepts an upgrade.
+#[allow(box_pointers)]
+fn upgrade_frames(
+    mut response: Response<Vec<u8>>,
+    session: &Arc<Mutex<Option<Session>>>,
+) -> FutFrames {
+    if let Some(mut upgraded) = response.extensions_mut().remove::<Session>() {
+        let messages = upgraded.start();
+        match session.lock() {
+            Ok(mut guard) => *guard = Some(upgraded),
+            Err(poisoned) => *poisoned.into_inner() = Some(upgraded),
+        }
+
+        let (parts, _) = response.into_parts();
+        Box::new(
+            stream::once(Ok(Frame::Upgrade(Response::from_parts(parts, ()))))
+                .chain(websocket_frames(messages)),
+        )
+    } else {
+        frames(response)
+    }
+}
+
+/// The frames to send for WebSocket messages, each after its delay.  The connection is closed
+/// after a close message.
+#[allow(box_pointers)]
+fn websocket_frames(messages: Vec<(Duration, Message)>) -> FutFrames {
+    let close = messages.iter().any(|(_, message)| match message {
+        Message::Close(_) => true,
+        _ => false,
+    });
+
+    // Each delay starts once the message before has been sent.
+    let messages = stream::iter_ok(messages).and_then(|(delay, message)| {
+        Delay::new(Instant::now() + delay)
+            .map_err(|e| io::Error::new(ErrorKind::Other, e))
+            .map(move |_| Frame::Message(message))
+    });
+    let end = if close { Some(Frame::Close) } else { None };
+
+    Box::new(messages.chain(stream::iter_ok(end)))
+}
+
+/// The frames to send for a request the codec refused to read, after which the connection is
+/// closed.
+#[allow(box_pointers)]
+fn rejected_frames(status: StatusCode) -> FutFrames {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    let _ = response
+        .headers_mut()
+        .insert(CONNECTION, HeaderValue::from_static("close"));
+
+    Box::new(stream::iter_ok(vec![
+        Frame::Response(response),
+        Frame::Close,
+    ]))
+}
+
+#[allow(box_pointers)]
+type FutServe = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// Accept plain HTTP connections on the given listener.
+#[allow(box_pointers)]
+fn serve(listener: TcpListener, handler: Handler) -> FutServe {
     let map_stderr = handler.stderr.clone();
     let process_stdout = handler.stdout.clone();
 
-    tokio::run({
+    Box::new(
         listener
             .incoming()
             .map_err(move |e| try_error!(map_stderr, "Failed to accept socket: {}", e))
             .for_each(move |socket| {
                 header::socket_info(&socket, &process_stdout);
-                handle(handler.clone(), socket);
+                let handler = Handler {
+                    peer_addr: socket.peer_addr().ok(),
+                    ..handler.clone()
+                };
+                let preface_stderr = handler.stderr.clone();
+                let _ = tokio::spawn(
+                    http2::Preface::new(socket)
+                        .map(move |(socket, prior_knowledge)| {
+                            if prior_knowledge {
+                                handle_h2(handler, socket)
+                            } else {
+                                handle(handler, socket)
+                            }
+                        })
+                        .map_err(move |e| {
+                            try_error!(preface_stderr, "Failed to read from socket: {}", e)
+                        }),
+                );
                 Ok(())
-            })
-    });
+            }),
+    )
+}
+
+/// Accept HTTPS connections on the given listener.
+#[allow(box_pointers)]
+fn serve_tls(listener: TcpListener, acceptor: TlsAcceptor, handler: Handler) -> FutServe {
+    let map_stderr = handler.stderr.clone();
+    let process_stdout = handler.stdout.clone();
+
+    Box::new(
+        listener
+            .incoming()
+            .map_err(move |e| try_error!(map_stderr, "Failed to accept socket: {}", e))
+            .for_each(move |socket| {
+                header::socket_info(&socket, &process_stdout);
+                let handler = Handler {
+                    tls: true,
+                    peer_addr: socket.peer_addr().ok(),
+                    ..handler.clone()
+                };
+                let handshake_stderr = handler.stderr.clone();
+                let _ = tokio::spawn(
+                    acceptor
+                        .accept(socket)
+                        .map(move |stream| {
+                            let client_cert = match tls::client_certificate(&stream) {
+                                Ok(client_cert) => client_cert,
+                                Err(e) => {
+                                    try_error!(
+                                        handler.stderr,
+                                        "Unable to read the client certificate: {}",
+                                        e
+                                    );
+                                    None
+                                }
+                            };
+                            let handler = Handler {
+                                client_cert,
+                                server_name: tls::server_name(&stream),
+                                ..handler
+                            };
+                            if tls::is_h2(&stream) {
+                                handle_h2(handler, stream)
+                            } else {
+                                handle(handler, stream)
+                            }
+                        })
+                        .map_err(move |e| {
+                            try_error!(handshake_stderr, "TLS handshake failed: {}", e)
+                        }),
+                );
+                Ok(())
+            }),
+    )
+}
+
+/// Start the async runtime handling.
+pub fn run(socket_addr: &SocketAddr, handler: Handler) -> Result<(), Error> {
+    let listener = TcpListener::bind(&socket_addr)?;
+
+    // Run the server.
+    // try_trace!(handler.stdout, "{:?}", current);
+    try_info!(handler.stdout, "Listening on '{}'", socket_addr);
+
+    tokio::run(fetch_body_urls(&handler).and_then(move |_| serve(listener, handler)));
 
     Ok(())
 }
+
+/// Download the response bodies configured with a `body_url`, for the root and every virtual
+/// host, before serving any requests.
+fn fetch_body_urls(handler: &Handler) -> impl Future<Item = (), Error = ()> + Send {
+    let sources: Vec<(&config::Mappings, &Path)> = iter::once(handler)
+        .chain(
+            handler
+                .virtual_hosts
+                .iter()
+                .map(|(_, host_handler)| host_handler),
+        )
+        .map(|handler| (&handler.static_mappings, handler.files_path.as_path()))
+        .collect();
+
+    body_url::fetch_all(&sources, handler.stdout.clone(), handler.stderr.clone())
+}
+
+/// Start the async runtime handling, terminating TLS with the certificate and key configured on the
+/// given runtime.
+pub fn run_tls(
+    socket_addr: &SocketAddr,
+    runtime: &config::Runtime,
+    handler: Handler,
+) -> Result<(), Error> {
+    let acceptor = tls::acceptor(runtime)?;
+    let listener = TcpListener::bind(&socket_addr)?;
+
+    try_info!(handler.stdout, "Listening on '{}' (TLS)", socket_addr);
+
+    tokio::run(fetch_body_urls(&handler).and_then(move |_| serve_tls(listener, acceptor, handler)));
+
+    Ok(())
+}
+
+/// Start the async runtime handling on every listener configured on the given runtime.
+///
+/// All of the listeners share the same handler, and therefore the same mappings.
+#[allow(box_pointers)]
+pub fn run_all(runtime: &config::Runtime, handler: Handler) -> Result<(), Error> {
+    let servers = bind_all(runtime, &handler)?;
+
+    tokio::run(fetch_body_urls(&handler).and_then(move |_| {
+        for (_, server) in servers {
+            let _ = tokio::spawn(server);
+        }
+        Ok(())
+    }));
+
+    Ok(())
+}
+
+/// Start the async runtime handling on every listener configured on the given runtime, in the
+/// background, returning a handle that stops it.
+///
+/// All of the listeners share the same handler, and therefore the same mappings.
+#[allow(box_pointers)]
+pub fn start(runtime: &config::Runtime, handler: Handler) -> Result<ServerHandle, Error> {
+    let servers = bind_all(runtime, &handler)?;
+    let local_addrs: Vec<SocketAddr> = servers.iter().map(|(addr, _)| *addr).collect();
+    let (stop, stopped) = oneshot::channel();
+    let stopped = stopped.shared();
+    let in_flight = handler.in_flight.clone();
+
+    let mut tokio_runtime = tokio::runtime::Runtime::new()?;
+    let stop_accepting = stopped.clone();
+    let stop_stdout = handler.stdout.clone();
+    tokio_runtime.spawn(fetch_body_urls(&handler).and_then(move |_| {
+        for (addr, server) in servers {
+            let stdout = stop_stdout.clone();
+            let _ = tokio::spawn(
+                server
+                    .select(stop_accepting.clone().then(|_| Ok(())))
+                    .map(move |_| try_info!(stdout, "Stopped listening on '{}'", addr))
+                    .map_err(|_| ()),
+            );
+        }
+        Ok(())
+    }));
+
+    Ok(ServerHandle::new(
+        tokio_runtime,
+        stop,
+        stopped,
+        in_flight,
+        local_addrs,
+    ))
+}
+
+/// Bind every listener configured on the given runtime.
+#[allow(box_pointers)]
+fn bind_all(
+    runtime: &config::Runtime,
+    handler: &Handler,
+) -> Result<Vec<(SocketAddr, FutServe)>, Error> {
+    let mut servers = Vec::new();
+
+    for listener in runtime.all_listeners()? {
+        let tcp_listener = TcpListener::bind(&listener.socket_addr()?)?;
+        let local_addr = tcp_listener.local_addr()?;
+        try_info!(handler.stdout, "Listening on '{}'", listener);
+
+        if *listener.tls() {
+            servers.push((
+                local_addr,
+                serve_tls(tcp_listener, tls::acceptor(runtime)?, handler.clone()),
+            ));
+        } else {
+            servers.push((local_addr, serve(tcp_listener, handler.clone())));
+        }
+    }
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::frames;
+    use crate::config::Dribble;
+    use crate::server::server_handle::InFlight;
+    use futures::Stream;
+    use http::Response;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::runtime::current_thread::Runtime;
+
+    #[test]
+    fn in_flight_until_dribbled() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let mut response = Response::new(b"0123456789".to_vec());
+        let _ = response.extensions_mut().insert(Dribble::new(4, 40));
+        let mut pending = InFlight::start(&in_flight).hold(frames(response));
+        let mut sent = 0;
+
+        match Runtime::new() {
+            Ok(mut runtime) => loop {
+                match runtime.block_on(pending.into_future()) {
+                    Ok((Some(_), rest)) => {
+                        assert_eq!(in_flight.load(Ordering::SeqCst), 1);
+                        sent += 1;
+                        pending = rest;
+                    }
+                    Ok((None, _)) => break,
+                    Err((e, _)) => {
+                        assert!(false, e.to_string());
+                        break;
+                    }
+                }
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        // The head, then the four pieces of the body.
+        assert_eq!(sent, 5);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+    }
+}
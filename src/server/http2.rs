@@ -0,0 +1,225 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTTP/2 connections.
+//!
+//! HTTP/2 is negotiated with ALPN on TLS listeners.  On plain listeners, a client with prior
+//! knowledge starts the connection with the HTTP/2 preface, `PRI * HTTP/2.0`, which no HTTP/1.1
+//! request starts with.
+use bytes::Bytes;
+use futures::{try_ready, Async, Future, Poll, Stream};
+use h2::server::SendResponse;
+use h2::RecvStream;
+use http::header::HeaderMap;
+use http::Response;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::timer::Delay;
+
+/// The ALPN protocol name of HTTP/2 over TLS.
+crate const ALPN_H2: &str = "h2";
+
+/// The start of the connection preface sent by an HTTP/2 client.
+const PREFACE: &[u8] = b"PRI ";
+
+/// How long to wait before peeking again when only part of the preface has arrived.  A peek
+/// doesn't consume the bytes, so the socket stays readable and can't wake the task itself.
+const PEEK_RETRY_MS: u64 = 5;
+
+/// Headers that only apply to HTTP/1.1 connections, which HTTP/2 forbids.
+const CONNECTION_HEADERS: [&str; 5] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Peek at the start of a plain connection, without reading it, to tell whether the client is
+/// speaking HTTP/2.  Peeking continues until the whole preface start has arrived, the bytes stop
+/// matching it, or the client closes the connection.
+crate struct Preface {
+    socket: Option<TcpStream>,
+    retry: Option<Delay>,
+}
+
+impl Preface {
+    crate fn new(socket: TcpStream) -> Self {
+        Self {
+            socket: Some(socket),
+            retry: None,
+        }
+    }
+}
+
+impl Future for Preface {
+    type Item = (TcpStream, bool);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            if let Some(retry) = self.retry.as_mut() {
+                try_ready!(retry
+                    .poll()
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e)));
+                self.retry = None;
+            }
+
+            let mut peeked = [0; 4];
+            let len = match self.socket.as_mut() {
+                Some(socket) => try_ready!(socket.poll_peek(&mut peeked)),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "preface polled after completion",
+                    ));
+                }
+            };
+
+            // An empty peek is the end of the stream.
+            let prior_knowledge = if len == 0 {
+                false
+            } else {
+                match preface_state(&peeked[..len]) {
+                    Some(prior_knowledge) => prior_knowledge,
+                    None => {
+                        self.retry = Some(Delay::new(
+                            Instant::now() + Duration::from_millis(PEEK_RETRY_MS),
+                        ));
+                        continue;
+                    }
+                }
+            };
+
+            return match self.socket.take() {
+                Some(socket) => Ok(Async::Ready((socket, prior_knowledge))),
+                None => Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "preface polled after completion",
+                )),
+            };
+        }
+    }
+}
+
+/// Do the first bytes of a connection start the HTTP/2 preface?  `None` when they match so far,
+/// but more bytes are needed to tell.
+fn preface_state(peeked: &[u8]) -> Option<bool> {
+    if peeked.len() < PREFACE.len() && PREFACE.starts_with(peeked) {
+        None
+    } else {
+        Some(peeked.starts_with(PREFACE))
+    }
+}
+
+/// Read the whole body of a request, giving the flow control capacity back as it is read.
+crate fn read_body(mut body: RecvStream) -> impl Future<Item = Vec<u8>, Error = h2::Error> {
+    let mut release_capacity = body.release_capacity().clone();
+
+    body.fold(Vec::new(), move |mut read, data| {
+        read.extend_from_slice(&data);
+        release_capacity.release_capacity(data.len()).map(|_| read)
+    })
+}
+
+/// Send a response on a stream, followed by the trailers, if there are any.
+crate fn send_response(
+    send: &mut SendResponse<Bytes>,
+    response: Response<Vec<u8>>,
+    trailers: HeaderMap,
+) -> Result<(), h2::Error> {
+    let (mut parts, body) = response.into_parts();
+    for header in &CONNECTION_HEADERS {
+        let _ = parts.headers.remove(*header);
+    }
+
+    let end_of_stream = body.is_empty() && trailers.is_empty();
+    let mut stream = send.send_response(Response::from_parts(parts, ()), end_of_stream)?;
+    if !body.is_empty() {
+        stream.send_data(Bytes::from(body), trailers.is_empty())?;
+    }
+    if !trailers.is_empty() {
+        stream.send_trailers(trailers)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{preface_state, Preface};
+    use futures::Stream;
+    use std::io::Write;
+    use std::net;
+    use std::thread;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn preface() {
+        assert_eq!(preface_state(b"PRI "), Some(true));
+        assert_eq!(preface_state(b"PRI"), None);
+        assert_eq!(preface_state(b"PR"), None);
+        assert_eq!(preface_state(b"GET "), Some(false));
+        assert_eq!(preface_state(b"PO"), Some(false));
+    }
+
+    fn peek_split(first: &'static [u8], rest: &'static [u8]) -> bool {
+        let listener = match TcpListener::bind(&([127, 0, 0, 1], 0).into()) {
+            Ok(listener) => listener,
+            Err(e) => {
+                assert!(false, e.to_string());
+                return false;
+            }
+        };
+        let addr = match listener.local_addr() {
+            Ok(addr) => addr,
+            Err(e) => {
+                assert!(false, e.to_string());
+                return false;
+            }
+        };
+
+        let client = thread::spawn(move || {
+            if let Ok(mut stream) = net::TcpStream::connect(addr) {
+                let _ = stream.write_all(first);
+                let _ = stream.flush();
+                thread::sleep(Duration::from_millis(50));
+                let _ = stream.write_all(rest);
+            }
+        });
+
+        let preface = listener.incoming().take(1).and_then(Preface::new).collect();
+        let prior_knowledge = match Runtime::new() {
+            Ok(mut runtime) => match runtime.block_on(preface) {
+                Ok(accepted) => accepted.iter().any(|(_, prior_knowledge)| *prior_knowledge),
+                Err(e) => {
+                    assert!(false, e.to_string());
+                    false
+                }
+            },
+            Err(e) => {
+                assert!(false, e.to_string());
+                false
+            }
+        };
+        let _ = client.join();
+        prior_knowledge
+    }
+
+    #[test]
+    fn partial_preface() {
+        assert!(peek_split(b"PR", b"I * HTTP/2.0\r\n\r\nSM\r\n\r\n"));
+    }
+
+    #[test]
+    fn partial_request() {
+        assert!(!peek_split(b"PO", b"ST / HTTP/1.1\r\n\r\n"));
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Content type inference for body files.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The built in content types, by file extension.
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "application/javascript"),
+    ("json", "application/json"),
+    ("md", "text/markdown"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("proto", "text/plain"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("txt", "text/plain"),
+    ("wasm", "application/wasm"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xml", "application/xml"),
+    ("yaml", "application/x-yaml"),
+    ("yml", "application/x-yaml"),
+    ("zip", "application/zip"),
+];
+
+/// The content type of a body file, from its extension.  The `overrides`, keyed by extension,
+/// are checked before the built in types.
+crate fn content_type<'a>(
+    file_name: &str,
+    overrides: &'a BTreeMap<String, String>,
+) -> Option<&'a str> {
+    let extension = Path::new(file_name)
+        .extension()?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+
+    overrides
+        .iter()
+        .find(|(key, _)| key.trim_start_matches('.').eq_ignore_ascii_case(&extension))
+        .map(|(_, content_type)| content_type.as_str())
+        .or_else(|| {
+            MIME_TYPES
+                .iter()
+                .find(|(key, _)| *key == extension)
+                .map(|(_, content_type)| *content_type)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::content_type;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn infer() {
+        let overrides = BTreeMap::new();
+        assert_eq!(
+            content_type("orders.json", &overrides),
+            Some("application/json")
+        );
+        assert_eq!(content_type("logo.PNG", &overrides), Some("image/png"));
+        assert_eq!(content_type("orders", &overrides), None);
+        assert_eq!(content_type("orders.unknown", &overrides), None);
+    }
+
+    #[test]
+    fn overrides() {
+        let mut overrides = BTreeMap::new();
+        let _ = overrides.insert("json".to_string(), "application/vnd.api+json".to_string());
+        let _ = overrides.insert(".hal".to_string(), "application/hal+json".to_string());
+        assert_eq!(
+            content_type("orders.json", &overrides),
+            Some("application/vnd.api+json")
+        );
+        assert_eq!(
+            content_type("orders.hal", &overrides),
+            Some("application/hal+json")
+        );
+        assert_eq!(content_type("notes.txt", &overrides), Some("text/plain"));
+    }
+}
@@ -7,8 +7,27 @@
 // modified, or distributed except according to those terms.
 
 //! Async runtime for the server.
+crate mod body_url;
+crate mod charset;
 crate mod codec;
+crate mod cors;
+crate mod encoding;
+crate mod etag;
 crate mod handler;
 crate mod header;
+crate mod http2;
+crate mod mime;
+crate mod multipart;
+crate mod rate_limiter;
+crate mod recorder;
+crate mod response_cache;
+crate mod sequences;
+crate mod server_handle;
+crate mod tls;
+crate mod transformer;
+crate mod webhook;
+crate mod websocket;
 
-pub use self::handler::{handle, run, Handler};
+pub use self::handler::{handle, handle_h2, run, run_all, run_tls, start, Handler};
+pub use self::server_handle::ServerHandle;
+pub use self::transformer::{register_transformer, ResponseTransformer};
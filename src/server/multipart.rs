@@ -0,0 +1,116 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Multipart response body assembly.
+use crate::config::{MultipartKind, ResponsePart};
+use uuid::Uuid;
+
+/// Generate a boundary for a multipart body.
+crate fn boundary() -> String {
+    format!("deadmock-{}", Uuid::new_v4().to_simple())
+}
+
+/// The `Content-Type` of a multipart body.
+crate fn content_type(kind: MultipartKind, boundary: &str) -> String {
+    format!("{}; boundary={}", kind.media_type(), boundary)
+}
+
+/// Assemble a multipart body.  Each part comes with the content type to send if the part doesn't
+/// configure one, and its body.
+crate fn encode(
+    kind: MultipartKind,
+    boundary: &str,
+    parts: &[(&ResponsePart, Option<&str>, Vec<u8>)],
+) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    for (part, content_type, part_body) in parts {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+
+        let disposition = match kind {
+            MultipartKind::Mixed => "attachment",
+            MultipartKind::FormData => "form-data",
+        };
+        if part.name().is_some() || part.filename().is_some() {
+            let mut value = disposition.to_string();
+            if let Some(name) = part.name() {
+                value.push_str(&format!("; name=\"{}\"", name));
+            }
+            if let Some(filename) = part.filename() {
+                value.push_str(&format!("; filename=\"{}\"", filename));
+            }
+            body.extend_from_slice(format!("Content-Disposition: {}\r\n", value).as_bytes());
+        }
+        if let Some(content_type) = part
+            .content_type()
+            .as_ref()
+            .map(String::as_str)
+            .or(*content_type)
+        {
+            body.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+        }
+        for header in part.headers() {
+            body.extend_from_slice(format!("{}: {}\r\n", header.key(), header.value()).as_bytes());
+        }
+
+        body.extend_from_slice(b"\r\n");
+        body.extend_from_slice(part_body);
+        body.extend_from_slice(b"\r\n");
+    }
+
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+#[cfg(test)]
+mod test {
+    use super::{boundary, content_type, encode};
+    use crate::config::{Header, MultipartKind, ResponsePart};
+
+    #[test]
+    fn boundaries() {
+        assert_ne!(boundary(), boundary());
+        assert_eq!(
+            content_type(MultipartKind::FormData, "abc"),
+            "multipart/form-data; boundary=abc"
+        );
+    }
+
+    #[test]
+    fn encoding() {
+        let mut metadata = ResponsePart::body("{}").header(Header::new("X-Part", "1"));
+        let _ = metadata.set_name(Some("metadata".to_string()));
+        let mut report = ResponsePart::file("report.csv");
+        let _ = report
+            .set_name(Some("report".to_string()))
+            .set_filename(Some("report.csv".to_string()));
+
+        let encoded = encode(
+            MultipartKind::FormData,
+            "abc",
+            &[
+                (&metadata, None, b"{}".to_vec()),
+                (&report, Some("text/csv"), b"id,name".to_vec()),
+            ],
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&encoded),
+            "--abc\r\n\
+             Content-Disposition: form-data; name=\"metadata\"\r\n\
+             X-Part: 1\r\n\
+             \r\n\
+             {}\r\n\
+             --abc\r\n\
+             Content-Disposition: form-data; name=\"report\"; filename=\"report.csv\"\r\n\
+             Content-Type: text/csv\r\n\
+             \r\n\
+             id,name\r\n\
+             --abc--\r\n"
+        );
+    }
+}
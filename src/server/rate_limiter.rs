@@ -0,0 +1,81 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Fixed window rate limiting for mappings.
+use crate::config::RateLimit;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug)]
+struct Window {
+    start: Instant,
+    count: u32,
+}
+
+/// Tracks the requests made to each rate limited mapping, by mapping id.
+#[derive(Clone, Debug, Default)]
+crate struct RateLimiter {
+    windows: HashMap<Uuid, Window>,
+}
+
+impl RateLimiter {
+    /// Count a request to the mapping with the given id.  If the limit has been reached, the time
+    /// left in the current window is returned as the error.
+    crate fn check(
+        &mut self,
+        id: Uuid,
+        rate_limit: &RateLimit,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let length = Duration::from_millis(*rate_limit.window_ms());
+        let window = self.windows.entry(id).or_insert(Window {
+            start: now,
+            count: 0,
+        });
+
+        if now.duration_since(window.start) >= length {
+            window.start = now;
+            window.count = 0;
+        }
+
+        if window.count < *rate_limit.requests() {
+            window.count += 1;
+            Ok(())
+        } else {
+            Err(length - now.duration_since(window.start))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RateLimiter;
+    use crate::config::RateLimit;
+    use std::time::{Duration, Instant};
+    use uuid::Uuid;
+
+    #[test]
+    fn fixed_window() {
+        let mut limiter = RateLimiter::default();
+        let rate_limit = RateLimit::new(2, 1000, None);
+        let start = Instant::now();
+        let (orders, users) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(limiter.check(orders, &rate_limit, start).is_ok());
+        assert!(limiter.check(orders, &rate_limit, start).is_ok());
+        assert_eq!(
+            limiter.check(orders, &rate_limit, start + Duration::from_millis(400)),
+            Err(Duration::from_millis(600))
+        );
+        assert!(limiter.check(users, &rate_limit, start).is_ok());
+        assert!(limiter
+            .check(orders, &rate_limit, start + Duration::from_millis(1000))
+            .is_ok());
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Record mode, which saves proxied request/response pairs as new mappings.
+//!
+//! The record directory is laid out like any other mappings path, with the mapping files in
+//! `mappings` and the response bodies in `files`, so it can be served as is.
+use crate::config::mappings::file_stem;
+use crate::config::{self, Format, Header, Mapping};
+use crate::error::Error;
+use crate::util;
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, Request, StatusCode, Uri};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str;
+use uuid::Uuid;
+
+/// Headers that describe how the upstream response was framed, rather than the response itself.
+const SKIPPED_HEADERS: [&str; 3] = ["connection", "content-length", "transfer-encoding"];
+
+/// A proxied request, saved along with the upstream response once it arrives.
+#[derive(Clone, Debug)]
+crate struct Recording {
+    dir: PathBuf,
+    method: String,
+    uri: Uri,
+    body: Vec<u8>,
+}
+
+impl Recording {
+    /// Record the given request into the given directory.
+    crate fn new(dir: &Path, request: &Request<Vec<u8>>) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            method: request.method().to_string(),
+            uri: request.uri().clone(),
+            body: request.body().clone(),
+        }
+    }
+
+    /// Save the request, with the given upstream response, as a new mapping.  Returns the path
+    /// of the mapping file.
+    crate fn save(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<PathBuf, Error> {
+        let path = self.uri.path();
+        let name = format!("Recorded {} {}", self.method, path);
+        let stem = format!("{}-{}", file_stem(&name), Uuid::new_v4().to_simple());
+
+        let mut request = config::Request::builder()
+            .method(self.method.clone())
+            .url(path);
+        for (key, value) in self
+            .uri
+            .query()
+            .map(util::parse_urlencoded)
+            .unwrap_or_default()
+        {
+            request = request.query_param(key, value);
+        }
+        if let Ok(request_body) = str::from_utf8(&self.body) {
+            if !request_body.is_empty() {
+                request = request.body(request_body);
+            }
+        }
+
+        let mut response = config::Response::builder().status(status.as_u16());
+        for (key, value) in headers {
+            if let Ok(value) = value.to_str() {
+                if !SKIPPED_HEADERS.contains(&key.as_str()) {
+                    response = response.headers(Header::new(key.as_str(), value));
+                }
+            }
+        }
+        if !body.is_empty() {
+            let files = self.dir.join("files");
+            let body_file_name = format!("{}.{}", stem, extension(headers));
+            fs::create_dir_all(&files)?;
+            fs::write(files.join(&body_file_name), body)?;
+            response = response.body_file_name(body_file_name);
+        }
+
+        let mapping = Mapping::builder()
+            .name(name)
+            .request(request.build())
+            .response(response.build())
+            .build();
+        let mappings = self.dir.join("mappings");
+        let mapping_path = mappings.join(format!("{}.{}", stem, Format::Toml.extension()));
+        fs::create_dir_all(&mappings)?;
+        fs::write(&mapping_path, Format::Toml.to_string_pretty(&mapping)?)?;
+        Ok(mapping_path)
+    }
+}
+
+/// The body file extension for a response with the given headers.
+fn extension(headers: &HeaderMap) -> &'static str {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    if content_type.contains("json") {
+        "json"
+    } else if content_type.contains("xml") {
+        "xml"
+    } else if content_type.contains("html") {
+        "html"
+    } else if content_type.starts_with("text/") {
+        "txt"
+    } else {
+        "bin"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Recording;
+    use crate::config::Mappings;
+    use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use http::{HeaderMap, HeaderValue, Request, StatusCode};
+    use std::env;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn save() {
+        let dir = env::temp_dir().join(format!("deadmock-record-{}", Uuid::new_v4()));
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let _ = headers.insert(CONTENT_LENGTH, HeaderValue::from_static("13"));
+
+        match Request::get("/orders?status=open").body(vec![]) {
+            Ok(request) => {
+                let recording = Recording::new(&dir, &request);
+                match recording.save(StatusCode::CREATED, &headers, b"{\"orders\":[]}") {
+                    Ok(path) => assert!(path.starts_with(dir.join("mappings"))),
+                    Err(e) => assert!(false, e.to_string()),
+                }
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+
+        match Mappings::from_path(&dir.join("mappings")) {
+            Ok(mappings) => match mappings.inner().values().next() {
+                Some(mapping) => {
+                    assert_eq!(mapping.name(), "Recorded GET /orders");
+                    assert_eq!(mapping.response().status(), &Some(201));
+                    assert_eq!(mapping.response().headers().len(), 1);
+                    match mapping.response().body_file_name() {
+                        Some(body_file_name) => {
+                            assert!(body_file_name.ends_with(".json"));
+                            match fs::read(dir.join("files").join(body_file_name)) {
+                                Ok(body) => assert_eq!(body, b"{\"orders\":[]}"),
+                                Err(e) => assert!(false, e.to_string()),
+                            }
+                        }
+                        None => assert!(false, "Expected a body file"),
+                    }
+                }
+                None => assert!(false, "Expected a recorded mapping"),
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Caching of proxied upstream responses.
+use crate::config::ProxyCache;
+use http::header::{AUTHORIZATION, COOKIE, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use http::{HeaderMap, Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Requests with any of these headers are never answered from, or stored in, the cache.  The
+/// upstream response depends on who is asking, or on what the client already has.
+const BYPASS_HEADERS: [http::header::HeaderName; 4] =
+    [AUTHORIZATION, COOKIE, IF_NONE_MATCH, IF_MODIFIED_SINCE];
+
+/// The upstream statuses that are cached.  Errors, partial content and `304 Not Modified` are
+/// always fetched again.
+const CACHEABLE_STATUSES: [u16; 9] = [200, 203, 204, 300, 301, 404, 405, 410, 414];
+
+#[derive(Clone, Debug)]
+struct Entry {
+    expires: Instant,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+/// The cached upstream responses, by cache key.
+#[derive(Clone, Debug, Default)]
+crate struct ResponseCache {
+    entries: HashMap<String, Entry>,
+}
+
+impl ResponseCache {
+    /// The cache key for a request to the given upstream url, or `None` if the request carries
+    /// credentials or conditional headers, so must bypass the cache.
+    crate fn key(
+        request: &Request<Vec<u8>>,
+        url: &str,
+        proxy_cache: &ProxyCache,
+    ) -> Option<String> {
+        if BYPASS_HEADERS
+            .iter()
+            .any(|name| request.headers().contains_key(name))
+        {
+            return None;
+        }
+
+        let mut key = format!("{} {}", request.method(), url);
+
+        for name in proxy_cache.key_headers() {
+            let values: Vec<String> = request
+                .headers()
+                .get_all(name.as_str())
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                .collect();
+            key.push_str(&format!(
+                "\n{}: {}",
+                name.to_ascii_lowercase(),
+                values.join(",")
+            ));
+        }
+        Some(key)
+    }
+
+    /// A copy of the cached response for the given key, unless it has expired.
+    crate fn get(&mut self, key: &str, now: Instant) -> Option<Response<Vec<u8>>> {
+        if self
+            .entries
+            .get(key)
+            .map_or(false, |entry| entry.expires <= now)
+        {
+            let _ = self.entries.remove(key);
+        }
+
+        self.entries.get(key).map(|entry| {
+            let mut response = Response::new(entry.body.clone());
+            *response.status_mut() = entry.status;
+            *response.headers_mut() = entry.headers.clone();
+            response
+        })
+    }
+
+    /// Cache a response under the given key for the given time, if its status is cacheable.
+    crate fn insert(
+        &mut self,
+        key: String,
+        response: &Response<Vec<u8>>,
+        ttl: Duration,
+        now: Instant,
+    ) {
+        if !CACHEABLE_STATUSES.contains(&response.status().as_u16()) {
+            return;
+        }
+
+        let _ = self.entries.insert(
+            key,
+            Entry {
+                expires: now + ttl,
+                status: response.status(),
+                headers: response.headers().clone(),
+                body: response.body().clone(),
+            },
+        );
+    }
+
+    /// Drop every cached response.
+    crate fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ResponseCache;
+    use crate::config::ProxyCache;
+    use http::{Request, Response, StatusCode};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn key() {
+        let proxy_cache = ProxyCache::new(1000, vec!["Accept".to_string()]);
+        match (
+            Request::get("/orders")
+                .header("Accept", "application/json")
+                .body(vec![]),
+            Request::get("/orders")
+                .header("Accept", "text/xml")
+                .body(vec![]),
+        ) {
+            (Ok(json), Ok(xml)) => {
+                let json_key = ResponseCache::key(&json, "http://upstream/orders", &proxy_cache);
+                assert_eq!(
+                    json_key,
+                    Some("GET http://upstream/orders\naccept: application/json".to_string())
+                );
+                assert_ne!(
+                    json_key,
+                    ResponseCache::key(&xml, "http://upstream/orders", &proxy_cache)
+                );
+            }
+            _ => assert!(false, "Unable to build the requests"),
+        }
+    }
+
+    #[test]
+    fn bypass() {
+        let proxy_cache = ProxyCache::new(1000, vec![]);
+        for (name, value) in &[
+            ("Authorization", "Bearer abc"),
+            ("Cookie", "session=abc"),
+            ("If-None-Match", "\"abc\""),
+            ("If-Modified-Since", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        ] {
+            match Request::get("/orders").header(*name, *value).body(vec![]) {
+                Ok(request) => assert!(
+                    ResponseCache::key(&request, "http://upstream/orders", &proxy_cache).is_none(),
+                    "{} should bypass the cache",
+                    name
+                ),
+                Err(e) => assert!(false, e.to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn uncacheable_statuses() {
+        let mut cache = ResponseCache::default();
+        let now = Instant::now();
+
+        for status in &[
+            StatusCode::NOT_MODIFIED,
+            StatusCode::PARTIAL_CONTENT,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            let mut response = Response::new(vec![]);
+            *response.status_mut() = *status;
+            cache.insert("key".to_string(), &response, Duration::from_secs(1), now);
+            assert!(cache.get("key", now).is_none(), "{} was cached", status);
+        }
+
+        let mut response = Response::new(vec![]);
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        cache.insert("key".to_string(), &response, Duration::from_secs(1), now);
+        assert!(cache.get("key", now).is_some());
+    }
+
+    #[test]
+    fn expiry() {
+        let mut cache = ResponseCache::default();
+        let now = Instant::now();
+        let mut response = Response::new(b"cached".to_vec());
+        *response.status_mut() = StatusCode::NON_AUTHORITATIVE_INFORMATION;
+
+        cache.insert(
+            "key".to_string(),
+            &response,
+            Duration::from_millis(100),
+            now,
+        );
+        match cache.get("key", now + Duration::from_millis(50)) {
+            Some(cached) => {
+                assert_eq!(cached.status(), StatusCode::NON_AUTHORITATIVE_INFORMATION);
+                assert_eq!(cached.body(), b"cached");
+            }
+            None => assert!(false, "Expected a cached response"),
+        }
+        assert!(cache.get("key", now + Duration::from_millis(100)).is_none());
+        assert!(cache.get("other", now).is_none());
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Call counting for response sequences.
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Counts the matches of each mapping with a response sequence, by mapping id.
+#[derive(Clone, Debug, Default)]
+crate struct Sequences {
+    calls: HashMap<Uuid, usize>,
+}
+
+impl Sequences {
+    /// Count a match of the mapping with the given id, returning the number of earlier matches.
+    crate fn next(&mut self, id: Uuid) -> usize {
+        let calls = self.calls.entry(id).or_insert(0);
+        let call = *calls;
+        *calls = calls.saturating_add(1);
+        call
+    }
+
+    /// Start every sequence again from its first response.
+    crate fn reset(&mut self) {
+        self.calls.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Sequences;
+    use uuid::Uuid;
+
+    #[test]
+    fn next() {
+        let mut sequences = Sequences::default();
+        let (create, update) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(sequences.next(create), 0);
+        assert_eq!(sequences.next(create), 1);
+        assert_eq!(sequences.next(update), 0);
+        sequences.reset();
+        assert_eq!(sequences.next(create), 0);
+    }
+}
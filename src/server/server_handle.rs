@@ -0,0 +1,222 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Stopping a running server.
+use futures::future::Shared;
+use futures::sync::oneshot::{Receiver, Sender};
+use futures::{stream, Async, Future, Stream};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+/// How long in-flight requests are given to finish once the server is shut down, unless the
+/// handle is given another drain timeout.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the in-flight requests are counted while draining.
+const DRAIN_POLL: Duration = Duration::from_millis(10);
+
+/// A handle to a server started with [`start`](crate::server::start).
+///
+/// [`shutdown`](ServerHandle::shutdown) stops the server accepting connections, and releases the
+/// ports, straight away.  [`await_terminated`](ServerHandle::await_terminated) waits for the
+/// server to be shut down, gives the requests in flight until the drain timeout to finish, then
+/// stops the server.  Dropping the handle stops the server without draining.
+///
+/// # Example
+///
+/// ```no_run
+/// # use libdeadmock::config::{self, Runtime};
+/// # use libdeadmock::matcher::Enabled;
+/// # use libdeadmock::server::{self, Handler};
+/// # use std::path::PathBuf;
+/// # use std::time::Duration;
+/// #
+/// # fn main() {
+/// let runtime = Runtime::builder().ip("127.0.0.1").port(0).build();
+/// let handler = Handler::new(
+///     Enabled::all(),
+///     config::Mappings::default(),
+///     config::Proxy::default(),
+///     PathBuf::from("tests"),
+/// );
+///
+/// match server::start(&runtime, handler) {
+///     Ok(server) => {
+///         let server = server.drain_timeout(Duration::from_secs(5));
+///         println!("Listening on {:?}", server.local_addrs());
+///         server.shutdown();
+///         assert!(server.await_terminated());
+///     }
+///     Err(e) => assert!(false, e.to_string()),
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ServerHandle {
+    runtime: Mutex<Option<Runtime>>,
+    stop: Mutex<Option<Sender<()>>>,
+    stopped: Shared<Receiver<()>>,
+    in_flight: Arc<AtomicUsize>,
+    local_addrs: Vec<SocketAddr>,
+    drain_timeout: Duration,
+}
+
+impl ServerHandle {
+    crate fn new(
+        runtime: Runtime,
+        stop: Sender<()>,
+        stopped: Shared<Receiver<()>>,
+        in_flight: Arc<AtomicUsize>,
+        local_addrs: Vec<SocketAddr>,
+    ) -> Self {
+        Self {
+            runtime: Mutex::new(Some(runtime)),
+            stop: Mutex::new(Some(stop)),
+            stopped,
+            in_flight,
+            local_addrs,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+
+    /// Give the requests in flight this long to finish once the server is shut down.
+    pub fn drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    /// The addresses the server is listening on.  A listener configured with port 0 is given a
+    /// free port by the operating system.
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.local_addrs
+    }
+
+    /// The number of requests being answered.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting connections, and release the ports.  Requests already received are still
+    /// answered.
+    pub fn shutdown(&self) {
+        let stop = match self.stop.lock() {
+            Ok(mut guard) => guard.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        if let Some(stop) = stop {
+            let _ = stop.send(());
+        }
+    }
+
+    /// Wait for the server to be shut down, then for the requests in flight to finish, until the
+    /// drain timeout, before stopping the server.  Returns `false` if requests were still in
+    /// flight at the drain timeout.
+    pub fn await_terminated(&self) -> bool {
+        let _ = self.stopped.clone().wait();
+
+        let deadline = Instant::now() + self.drain_timeout;
+        while self.in_flight() > 0 && Instant::now() < deadline {
+            thread::sleep(DRAIN_POLL);
+        }
+        let drained = self.in_flight() == 0;
+
+        self.stop_runtime();
+        drained
+    }
+
+    fn stop_runtime(&self) {
+        let runtime = match self.runtime.lock() {
+            Ok(mut guard) => guard.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        if let Some(runtime) = runtime {
+            let _ = runtime.shutdown_now().wait();
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+        self.stop_runtime();
+    }
+}
+
+/// Counts a request as in flight until it is dropped.
+crate struct InFlight {
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl InFlight {
+    crate fn start(in_flight: &Arc<AtomicUsize>) -> Self {
+        let _ = in_flight.fetch_add(1, Ordering::SeqCst);
+        Self {
+            in_flight: in_flight.clone(),
+        }
+    }
+
+    /// Keep the request in flight until the last item of the given stream, i.e. the last frame
+    /// of its response, has been taken, or the stream is dropped.
+    crate fn hold<S: Stream>(
+        self,
+        frames: S,
+    ) -> impl Stream<Item = S::Item, Error = S::Error> {
+        let mut guard = Some(self);
+        frames.chain(stream::poll_fn(move || {
+            let _ = guard.take();
+            Ok(Async::Ready(None))
+        }))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let _ = self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::InFlight;
+    use futures::{stream, Stream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn in_flight() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let first = InFlight::start(&in_flight);
+        let second = InFlight::start(&in_flight);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 2);
+
+        drop(first);
+        drop(second);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn hold() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let mut frames = InFlight::start(&in_flight)
+            .hold(stream::iter_ok::<_, ()>(vec![1, 2]))
+            .wait();
+        assert_eq!(frames.next(), Some(Ok(1)));
+        assert_eq!(frames.next(), Some(Ok(2)));
+        assert_eq!(in_flight.load(Ordering::SeqCst), 1);
+        assert_eq!(frames.next(), None);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+
+        let frames = InFlight::start(&in_flight).hold(stream::iter_ok::<_, ()>(vec![1, 2]));
+        assert_eq!(in_flight.load(Ordering::SeqCst), 1);
+        drop(frames);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! TLS termination for the async runtime.
+use crate::config::Runtime;
+use crate::error::Error::{self, InvalidTlsConfig};
+use crate::server::http2;
+use crate::x509::ClientCertificate;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio_rustls::rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use tokio_rustls::rustls::sign::{self, CertifiedKey};
+use tokio_rustls::rustls::{
+    AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, Certificate, NoClientAuth,
+    PrivateKey, ResolvesServerCert, RootCertStore, ServerConfig, Session, SignatureScheme,
+};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::webpki::DNSNameRef;
+use tokio_rustls::TlsAcceptor;
+
+/// The protocols offered with ALPN when the runtime doesn't configure any.
+const DEFAULT_ALPN: [&str; 2] = [http2::ALPN_H2, "http/1.1"];
+
+/// Choose the certificate for a connection by the SNI server name the client asks for, falling
+/// back to the default certificate.
+struct ServerCerts {
+    default: CertifiedKey,
+    by_name: HashMap<String, CertifiedKey>,
+}
+
+impl ResolvesServerCert for ServerCerts {
+    fn resolve(
+        &self,
+        server_name: Option<DNSNameRef<'_>>,
+        _sigschemes: &[SignatureScheme],
+    ) -> Option<CertifiedKey> {
+        let certified_key = server_name
+            .and_then(|server_name| {
+                let server_name: &str = server_name.into();
+                self.by_name.get(&server_name.to_ascii_lowercase())
+            })
+            .unwrap_or(&self.default);
+        Some(certified_key.clone())
+    }
+}
+
+/// Build a TLS acceptor from the certificate and key configured on the runtime.
+crate fn acceptor(runtime: &Runtime) -> Result<TlsAcceptor, Error> {
+    let cert_path = runtime
+        .tls_cert()
+        .as_ref()
+        .ok_or_else(|| InvalidTlsConfig("no certificate configured".to_string()))?;
+    let key_path = runtime
+        .tls_key()
+        .as_ref()
+        .ok_or_else(|| InvalidTlsConfig("no private key configured".to_string()))?;
+
+    let mut cert_chain = load_certs(cert_path)?;
+    if let Some(ca_path) = runtime.tls_ca() {
+        cert_chain.extend(load_certs(ca_path)?);
+    }
+
+    let client_auth = if let Some(client_ca_path) = runtime.tls_client_ca() {
+        let mut roots = RootCertStore::empty();
+        for cert in load_certs(client_ca_path)? {
+            roots.add(&cert).map_err(|e| {
+                InvalidTlsConfig(format!(
+                    "invalid client CA certificate in '{}': {:?}",
+                    client_ca_path, e
+                ))
+            })?;
+        }
+        if runtime.tls_client_auth_required().unwrap_or(false) {
+            AllowAnyAuthenticatedClient::new(roots)
+        } else {
+            AllowAnyAnonymousOrAuthenticatedClient::new(roots)
+        }
+    } else if runtime.tls_client_auth_required().unwrap_or(false) {
+        return Err(InvalidTlsConfig(
+            "'tls_client_auth_required' requires 'tls_client_ca'".to_string(),
+        ));
+    } else {
+        NoClientAuth::new()
+    };
+
+    let mut config = ServerConfig::new(client_auth);
+    if runtime.server_certs().is_empty() {
+        config
+            .set_single_cert(cert_chain, load_key(key_path)?)
+            .map_err(|e| InvalidTlsConfig(e.to_string()))?;
+    } else {
+        let mut server_certs = ServerCerts {
+            default: certified_key(cert_chain, &load_key(key_path)?)?,
+            by_name: HashMap::new(),
+        };
+        for server_cert in runtime.server_certs() {
+            let certified_key = certified_key(
+                load_certs(server_cert.tls_cert())?,
+                &load_key(server_cert.tls_key())?,
+            )?;
+            let _ = server_certs.by_name.insert(
+                server_cert.server_name().to_ascii_lowercase(),
+                certified_key,
+            );
+        }
+        config.cert_resolver = Arc::new(server_certs);
+    }
+
+    let protocols: Vec<String> = if runtime.tls_alpn().is_empty() {
+        DEFAULT_ALPN
+            .iter()
+            .map(|protocol| protocol.to_string())
+            .collect()
+    } else {
+        runtime.tls_alpn().clone()
+    };
+    if let Some(protocol) = protocols
+        .iter()
+        .find(|protocol| protocol.is_empty() || protocol.len() > 255)
+    {
+        return Err(InvalidTlsConfig(format!(
+            "invalid ALPN protocol '{}'",
+            protocol
+        )));
+    }
+    config.set_protocols(&protocols);
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// The SNI server name the client asked for during the handshake, if it sent one.
+crate fn server_name<IO>(stream: &TlsStream<IO>) -> Option<String> {
+    stream.get_ref().1.get_sni_hostname().map(str::to_string)
+}
+
+/// Did the client choose HTTP/2 with ALPN during the handshake?
+crate fn is_h2<IO>(stream: &TlsStream<IO>) -> bool {
+    stream.get_ref().1.get_alpn_protocol() == Some(http2::ALPN_H2)
+}
+
+/// The certificate the client presented during the handshake, if it presented one.
+crate fn client_certificate<IO>(
+    stream: &TlsStream<IO>,
+) -> Result<Option<ClientCertificate>, String> {
+    stream
+        .get_ref()
+        .1
+        .get_peer_certificates()
+        .and_then(|certs| certs.into_iter().next())
+        .map(|cert| ClientCertificate::from_der(&cert.0))
+        .transpose()
+}
+
+fn certified_key(cert_chain: Vec<Certificate>, key: &PrivateKey) -> Result<CertifiedKey, Error> {
+    let key = sign::any_supported_type(key)
+        .map_err(|_| InvalidTlsConfig("unsupported private key type".to_string()))?;
+    Ok(CertifiedKey::new(cert_chain, Arc::new(key)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<Certificate>, Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    match certs(&mut reader) {
+        Ok(ref certs) if certs.is_empty() => Err(InvalidTlsConfig(format!(
+            "no certificates found in '{}'",
+            path
+        ))),
+        Ok(certs) => Ok(certs),
+        Err(_) => Err(InvalidTlsConfig(format!(
+            "unable to read certificates from '{}'",
+            path
+        ))),
+    }
+}
+
+fn load_key(path: &str) -> Result<PrivateKey, Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut keys = pkcs8_private_keys(&mut reader).unwrap_or_default();
+
+    if keys.is_empty() {
+        let mut reader = BufReader::new(File::open(path)?);
+        keys = rsa_private_keys(&mut reader).unwrap_or_default();
+    }
+
+    if keys.is_empty() {
+        Err(InvalidTlsConfig(format!(
+            "no private key found in '{}'",
+            path
+        )))
+    } else {
+        Ok(keys.remove(0))
+    }
+}
+
+#[cfg(test)]
+crate mod test {
+    use super::{acceptor, load_certs, load_key};
+    use crate::config::{Runtime, ServerCert};
+
+    crate const TEST_CERT: &str = "tests/tls/cert.pem";
+    crate const TEST_KEY: &str = "tests/tls/key.pem";
+
+    #[test]
+    fn load_test_cert_and_key() {
+        assert!(load_certs(TEST_CERT).is_ok());
+        assert!(load_key(TEST_KEY).is_ok());
+    }
+
+    #[test]
+    fn no_key_in_cert() {
+        assert!(load_key(TEST_CERT).is_err());
+    }
+
+    #[test]
+    fn acceptor_requires_cert() {
+        assert!(acceptor(&Runtime::default()).is_err());
+    }
+
+    #[test]
+    fn acceptor_with_server_certs() {
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .server_cert(ServerCert::new("localhost", TEST_CERT, TEST_KEY))
+            .build();
+        assert!(acceptor(&runtime).is_ok());
+
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .server_cert(ServerCert::new("localhost", TEST_CERT, TEST_CERT))
+            .build();
+        assert!(acceptor(&runtime).is_err());
+    }
+
+    #[test]
+    fn acceptor_with_client_ca() {
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .tls_client_ca(TEST_CERT)
+            .build();
+        assert!(acceptor(&runtime).is_ok());
+
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .tls_client_ca(TEST_KEY)
+            .build();
+        assert!(acceptor(&runtime).is_err());
+
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .tls_client_ca(TEST_CERT)
+            .tls_client_auth_required(true)
+            .build();
+        assert!(acceptor(&runtime).is_ok());
+
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .tls_client_auth_required(true)
+            .build();
+        assert!(acceptor(&runtime).is_err());
+    }
+
+    #[test]
+    fn acceptor_with_alpn() {
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .tls_alpn("http/1.1")
+            .build();
+        assert!(acceptor(&runtime).is_ok());
+
+        let runtime = Runtime::builder()
+            .tls(TEST_CERT, TEST_KEY)
+            .tls_alpn("")
+            .build();
+        assert!(acceptor(&runtime).is_err());
+    }
+}
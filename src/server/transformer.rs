@@ -0,0 +1,184 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! User provided response transformation
+//!
+//! Embedding applications register their own
+//! [`ResponseTransformer`](crate::server::ResponseTransformer) implementations by name with
+//! [`register_transformer`](crate::server::register_transformer), and a response opts in by
+//! naming them in its `transformers` field.
+use crate::error::Error::{self, InvalidResponseConfig};
+use crate::util::ServerName;
+use crate::x509::ClientCertificate;
+use http::uri::Scheme;
+use http::{Request, Response};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+/// Change a response after the mapping has built it, i.e. to sign the body or fill in values
+/// that templates can't compute.
+pub trait ResponseTransformer: fmt::Debug {
+    /// Transform the response built for the given request.
+    fn transform(
+        &self,
+        request: &Request<Vec<u8>>,
+        response: Response<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Error>;
+}
+
+type Registry = HashMap<String, Arc<dyn ResponseTransformer + Send + Sync>>;
+
+lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(HashMap::new());
+}
+
+/// Register a response transformer under the given name, replacing any transformer already
+/// registered with that name.
+///
+/// The transformer is only applied to responses that name it in `transformers`.  Transformers
+/// are applied in the order they are named, after the body has been rendered and compressed.
+///
+/// # Example
+///
+/// ```
+/// # use http::{Request, Response};
+/// # use libdeadmock::error::Error;
+/// # use libdeadmock::server::{register_transformer, ResponseTransformer};
+/// #
+/// #[derive(Debug)]
+/// struct RequestId;
+///
+/// impl ResponseTransformer for RequestId {
+///     fn transform(
+///         &self,
+///         request: &Request<Vec<u8>>,
+///         mut response: Response<Vec<u8>>,
+///     ) -> Result<Response<Vec<u8>>, Error> {
+///         if let Some(id) = request.headers().get("X-Request-Id") {
+///             let _ = response.headers_mut().insert("X-Request-Id", id.clone());
+///         }
+///         Ok(response)
+///     }
+/// }
+///
+/// # fn main() {
+/// register_transformer("request-id", RequestId);
+/// # }
+/// ```
+pub fn register_transformer<S, T>(name: S, transformer: T)
+where
+    S: Into<String>,
+    T: ResponseTransformer + Send + Sync + 'static,
+{
+    let mut registry = match REGISTRY.write() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let _ = registry.insert(name.into(), Arc::new(transformer));
+}
+
+fn registered(name: &str) -> Option<Arc<dyn ResponseTransformer + Send + Sync>> {
+    let registry = match REGISTRY.read() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    registry.get(name).cloned()
+}
+
+/// Apply the named transformers to a response, in order.
+crate fn apply(
+    names: &[String],
+    request: &Request<Vec<u8>>,
+    response: Response<Vec<u8>>,
+) -> Result<Response<Vec<u8>>, Error> {
+    names.iter().fold(Ok(response), |response, name| {
+        let transformer = registered(name).ok_or_else(|| {
+            InvalidResponseConfig(format!("response transformer '{}' is not registered", name))
+        })?;
+        transformer.transform(request, response?)
+    })
+}
+
+/// A copy of a request, with the connection details the server records in the extensions, to
+/// hand to the transformers once the response is ready.
+crate fn copy_request(request: &Request<Vec<u8>>) -> Request<Vec<u8>> {
+    let mut copy = Request::new(request.body().clone());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.version_mut() = request.version();
+    *copy.headers_mut() = request.headers().clone();
+
+    let extensions = request.extensions();
+    if let Some(scheme) = extensions.get::<Scheme>() {
+        let _ = copy.extensions_mut().insert(scheme.clone());
+    }
+    if let Some(peer_addr) = extensions.get::<SocketAddr>() {
+        let _ = copy.extensions_mut().insert(*peer_addr);
+    }
+    if let Some(client_cert) = extensions.get::<ClientCertificate>() {
+        let _ = copy.extensions_mut().insert(client_cert.clone());
+    }
+    if let Some(server_name) = extensions.get::<ServerName>() {
+        let _ = copy.extensions_mut().insert(server_name.clone());
+    }
+    copy
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply, copy_request, register_transformer, ResponseTransformer};
+    use crate::error::Error;
+    use crate::matcher::RequestExt;
+    use http::uri::Scheme;
+    use http::{Request, Response};
+
+    #[derive(Debug)]
+    struct Echo;
+
+    impl ResponseTransformer for Echo {
+        fn transform(
+            &self,
+            request: &Request<Vec<u8>>,
+            mut response: Response<Vec<u8>>,
+        ) -> Result<Response<Vec<u8>>, Error> {
+            response
+                .body_mut()
+                .extend_from_slice(request.uri().path().as_bytes());
+            Ok(response)
+        }
+    }
+
+    #[test]
+    fn apply_in_order() {
+        register_transformer("echo-path", Echo);
+
+        match Request::builder()
+            .uri("/orders")
+            .extension(Scheme::HTTPS)
+            .body(vec![])
+        {
+            Ok(request) => {
+                let request = copy_request(&request);
+                assert!(request.is_tls());
+
+                let names = vec!["echo-path".to_string(), "echo-path".to_string()];
+                match apply(&names, &request, Response::new(b"path: ".to_vec())) {
+                    Ok(response) => assert_eq!(response.body(), b"path: /orders/orders"),
+                    Err(e) => assert!(false, e.to_string()),
+                }
+
+                let names = vec!["not-registered".to_string()];
+                assert!(apply(&names, &request, Response::new(vec![])).is_err());
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
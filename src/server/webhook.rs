@@ -0,0 +1,136 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Sending the webhooks of a matched mapping.
+use crate::config::Webhook;
+use crate::error::Error::{self, InvalidWebhook};
+use crate::template;
+use futures::Future;
+use hyper::{Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use slog::{error, info, Logger};
+use slog_try::{try_error, try_info};
+use std::time::{Duration, Instant};
+use tokio::prelude::FutureExt;
+use tokio::timer::Delay;
+
+/// The webhook method used when none is configured.
+const DEFAULT_METHOD: &str = "POST";
+
+/// A webhook rendered against the request that matched its mapping, ready to be sent.
+#[derive(Debug)]
+crate struct PendingWebhook {
+    request: Request<Body>,
+    delay: Duration,
+}
+
+impl PendingWebhook {
+    /// Render a webhook against the request that matched its mapping.
+    crate fn new(webhook: &Webhook, request: &Request<Vec<u8>>) -> Result<Self, Error> {
+        let mut builder = Request::builder();
+        let _ = builder
+            .method(
+                webhook
+                    .method()
+                    .as_ref()
+                    .map_or(DEFAULT_METHOD, String::as_str),
+            )
+            .uri(&template::render(webhook.url(), request)?[..]);
+
+        for header in webhook.headers() {
+            let value = template::render(header.value(), request)?;
+            let _ = builder.header(&header.key()[..], &value[..]);
+        }
+
+        let body = match webhook.body() {
+            Some(body) => template::render(body, request)?,
+            None => String::new(),
+        };
+
+        Ok(Self {
+            request: builder
+                .body(Body::from(body))
+                .map_err(|e| InvalidWebhook(e.to_string()))?,
+            delay: Duration::from_millis(webhook.delay_ms().unwrap_or(0)),
+        })
+    }
+
+    /// Send the request, once the delay has passed.  Failures are logged, never returned.
+    crate fn send(
+        self,
+        stdout: Option<Logger>,
+        stderr: Option<Logger>,
+    ) -> impl Future<Item = (), Error = ()> + Send {
+        let uri = self.request.uri().to_string();
+        let request = self.request;
+
+        Delay::new(Instant::now() + self.delay)
+            .map_err(|e| e.to_string())
+            .and_then(|_| HttpsConnector::new(4).map_err(|e| e.to_string()))
+            .and_then(move |connector| {
+                Client::builder()
+                    .build::<_, Body>(connector)
+                    .request(request)
+                    .timeout(Duration::from_secs(10))
+                    .map_err(|e| e.to_string())
+            })
+            .then(move |result| {
+                match result {
+                    Ok(response) => {
+                        try_info!(stdout, "Sent webhook to {}: {}", uri, response.status())
+                    }
+                    Err(e) => try_error!(stderr, "Unable to send webhook to {}: {}", uri, e),
+                }
+                Ok(())
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PendingWebhook;
+    use crate::config::{Header, Webhook};
+    use http::Request;
+    use std::time::Duration;
+
+    #[test]
+    fn render() {
+        let webhook = Webhook::builder()
+            .url("{{request.header \"X-Callback-Url\"}}")
+            .headers(Header::new("X-Order", "{{request.pathSegment 1}}"))
+            .body("shipped")
+            .delay_ms(250)
+            .build();
+
+        match Request::get("/orders/42")
+            .header("X-Callback-Url", "http://localhost:9000/callback")
+            .body(vec![])
+        {
+            Ok(request) => match PendingWebhook::new(&webhook, &request) {
+                Ok(pending) => {
+                    assert_eq!(pending.request.method(), "POST");
+                    assert_eq!(
+                        pending.request.uri().to_string(),
+                        "http://localhost:9000/callback"
+                    );
+                    assert_eq!(
+                        pending
+                            .request
+                            .headers()
+                            .get("X-Order")
+                            .map(|v| v.as_bytes()),
+                        Some(&b"42"[..])
+                    );
+                    assert_eq!(pending.delay, Duration::from_millis(250));
+                }
+                Err(e) => assert!(false, e.to_string()),
+            },
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+}
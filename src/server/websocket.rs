@@ -0,0 +1,144 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Scripted WebSocket exchanges.
+use crate::config::{WebSocket, WebSocketStep};
+use crate::server::codec::Message;
+use std::time::Duration;
+
+/// The close code sent when the client sends a message the script doesn't expect.
+const POLICY_VIOLATION: u16 = 1008;
+
+/// The progress of a scripted exchange on one upgraded connection.
+#[derive(Clone, Debug)]
+crate struct Session {
+    steps: Vec<WebSocketStep>,
+    position: usize,
+}
+
+impl Session {
+    crate fn new(websocket: &WebSocket) -> Self {
+        Self {
+            steps: websocket.steps().clone(),
+            position: 0,
+        }
+    }
+
+    /// The messages to send once the connection is upgraded, each with the delay after the
+    /// previous one before it is sent.
+    crate fn start(&mut self) -> Vec<(Duration, Message)> {
+        self.advance()
+    }
+
+    /// The messages to send in reply to a message from the client.
+    crate fn receive(&mut self, message: &Message) -> Vec<(Duration, Message)> {
+        let text = match message {
+            Message::Text(text) => text.clone(),
+            Message::Binary(data) => String::from_utf8_lossy(data).into_owned(),
+            Message::Ping(data) => {
+                return vec![(Duration::from_millis(0), Message::Pong(data.clone()))]
+            }
+            Message::Pong(_) => return vec![],
+            Message::Close(code) => {
+                self.position = self.steps.len();
+                return vec![(Duration::from_millis(0), Message::Close(*code))];
+            }
+        };
+
+        match self.steps.get(self.position) {
+            Some(step) if step.matches(&text) => {
+                self.position += 1;
+                self.advance()
+            }
+            Some(_) => {
+                self.position = self.steps.len();
+                vec![(
+                    Duration::from_millis(0),
+                    Message::Close(Some(POLICY_VIOLATION)),
+                )]
+            }
+            None => vec![],
+        }
+    }
+
+    /// Run the steps up to the next one that waits for the client.
+    fn advance(&mut self) -> Vec<(Duration, Message)> {
+        let mut messages = vec![];
+        while let Some(step) = self.steps.get(self.position) {
+            if step.is_expect() {
+                break;
+            }
+            self.position += 1;
+
+            let delay = Duration::from_millis(step.delay_ms().unwrap_or(0));
+            if let Some(text) = step.send() {
+                messages.push((delay, Message::Text(text.clone())));
+            } else if let Some(code) = step.close() {
+                messages.push((delay, Message::Close(Some(*code))));
+                self.position = self.steps.len();
+            }
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Session;
+    use crate::config::{WebSocket, WebSocketStep};
+    use crate::server::codec::Message;
+    use std::time::Duration;
+
+    fn text(text: &str) -> Message {
+        Message::Text(text.to_string())
+    }
+
+    #[test]
+    fn scripted_exchange() {
+        let mut session = Session::new(&WebSocket::new(vec![
+            WebSocketStep::send("hello", None),
+            WebSocketStep::expect("subscribe"),
+            WebSocketStep::send("101", Some(100)),
+            WebSocketStep::close(1000, Some(200)),
+            WebSocketStep::send("never sent", None),
+        ]));
+
+        assert_eq!(
+            session.start(),
+            vec![(Duration::from_millis(0), text("hello"))]
+        );
+        assert_eq!(
+            session.receive(&Message::Ping(b"are you there".to_vec())),
+            vec![(
+                Duration::from_millis(0),
+                Message::Pong(b"are you there".to_vec())
+            )]
+        );
+        assert_eq!(
+            session.receive(&text("subscribe")),
+            vec![
+                (Duration::from_millis(100), text("101")),
+                (Duration::from_millis(200), Message::Close(Some(1000))),
+            ]
+        );
+        assert!(session.receive(&text("subscribe")).is_empty());
+    }
+
+    #[test]
+    fn unexpected_message() {
+        let mut session = Session::new(&WebSocket::new(vec![WebSocketStep::expect_pattern(
+            "^subscribe",
+        )]));
+
+        assert!(session.start().is_empty());
+        assert_eq!(
+            session.receive(&text("unsubscribe")),
+            vec![(Duration::from_millis(0), Message::Close(Some(1008)))]
+        );
+    }
+}
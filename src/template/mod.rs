@@ -0,0 +1,628 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Response templating.
+//!
+//! Response bodies and header values on a mapping with `templated = true` are rendered as
+//! [Handlebars](https://handlebarsjs.com/) templates for every request.  The following request
+//! data is available to a template:
+//!
+//! * `{{request.method}}` - The request method, i.e. `GET`.
+//! * `{{request.scheme}}` - The request scheme, `https` on a TLS listener, otherwise `http`.
+//! * `{{request.host}}` - The request host, from the request uri or the `Host` header.
+//! * `{{request.path}}` - The request path, i.e. `/orders/12345`.
+//! * `{{request.url}}` - The request path and query, i.e. `/orders?page=2`.
+//! * `{{request.pathSegment <index>}}` - A segment of the request path, counting from 0, i.e.
+//! `12345` is segment 1 of `/orders/12345`.
+//! * `{{request.query.<name>}}` - The value of a query parameter.
+//! * `{{request.queryParam "<name>"}}` - The decoded value of a query parameter.
+//! * `{{request.header "<name>"}}` - The value of a request header.  Header names are case
+//! insensitive.
+//! * `{{request.body}}` - The request body.
+//! * `{{request.clientCert.subject}}`, `{{request.clientCert.subjectAltNames}}` and
+//! `{{request.clientCert.fingerprint}}` - The client certificate presented over mutual TLS.
+//! `request.clientCert` is missing if the client didn't present one.
+//!
+//! The following helpers generate values:
+//!
+//! * `{{uuid}}` - A random (v4) UUID.
+//! * `{{randomInt lower=<n> upper=<n>}}` - A random integer between `lower` and `upper`,
+//! inclusive.  The bounds default to 0 and 100.
+//! * `{{randomString length=<n> type="<type>"}}` - A random string of `alphanumeric`,
+//! `alphabetic`, `numeric` or `hex` characters.  Defaults to 16 alphanumeric characters.
+//! * `{{now format="<format>" offset="<offset>"}}` - The current UTC time, as `RFC3339`,
+//! `RFC2822`, `epoch` seconds, `epoch_ms` milliseconds, or a `strftime` pattern, i.e.
+//! `%Y-%m-%d`.  Defaults to `RFC3339`.  The optional offset is a signed number of seconds,
+//! minutes, hours or days, i.e. `+2h`, `-30m`, `+1d` or `-10s`.
+//!
+//! The `{{jsonPath <json> "<path>"}}` helper selects from a JSON document, i.e.
+//! `{{jsonPath request.body "$.order.id"}}`.  A string is written as is, any other value as
+//! JSON, and a path that selects several values as a JSON array.
+use crate::error::Error;
+use crate::matcher::json_path;
+use crate::util;
+use crate::x509::ClientCertificate;
+use chrono::Utc;
+use handlebars::{
+    no_escape, Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+};
+use http::header::HOST;
+use http::Request;
+use lazy_static::lazy_static;
+use rand::Rng;
+use serde_json::{json, Map, Value};
+use std::convert::TryFrom;
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// The characters of a random `alphanumeric` string.  The first 52 are `alphabetic`, and the
+/// rest `numeric`.
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+/// The characters of a random `hex` string.
+const HEX: &[u8] = b"0123456789abcdef";
+
+lazy_static! {
+    static ref HANDLEBARS: Handlebars = registry();
+}
+
+fn registry() -> Handlebars {
+    let mut handlebars = Handlebars::new();
+    // Response bodies are usually JSON, not HTML.
+    handlebars.register_escape_fn(no_escape);
+    handlebars.register_helper("request.header", Box::new(header_helper));
+    handlebars.register_helper("request.pathSegment", Box::new(path_segment_helper));
+    handlebars.register_helper("request.queryParam", Box::new(query_param_helper));
+    handlebars.register_helper("jsonPath", Box::new(json_path_helper));
+    handlebars.register_helper("uuid", Box::new(uuid_helper));
+    handlebars.register_helper("randomInt", Box::new(random_int_helper));
+    handlebars.register_helper("randomString", Box::new(random_string_helper));
+    handlebars.register_helper("now", Box::new(now_helper));
+    handlebars
+}
+
+fn uuid_helper(
+    _: &Helper<'_, '_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    out.write(&Uuid::new_v4().to_string())?;
+    Ok(())
+}
+
+fn random_int_helper(
+    h: &Helper<'_, '_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let bound = |name: &str, default: i64| {
+        h.hash_get(name)
+            .map_or(Some(default), |value| value.value().as_i64())
+            .ok_or_else(|| RenderError::new(format!("randomInt: `{}` must be an integer", name)))
+    };
+    let lower = bound("lower", 0)?;
+    let upper = bound("upper", 100)?;
+
+    if lower > upper {
+        return Err(RenderError::new(
+            "randomInt: `lower` must not be greater than `upper`",
+        ));
+    }
+
+    let upper = upper
+        .checked_add(1)
+        .ok_or_else(|| RenderError::new("randomInt: `upper` must be less than the largest i64"))?;
+    out.write(&rand::thread_rng().gen_range(lower, upper).to_string())?;
+    Ok(())
+}
+
+fn random_string_helper(
+    h: &Helper<'_, '_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let length = h
+        .hash_get("length")
+        .map_or(Some(16), |value| value.value().as_u64())
+        .and_then(|length| usize::try_from(length).ok())
+        .ok_or_else(|| RenderError::new("randomString: `length` must be a positive integer"))?;
+    let characters: &[u8] = match h
+        .hash_get("type")
+        .and_then(|value| value.value().as_str())
+        .unwrap_or("alphanumeric")
+    {
+        "alphanumeric" => ALPHANUMERIC,
+        "alphabetic" => &ALPHANUMERIC[..52],
+        "numeric" => &ALPHANUMERIC[52..],
+        "hex" => HEX,
+        other => {
+            return Err(RenderError::new(format!(
+                "randomString: unknown type '{}'",
+                other
+            )));
+        }
+    };
+
+    let mut rng = rand::thread_rng();
+    let random: String = (0..length)
+        .map(|_| char::from(characters[rng.gen_range(0, characters.len())]))
+        .collect();
+    out.write(&random)?;
+    Ok(())
+}
+
+fn now_helper(
+    h: &Helper<'_, '_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let offset = match h
+        .hash_get("offset")
+        .and_then(|value| value.value().as_str())
+    {
+        Some(offset) => parse_offset(offset)
+            .ok_or_else(|| RenderError::new(format!("now: invalid offset '{}'", offset)))?,
+        None => chrono::Duration::zero(),
+    };
+    let now = Utc::now()
+        .checked_add_signed(offset)
+        .ok_or_else(|| RenderError::new("now: the offset is out of range"))?;
+
+    let formatted = match h
+        .hash_get("format")
+        .and_then(|value| value.value().as_str())
+        .unwrap_or("RFC3339")
+    {
+        "RFC3339" => now.to_rfc3339(),
+        "RFC2822" => now.to_rfc2822(),
+        "epoch" => now.timestamp().to_string(),
+        "epoch_ms" => now.timestamp_millis().to_string(),
+        pattern => {
+            let mut formatted = String::new();
+            write!(formatted, "{}", now.format(pattern))
+                .map_err(|_| RenderError::new(format!("now: invalid format '{}'", pattern)))?;
+            formatted
+        }
+    };
+    out.write(&formatted)?;
+    Ok(())
+}
+
+/// Parse a time offset, i.e. `+2h` or `-30m`.  An offset too large for a `chrono::Duration` is
+/// invalid.
+fn parse_offset(offset: &str) -> Option<chrono::Duration> {
+    let offset = offset.trim();
+    let (sign, rest) = if offset.starts_with('-') {
+        (-1, &offset[1..])
+    } else if offset.starts_with('+') {
+        (1, &offset[1..])
+    } else {
+        (1, offset)
+    };
+
+    let unit = rest.chars().last()?;
+    let amount = rest[..rest.len() - unit.len_utf8()]
+        .parse::<i64>()
+        .ok()?
+        .checked_mul(sign)?;
+    let seconds_per_unit = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        _ => return None,
+    };
+
+    // `chrono::Duration::seconds` panics out of range, where milliseconds can't.
+    amount
+        .checked_mul(seconds_per_unit)?
+        .checked_mul(1000)
+        .map(chrono::Duration::milliseconds)
+}
+
+fn header_helper(
+    h: &Helper<'_, '_>,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let name = h
+        .param(0)
+        .and_then(|param| param.value().as_str())
+        .ok_or_else(|| RenderError::new("request.header requires a header name"))?;
+
+    if let Some(value) = ctx.data()["request"]["headers"][name.to_lowercase()].as_str() {
+        out.write(value)?;
+    }
+
+    Ok(())
+}
+
+fn path_segment_helper(
+    h: &Helper<'_, '_>,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let index = h
+        .param(0)
+        .and_then(|param| param.value().as_u64())
+        .and_then(|index| usize::try_from(index).ok())
+        .ok_or_else(|| RenderError::new("request.pathSegment requires a segment index"))?;
+
+    if let Some(segment) = ctx.data()["request"]["pathSegments"][index].as_str() {
+        out.write(segment)?;
+    }
+
+    Ok(())
+}
+
+fn query_param_helper(
+    h: &Helper<'_, '_>,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let name = h
+        .param(0)
+        .and_then(|param| param.value().as_str())
+        .ok_or_else(|| RenderError::new("request.queryParam requires a parameter name"))?;
+
+    if let Some(query) = ctx.data()["request"]["url"]
+        .as_str()
+        .and_then(|url| url.splitn(2, '?').nth(1))
+    {
+        if let Some((_, value)) = util::parse_urlencoded(query)
+            .into_iter()
+            .find(|(param, _)| param == name)
+        {
+            out.write(&value)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn json_path_helper(
+    h: &Helper<'_, '_>,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext<'_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let document = h
+        .param(0)
+        .map(|param| param.value())
+        .ok_or_else(|| RenderError::new("jsonPath requires a JSON document"))?;
+    let path = h
+        .param(1)
+        .and_then(|param| param.value().as_str())
+        .ok_or_else(|| RenderError::new("jsonPath requires a path"))?;
+
+    // The request body is a string, anything else is already JSON.
+    let parsed;
+    let document = if let Some(text) = document.as_str() {
+        parsed = serde_json::from_str::<Value>(text)
+            .map_err(|e| RenderError::new(format!("jsonPath: invalid JSON: {}", e)))?;
+        &parsed
+    } else {
+        document
+    };
+
+    let selected = json_path::select(document, path)
+        .map_err(|e| RenderError::new(format!("jsonPath: {}", e)))?;
+
+    let rendered = if selected.len() > 1 {
+        Value::from(selected.into_iter().cloned().collect::<Vec<Value>>()).to_string()
+    } else {
+        match selected.first() {
+            Some(Value::String(value)) => value.clone(),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        }
+    };
+    out.write(&rendered)?;
+
+    Ok(())
+}
+
+/// Render the given template against the given request.
+pub fn render(template: &str, request: &Request<Vec<u8>>) -> Result<String, Error> {
+    Ok(HANDLEBARS.render_template(template, &context(request))?)
+}
+
+/// Render the given template against the given request, with extra data alongside `request`.
+crate fn render_with(
+    template: &str,
+    request: &Request<Vec<u8>>,
+    name: &str,
+    data: Value,
+) -> Result<String, Error> {
+    let mut context = context(request);
+    context[name] = data;
+    Ok(HANDLEBARS.render_template(template, &context)?)
+}
+
+/// The request data available to templates, as JSON, i.e. for an echo response.
+crate fn request_json(request: &Request<Vec<u8>>) -> Value {
+    context(request)["request"].take()
+}
+
+fn context(request: &Request<Vec<u8>>) -> Value {
+    let headers: Map<String, Value> = request
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), Value::from(value)))
+        })
+        .collect();
+    let query: Map<String, Value> = request
+        .uri()
+        .query()
+        .map(|query| {
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let mut parts = pair.splitn(2, '=');
+                    let name = parts.next().unwrap_or("").to_string();
+                    (name, Value::from(parts.next().unwrap_or("")))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let host = request.uri().host().map(str::to_string).or_else(|| {
+        request
+            .headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(str::to_string)
+    });
+
+    let path_segments: Vec<&str> = request
+        .uri()
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    let mut context = json!({
+        "request": {
+            "method": request.method().as_str(),
+            "scheme": util::request_scheme(request),
+            "host": host,
+            "path": request.uri().path(),
+            "pathSegments": path_segments,
+            "url": request.uri().path_and_query().map_or("", |pq| pq.as_str()),
+            "query": query,
+            "headers": headers,
+            "body": String::from_utf8_lossy(request.body()),
+        }
+    });
+
+    if let Some(client_cert) = request.extensions().get::<ClientCertificate>() {
+        context["request"]["clientCert"] = json!({
+            "subject": client_cert.subject,
+            "subjectAltNames": client_cert.subject_alt_names,
+            "fingerprint": client_cert.fingerprint,
+        });
+    }
+    context
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_offset, render, render_with, request_json};
+    use crate::x509::test::test_client_certificate;
+    use http::Request;
+    use serde_json::json;
+
+    fn request() -> Request<Vec<u8>> {
+        Request::builder()
+            .method("POST")
+            .uri("/orders/12345?page=2")
+            .header("X-Id", "abc")
+            .body(b"{\"qty\": 1}".to_vec())
+            .expect("Unable to build request!")
+    }
+
+    #[test]
+    fn render_request_data() {
+        let template = r#"{"method":"{{request.method}}","path":"{{request.path}}","url":"{{request.url}}","page":"{{request.query.page}}"}"#;
+        match render(template, &request()) {
+            Ok(body) => assert_eq!(
+                body,
+                r#"{"method":"POST","path":"/orders/12345","url":"/orders/12345?page=2","page":"2"}"#
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn render_header() {
+        match render(
+            r#"{{request.header "x-id"}}-{{request.header "X-Missing"}}"#,
+            &request(),
+        ) {
+            Ok(body) => assert_eq!(body, "abc-"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn render_body() {
+        match render("{{request.body}}", &request()) {
+            Ok(body) => assert_eq!(body, "{\"qty\": 1}"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn render_path_and_query_params() {
+        match render(
+            r#"{{request.pathSegment 0}}/{{request.pathSegment 1}}/{{request.pathSegment 2}}?{{request.queryParam "page"}}"#,
+            &request(),
+        ) {
+            Ok(body) => assert_eq!(body, "orders/12345/?2"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn render_json_path() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/orders")
+            .body(br#"{"order": {"id": "abc", "qty": 2, "items": [1, 2]}}"#.to_vec())
+            .expect("Unable to build request!");
+        match render(
+            r#"{{jsonPath request.body "$.order.id"}} {{jsonPath request.body "$.order.qty"}} {{jsonPath request.body "$.order.items[*]"}} {{jsonPath request.body "$.missing"}}"#,
+            &request,
+        ) {
+            Ok(body) => assert_eq!(body, "abc 2 [1,2] "),
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(render(r#"{{jsonPath request.body "$.id"}}"#, &request()).is_ok());
+        assert!(render(r#"{{jsonPath request.method "$.id"}}"#, &request()).is_err());
+    }
+
+    #[test]
+    fn render_location() {
+        let request = Request::builder()
+            .uri("/orders")
+            .header("Host", "localhost:8080")
+            .body(vec![])
+            .expect("Unable to build request!");
+        match render(
+            "{{request.scheme}}://{{request.host}}/orders/{{uuid}}",
+            &request,
+        ) {
+            Ok(location) => {
+                assert!(location.starts_with("http://localhost:8080/orders/"));
+                assert_eq!(location.len(), "http://localhost:8080/orders/".len() + 36);
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn render_extra_data() {
+        match render_with(
+            "{{request.path}}?hop={{redirect.next}}",
+            &request(),
+            "redirect",
+            json!({ "next": 2 }),
+        ) {
+            Ok(location) => assert_eq!(location, "/orders/12345?hop=2"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn echo_request() {
+        let echoed = request_json(&request());
+        assert_eq!(echoed["method"], "POST");
+        assert_eq!(echoed["url"], "/orders/12345?page=2");
+        assert_eq!(echoed["headers"]["x-id"], "abc");
+        assert_eq!(echoed["body"], "{\"qty\": 1}");
+        assert!(echoed.get("clientCert").is_none());
+    }
+
+    #[test]
+    fn render_client_cert() {
+        let mut request = request();
+        let _ = request.extensions_mut().insert(test_client_certificate());
+        match render(
+            "{{request.clientCert.subject}} {{request.clientCert.subjectAltNames.[0]}}",
+            &request,
+        ) {
+            Ok(body) => assert_eq!(body, "CN=localhost localhost"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn render_random_values() {
+        match render(
+            r#"{{randomInt lower=5 upper=5}} {{randomString length=8 type="numeric"}}"#,
+            &request(),
+        ) {
+            Ok(body) => {
+                assert!(body.starts_with("5 "));
+                assert_eq!(body.len(), 10);
+                assert!(body[2..].chars().all(|c| c.is_ascii_digit()));
+            }
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(render("{{randomInt lower=10 upper=1}}", &request()).is_err());
+        assert!(render(
+            "{{randomInt lower=9223372036854775807 upper=9223372036854775807}}",
+            &request()
+        )
+        .is_err());
+        match render(
+            "{{randomInt lower=9223372036854775806 upper=9223372036854775806}}",
+            &request(),
+        ) {
+            Ok(body) => assert_eq!(body, "9223372036854775806"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(render(r#"{{randomString type="emoji"}}"#, &request()).is_err());
+    }
+
+    #[test]
+    fn render_now() {
+        match (
+            render(r#"{{now format="epoch"}}"#, &request()),
+            render(r#"{{now format="epoch" offset="+2h"}}"#, &request()),
+        ) {
+            (Ok(now), Ok(later)) => match (now.parse::<i64>(), later.parse::<i64>()) {
+                (Ok(now), Ok(later)) => assert!((later - now - 7200).abs() <= 1),
+                _ => assert!(false, "Expected epoch seconds"),
+            },
+            _ => assert!(false, "Unable to render the current time"),
+        }
+        match render(r#"{{now format="%Y"}}"#, &request()) {
+            Ok(year) => assert_eq!(year.len(), 4),
+            Err(e) => assert!(false, e.to_string()),
+        }
+        assert!(render(r#"{{now offset="2 weeks"}}"#, &request()).is_err());
+        assert!(render(r#"{{now offset="+99999999999999d"}}"#, &request()).is_err());
+        assert!(render(r#"{{now offset="+9999999999d"}}"#, &request()).is_err());
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offset("+2h"), Some(chrono::Duration::hours(2)));
+        assert_eq!(parse_offset("-30m"), Some(chrono::Duration::minutes(-30)));
+        assert_eq!(parse_offset("1d"), Some(chrono::Duration::days(1)));
+        assert_eq!(parse_offset("10x"), None);
+        assert_eq!(parse_offset(""), None);
+        assert_eq!(parse_offset("+99999999999999d"), None);
+        assert_eq!(parse_offset("--9223372036854775808s"), None);
+    }
+
+    #[test]
+    fn invalid_template() {
+        assert!(render("{{#if}}", &request()).is_err());
+    }
+}
@@ -7,20 +7,56 @@
 // modified, or distributed except according to those terms.
 
 //! `libdeadmock` utilities
-use crate::error::Error;
+use crate::error::Error::{self, EnvVarNotFound, InvalidRequestConfig};
+use cached::{cached_key_result, SizedCache};
 use futures::{future, Future};
+use glob::GlobError;
 use http::header::{HeaderValue, CONTENT_TYPE};
-use http::{Response, StatusCode};
+use http::uri::Scheme;
+use http::{Request, Response, StatusCode};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
 use serde_derive::Serialize;
-use std::fs::{self, DirEntry};
+use std::env;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 
+lazy_static! {
+    static ref ENV_VAR_RE: Regex =
+        Regex::new(r"\$(\$?)\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("invalid env var regex");
+}
+
+/// The most compiled regexes kept in the shared regex cache.
+const REGEX_CACHE_SIZE: usize = 1024;
+
+cached_key_result! {
+    REGEX: SizedCache<String, Regex> = SizedCache::with_size(REGEX_CACHE_SIZE);
+    Key = { pattern.to_string() };
+    fn generate_regex(pattern: &str) -> Result<Regex, String> = {
+        Regex::new(pattern).map_err(|e| e.to_string())
+    }
+}
+
+/// Compile a regex, or fetch it from the cache shared by every matcher.  The least recently used
+/// regexes are dropped once the cache is full.
+crate fn regex(pattern: &str) -> Result<Regex, String> {
+    generate_regex(pattern)
+}
+
+/// Compile a regex from a request configuration, or fetch it from the shared cache.  A pattern
+/// that doesn't compile is reported as an invalid request configuration, rather than a request
+/// that doesn't match.
+crate fn pattern(pattern: &str) -> Result<Regex, Error> {
+    regex(pattern).map_err(|e| InvalidRequestConfig(format!("invalid regex '{}': {}", pattern, e)))
+}
+
 #[allow(box_pointers)]
-crate type FutResponse = Box<dyn Future<Item = Response<String>, Error = String> + Send>;
+crate type FutResponse = Box<dyn Future<Item = Response<Vec<u8>>, Error = String> + Send>;
 
 crate fn visit_dirs<F>(dir: &Path, cb: &mut F) -> Result<(), Error>
 where
-    F: FnMut(&DirEntry) -> Result<(), Error>,
+    F: FnMut(&Path) -> Result<(), Error>,
 {
     if fs::metadata(dir)?.is_dir() {
         for entry in fs::read_dir(dir)? {
@@ -28,34 +64,328 @@ where
             if fs::metadata(entry.path())?.is_dir() {
                 visit_dirs(&entry.path(), cb)?;
             } else {
-                cb(&entry)?;
+                cb(&entry.path())?;
             }
         }
     }
     Ok(())
 }
 
+/// The scheme a request was received over.
+///
+/// This is the scheme of an absolute-form request uri, otherwise the scheme the server recorded
+/// in the request extensions for the listener, defaulting to `http`.
+crate fn request_scheme(request: &Request<Vec<u8>>) -> &str {
+    request
+        .uri()
+        .scheme_str()
+        .or_else(|| request.extensions().get::<Scheme>().map(Scheme::as_str))
+        .unwrap_or("http")
+}
+
+/// The address of the client that sent a request, as recorded by the server in the request
+/// extensions.
+crate fn remote_addr(request: &Request<Vec<u8>>) -> Option<SocketAddr> {
+    request.extensions().get::<SocketAddr>().cloned()
+}
+
+/// The SNI server name a TLS client asked for, as recorded by the server in the request
+/// extensions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+crate struct ServerName {
+    crate name: String,
+}
+
+/// The SNI server name the client that sent a request asked for, if any.
+crate fn server_name(request: &Request<Vec<u8>>) -> Option<&str> {
+    request
+        .extensions()
+        .get::<ServerName>()
+        .map(|server_name| server_name.name.as_str())
+}
+
+/// Does the given address or CIDR block, i.e. `10.1.0.0/16`, contain the given address?
+///
+/// IPv4-mapped IPv6 addresses are compared as IPv4.
+crate fn cidr_contains(cidr: &str, addr: IpAddr) -> Result<bool, String> {
+    let mut parts = cidr.trim().splitn(2, '/');
+    let network: IpAddr = parts
+        .next()
+        .unwrap_or("")
+        .parse()
+        .map_err(|e| format!("invalid address '{}': {}", cidr, e))?;
+    let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+    let prefix = match parts.next() {
+        Some(prefix) => prefix
+            .parse::<u32>()
+            .ok()
+            .filter(|prefix| *prefix <= max_prefix)
+            .ok_or_else(|| format!("invalid prefix length in '{}'", cidr))?,
+        None => max_prefix,
+    };
+
+    Ok(match (network, unmap(addr)) {
+        (IpAddr::V4(network), IpAddr::V4(addr)) => {
+            let mask = u32::max_value().checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(network) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(addr)) => {
+            let mask = u128::max_value().checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(network) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    })
+}
+
+fn unmap(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, _, _] => v6.to_ipv4().map_or(addr, IpAddr::V4),
+            _ => addr,
+        },
+        IpAddr::V4(_) => addr,
+    }
+}
+
+/// Visit every file matching the given glob pattern, i.e. `mappings/**/orders-*.json`.
+crate fn visit_glob<F>(pattern: &str, cb: &mut F) -> Result<(), Error>
+where
+    F: FnMut(&Path) -> Result<(), Error>,
+{
+    for entry in glob::glob(pattern)? {
+        let path = entry.map_err(GlobError::into_error)?;
+        if fs::metadata(&path)?.is_file() {
+            cb(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Used to skip serializing `false` flags.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+crate fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Replace every `${ENV_VAR}` placeholder in `input` with the value of that environment variable.
+/// An escaped placeholder, `$${ENV_VAR}`, is left as the literal `${ENV_VAR}`.
+crate fn interpolate_env(input: &str) -> Result<String, Error> {
+    let mut missing = None;
+    let output = ENV_VAR_RE.replace_all(input, |caps: &Captures<'_>| {
+        if !caps[1].is_empty() {
+            return format!("${{{}}}", &caps[2]);
+        }
+        env::var(&caps[2]).unwrap_or_else(|_| {
+            if missing.is_none() {
+                missing = Some(caps[2].to_string());
+            }
+            String::new()
+        })
+    });
+
+    if let Some(name) = missing {
+        Err(EnvVarNotFound(name))
+    } else {
+        Ok(output.into_owned())
+    }
+}
+
+/// Parse `application/x-www-form-urlencoded` data, i.e. a query string, into decoded
+/// `(name, value)` pairs.
+crate fn parse_urlencoded(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+            (
+                percent_decode(&name.replace('+', " ")),
+                percent_decode(&value.replace('+', " ")),
+            )
+        })
+        .collect()
+}
+
+/// Get a parameter, i.e. `boundary` or `name`, from a header value, without any quotes.
+crate fn header_parameter(value: &str, key: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let mut kv = param.splitn(2, '=');
+        let name = kv.next()?.trim();
+        let value = kv.next()?.trim();
+
+        if name.eq_ignore_ascii_case(key) {
+            Some(value.trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Percent-decode a url component.  Invalid escapes are left as is.
+crate fn percent_decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
 #[allow(box_pointers)]
 crate fn error_response_fut(body: String, status_code: StatusCode) -> FutResponse {
     Box::new(future::ok(error_response(body, status_code)))
 }
 
-crate fn error_response(message: String, status_code: StatusCode) -> Response<String> {
+crate fn error_response(message: String, status_code: StatusCode) -> Response<Vec<u8>> {
     let mut response = Response::builder();
     let _ = response
         .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
         .status(status_code);
 
-    if let Ok(message) = serde_json::to_string(&ErrorMessage { message }) {
+    if let Ok(message) = serde_json::to_vec(&ErrorMessage { message }) {
         if let Ok(response) = response.body(message) {
             return response;
         }
     }
 
-    Response::new(r#"{ "message": "Unable to process body" }"#.to_string())
+    Response::new(br#"{ "message": "Unable to process body" }"#.to_vec())
 }
 
 #[derive(Serialize)]
 struct ErrorMessage {
     message: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::{cidr_contains, interpolate_env, parse_urlencoded, percent_decode, regex};
+    use std::env;
+    use std::net::IpAddr;
+
+    #[test]
+    fn cached_regex() {
+        match regex("^/orders/[0-9]+$") {
+            Ok(regex) => assert!(regex.is_match("/orders/12")),
+            Err(e) => assert!(false, e),
+        }
+        match regex("^/orders/[0-9]+$") {
+            Ok(regex) => assert!(!regex.is_match("/orders/ab")),
+            Err(e) => assert!(false, e),
+        }
+        assert!(regex("^/orders/(.*").is_err());
+    }
+
+    fn contains(cidr: &str, addr: &str) -> bool {
+        match addr.parse::<IpAddr>() {
+            Ok(addr) => cidr_contains(cidr, addr).unwrap_or(false),
+            Err(e) => {
+                assert!(false, e.to_string());
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn cidr() {
+        assert!(contains("10.1.0.0/16", "10.1.200.3"));
+        assert!(!contains("10.1.0.0/16", "10.2.0.1"));
+        assert!(contains("192.168.1.7", "192.168.1.7"));
+        assert!(!contains("192.168.1.7", "192.168.1.8"));
+        assert!(contains("0.0.0.0/0", "8.8.8.8"));
+        assert!(contains("10.1.0.0/16", "::ffff:10.1.2.3"));
+        assert!(contains("fd00::/8", "fd12:3456::1"));
+        assert!(!contains("fd00::/8", "::1"));
+        assert!(!contains("::/0", "127.0.0.1"));
+        assert!(cidr_contains("10.1.0.0/33", [10, 1, 0, 1].into()).is_err());
+        assert!(cidr_contains("not-an-ip", [10, 1, 0, 1].into()).is_err());
+    }
+
+    #[test]
+    fn decode_component() {
+        assert_eq!(percent_decode("plain"), "plain");
+        assert_eq!(percent_decode("a+b"), "a+b");
+        assert_eq!(percent_decode("a%20b%2Fc"), "a b/c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn urlencoded() {
+        assert_eq!(
+            parse_urlencoded("q=a+b%2Bc&empty=&flag"),
+            vec![
+                ("q".to_string(), "a b+c".to_string()),
+                ("empty".to_string(), "".to_string()),
+                ("flag".to_string(), "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn interpolate() {
+        env::set_var("DEADMOCK_TEST_HOST", "a.url.com");
+        match interpolate_env("http://${DEADMOCK_TEST_HOST}/path") {
+            Ok(interpolated) => assert_eq!(interpolated, "http://a.url.com/path"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+        env::remove_var("DEADMOCK_TEST_HOST");
+    }
+
+    #[test]
+    fn interpolate_nothing() {
+        match interpolate_env("http://a.url.com/$path") {
+            Ok(interpolated) => assert_eq!(interpolated, "http://a.url.com/$path"),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn interpolate_escaped() {
+        env::remove_var("DEADMOCK_TEST_ESCAPED");
+        match interpolate_env("const url = `$${DEADMOCK_TEST_ESCAPED}/orders`;") {
+            Ok(interpolated) => assert_eq!(
+                interpolated,
+                "const url = `${DEADMOCK_TEST_ESCAPED}/orders`;"
+            ),
+            Err(e) => assert!(false, e.to_string()),
+        }
+    }
+
+    #[test]
+    fn interpolate_missing() {
+        env::remove_var("DEADMOCK_TEST_MISSING");
+        match interpolate_env("${DEADMOCK_TEST_MISSING}") {
+            Ok(_) => assert!(false, "Not expected to succeed!"),
+            Err(e) => assert_eq!(
+                format!("{}", e),
+                "environment variable 'DEADMOCK_TEST_MISSING' not found!"
+            ),
+        }
+    }
+}
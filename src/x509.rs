@@ -0,0 +1,274 @@
+// Copyright (c) 2018 libdeadmock developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! X.509 client certificates
+//!
+//! Only the parts of a DER encoded certificate that can be matched on are read: the subject
+//! distinguished name and the subject alternative names.  The certificate has already been
+//! verified by rustls during the handshake.
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const BOOLEAN: u8 = 0x01;
+const OCTET_STRING: u8 = 0x04;
+const OID: u8 = 0x06;
+const VERSION: u8 = 0xa0;
+const EXTENSIONS: u8 = 0xa3;
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+const RFC822_NAME: u8 = 0x81;
+const DNS_NAME: u8 = 0x82;
+const URI: u8 = 0x86;
+const IP_ADDRESS: u8 = 0x87;
+
+/// The certificate a client presented during the TLS handshake.
+///
+/// The server adds this to the extensions of every request received on a connection where the
+/// client presented a certificate.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+crate struct ClientCertificate {
+    /// The subject distinguished name, with the attributes in certificate order, i.e.
+    /// `C=GB, O=Acme, CN=client`.
+    crate subject: String,
+    /// The DNS names, email addresses, URIs and IP addresses in the subject alternative name
+    /// extension.
+    crate subject_alt_names: Vec<String>,
+    /// The lowercase hex encoded SHA-256 digest of the DER encoded certificate.
+    crate fingerprint: String,
+}
+
+impl ClientCertificate {
+    /// Read a DER encoded certificate.
+    crate fn from_der(der: &[u8]) -> Result<Self, String> {
+        let (certificate, _) = read(der, SEQUENCE)?;
+        let (tbs_certificate, _) = read(certificate, SEQUENCE)?;
+
+        // version, serialNumber, signature, issuer, validity
+        let mut rest = tbs_certificate;
+        if rest.first() == Some(&VERSION) {
+            rest = skip(rest)?;
+        }
+        for _ in 0..4 {
+            rest = skip(rest)?;
+        }
+
+        let (subject, rest) = read(rest, SEQUENCE)?;
+        let mut rest = skip(rest)?;
+
+        let mut subject_alt_names = vec![];
+        while !rest.is_empty() {
+            let (tag, value, next) = tlv(rest)?;
+            if tag == EXTENSIONS {
+                subject_alt_names = self::subject_alt_names(value)?;
+            }
+            rest = next;
+        }
+
+        Ok(Self {
+            subject: name(subject)?,
+            subject_alt_names,
+            fingerprint: format!("{:x}", Sha256::digest(der)),
+        })
+    }
+}
+
+/// Split a DER tag, length and value from the front of `input`.
+fn tlv(input: &[u8]) -> Result<(u8, &[u8], &[u8]), String> {
+    let truncated = || "truncated certificate".to_string();
+    let tag = *input.get(0).ok_or_else(truncated)?;
+    let first = *input.get(1).ok_or_else(truncated)?;
+
+    let (len, start) = if first < 0x80 {
+        (usize::from(first), 2)
+    } else {
+        let octets = usize::from(first & 0x7f);
+        if octets == 0 || octets > 4 {
+            return Err("unsupported length in certificate".to_string());
+        }
+        let len = input
+            .get(2..2 + octets)
+            .ok_or_else(truncated)?
+            .iter()
+            .fold(0, |len, octet| (len << 8) | usize::from(*octet));
+        (len, 2 + octets)
+    };
+
+    let value = input.get(start..start + len).ok_or_else(truncated)?;
+    Ok((tag, value, &input[start + len..]))
+}
+
+/// Read a value with the given tag from the front of `input`.
+fn read(input: &[u8], expected: u8) -> Result<(&[u8], &[u8]), String> {
+    let (tag, value, rest) = tlv(input)?;
+    if tag == expected {
+        Ok((value, rest))
+    } else {
+        Err(format!(
+            "expected tag {:#04x} in certificate, found {:#04x}",
+            expected, tag
+        ))
+    }
+}
+
+fn skip(input: &[u8]) -> Result<&[u8], String> {
+    tlv(input).map(|(_, _, rest)| rest)
+}
+
+/// Format a distinguished name, i.e. `C=GB, O=Acme, CN=client`.
+fn name(mut input: &[u8]) -> Result<String, String> {
+    let mut attributes = vec![];
+    while !input.is_empty() {
+        let (mut rdn, rest) = read(input, SET)?;
+        while !rdn.is_empty() {
+            let (attribute, next) = read(rdn, SEQUENCE)?;
+            let (oid, value) = read(attribute, OID)?;
+            let (_, value, _) = tlv(value)?;
+            attributes.push(format!(
+                "{}={}",
+                attribute_type(oid),
+                String::from_utf8_lossy(value)
+            ));
+            rdn = next;
+        }
+        input = rest;
+    }
+    Ok(attributes.join(", "))
+}
+
+fn attribute_type(oid: &[u8]) -> String {
+    match oid {
+        [0x55, 0x04, 0x03] => "CN".to_string(),
+        [0x55, 0x04, 0x05] => "serialNumber".to_string(),
+        [0x55, 0x04, 0x06] => "C".to_string(),
+        [0x55, 0x04, 0x07] => "L".to_string(),
+        [0x55, 0x04, 0x08] => "ST".to_string(),
+        [0x55, 0x04, 0x0a] => "O".to_string(),
+        [0x55, 0x04, 0x0b] => "OU".to_string(),
+        [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01] => "emailAddress".to_string(),
+        _ => dotted(oid),
+    }
+}
+
+/// Format an object identifier, i.e. `2.5.4.3`.
+fn dotted(oid: &[u8]) -> String {
+    let mut arcs = vec![];
+    let mut arc: u64 = 0;
+    for octet in oid {
+        arc = (arc << 7) | u64::from(octet & 0x7f);
+        if octet & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        }
+    }
+    arcs.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The subject alternative names in the `[3]` extensions of a certificate.
+fn subject_alt_names(input: &[u8]) -> Result<Vec<String>, String> {
+    let (mut extensions, _) = read(input, SEQUENCE)?;
+    while !extensions.is_empty() {
+        let (extension, rest) = read(extensions, SEQUENCE)?;
+        let (oid, mut value) = read(extension, OID)?;
+
+        if oid == SUBJECT_ALT_NAME {
+            if value.first() == Some(&BOOLEAN) {
+                value = skip(value)?;
+            }
+            let (octets, _) = read(value, OCTET_STRING)?;
+            let (mut general_names, _) = read(octets, SEQUENCE)?;
+
+            let mut names = vec![];
+            while !general_names.is_empty() {
+                let (tag, name, next) = tlv(general_names)?;
+                match tag {
+                    RFC822_NAME | DNS_NAME | URI => {
+                        names.push(String::from_utf8_lossy(name).into_owned())
+                    }
+                    IP_ADDRESS => {
+                        if let Some(addr) = ip_addr(name) {
+                            names.push(addr.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+                general_names = next;
+            }
+            return Ok(names);
+        }
+        extensions = rest;
+    }
+    Ok(vec![])
+}
+
+fn ip_addr(octets: &[u8]) -> Option<IpAddr> {
+    if octets.len() == 4 {
+        let mut v4 = [0; 4];
+        v4.copy_from_slice(octets);
+        Some(IpAddr::V4(Ipv4Addr::from(v4)))
+    } else if octets.len() == 16 {
+        let mut v6 = [0; 16];
+        v6.copy_from_slice(octets);
+        Some(IpAddr::V6(Ipv6Addr::from(v6)))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+crate mod test {
+    use super::{dotted, ClientCertificate};
+    use std::fs::File;
+    use std::io::BufReader;
+    use tokio_rustls::rustls::internal::pemfile::certs;
+
+    crate fn test_client_certificate() -> ClientCertificate {
+        let file = File::open("tests/tls/cert.pem").expect("Unable to open the test certificate!");
+        let certs = certs(&mut BufReader::new(file)).unwrap_or_default();
+        let cert = certs.get(0).expect("No test certificate!");
+        ClientCertificate::from_der(&cert.0).expect("Unable to read the test certificate!")
+    }
+
+    #[test]
+    fn read_certificate() {
+        let certificate = test_client_certificate();
+        assert_eq!(certificate.subject, "CN=localhost");
+        assert_eq!(
+            certificate.subject_alt_names,
+            vec!["localhost".to_string(), "127.0.0.1".to_string()]
+        );
+        assert_eq!(
+            certificate.fingerprint,
+            "2afd6ad4e8290cf07bbc537638200315e8c34053178cb2a761decee276315164"
+        );
+    }
+
+    #[test]
+    fn invalid_certificate() {
+        assert!(ClientCertificate::from_der(b"").is_err());
+        assert!(ClientCertificate::from_der(&[0x30, 0x05, 0x02]).is_err());
+    }
+
+    #[test]
+    fn object_identifier() {
+        assert_eq!(dotted(&[0x55, 0x1d, 0x11]), "2.5.29.17");
+        assert_eq!(
+            dotted(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01]),
+            "1.2.840.113549.1.9.1"
+        );
+    }
+}